@@ -0,0 +1,193 @@
+// Presentations released against a secret, for exchanging a credential for e.g. a payment atomically.
+// The prover creates a pre-presentation for an adaptor point A = g_tilde^a without knowing a. Like a
+// Schnorr adaptor signature, the challenge is computed over T*A instead of the Schnorr commitment T
+// and the response for t is withheld as the pre-response r_t - c*t. The verifier checks the
+// pre-presentation against A, it is not a valid presentation until the response is adapted with a.
+// Whoever knows a completes it into a normal `Presentation`, and anyone with the pre-presentation and
+// the completed presentation extracts a, so revealing either one reveals the other.
+
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::pok_sig::PoKOfSignature;
+use crate::presentation::{challenge_suffix, Presentation};
+use crate::signature::Signature;
+use crate::types::{Challenge, Message};
+use crate::validation::deserialize_point;
+use crate::{OtherGroup, OTHER_GROUP_SIZE};
+use amcl_wrapper::field_elem::FieldElement;
+use amcl_wrapper::group_elem::GroupElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::HashSet;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AdaptorPresentation {
+    /// Has T*A as the Schnorr commitment and the pre-response for t
+    pub pre_presentation: Presentation,
+    #[serde(deserialize_with = "deserialize_point")]
+    pub adaptor_point: OtherGroup,
+}
+
+impl AdaptorPresentation {
+    pub fn new(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[FieldElement],
+        revealed_msg_indices: HashSet<usize>,
+        nonce: &[u8],
+        adaptor_point: &OtherGroup,
+    ) -> Result<Self, PSError> {
+        Self::new_with_rng(
+            sig,
+            vk,
+            params,
+            messages,
+            revealed_msg_indices,
+            nonce,
+            adaptor_point,
+            &mut thread_rng(),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[FieldElement],
+        revealed_msg_indices: HashSet<usize>,
+        nonce: &[u8],
+        adaptor_point: &OtherGroup,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        if adaptor_point.is_identity() {
+            return Err(PSError::GeneralError {
+                msg: String::from("Adaptor point is the identity"),
+            });
+        }
+        let revealed_msgs = revealed_msg_indices
+            .iter()
+            .filter(|i| **i < messages.len())
+            .map(|i| (*i, messages[*i].clone()))
+            .collect();
+        let pok = PoKOfSignature::init_with_rng(
+            sig,
+            vk,
+            params,
+            &Message::from_field_elements(messages),
+            None,
+            revealed_msg_indices,
+            rng,
+        )?;
+        // Same bytes as `Presentation::challenge` with T*A as the commitment
+        let adapted_commitment = pok.pok_vc.commitment() + adaptor_point;
+        let mut bytes = pok.to_bytes();
+        bytes.truncate(bytes.len() - OTHER_GROUP_SIZE);
+        bytes.append(&mut adapted_commitment.to_bytes());
+        bytes.append(&mut challenge_suffix(nonce, None));
+        let challenge = Challenge::from_msg_hash(&bytes);
+        let mut proof = pok.gen_proof(&challenge)?;
+        proof.proof_vc.commitment = adapted_commitment;
+        Ok(Self {
+            pre_presentation: Presentation {
+                proof,
+                revealed_msgs,
+                nonce: nonce.to_vec(),
+                disclosure_hash: None,
+            },
+            adaptor_point: adaptor_point.clone(),
+        })
+    }
+
+    /// Verify that completing with the discrete log of the adaptor point gives a valid presentation
+    pub fn verify(&self, vk: &Verkey, params: &Params) -> Result<bool, PSError> {
+        let pre = &self.pre_presentation;
+        if self.adaptor_point.is_identity()
+            || pre.disclosure_hash.is_some()
+            || pre.revealed_msgs.keys().any(|i| *i >= vk.Y_tilde.len())
+        {
+            return Ok(false);
+        }
+        let challenge = pre.challenge(vk, params);
+        let mut proof = pre.proof.clone();
+        proof.proof_vc.commitment = &proof.proof_vc.commitment - &self.adaptor_point;
+        proof.verify(vk, params, pre.revealed_msgs.clone(), &challenge)
+    }
+
+    /// Adapt the pre-response with `adaptor_secret`, the discrete log of the adaptor point
+    pub fn complete(
+        &self,
+        adaptor_secret: &FieldElement,
+        params: &Params,
+    ) -> Result<Presentation, PSError> {
+        if &params.g_tilde * adaptor_secret != self.adaptor_point {
+            return Err(PSError::GeneralError {
+                msg: String::from("Not the secret of the adaptor point"),
+            });
+        }
+        let mut presentation = self.pre_presentation.clone();
+        presentation.proof.proof_vc.responses[0] += adaptor_secret;
+        Ok(presentation)
+    }
+
+    /// The adaptor secret from the presentation completed from this one
+    pub fn extract_secret(
+        &self,
+        completed: &Presentation,
+        params: &Params,
+    ) -> Result<FieldElement, PSError> {
+        let (pre, done) = (
+            &self.pre_presentation.proof.proof_vc.responses,
+            &completed.proof.proof_vc.responses,
+        );
+        if pre.len() == 0 || done.len() != pre.len() {
+            return Err(PSError::GeneralError {
+                msg: String::from("Not completed from this pre-presentation"),
+            });
+        }
+        let secret = &done[0] - &pre[0];
+        if &params.g_tilde * &secret != self.adaptor_point {
+            return Err(PSError::GeneralError {
+                msg: String::from("Not completed from this pre-presentation"),
+            });
+        }
+        Ok(secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use amcl_wrapper::field_elem::FieldElementVector;
+
+    #[test]
+    fn test_adaptor_presentation() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let msgs = FieldElementVector::random(3);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        // The seller of the credential learns a when the payment is made
+        let a = FieldElement::random();
+        let A = &params.g_tilde * &a;
+        let revealed: HashSet<usize> = vec![1].into_iter().collect();
+
+        let adaptor =
+            AdaptorPresentation::new(&sig, &vk, &params, msgs.as_slice(), revealed, b"n", &A)
+                .unwrap();
+        assert!(adaptor.verify(&vk, &params).unwrap());
+        assert!(!adaptor.pre_presentation.verify(&vk, &params).unwrap());
+        let mut other = adaptor.clone();
+        other.adaptor_point = &params.g_tilde * &FieldElement::random();
+        assert!(!other.verify(&vk, &params).unwrap());
+
+        assert!(adaptor.complete(&FieldElement::random(), &params).is_err());
+        let presentation = adaptor.complete(&a, &params).unwrap();
+        assert!(presentation.verify(&vk, &params).unwrap());
+        assert_eq!(adaptor.extract_secret(&presentation, &params).unwrap(), a);
+        assert!(adaptor
+            .extract_secret(&adaptor.pre_presentation, &params)
+            .is_err());
+    }
+}
@@ -0,0 +1,242 @@
+// One self-describing encoding for all artifacts, for storage and transport layers that route bytes
+// without knowing their type. An artifact is the magic "PSA", the envelope version, the signature group
+// of the build and the kind, followed by the artifact's own encoding: `to_versioned_bytes` for verkeys,
+// `to_bytes` for signatures, blind signatures and proofs and either presentation format. Parsing
+// validates group elements with `ValidationProfile::Paranoid`. Presentations can only be decoded with
+// the issuer's verkey, so they are carried as bytes for `Presentation::from_bytes`.
+
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::pok_sig::PoKOfSignatureProof;
+use crate::presentation::Presentation;
+use crate::signature::Signature;
+use crate::utils::ByteReader;
+use crate::validation::ValidationProfile;
+use crate::{OTHER_GROUP_SIZE, SIGNATURE_GROUP_SIZE};
+use amcl_wrapper::group_elem::GroupElement;
+
+const MAGIC: &[u8; 3] = b"PSA";
+const ARTIFACT_FORMAT_V1: u8 = 1;
+/// The signature group, artifacts of a `SignatureG1` build do not parse in a `SignatureG2` build
+#[cfg(feature = "SignatureG2")]
+const GROUP_TAG: u8 = 2;
+#[cfg(feature = "SignatureG1")]
+const GROUP_TAG: u8 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ArtifactKind {
+    Verkey = 1,
+    Signature = 2,
+    BlindSignature = 3,
+    Proof = 4,
+    Presentation = 5,
+    Params = 6,
+}
+
+#[derive(Clone, Debug)]
+pub enum Artifact {
+    Verkey(Verkey),
+    Signature(Signature),
+    /// A signature from `BlindSignature::new` still to be unblinded
+    BlindSignature(Signature),
+    Proof(Box<PoKOfSignatureProof>),
+    /// The output of `Presentation::to_bytes` or `Presentation::to_bytes_v2`
+    Presentation(Vec<u8>),
+    Params(Params),
+}
+
+impl ArtifactKind {
+    fn from_u8(tag: u8) -> Result<Self, PSError> {
+        Ok(match tag {
+            1 => Self::Verkey,
+            2 => Self::Signature,
+            3 => Self::BlindSignature,
+            4 => Self::Proof,
+            5 => Self::Presentation,
+            6 => Self::Params,
+            t => {
+                return Err(PSError::InvalidEncoding {
+                    msg: format!("Unknown artifact kind {}", t),
+                })
+            }
+        })
+    }
+}
+
+impl Artifact {
+    /// Presentation in the compact version 2 format
+    pub fn presentation(presentation: &Presentation, vk: &Verkey, params: &Params) -> Self {
+        Artifact::Presentation(presentation.to_bytes_v2(vk, params))
+    }
+
+    pub fn kind(&self) -> ArtifactKind {
+        match self {
+            Artifact::Verkey(_) => ArtifactKind::Verkey,
+            Artifact::Signature(_) => ArtifactKind::Signature,
+            Artifact::BlindSignature(_) => ArtifactKind::BlindSignature,
+            Artifact::Proof(_) => ArtifactKind::Proof,
+            Artifact::Presentation(_) => ArtifactKind::Presentation,
+            Artifact::Params(_) => ArtifactKind::Params,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&[ARTIFACT_FORMAT_V1, GROUP_TAG, self.kind() as u8]);
+        let mut payload = match self {
+            Artifact::Verkey(vk) => vk.to_versioned_bytes(),
+            Artifact::Signature(sig) | Artifact::BlindSignature(sig) => sig.to_bytes(),
+            Artifact::Proof(proof) => proof.to_bytes(),
+            Artifact::Presentation(bytes) => bytes.clone(),
+            // g, g_tilde and the hash suite
+            Artifact::Params(params) => {
+                let mut bytes = params.g.to_bytes();
+                bytes.append(&mut params.g_tilde.to_bytes());
+                bytes.extend_from_slice(params.hash_suite.as_bytes());
+                bytes
+            }
+        };
+        bytes.append(&mut payload);
+        bytes
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, PSError> {
+        let mut reader = ByteReader::new(bytes);
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(PSError::InvalidEncoding {
+                msg: String::from("Not an artifact"),
+            });
+        }
+        let version = reader.read_u8()?;
+        if version != ARTIFACT_FORMAT_V1 {
+            return Err(PSError::InvalidEncoding {
+                msg: format!("Unknown artifact format version {}", version),
+            });
+        }
+        let group = reader.read_u8()?;
+        if group != GROUP_TAG {
+            return Err(PSError::InvalidEncoding {
+                msg: format!(
+                    "Artifact has signatures in G{} but this build in G{}",
+                    group, GROUP_TAG
+                ),
+            });
+        }
+        let kind = ArtifactKind::from_u8(reader.read_u8()?)?;
+        let payload = reader.take(reader.remaining())?;
+        let profile = ValidationProfile::Paranoid;
+        Ok(match kind {
+            ArtifactKind::Verkey => Artifact::Verkey(Verkey::from_versioned_bytes(payload)?),
+            ArtifactKind::Signature => {
+                Artifact::Signature(Signature::from_bytes_with_profile(payload, profile)?)
+            }
+            ArtifactKind::BlindSignature => {
+                Artifact::BlindSignature(Signature::from_bytes_with_profile(payload, profile)?)
+            }
+            ArtifactKind::Proof => Artifact::Proof(Box::new(
+                PoKOfSignatureProof::from_bytes_with_profile(payload, profile)?,
+            )),
+            ArtifactKind::Presentation => {
+                if payload.is_empty() {
+                    return Err(PSError::InvalidEncoding {
+                        msg: String::from("Empty presentation"),
+                    });
+                }
+                Artifact::Presentation(payload.to_vec())
+            }
+            ArtifactKind::Params => {
+                let mut reader = ByteReader::new(payload);
+                let g = profile.point_from_bytes(reader.take(SIGNATURE_GROUP_SIZE)?)?;
+                let g_tilde = profile.point_from_bytes(reader.take(OTHER_GROUP_SIZE)?)?;
+                let hash_suite = String::from_utf8(reader.take(reader.remaining())?.to_vec())
+                    .map_err(|_| PSError::InvalidEncoding {
+                        msg: String::from("Hash suite is not UTF-8"),
+                    })?;
+                Artifact::Params(Params {
+                    g,
+                    g_tilde,
+                    hash_suite,
+                })
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use crate::pok_sig::PoKOfSignature;
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_artifact_parse() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let msgs = FieldElementVector::random(3);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let pok = PoKOfSignature::init(
+            &sig,
+            &vk,
+            &params,
+            &Message::from_field_elements(msgs.as_slice()),
+            None,
+            HashSet::new(),
+        )
+        .unwrap();
+        let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
+        let proof = pok.gen_proof(&chal).unwrap();
+        let pres = Presentation::new(
+            &sig,
+            &vk,
+            &params,
+            msgs.as_slice(),
+            HashSet::new(),
+            b"nonce",
+        )
+        .unwrap();
+
+        let artifacts = vec![
+            Artifact::Verkey(vk.clone()),
+            Artifact::Signature(sig.clone()),
+            Artifact::BlindSignature(sig.clone()),
+            Artifact::Proof(Box::new(proof)),
+            Artifact::presentation(&pres, &vk, &params),
+            Artifact::Params(params.clone()),
+        ];
+        for a in &artifacts {
+            let bytes = a.to_bytes();
+            let parsed = Artifact::parse(&bytes).unwrap();
+            assert_eq!(parsed.kind(), a.kind());
+            assert_eq!(parsed.to_bytes(), bytes);
+            match parsed {
+                Artifact::Verkey(v) => assert_eq!(v, vk),
+                Artifact::Signature(s) => assert!(s
+                    .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+                    .unwrap()),
+                Artifact::Proof(p) => {
+                    assert!(p.verify(&vk, &params, Default::default(), &chal).unwrap())
+                }
+                Artifact::Presentation(p) => assert!(Presentation::from_bytes(&p, &vk, &params)
+                    .unwrap()
+                    .verify(&vk, &params)
+                    .unwrap()),
+                Artifact::Params(p) => assert_eq!(p.hash_suite, params.hash_suite),
+                Artifact::BlindSignature(_) => (),
+            }
+        }
+
+        let bytes = Artifact::Signature(sig).to_bytes();
+        assert!(Artifact::parse(&bytes[1..]).is_err());
+        assert!(Artifact::parse(&bytes[..bytes.len() - 1]).is_err());
+        let mut other_group = bytes.clone();
+        other_group[4] ^= 3;
+        assert!(Artifact::parse(&other_group).is_err());
+        let mut unknown_kind = bytes;
+        unknown_kind[5] = 0;
+        assert!(Artifact::parse(&unknown_kind).is_err());
+    }
+}
@@ -0,0 +1,140 @@
+// Async variants of signing and verification for services that sign with a remote KMS or spread
+// verification over workers. Every `PsSigner` is an `AsyncPsSigner` that completes immediately, and
+// the methods of `AsyncVerifier` default to the synchronous verification, so an implementation only
+// overrides what it runs elsewhere. The futures are `Send` and need no particular executor.
+
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::presentation::Presentation;
+use crate::signature::Signature;
+use crate::signer::PsSigner;
+use crate::types::Message;
+use crate::SignatureGroup;
+use amcl_wrapper::field_elem::FieldElement;
+use std::future::Future;
+
+pub trait AsyncPsSigner {
+    /// Number of messages signed
+    fn msg_count(&self) -> usize;
+
+    fn sign(
+        &self,
+        messages: &[FieldElement],
+        params: &Params,
+    ) -> impl Future<Output = Result<Signature, PSError>> + Send;
+
+    /// Blind signature on the messages in `commitment` followed by `messages`, see `PsSigner::blind_sign`
+    fn blind_sign(
+        &self,
+        commitment: &SignatureGroup,
+        messages: &[FieldElement],
+        params: &Params,
+    ) -> impl Future<Output = Result<Signature, PSError>> + Send;
+}
+
+impl<S: PsSigner + Sync + ?Sized> AsyncPsSigner for S {
+    fn msg_count(&self) -> usize {
+        PsSigner::msg_count(self)
+    }
+
+    async fn sign(&self, messages: &[FieldElement], params: &Params) -> Result<Signature, PSError> {
+        PsSigner::sign(self, messages, params)
+    }
+
+    async fn blind_sign(
+        &self,
+        commitment: &SignatureGroup,
+        messages: &[FieldElement],
+        params: &Params,
+    ) -> Result<Signature, PSError> {
+        PsSigner::blind_sign(self, commitment, messages, params)
+    }
+}
+
+pub trait AsyncVerifier: Sync {
+    fn verify_signature(
+        &self,
+        sig: &Signature,
+        messages: &[Message],
+        vk: &Verkey,
+        params: &Params,
+    ) -> impl Future<Output = Result<bool, PSError>> + Send {
+        async move { sig.verify(messages, vk, params) }
+    }
+
+    fn verify_presentation(
+        &self,
+        presentation: &Presentation,
+        vk: &Verkey,
+        params: &Params,
+    ) -> impl Future<Output = Result<bool, PSError>> + Send {
+        async move { presentation.verify(vk, params) }
+    }
+}
+
+/// Verifies in the calling task
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LocalVerifier;
+
+impl AsyncVerifier for LocalVerifier {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use std::collections::HashSet;
+    use std::pin::pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::{Context, Poll, Waker};
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    /// Counts the presentations handed to it and verifies signatures locally
+    struct Worker {
+        presentations: AtomicUsize,
+    }
+
+    impl AsyncVerifier for Worker {
+        fn verify_presentation(
+            &self,
+            presentation: &Presentation,
+            vk: &Verkey,
+            params: &Params,
+        ) -> impl Future<Output = Result<bool, PSError>> + Send {
+            self.presentations.fetch_add(1, Ordering::SeqCst);
+            LocalVerifier.verify_presentation(presentation, vk, params)
+        }
+    }
+
+    #[test]
+    fn test_async_sign_verify() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let msgs = FieldElementVector::random(3);
+        assert_eq!(AsyncPsSigner::msg_count(&sk), 3);
+        let sig = block_on(AsyncPsSigner::sign(&sk, msgs.as_slice(), &params)).unwrap();
+        let worker = Worker {
+            presentations: AtomicUsize::new(0),
+        };
+        let msgs_1 = Message::from_field_elements(msgs.as_slice());
+        assert!(block_on(worker.verify_signature(&sig, &msgs_1, &vk, &params)).unwrap());
+        let mut reversed = msgs_1.clone();
+        reversed.reverse();
+        assert!(!block_on(LocalVerifier.verify_signature(&sig, &reversed, &vk, &params)).unwrap());
+
+        let mut revealed = HashSet::new();
+        revealed.insert(2);
+        let p = Presentation::new(&sig, &vk, &params, msgs.as_slice(), revealed, b"n").unwrap();
+        assert!(block_on(worker.verify_presentation(&p, &vk, &params)).unwrap());
+        assert_eq!(worker.presentations.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,235 @@
+// Verifier attestations. A verifier at the edge, e.g. a gateway, verifies a presentation and signs the
+// outcome so that systems behind it can trust the result without the presentation, the issuer's verkey
+// or any pairing. The statement is the fingerprint of the presentation, the id of the issuer's verkey,
+// the hash of the compiled policy checked, whether the presentation was accepted and the time. It is
+// signed with the verifier's own PS key as the messages (H(statement), 0, 0, ...) like a
+// `KeyRevocation`, so a verifier key for 1 message suffices.
+//
+// An attestation is only as good as the verifier that signed it. Relying parties must pin the
+// verifier's verkey and check the timestamp, see `VerificationAttestation::check`.
+
+use crate::context::{verkey_id, VerkeyId};
+use crate::errors::PSError;
+use crate::hash_to_field::hash_to_field;
+use crate::keys::{Params, Verkey};
+use crate::policy::{CompiledPolicy, PolicyReport};
+use crate::presentation::Presentation;
+use crate::signature::{Fingerprint, Signature};
+use crate::signer::PsSigner;
+use crate::types::Message;
+use amcl_wrapper::field_elem::FieldElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+
+/// Tag of `hash_to_field` for the message of a `VerificationAttestation`
+pub const ATTESTATION_DST: &[u8] = b"PS-SIG-V01-CS01-VERIFICATION-ATTESTATION_XMD:SHA-256_";
+
+/// What a verifier attests
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttestationStatement {
+    /// `Presentation::fingerprint`
+    pub presentation: Fingerprint,
+    pub issuer: VerkeyId,
+    /// `CompiledPolicy::hash`
+    pub policy: Fingerprint,
+    pub accepted: bool,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+impl AttestationStatement {
+    /// Unambiguous encoding, all fields have a fixed size
+    pub fn to_canonical_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&self.presentation);
+        bytes.extend_from_slice(&self.issuer);
+        bytes.extend_from_slice(&self.policy);
+        bytes.push(self.accepted as u8);
+        bytes.extend_from_slice(&self.timestamp.to_be_bytes());
+        bytes
+    }
+
+    fn messages(&self, count: usize) -> Vec<FieldElement> {
+        let mut msgs = vec![FieldElement::zero(); count];
+        msgs[0] = hash_to_field(&self.to_canonical_bytes(), ATTESTATION_DST);
+        msgs
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VerificationAttestation {
+    pub statement: AttestationStatement,
+    pub sig: Signature,
+}
+
+impl VerificationAttestation {
+    /// Verify `presentation` under the issuer's verkey `vk`, evaluate `policy` and sign the outcome
+    /// with the verifier's `signer`. Rejected presentations are attested as well.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new<S: PsSigner + ?Sized>(
+        presentation: &Presentation,
+        vk: &Verkey,
+        params: &Params,
+        policy: &CompiledPolicy,
+        timestamp: u64,
+        signer: &S,
+        verifier_vk: &Verkey,
+    ) -> Result<(PolicyReport, Self), PSError> {
+        Self::new_with_rng(
+            presentation,
+            vk,
+            params,
+            policy,
+            timestamp,
+            signer,
+            verifier_vk,
+            &mut thread_rng(),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_rng<S: PsSigner + ?Sized, R: RngCore + CryptoRng>(
+        presentation: &Presentation,
+        vk: &Verkey,
+        params: &Params,
+        policy: &CompiledPolicy,
+        timestamp: u64,
+        signer: &S,
+        verifier_vk: &Verkey,
+        rng: &mut R,
+    ) -> Result<(PolicyReport, Self), PSError> {
+        if verifier_vk.Y_tilde.is_empty() || signer.msg_count() != verifier_vk.Y_tilde.len() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: verifier_vk.Y_tilde.len().max(1),
+                given: signer.msg_count(),
+            });
+        }
+        let proof_valid = presentation.verify(vk, params)?;
+        let violations = if proof_valid {
+            policy.evaluate(vk, &presentation.revealed_msgs)
+        } else {
+            vec![]
+        };
+        let report = PolicyReport {
+            proof_valid,
+            violations,
+        };
+        let statement = AttestationStatement {
+            presentation: presentation.fingerprint(),
+            issuer: verkey_id(vk),
+            policy: policy.hash(),
+            accepted: report.is_accepted(),
+            timestamp,
+        };
+        let msgs = statement.messages(verifier_vk.Y_tilde.len());
+        let sig = signer.sign_with_rng(&msgs, params, rng)?;
+        Ok((report, Self { statement, sig }))
+    }
+
+    /// Whether the attestation is signed by the verifier with `verifier_vk`
+    pub fn verify(&self, verifier_vk: &Verkey, params: &Params) -> Result<bool, PSError> {
+        if verifier_vk.Y_tilde.is_empty() {
+            return Ok(false);
+        }
+        let msgs = self.statement.messages(verifier_vk.Y_tilde.len());
+        self.sig
+            .verify(&Message::from_field_elements(&msgs), verifier_vk, params)
+    }
+
+    /// Whether the attestation is signed by the verifier, accepts a presentation checked against
+    /// `policy` and was made at most `max_age` seconds before `now`
+    pub fn check(
+        &self,
+        verifier_vk: &Verkey,
+        params: &Params,
+        policy: &CompiledPolicy,
+        now: u64,
+        max_age: u64,
+    ) -> Result<bool, PSError> {
+        let statement = &self.statement;
+        if !statement.accepted
+            || statement.policy != policy.hash()
+            || statement.timestamp > now
+            || now - statement.timestamp > max_age
+        {
+            return Ok(false);
+        }
+        self.verify(verifier_vk, params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use crate::policy::Policy;
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_verification_attestation() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let (verifier_sk, verifier_vk) = keygen(1, &params);
+        let msgs = FieldElementVector::random(3);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let revealed: HashSet<usize> = vec![1].into_iter().collect();
+        let presentation =
+            Presentation::new(&sig, &vk, &params, msgs.as_slice(), revealed, b"nonce").unwrap();
+        let names = ["name", "dob", "country"];
+        let policy = CompiledPolicy::compile(&[Policy::require_revealed("dob")], &names).unwrap();
+        let other_policy =
+            CompiledPolicy::compile(&[Policy::require_revealed("country")], &names).unwrap();
+        assert_ne!(policy.hash(), other_policy.hash());
+
+        let (report, attestation) = VerificationAttestation::new(
+            &presentation,
+            &vk,
+            &params,
+            &policy,
+            1000,
+            &verifier_sk,
+            &verifier_vk,
+        )
+        .unwrap();
+        assert!(report.is_accepted());
+        assert!(attestation.verify(&verifier_vk, &params).unwrap());
+        assert!(attestation
+            .check(&verifier_vk, &params, &policy, 1010, 60)
+            .unwrap());
+        assert!(!attestation
+            .check(&verifier_vk, &params, &policy, 1100, 60)
+            .unwrap());
+        assert!(!attestation
+            .check(&verifier_vk, &params, &other_policy, 1010, 60)
+            .unwrap());
+        let (_, impostor_vk) = keygen(1, &params);
+        assert!(!attestation.verify(&impostor_vk, &params).unwrap());
+
+        // The statement cannot be changed
+        let mut forged = attestation.clone();
+        forged.statement.timestamp = 1050;
+        assert!(!forged.verify(&verifier_vk, &params).unwrap());
+
+        // Rejections are attested too
+        let (report, attestation) = VerificationAttestation::new(
+            &presentation,
+            &vk,
+            &params,
+            &other_policy,
+            1000,
+            &verifier_sk,
+            &verifier_vk,
+        )
+        .unwrap();
+        assert!(!report.is_accepted());
+        assert!(!attestation.statement.accepted);
+        assert!(attestation.verify(&verifier_vk, &params).unwrap());
+        assert!(!attestation
+            .check(&verifier_vk, &params, &other_policy, 1010, 60)
+            .unwrap());
+        let mut forged = attestation.clone();
+        forged.statement.accepted = true;
+        assert!(!forged.verify(&verifier_vk, &params).unwrap());
+    }
+}
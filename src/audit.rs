@@ -0,0 +1,272 @@
+// Audit mode for verifying presentations. Verification can record a transcript of its inputs, the
+// challenge, the reconstructed J and the operands of the final pairing check so that a verification
+// decision can be re-checked offline, e.g. when a holder disputes a rejection. Intermediate values
+// are only recorded as hashes, the inputs are public data of the presentation and of the issuer.
+//
+// `replay_verify` verifies the recorded inputs again and fails if any recorded value differs from the
+// recomputed one, so a transcript cannot claim a result its inputs do not give.
+//
+// For processing records, `Presentation::verify_with_access_log` reports an `AttributeAccess` for each
+// revealed attribute of an accepted presentation, with the purpose the verifier requested it for.
+
+use crate::context::VerkeyId;
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::presentation::Presentation;
+use crate::types::Challenge;
+use crate::utils::{hash_with_domain, HASH_SIZE};
+use crate::validation::ValidationProfile;
+use amcl_wrapper::field_elem::FieldElement;
+use amcl_wrapper::group_elem::GroupElement;
+
+pub type TranscriptHash = [u8; HASH_SIZE];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VerificationTranscript {
+    pub presentation: Presentation,
+    pub verkey: Verkey,
+    pub params: Params,
+    pub profile: ValidationProfile,
+    pub challenge: Challenge,
+    /// Whether the checks before the pairing passed, see `Presentation::verify_fast_reject`
+    pub fast_checks_passed: bool,
+    /// Hash of J with the revealed messages added, absent if the cheap checks failed
+    pub J_commitment: Option<TranscriptHash>,
+    /// Hash of (sigma_1, J*X_tilde, sigma_2^-1, g_tilde), absent if the pairing was not computed
+    pub pairing_operands_commitment: Option<TranscriptHash>,
+    pub result: bool,
+}
+
+impl VerificationTranscript {
+    /// Verify `presentation` and record the transcript, the result is in `result`
+    pub fn record(
+        presentation: &Presentation,
+        vk: &Verkey,
+        params: &Params,
+        profile: ValidationProfile,
+    ) -> Result<Self, PSError> {
+        let proof = &presentation.proof;
+        let challenge = presentation.challenge(vk, params);
+        let mut transcript = Self {
+            presentation: presentation.clone(),
+            verkey: vk.clone(),
+            params: params.clone(),
+            profile,
+            challenge: challenge.clone(),
+            fast_checks_passed: false,
+            J_commitment: None,
+            pairing_operands_commitment: None,
+            result: false,
+        };
+        if !presentation.verify_fast_reject(vk, params)? {
+            return Ok(transcript);
+        }
+        transcript.fast_checks_passed = true;
+
+        let J = proof.full_J(vk, &presentation.revealed_msgs);
+        transcript.J_commitment = Some(hash_with_domain(b"PS audit J", &J.to_bytes()));
+        if !(profile.is_valid_point(&proof.sig.sigma_1)
+            && profile.is_valid_point(&proof.sig.sigma_2)
+            && profile.is_valid_point(&proof.J)
+            && profile.is_valid_point(&proof.proof_vc.commitment)
+            && profile.is_valid_verkey(vk))
+        {
+            return Ok(transcript);
+        }
+
+        let J_X = &J + &vk.X_tilde;
+        let sigma_2_inv = -&proof.sig.sigma_2;
+        let mut operands = vec![];
+        operands.append(&mut proof.sig.sigma_1.to_bytes());
+        operands.append(&mut J_X.to_bytes());
+        operands.append(&mut sigma_2_inv.to_bytes());
+        operands.append(&mut params.g_tilde.to_bytes());
+        transcript.pairing_operands_commitment =
+            Some(hash_with_domain(b"PS audit pairing operands", &operands));
+        transcript.result =
+            crate::ate_2_pairing(&proof.sig.sigma_1, &J_X, &sigma_2_inv, &params.g_tilde).is_one();
+        Ok(transcript)
+    }
+
+    /// Hash of the whole transcript to refer to it in dispute records
+    pub fn digest(&self) -> TranscriptHash {
+        let mut bytes = self.presentation.to_bytes();
+        bytes.append(&mut self.verkey.to_bytes());
+        bytes.append(&mut self.params.g.to_bytes());
+        bytes.append(&mut self.params.g_tilde.to_bytes());
+        bytes.push(self.profile as u8);
+        bytes.append(&mut self.challenge.as_field().to_bytes());
+        bytes.push(self.fast_checks_passed as u8);
+        for h in &[&self.J_commitment, &self.pairing_operands_commitment] {
+            match h {
+                Some(h) => {
+                    bytes.push(1);
+                    bytes.extend_from_slice(h);
+                }
+                None => bytes.push(0),
+            }
+        }
+        bytes.push(self.result as u8);
+        hash_with_domain(b"PS audit transcript", &bytes)
+    }
+}
+
+/// What the verifier requested the attributes for
+#[derive(Clone, Copy, Debug)]
+pub struct AccessContext<'a> {
+    /// `attribute_names[i]` is the name of the i-th message of the signature
+    pub attribute_names: &'a [&'a str],
+    pub purpose: &'a str,
+}
+
+/// A revealed attribute accepted by the verifier
+#[derive(Clone, Debug)]
+pub struct AttributeAccess<'a> {
+    pub index: usize,
+    pub attribute: &'a str,
+    pub value: &'a FieldElement,
+    /// Verkey of the issuer, see `context::verkey_id`
+    pub issuer: &'a VerkeyId,
+    pub purpose: &'a str,
+}
+
+/// Verify the inputs of `transcript` again and return the result. Fails if any recorded value, including
+/// the result, differs from the recomputed one.
+pub fn replay_verify(transcript: &VerificationTranscript) -> Result<bool, PSError> {
+    let replayed = VerificationTranscript::record(
+        &transcript.presentation,
+        &transcript.verkey,
+        &transcript.params,
+        transcript.profile,
+    )?;
+    let mismatch = if replayed.challenge != transcript.challenge {
+        Some("challenge")
+    } else if replayed.fast_checks_passed != transcript.fast_checks_passed {
+        Some("fast checks")
+    } else if replayed.J_commitment != transcript.J_commitment {
+        Some("J")
+    } else if replayed.pairing_operands_commitment != transcript.pairing_operands_commitment {
+        Some("pairing operands")
+    } else if replayed.result != transcript.result {
+        Some("result")
+    } else {
+        None
+    };
+    match mismatch {
+        Some(what) => Err(PSError::GeneralError {
+            msg: format!("Transcript does not replay, {} differs", what),
+        }),
+        None => Ok(replayed.result),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use crate::signature::Signature;
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_verification_transcript() {
+        let count_msgs = 5;
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(count_msgs, &params);
+        let msgs = FieldElementVector::random(count_msgs);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let mut revealed = HashSet::new();
+        revealed.insert(2);
+        let presentation =
+            Presentation::new(&sig, &vk, &params, msgs.as_slice(), revealed, b"nonce").unwrap();
+
+        let transcript = VerificationTranscript::record(
+            &presentation,
+            &vk,
+            &params,
+            ValidationProfile::default(),
+        )
+        .unwrap();
+        assert!(transcript.result);
+        assert!(transcript.pairing_operands_commitment.is_some());
+        assert!(replay_verify(&transcript).unwrap());
+
+        // Claimed result or intermediate values not matching the inputs
+        let mut transcript_2 = transcript.clone();
+        transcript_2.result = false;
+        assert!(replay_verify(&transcript_2).is_err());
+        assert_ne!(transcript_2.digest(), transcript.digest());
+        let mut transcript_3 = transcript.clone();
+        transcript_3.J_commitment = Some([0; HASH_SIZE]);
+        assert!(replay_verify(&transcript_3).is_err());
+
+        // Rejection is recorded and replays as well
+        let mut presentation_1 = presentation.clone();
+        presentation_1.nonce = b"other nonce".to_vec();
+        let transcript_4 = VerificationTranscript::record(
+            &presentation_1,
+            &vk,
+            &params,
+            ValidationProfile::default(),
+        )
+        .unwrap();
+        assert!(!transcript_4.result);
+        assert!(!transcript_4.fast_checks_passed);
+        assert!(transcript_4.J_commitment.is_none());
+        assert!(!replay_verify(&transcript_4).unwrap());
+    }
+
+    #[test]
+    fn test_attribute_access_log() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let msgs = FieldElementVector::random(3);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let revealed: HashSet<usize> = vec![0, 2].into_iter().collect();
+        let presentation =
+            Presentation::new(&sig, &vk, &params, msgs.as_slice(), revealed, b"nonce").unwrap();
+        let context = AccessContext {
+            attribute_names: &["name", "email", "age"],
+            purpose: "age check",
+        };
+
+        let mut log = vec![];
+        let valid = presentation
+            .verify_with_access_log(&vk, &params, &context, |a| {
+                assert_eq!(a.issuer, &crate::context::verkey_id(&vk));
+                log.push((
+                    a.attribute.to_string(),
+                    a.value.clone(),
+                    a.purpose.to_string(),
+                ))
+            })
+            .unwrap();
+        assert!(valid);
+        assert_eq!(
+            log,
+            vec![
+                ("name".to_string(), msgs[0].clone(), "age check".to_string()),
+                ("age".to_string(), msgs[2].clone(), "age check".to_string())
+            ]
+        );
+
+        // Nothing is logged for a rejected presentation
+        let mut rejected = presentation.clone();
+        rejected.nonce = b"other".to_vec();
+        let mut count = 0;
+        assert!(!rejected
+            .verify_with_access_log(&vk, &params, &context, |_| count += 1)
+            .unwrap());
+        assert_eq!(count, 0);
+        let unnamed = AccessContext {
+            attribute_names: &["name"],
+            purpose: "age check",
+        };
+        assert!(presentation
+            .verify_with_access_log(&vk, &params, &unnamed, |_| ())
+            .is_err());
+    }
+}
@@ -0,0 +1,54 @@
+// Runs the conformance cases in the `.json` files of a directory, each file holding one case or an
+// array of cases, and prints the report as JSON. Exits with 1 if any case failed and 2 on usage
+// or IO errors.
+//
+//     cargo run --features conformance --bin conformance -- <directory>
+
+use ps_sig::conformance::{run_cases, ConformanceCase};
+use std::path::Path;
+use std::process::exit;
+
+fn read_cases(dir: &Path) -> Result<Vec<ConformanceCase>, String> {
+    let mut paths = std::fs::read_dir(dir)
+        .map_err(|e| format!("Cannot read {}: {}", dir.display(), e))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|x| x == "json"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    let mut cases = vec![];
+    for path in paths {
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        let value: serde_json::Value = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid JSON in {}: {}", path.display(), e))?;
+        let parsed = if value.is_array() {
+            serde_json::from_value(value)
+        } else {
+            serde_json::from_value(value).map(|c| vec![c])
+        };
+        cases.extend(parsed.map_err(|e| format!("Invalid case in {}: {}", path.display(), e))?);
+    }
+    Ok(cases)
+}
+
+fn main() {
+    let dir = match std::env::args().nth(1) {
+        Some(dir) => dir,
+        None => {
+            eprintln!("Usage: conformance <directory>");
+            exit(2);
+        }
+    };
+    let cases = match read_cases(Path::new(&dir)) {
+        Ok(cases) => cases,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(2);
+        }
+    };
+    let report = run_cases(&cases);
+    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    if !report.all_passed() {
+        exit(1);
+    }
+}
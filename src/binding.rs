@@ -0,0 +1,366 @@
+// Binding credentials to a holder secret derived from a biometric or a device key so that a credential
+// cannot be handed over to another holder. The secret is registered once with the issuer as g^s. During
+// blind issuance the holder proves that the first committed message is a registered secret, and since
+// every presentation must keep that message hidden, creating a presentation needs knowledge of the
+// secret. Sharing a credential therefore means sharing the device or biometric secret as well.
+
+use crate::blind_signature::{BlindingKey, ProofSignatureGroup, ProverCommittingSignatureGroup};
+use crate::errors::PSError;
+use crate::hash_to_field::hash_to_field;
+use crate::keys::{Params, Verkey};
+use crate::pok_sig::{PoKOfSignature, PoKOfSignatureProof};
+use crate::signature::Signature;
+use crate::types::{Challenge, Message};
+use crate::validation::deserialize_point;
+use crate::{SignatureGroup, SignatureGroupVec};
+use amcl_wrapper::field_elem::{FieldElement, FieldElementVector};
+use amcl_wrapper::group_elem::GroupElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::{HashMap, HashSet};
+
+/// Tag of `hash_to_field` for `BindingSecret::from_material`
+pub const BINDING_SECRET_DST: &[u8] = b"PS-SIG-V01-CS01-BINDING-SECRET_XMD:SHA-256_";
+
+/// Index of the binding secret among the messages of a bound credential
+pub const BINDING_MSG_IDX: usize = 0;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BindingSecret {
+    pub s: FieldElement,
+}
+
+impl BindingSecret {
+    /// Derive the secret from device or biometric key material
+    pub fn from_material(material: &[u8]) -> Self {
+        Self {
+            s: hash_to_field(material, BINDING_SECRET_DST),
+        }
+    }
+
+    /// Value registered with the issuer, g^s
+    pub fn registration(&self, params: &Params) -> SignatureGroup {
+        &params.g * &self.s
+    }
+}
+
+/// Registrations of binding secrets known to the issuer
+pub trait BindingRegistry {
+    fn is_registered(&self, registration: &SignatureGroup) -> bool;
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct InMemoryBindingRegistry {
+    registrations: Vec<SignatureGroup>,
+}
+
+impl BindingRegistry for InMemoryBindingRegistry {
+    fn is_registered(&self, registration: &SignatureGroup) -> bool {
+        self.registrations.contains(registration)
+    }
+}
+
+impl InMemoryBindingRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A secret can be registered only once
+    pub fn register(&mut self, registration: SignatureGroup) -> Result<(), PSError> {
+        if registration.is_identity() || self.is_registered(&registration) {
+            return Err(PSError::GeneralError {
+                msg: String::from("Binding secret is invalid or already registered"),
+            });
+        }
+        self.registrations.push(registration);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.registrations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.registrations.is_empty()
+    }
+}
+
+/// Request for a blind signature where the first committed message is the binding secret. Contains a
+/// proof of knowledge of the opening of `commitment` and of the discrete log of `registration` with
+/// the same response for the binding secret.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BoundCredentialRequest {
+    #[serde(deserialize_with = "deserialize_point")]
+    pub commitment: SignatureGroup,
+    #[serde(deserialize_with = "deserialize_point")]
+    pub registration: SignatureGroup,
+    pub proof_commitment: ProofSignatureGroup,
+    pub proof_registration: ProofSignatureGroup,
+}
+
+impl BoundCredentialRequest {
+    /// `committed_messages` are the messages committed after the binding secret, the commitment is
+    /// Y_1^s * Y_2^committed_messages[0] * ... * g^blinding
+    pub fn new(
+        secret: &BindingSecret,
+        committed_messages: &[FieldElement],
+        blinding: &FieldElement,
+        blinding_key: &BlindingKey,
+        params: &Params,
+    ) -> Result<Self, PSError> {
+        Self::new_with_rng(
+            secret,
+            committed_messages,
+            blinding,
+            blinding_key,
+            params,
+            &mut thread_rng(),
+        )
+    }
+
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        secret: &BindingSecret,
+        committed_messages: &[FieldElement],
+        blinding: &FieldElement,
+        blinding_key: &BlindingKey,
+        params: &Params,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        if committed_messages.len() >= blinding_key.msg_count() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: blinding_key.msg_count().saturating_sub(1),
+                given: committed_messages.len(),
+            });
+        }
+        let mut secrets = vec![secret.s.clone()];
+        secrets.extend_from_slice(committed_messages);
+        secrets.push(blinding.clone());
+        let bases = commitment_bases(committed_messages.len(), blinding_key, params);
+
+        let commitment = SignatureGroupVec::from(bases.as_slice())
+            .multi_scalar_mul_const_time(&FieldElementVector::from(secrets.as_slice()))
+            .unwrap();
+        let registration = secret.registration(params);
+
+        let mut committing_comm = ProverCommittingSignatureGroup::new();
+        let mut committing_reg = ProverCommittingSignatureGroup::new();
+        let r_s = FieldElement::random_using_rng(rng);
+        committing_reg.commit(&params.g, Some(&r_s));
+        committing_comm.commit(&bases[0], Some(&r_s));
+        for b in &bases[1..] {
+            committing_comm.commit(b, Some(&FieldElement::random_using_rng(rng)));
+        }
+        let committed_comm = committing_comm.finish();
+        let committed_reg = committing_reg.finish();
+
+        let mut extra = committed_reg.to_bytes();
+        extra.append(&mut commitment.to_bytes());
+        extra.append(&mut registration.to_bytes());
+        let challenge = committed_comm.gen_challenge(extra);
+
+        let proof_commitment = committed_comm.gen_proof(&challenge, &secrets)?;
+        let proof_registration =
+            committed_reg.gen_proof(&challenge, std::slice::from_ref(&secret.s))?;
+        Ok(Self {
+            commitment,
+            registration,
+            proof_commitment,
+            proof_registration,
+        })
+    }
+
+    /// Number of committed messages including the binding secret
+    pub fn committed_msg_count(&self) -> usize {
+        self.proof_commitment.responses.len().saturating_sub(1)
+    }
+
+    /// Issuer side check before blind signing `self.commitment`. The binding secret must be
+    /// registered and be the first message in the commitment.
+    pub fn verify<B: BindingRegistry>(
+        &self,
+        blinding_key: &BlindingKey,
+        params: &Params,
+        registry: &B,
+    ) -> Result<bool, PSError> {
+        if !registry.is_registered(&self.registration) {
+            return Ok(false);
+        }
+        let count_committed = self.committed_msg_count();
+        if count_committed == 0 || count_committed > blinding_key.msg_count() {
+            return Ok(false);
+        }
+        if self.proof_registration.responses.len() != 1
+            || self.proof_registration.responses[0] != self.proof_commitment.responses[0]
+        {
+            return Ok(false);
+        }
+        let bases = commitment_bases(count_committed - 1, blinding_key, params);
+
+        // Same bytes as `ProverCommitted::to_bytes` of both proofs
+        let mut bytes = vec![];
+        for b in &bases {
+            bytes.append(&mut b.to_bytes());
+        }
+        bytes.append(&mut self.proof_commitment.commitment.to_bytes());
+        bytes.append(&mut params.g.to_bytes());
+        bytes.append(&mut self.proof_registration.commitment.to_bytes());
+        bytes.append(&mut self.commitment.to_bytes());
+        bytes.append(&mut self.registration.to_bytes());
+        let challenge = Challenge::from_msg_hash(&bytes);
+
+        Ok(self
+            .proof_commitment
+            .verify(&bases, &self.commitment, &challenge)?
+            && self.proof_registration.verify(
+                std::slice::from_ref(&params.g),
+                &self.registration,
+                &challenge,
+            )?)
+    }
+}
+
+/// Y_1, Y_2, ..., Y_{count+1}, g
+fn commitment_bases(
+    count_committed_msgs: usize,
+    blinding_key: &BlindingKey,
+    params: &Params,
+) -> Vec<SignatureGroup> {
+    let mut bases = blinding_key.Y[..=count_committed_msgs].to_vec();
+    bases.push(params.g.clone());
+    bases
+}
+
+/// Start a proof of knowledge of a bound credential. Fails if the binding secret is to be revealed.
+pub fn init_bound_pok(
+    sig: &Signature,
+    vk: &Verkey,
+    params: &Params,
+    messages: &[FieldElement],
+    revealed_msg_indices: HashSet<usize>,
+) -> Result<PoKOfSignature, PSError> {
+    if revealed_msg_indices.contains(&BINDING_MSG_IDX) {
+        return Err(PSError::GeneralError {
+            msg: String::from("Binding secret cannot be revealed"),
+        });
+    }
+    PoKOfSignature::init(
+        sig,
+        vk,
+        params,
+        &Message::from_field_elements(messages),
+        None,
+        revealed_msg_indices,
+    )
+}
+
+/// Verify a proof of knowledge of a bound credential. Rejects proofs revealing the binding secret.
+pub fn verify_bound_pok(
+    proof: &PoKOfSignatureProof,
+    vk: &Verkey,
+    params: &Params,
+    revealed_msgs: HashMap<usize, FieldElement>,
+    challenge: &Challenge,
+) -> Result<bool, PSError> {
+    if revealed_msgs.contains_key(&BINDING_MSG_IDX) {
+        return Ok(false);
+    }
+    proof.verify(vk, params, revealed_msgs, challenge)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blind_signature::BlindSignature;
+    use crate::keys::keygen;
+    use crate::types::Blinding;
+
+    #[test]
+    fn test_bound_credential() {
+        let count_msgs = 4;
+        let count_committed = 2;
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(count_msgs, &params);
+        let blinding_key = BlindingKey::new(&sk, &params);
+
+        let secret = BindingSecret::from_material(b"device key");
+        let mut registry = InMemoryBindingRegistry::new();
+        registry.register(secret.registration(&params)).unwrap();
+        assert!(registry.register(secret.registration(&params)).is_err());
+
+        let msgs = FieldElementVector::random(count_msgs - 1);
+        let blinding = FieldElement::random();
+        let request = BoundCredentialRequest::new(
+            &secret,
+            &msgs.as_slice()[..count_committed - 1],
+            &blinding,
+            &blinding_key,
+            &params,
+        )
+        .unwrap();
+        assert_eq!(request.committed_msg_count(), count_committed);
+        assert!(request.verify(&blinding_key, &params, &registry).unwrap());
+
+        // Unregistered secret
+        let other = BindingSecret::from_material(b"other device");
+        let request_1 = BoundCredentialRequest::new(
+            &other,
+            &msgs.as_slice()[..count_committed - 1],
+            &blinding,
+            &blinding_key,
+            &params,
+        )
+        .unwrap();
+        assert!(!request_1.verify(&blinding_key, &params, &registry).unwrap());
+
+        // Registration of a different secret than the committed one
+        let mut request_2 = request_1.clone();
+        request_2.registration = request.registration.clone();
+        request_2.proof_registration = request.proof_registration.clone();
+        assert!(!request_2.verify(&blinding_key, &params, &registry).unwrap());
+
+        let sig_blinded = BlindSignature::new(
+            &request.commitment,
+            &Message::from_field_elements(&msgs.as_slice()[count_committed - 1..]),
+            &sk,
+            &blinding_key,
+            &params,
+        )
+        .unwrap();
+        let sig = BlindSignature::unblind(&sig_blinded, &Blinding(blinding.clone()));
+        let mut all_msgs = vec![secret.s.clone()];
+        all_msgs.extend_from_slice(msgs.as_slice());
+        assert!(sig
+            .verify(&Message::from_field_elements(&all_msgs), &vk, &params)
+            .unwrap());
+
+        let mut revealed_indices = HashSet::new();
+        revealed_indices.insert(2);
+        let pok = init_bound_pok(&sig, &vk, &params, &all_msgs, revealed_indices.clone()).unwrap();
+        let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
+        let proof = pok.gen_proof(&chal).unwrap();
+        let mut revealed_msgs = HashMap::new();
+        revealed_msgs.insert(2, all_msgs[2].clone());
+        assert!(verify_bound_pok(&proof, &vk, &params, revealed_msgs, &chal).unwrap());
+
+        // Binding secret must stay hidden
+        revealed_indices.insert(BINDING_MSG_IDX);
+        assert!(init_bound_pok(&sig, &vk, &params, &all_msgs, revealed_indices.clone()).is_err());
+        let pok = PoKOfSignature::init(
+            &sig,
+            &vk,
+            &params,
+            &Message::from_field_elements(&all_msgs),
+            None,
+            revealed_indices,
+        )
+        .unwrap();
+        let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
+        let proof = pok.gen_proof(&chal).unwrap();
+        let mut revealed_msgs = HashMap::new();
+        revealed_msgs.insert(2, all_msgs[2].clone());
+        revealed_msgs.insert(BINDING_MSG_IDX, all_msgs[BINDING_MSG_IDX].clone());
+        assert!(proof
+            .verify(&vk, &params, revealed_msgs.clone(), &chal)
+            .unwrap());
+        assert!(!verify_bound_pok(&proof, &vk, &params, revealed_msgs, &chal).unwrap());
+    }
+}
@@ -1,28 +1,31 @@
 // Scheme defined in section 6.1 supporting blind signatures
 
 use crate::errors::PSError;
-use crate::keys::{Params, Sigkey, Verkey};
+use crate::keys::{Params, Sigkey};
 use crate::signature::Signature;
+use crate::types::{Blinding, Message};
+use crate::validation::{deserialize_point, deserialize_points};
 use amcl_wrapper::field_elem::{FieldElement, FieldElementVector};
 use amcl_wrapper::group_elem::{GroupElement, GroupElementVector};
-use crate::{ate_2_pairing, OtherGroup, OtherGroupVec, SignatureGroup, SignatureGroupVec};
+use crate::{SignatureGroup, SignatureGroupVec};
+use rand::{thread_rng, CryptoRng, RngCore};
 
 // The public key described in the paper is split into `BlindingKey` and `Verkey`. Only `Verkey` is
 // needed by the verifier. `BlindingKey` is used by the user to request a blind signature.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct BlindingKey {
+    #[serde(deserialize_with = "deserialize_point")]
     pub X: SignatureGroup,
+    #[serde(deserialize_with = "deserialize_points")]
     pub Y: Vec<SignatureGroup>,
 }
 
 impl BlindingKey {
     pub fn new(sig_key: &Sigkey, params: &Params) -> Self {
-        let X = &params.g * &sig_key.x;
-        let mut Y = vec![];
-        for i in 0..sig_key.y.len() {
-            Y.push(&params.g * &sig_key.y[i]);
+        Self {
+            X: sig_key.X(params),
+            Y: sig_key.Y(params),
         }
-        Self { X, Y }
     }
 
     pub fn msg_count(&self) -> usize {
@@ -31,6 +34,15 @@ impl BlindingKey {
 }
 
 // Implement proof of knowledge of committed values in a vector commitment for `SignatureGroup`
+// X and Y let anyone knowing a commitment's opening forge the part of a signature it covers
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for BlindingKey {
+    fn zeroize(&mut self) {
+        self.X.zeroize();
+        self.Y.iter_mut().zeroize();
+    }
+}
+
 impl_PoK_VC!(
     ProverCommittingSignatureGroup,
     ProverCommittedSignatureGroup,
@@ -52,37 +64,100 @@ impl BlindSignature {
     /// (XC)^u.Y_2^u.Y_3^u...Y_10^u can be computed using efficient multi-exponentiation techniques but it would be more efficient 
     /// if the signer could instead compute (g^u, C^u.g^{(x+y_2.m_2+y_3.m_3+...y_10.m_10).u}). The resulting signature will have the same form
     /// and can be unblinded in the same way as described in the paper.
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(
         commitment: &SignatureGroup,
-        messages: &[FieldElement],
+        messages: &[Message],
+        sigkey: &Sigkey,
+        blinding_key: &BlindingKey,
+        params: &Params,
+    ) -> Result<Signature, PSError> {
+        Self::new_with_rng(
+            commitment,
+            messages,
+            sigkey,
+            blinding_key,
+            params,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Same as `new` but `u` comes from `rng`
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        commitment: &SignatureGroup,
+        messages: &[Message],
         sigkey: &Sigkey,
         blinding_key: &BlindingKey,
         params: &Params,
+        rng: &mut R,
+    ) -> Result<Signature, PSError> {
+        Self::sign_with_rng(
+            commitment,
+            &Message::to_field_elements(messages),
+            sigkey,
+            blinding_key.msg_count(),
+            params,
+            rng,
+        )
+    }
+
+    /// Body of `new_with_rng` for a key over `msg_count` messages, also used by `PsSigner::blind_sign_with_rng`
+    /// of `Sigkey` which has no blinding key
+    pub(crate) fn sign_with_rng<R: RngCore + CryptoRng>(
+        commitment: &SignatureGroup,
+        messages: &[FieldElement],
+        sigkey: &Sigkey,
+        msg_count: usize,
+        params: &Params,
+        rng: &mut R,
     ) -> Result<Signature, PSError> {
         // There should be commitment to at least one message
-        Self::check_blinding_key_and_messages_compat(messages, blinding_key)?;
+        if messages.len() >= msg_count {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: messages.len(),
+                given: msg_count,
+            });
+        }
 
-        let u = FieldElement::random();
-        let offset = blinding_key.msg_count() - messages.len();
+        let u = FieldElement::random_using_rng(rng);
+        let offset = msg_count - messages.len();
         let (sigma_1, mut sigma_2) = Signature::sign_with_sigma_1_generated_from_given_exp(
             messages, sigkey, &u, offset, &params.g,
         )?;
-        sigma_2 += (commitment * &u);
+        sigma_2 += commitment * &u;
         Ok(Signature { sigma_1, sigma_2 })
     }
 
     /// Scheme as described in the paper
     pub fn new_from_paper(
         commitment: &SignatureGroup,
-        messages: &[FieldElement],
+        messages: &[Message],
         sigkey_X: &SignatureGroup,  // The signing key consists of a single group element
         blinding_key: &BlindingKey,
         params: &Params,
+    ) -> Result<Signature, PSError> {
+        Self::new_from_paper_with_rng(
+            commitment,
+            messages,
+            sigkey_X,
+            blinding_key,
+            params,
+            &mut thread_rng(),
+        )
+    }
+
+    pub fn new_from_paper_with_rng<R: RngCore + CryptoRng>(
+        commitment: &SignatureGroup,
+        messages: &[Message],
+        sigkey_X: &SignatureGroup,
+        blinding_key: &BlindingKey,
+        params: &Params,
+        rng: &mut R,
     ) -> Result<Signature, PSError> {
         // There should be commitment to at least one message
         Self::check_blinding_key_and_messages_compat(messages, blinding_key)?;
 
-        let u = FieldElement::random();
+        let u = FieldElement::random_using_rng(rng);
 
         // sigma_1 = g^u
         let sigma_1 = &params.g * &u;
@@ -91,9 +166,9 @@ impl BlindSignature {
         let mut points = SignatureGroupVec::with_capacity(messages.len());
         let mut scalars = FieldElementVector::with_capacity(messages.len());
         let offset = blinding_key.msg_count() - messages.len();
-        for i in 0..messages.len() {
-            scalars.push(messages[i].clone());
-            points.push(blinding_key.Y[offset + i].clone());
+        for (Y_i, m) in blinding_key.Y[offset..].iter().zip(messages) {
+            scalars.push(m.as_field().clone());
+            points.push(Y_i.clone());
         }
 
         let mut sigma_2 = sigkey_X + &points.multi_scalar_mul_const_time(&scalars).unwrap();
@@ -104,15 +179,15 @@ impl BlindSignature {
 
     /// Once signature on committed attributes (blind signature) is received, the signature needs to be unblinded.
     /// Takes the blinding used in the commitment.
-    pub fn unblind(sig: &Signature, blinding: &FieldElement) -> Signature {
+    pub fn unblind(sig: &Signature, blinding: &Blinding) -> Signature {
         let sigma_1 = sig.sigma_1.clone();
-        let sigma_1_t = &sigma_1 * blinding;
+        let sigma_1_t = &sigma_1 * &blinding.0;
         let sigma_2 = &sig.sigma_2 - sigma_1_t;
         Signature { sigma_1, sigma_2 }
     }
 
-    pub fn check_blinding_key_and_messages_compat(
-        messages: &[FieldElement],
+    pub fn check_blinding_key_and_messages_compat<M>(
+        messages: &[M],
         blinding_key: &BlindingKey,
     ) -> Result<(), PSError> {
         if messages.len() >= blinding_key.msg_count() {
@@ -138,7 +213,7 @@ mod tests {
     fn test_blinding_key() {
         let count_msgs = 5;
         let params = Params::new("test".as_bytes());
-        let (sk, vk) = keygen(count_msgs, &params);
+        let (sk, _) = keygen(count_msgs, &params);
         let blinding_key = BlindingKey::new(&sk, &params);
         assert_eq!(blinding_key.msg_count(), count_msgs);
     }
@@ -159,8 +234,8 @@ mod tests {
             let comm = (&blinding_key.Y[0] * &msg) + (&params.g * &blinding);
 
             let sig_blinded = BlindSignature::new(&comm, &[], &sk, &blinding_key, &params).unwrap();
-            let sig_unblinded = BlindSignature::unblind(&sig_blinded, &blinding);
-            assert!(sig_unblinded.verify(&[msg], &vk, &params).unwrap());
+            let sig_unblinded = BlindSignature::unblind(&sig_blinded, &Blinding(blinding.clone()));
+            assert!(sig_unblinded.verify(&[Message(msg)], &vk, &params).unwrap());
         }
     }
 
@@ -178,12 +253,13 @@ mod tests {
             // XXX: In production always use multi-scalar multiplication
             let mut comm = SignatureGroup::new();
             for i in 0..count_msgs {
-                comm += (&blinding_key.Y[i] * &msgs[i]);
+                comm += &blinding_key.Y[i] * &msgs[i];
             }
-            comm += (&params.g * &blinding);
+            comm += &params.g * &blinding;
             let sig_blinded = BlindSignature::new(&comm, &[], &sk, &blinding_key, &params).unwrap();
-            let sig_unblinded = BlindSignature::unblind(&sig_blinded, &blinding);
-            assert!(sig_unblinded.verify(msgs.as_slice(), &vk, &params).unwrap());
+            let sig_unblinded = BlindSignature::unblind(&sig_blinded, &Blinding(blinding.clone()));
+            let messages = Message::from_field_elements(msgs.as_slice());
+            assert!(sig_unblinded.verify(&messages, &vk, &params).unwrap());
         }
     }
 
@@ -202,20 +278,21 @@ mod tests {
             // XXX: In production always use multi-scalar multiplication
             let mut comm = SignatureGroup::new();
             for i in 0..count_blinded_msgs {
-                comm += (&blinding_key.Y[i] * &msgs[i]);
+                comm += &blinding_key.Y[i] * &msgs[i];
             }
-            comm += (&params.g * &blinding);
+            comm += &params.g * &blinding;
 
+            let messages = Message::from_field_elements(msgs.as_slice());
             let sig_blinded = BlindSignature::new(
                 &comm,
-                &msgs.as_slice()[count_blinded_msgs..count_msgs],
+                &messages[count_blinded_msgs..count_msgs],
                 &sk,
                 &blinding_key,
                 &params,
             )
             .unwrap();
-            let sig_unblinded = BlindSignature::unblind(&sig_blinded, &blinding);
-            assert!(sig_unblinded.verify(msgs.as_slice(), &vk, &params).unwrap());
+            let sig_unblinded = BlindSignature::unblind(&sig_blinded, &Blinding(blinding.clone()));
+            assert!(sig_unblinded.verify(&messages, &vk, &params).unwrap());
         }
     }
 
@@ -235,9 +312,9 @@ mod tests {
         // XXX: In production always use multi-scalar multiplication
         let mut comm = SignatureGroup::new();
         for i in 0..count_blinded_msgs {
-            comm += (&blinding_key.Y[i] * &msgs[i]);
+            comm += &blinding_key.Y[i] * &msgs[i];
         }
-        comm += (&params.g * &blinding);
+        comm += &params.g * &blinding;
 
         // User and signer engage in a proof of knowledge for the above commitment `comm`
         let mut bases = Vec::<SignatureGroup>::new();
@@ -264,27 +341,29 @@ mod tests {
         // Signer verifies the proof of knowledge.
         assert!(proof.verify(bases.as_slice(), &comm, &chal).unwrap());
 
+        let messages = Message::from_field_elements(msgs.as_slice());
         let sig_blinded = BlindSignature::new(
             &comm,
-            &msgs.as_slice()[count_blinded_msgs..count_msgs],
+            &messages[count_blinded_msgs..count_msgs],
             &sk,
             &blinding_key,
             &params
         )
             .unwrap();
-        let sig_unblinded = BlindSignature::unblind(&sig_blinded, &blinding);
-        assert!(sig_unblinded.verify(msgs.as_slice(), &vk, &params).unwrap());
+        let sig_unblinded = BlindSignature::unblind(&sig_blinded, &Blinding(blinding.clone()));
+        assert!(sig_unblinded.verify(&messages, &vk, &params).unwrap());
 
         let sig_blinded_paper = BlindSignature::new_from_paper(
             &comm,
-            &msgs.as_slice()[count_blinded_msgs..count_msgs],
+            &messages[count_blinded_msgs..count_msgs],
             &sk_X,
             &blinding_key,
             &params
         )
             .unwrap();
-        let sig_unblinded_paper = BlindSignature::unblind(&sig_blinded_paper, &blinding);
-        assert!(sig_unblinded_paper.verify(msgs.as_slice(), &vk, &params).unwrap());
+        let sig_unblinded_paper =
+            BlindSignature::unblind(&sig_blinded_paper, &Blinding(blinding.clone()));
+        assert!(sig_unblinded_paper.verify(&messages, &vk, &params).unwrap());
     }
 
     #[test]
@@ -307,14 +386,15 @@ mod tests {
             // XXX: In production always use multi-scalar multiplication
             let mut comm = SignatureGroup::new();
             for i in 0..count_blinded_msgs {
-                comm += (&blinding_key.Y[i] * &msgs[i]);
+                comm += &blinding_key.Y[i] * &msgs[i];
             }
-            comm += (&params.g * &blinding);
+            comm += &params.g * &blinding;
 
             let start = Instant::now();
+            let messages = Message::from_field_elements(msgs.as_slice());
             let sig_blinded = BlindSignature::new(
                 &comm,
-                &msgs.as_slice()[count_blinded_msgs..count_msgs],
+                &messages[count_blinded_msgs..count_msgs],
                 &sk,
                 &blinding_key,
                 &params
@@ -323,8 +403,8 @@ mod tests {
             total_signing += start.elapsed();
 
             let start = Instant::now();
-            let sig_unblinded = BlindSignature::unblind(&sig_blinded, &blinding);
-            assert!(sig_unblinded.verify(msgs.as_slice(), &vk, &params).unwrap());
+            let sig_unblinded = BlindSignature::unblind(&sig_blinded, &Blinding(blinding.clone()));
+            assert!(sig_unblinded.verify(&messages, &vk, &params).unwrap());
             total_verifying += start.elapsed();
         }
 
@@ -359,14 +439,15 @@ mod tests {
             // XXX: In production always use multi-scalar multiplication
             let mut comm = SignatureGroup::new();
             for i in 0..count_blinded_msgs {
-                comm += (&blinding_key.Y[i] * &msgs[i]);
+                comm += &blinding_key.Y[i] * &msgs[i];
             }
-            comm += (&params.g * &blinding);
+            comm += &params.g * &blinding;
 
             let start = Instant::now();
+            let messages = Message::from_field_elements(msgs.as_slice());
             let sig_blinded = BlindSignature::new(
                 &comm,
-                &msgs.as_slice()[count_blinded_msgs..count_msgs],
+                &messages[count_blinded_msgs..count_msgs],
                 &sk,
                 &blinding_key,
                 &params
@@ -377,7 +458,7 @@ mod tests {
             let start = Instant::now();
             let sig_blinded_paper = BlindSignature::new_from_paper(
                 &comm,
-                &msgs.as_slice()[count_blinded_msgs..count_msgs],
+                &messages[count_blinded_msgs..count_msgs],
                 &sk_X,
                 &blinding_key,
                 &params
@@ -386,12 +467,13 @@ mod tests {
             total_signing_paper += start.elapsed();
 
             let start = Instant::now();
-            let sig_unblinded = BlindSignature::unblind(&sig_blinded, &blinding);
-            assert!(sig_unblinded.verify(msgs.as_slice(), &vk, &params).unwrap());
+            let sig_unblinded = BlindSignature::unblind(&sig_blinded, &Blinding(blinding.clone()));
+            assert!(sig_unblinded.verify(&messages, &vk, &params).unwrap());
             total_verifying += start.elapsed();
 
-            let sig_unblinded_paper = BlindSignature::unblind(&sig_blinded_paper, &blinding);
-            assert!(sig_unblinded_paper.verify(msgs.as_slice(), &vk, &params).unwrap());
+            let sig_unblinded_paper =
+                BlindSignature::unblind(&sig_blinded_paper, &Blinding(blinding.clone()));
+            assert!(sig_unblinded_paper.verify(&messages, &vk, &params).unwrap());
         }
 
         println!(
@@ -0,0 +1,502 @@
+// Boolean flags kept hidden in a presentation. For a hidden message b the prover commits to it as
+// C = g^b * h^r, proves knowledge of the opening with the blinding of b shared with the proof of
+// knowledge of the signature, and proves with an OR-proof that C commits to 0 or 1: it knows log_h of
+// either C or C/g, the branch it cannot prove is simulated and the challenges of both branches add up
+// to the challenge of the presentation. Proving that the flag is 1 needs only the second branch. This
+// costs a few scalar multiplications, far less than a range proof. Policies ask for flags with
+// `Predicate::IsBit` and `Predicate::IsTrue`, see `CompiledPolicy::evaluate_with_flags`.
+
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::pok_sig::{PoKOfSignature, PoKOfSignatureProof};
+use crate::policy::{CompiledPolicy, PolicyReport, Predicate};
+use crate::signature::Signature;
+use crate::types::{Challenge, Message};
+use crate::validation::{deserialize_point, deserialize_points};
+use crate::SignatureGroup;
+use amcl_wrapper::field_elem::FieldElement;
+use amcl_wrapper::group_elem::GroupElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::{HashMap, HashSet};
+
+const DOMAIN: &[u8] = b"PS boolean flag : h";
+
+/// What is proven about a hidden flag
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FlagStatement {
+    /// The flag is 0 or 1
+    IsBit,
+    /// The flag is 1
+    IsTrue,
+}
+
+impl FlagStatement {
+    fn to_byte(self) -> u8 {
+        match self {
+            FlagStatement::IsBit => 0,
+            FlagStatement::IsTrue => 1,
+        }
+    }
+
+    /// Whether a proof of this statement satisfies `predicate` for a hidden message
+    pub fn proves(self, predicate: &Predicate) -> bool {
+        match predicate {
+            Predicate::IsBit => true,
+            Predicate::IsTrue => self == FlagStatement::IsTrue,
+            _ => false,
+        }
+    }
+}
+
+/// Second base of the flag commitments, nobody knows its discrete log to g
+fn commitment_base(params: &Params) -> Result<SignatureGroup, PSError> {
+    params.hash_to_signature_group(DOMAIN)
+}
+
+/// Public elements of a flag proof that go into the challenge
+fn challenge_bytes(
+    msg_idx: usize,
+    statement: FlagStatement,
+    commitment: &SignatureGroup,
+    opening_commitment: &SignatureGroup,
+    branch_commitments: &[SignatureGroup],
+) -> Vec<u8> {
+    let mut bytes = (msg_idx as u64).to_be_bytes().to_vec();
+    bytes.push(statement.to_byte());
+    bytes.append(&mut commitment.to_bytes());
+    bytes.append(&mut opening_commitment.to_bytes());
+    for b in branch_commitments {
+        bytes.append(&mut b.to_bytes());
+    }
+    bytes
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FlagProof {
+    pub msg_idx: usize,
+    pub statement: FlagStatement,
+    /// g^b * h^r
+    #[serde(deserialize_with = "deserialize_point")]
+    pub commitment: SignatureGroup,
+    /// Schnorr commitment g^blinding_b * h^blinding_r for the opening
+    #[serde(deserialize_with = "deserialize_point")]
+    pub opening_commitment: SignatureGroup,
+    pub opening_response: FieldElement,
+    /// One branch for `IsTrue`, the branches for 0 and 1 for `IsBit`
+    #[serde(deserialize_with = "deserialize_points")]
+    pub branch_commitments: Vec<SignatureGroup>,
+    pub branch_challenges: Vec<FieldElement>,
+    pub branch_responses: Vec<FieldElement>,
+}
+
+/// Prover state for a flag until the challenge is known
+struct FlagProver {
+    msg_idx: usize,
+    statement: FlagStatement,
+    bit: bool,
+    r: FieldElement,
+    commitment: SignatureGroup,
+    opening_commitment: SignatureGroup,
+    opening_blinding: FieldElement,
+    /// Blinding of the real branch
+    branch_blinding: FieldElement,
+    branch_commitments: Vec<SignatureGroup>,
+    /// Challenge and response of the simulated branch
+    simulated: Option<(FieldElement, FieldElement)>,
+}
+
+impl FlagProver {
+    fn new<R: RngCore + CryptoRng>(
+        msg_idx: usize,
+        statement: FlagStatement,
+        flag: &FieldElement,
+        msg_blinding: &FieldElement,
+        params: &Params,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        let bit = if flag.is_zero() {
+            false
+        } else if *flag == FieldElement::one() {
+            true
+        } else {
+            return Err(PSError::GeneralError {
+                msg: format!("Message {} is not a boolean flag", msg_idx),
+            });
+        };
+        if statement == FlagStatement::IsTrue && !bit {
+            return Err(PSError::GeneralError {
+                msg: format!("Flag {} is not true", msg_idx),
+            });
+        }
+        let h = commitment_base(params)?;
+        let r = FieldElement::random_using_rng(rng);
+        let commitment = &(&params.g * flag) + &(&h * &r);
+        let opening_blinding = FieldElement::random_using_rng(rng);
+        let opening_commitment = &(&params.g * msg_blinding) + &(&h * &opening_blinding);
+        let branch_blinding = FieldElement::random_using_rng(rng);
+        let real = &h * &branch_blinding;
+        let (branch_commitments, simulated) = match statement {
+            FlagStatement::IsTrue => (vec![real], None),
+            FlagStatement::IsBit => {
+                let c = FieldElement::random_using_rng(rng);
+                let z = FieldElement::random_using_rng(rng);
+                // Simulate the other branch as h^z * D^c for D = C or C/g
+                let other = if bit {
+                    commitment.clone()
+                } else {
+                    &commitment - &params.g
+                };
+                let fake = &(&h * &z) + &(&other * &c);
+                let branches = if bit {
+                    vec![fake, real]
+                } else {
+                    vec![real, fake]
+                };
+                (branches, Some((c, z)))
+            }
+        };
+        Ok(Self {
+            msg_idx,
+            statement,
+            bit,
+            r,
+            commitment,
+            opening_commitment,
+            opening_blinding,
+            branch_blinding,
+            branch_commitments,
+            simulated,
+        })
+    }
+
+    fn gen_proof(self, challenge: &Challenge) -> FlagProof {
+        let c = &challenge.0;
+        let opening_response = &self.opening_blinding - &(c * &self.r);
+        let (branch_challenges, branch_responses) = match self.simulated {
+            None => (
+                vec![c.clone()],
+                vec![&self.branch_blinding - &(c * &self.r)],
+            ),
+            Some((c_sim, z_sim)) => {
+                let c_real = c - &c_sim;
+                let z_real = &self.branch_blinding - &(&c_real * &self.r);
+                if self.bit {
+                    (vec![c_sim, c_real], vec![z_sim, z_real])
+                } else {
+                    (vec![c_real, c_sim], vec![z_real, z_sim])
+                }
+            }
+        };
+        FlagProof {
+            msg_idx: self.msg_idx,
+            statement: self.statement,
+            commitment: self.commitment,
+            opening_commitment: self.opening_commitment,
+            opening_response,
+            branch_commitments: self.branch_commitments,
+            branch_challenges,
+            branch_responses,
+        }
+    }
+}
+
+impl FlagProof {
+    fn to_bytes(&self) -> Vec<u8> {
+        challenge_bytes(
+            self.msg_idx,
+            self.statement,
+            &self.commitment,
+            &self.opening_commitment,
+            &self.branch_commitments,
+        )
+    }
+
+    /// `msg_response` is the response for the flag in the proof of knowledge of signature and `h`
+    /// the commitment base
+    fn verify(
+        &self,
+        msg_response: &FieldElement,
+        challenge: &Challenge,
+        h: &SignatureGroup,
+        params: &Params,
+    ) -> bool {
+        let branches = match self.statement {
+            FlagStatement::IsTrue => 1,
+            FlagStatement::IsBit => 2,
+        };
+        if self.branch_commitments.len() != branches
+            || self.branch_challenges.len() != branches
+            || self.branch_responses.len() != branches
+        {
+            return false;
+        }
+        let c = &challenge.0;
+        // g^response_b * h^response_r * C^challenge == opening commitment
+        if &(&(&params.g * msg_response) + &(h * &self.opening_response)) + &(&self.commitment * c)
+            != self.opening_commitment
+        {
+            return false;
+        }
+        let mut c_sum = FieldElement::zero();
+        for c_i in &self.branch_challenges {
+            c_sum += c_i;
+        }
+        if c_sum != *c {
+            return false;
+        }
+        let minus_g = &self.commitment - &params.g;
+        let bases = match self.statement {
+            FlagStatement::IsTrue => vec![minus_g],
+            FlagStatement::IsBit => vec![self.commitment.clone(), minus_g],
+        };
+        // h^z_i * D_i^c_i == A_i for D_0 = C, D_1 = C/g
+        bases.iter().enumerate().all(|(i, d)| {
+            &(h * &self.branch_responses[i]) + &(d * &self.branch_challenges[i])
+                == self.branch_commitments[i]
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FlagPresentation {
+    pub proof: PoKOfSignatureProof,
+    pub revealed_msgs: HashMap<usize, FieldElement>,
+    pub flags: Vec<FlagProof>,
+    pub nonce: Vec<u8>,
+}
+
+impl FlagPresentation {
+    /// `flags` are the hidden messages to prove statements about, each must be 0 or 1
+    pub fn new(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[FieldElement],
+        revealed_msg_indices: HashSet<usize>,
+        flags: &[(usize, FlagStatement)],
+        nonce: &[u8],
+    ) -> Result<Self, PSError> {
+        Self::new_with_rng(
+            sig,
+            vk,
+            params,
+            messages,
+            revealed_msg_indices,
+            flags,
+            nonce,
+            &mut thread_rng(),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[FieldElement],
+        revealed_msg_indices: HashSet<usize>,
+        flags: &[(usize, FlagStatement)],
+        nonce: &[u8],
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        let mut msg_blindings = HashMap::new();
+        let mut blindings = vec![];
+        for i in 0..messages.len() {
+            if revealed_msg_indices.contains(&i) {
+                continue;
+            }
+            let b = FieldElement::random_using_rng(rng);
+            msg_blindings.insert(i, b.clone());
+            blindings.push(b);
+        }
+        let mut provers = Vec::with_capacity(flags.len());
+        for (idx, statement) in flags {
+            let msg_blinding = msg_blindings
+                .get(idx)
+                .ok_or_else(|| PSError::GeneralError {
+                    msg: format!("Flag {} should be a hidden message", idx),
+                })?;
+            provers.push(FlagProver::new(
+                *idx,
+                *statement,
+                &messages[*idx],
+                msg_blinding,
+                params,
+                rng,
+            )?);
+        }
+        let revealed_msgs = revealed_msg_indices
+            .iter()
+            .filter(|i| **i < messages.len())
+            .map(|i| (*i, messages[*i].clone()))
+            .collect();
+
+        let pok = PoKOfSignature::init_with_rng(
+            sig,
+            vk,
+            params,
+            &Message::from_field_elements(messages),
+            Some(&blindings),
+            revealed_msg_indices,
+            rng,
+        )?;
+        let mut bytes = pok.to_bytes();
+        for p in &provers {
+            bytes.append(&mut challenge_bytes(
+                p.msg_idx,
+                p.statement,
+                &p.commitment,
+                &p.opening_commitment,
+                &p.branch_commitments,
+            ));
+        }
+        bytes.extend_from_slice(nonce);
+        let challenge = Challenge::from_msg_hash(&bytes);
+        Ok(Self {
+            proof: pok.gen_proof(&challenge)?,
+            revealed_msgs,
+            flags: provers
+                .into_iter()
+                .map(|p| p.gen_proof(&challenge))
+                .collect(),
+            nonce: nonce.to_vec(),
+        })
+    }
+
+    fn challenge(&self, vk: &Verkey, params: &Params) -> Challenge {
+        let revealed_msg_indices = self.revealed_msgs.keys().cloned().collect();
+        let mut bytes = self
+            .proof
+            .get_bytes_for_challenge(&revealed_msg_indices, vk, params);
+        for f in &self.flags {
+            bytes.append(&mut f.to_bytes());
+        }
+        bytes.extend_from_slice(&self.nonce);
+        Challenge::from_msg_hash(&bytes)
+    }
+
+    /// Verify the proof of knowledge of signature and every flag proof
+    pub fn verify(&self, vk: &Verkey, params: &Params) -> Result<bool, PSError> {
+        if self.revealed_msgs.keys().any(|i| *i >= vk.Y_tilde.len()) {
+            return Ok(false);
+        }
+        let challenge = self.challenge(vk, params);
+        if !self
+            .proof
+            .verify(vk, params, self.revealed_msgs.clone(), &challenge)?
+        {
+            return Ok(false);
+        }
+        let revealed_msg_indices = self.revealed_msgs.keys().cloned().collect();
+        let h = commitment_base(params)?;
+        for f in &self.flags {
+            if f.msg_idx >= vk.Y_tilde.len() {
+                return Ok(false);
+            }
+            let resp = match self
+                .proof
+                .get_resp_for_message(f.msg_idx, &revealed_msg_indices)
+            {
+                Ok(r) => r,
+                Err(_) => return Ok(false),
+            };
+            if !f.verify(&resp.0, &challenge, &h, params) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Verify and evaluate `policy` with the proven flags satisfying `IsBit` and `IsTrue` predicates
+    pub fn verify_with_policy(
+        &self,
+        vk: &Verkey,
+        params: &Params,
+        policy: &CompiledPolicy,
+    ) -> Result<PolicyReport, PSError> {
+        if !self.verify(vk, params)? {
+            return Ok(PolicyReport {
+                proof_valid: false,
+                violations: vec![],
+            });
+        }
+        let flags = self
+            .flags
+            .iter()
+            .map(|f| (f.msg_idx, f.statement))
+            .collect();
+        Ok(PolicyReport {
+            proof_valid: true,
+            violations: policy.evaluate_with_flags(vk, &self.revealed_msgs, &flags),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use crate::policy::{Policy, PolicyViolation};
+
+    #[test]
+    fn test_flag_presentation() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(4, &params);
+        let msgs = vec![
+            FieldElement::random(),
+            FieldElement::one(),
+            FieldElement::zero(),
+            FieldElement::random(),
+        ];
+        let sig = Signature::new(&Message::from_field_elements(&msgs), &sk, &params).unwrap();
+        let mut revealed = HashSet::new();
+        revealed.insert(3);
+        let flags = [(1, FlagStatement::IsTrue), (2, FlagStatement::IsBit)];
+        let p = FlagPresentation::new(&sig, &vk, &params, &msgs, revealed.clone(), &flags, b"n")
+            .unwrap();
+        assert!(p.verify(&vk, &params).unwrap());
+
+        let names = ["secret", "over_18", "student", "country"];
+        let policy = CompiledPolicy::compile(
+            &[
+                Predicate::is_true("over_18"),
+                Policy::require_predicate("student", Predicate::IsBit),
+            ],
+            &names,
+        )
+        .unwrap();
+        assert!(p
+            .verify_with_policy(&vk, &params, &policy)
+            .unwrap()
+            .is_accepted());
+        let policy = CompiledPolicy::compile(
+            &[Policy::require_predicate("student", Predicate::IsTrue)],
+            &names,
+        )
+        .unwrap();
+        assert_eq!(
+            p.verify_with_policy(&vk, &params, &policy)
+                .unwrap()
+                .violations,
+            vec![PolicyViolation::NotRevealed {
+                attribute: String::from("student")
+            }]
+        );
+
+        // A flag proof only verifies for the message it was made for
+        let mut wrong = p.clone();
+        wrong.flags[0].msg_idx = 2;
+        assert!(!wrong.verify(&vk, &params).unwrap());
+        let mut wrong = p.clone();
+        wrong.flags[1].statement = FlagStatement::IsTrue;
+        assert!(!wrong.verify(&vk, &params).unwrap());
+
+        // The prover cannot prove false statements
+        let bad = [(2, FlagStatement::IsTrue)];
+        assert!(
+            FlagPresentation::new(&sig, &vk, &params, &msgs, revealed.clone(), &bad, b"n").is_err()
+        );
+        let bad = [(0, FlagStatement::IsBit)];
+        assert!(FlagPresentation::new(&sig, &vk, &params, &msgs, revealed, &bad, b"n").is_err());
+    }
+}
@@ -0,0 +1,278 @@
+// Transcript of how the generators of `Params` are derived so that auditors can confirm they are
+// nothing-up-my-sleeve values. Each generator is the label hashed to the curve under the domain
+// separation tag of its group, retried with an incremented counter appended in the unlikely case the
+// result is the identity. Suites without tags, like amcl's map of legacy params, hash the label with
+// the generator's name appended instead. The transcript records every input, tag and counter,
+// verification recomputes them. The hash to curve suite is recorded as well, see `hash_to_curve`.
+
+use crate::errors::PSError;
+#[cfg(feature = "rfc9380")]
+use crate::hash_to_curve::RFC9380_SUITE;
+use crate::hash_to_curve::{hasher_for_suite, AmclMapIt, HashToCurve, HashableGroup, AMCL_SUITE};
+use crate::keys::Params;
+use crate::utils::{hash_with_domain, HASH_SIZE};
+use crate::{OtherGroup, SignatureGroup};
+
+/// Hash to curve suite of `Params::new`
+#[cfg(feature = "rfc9380")]
+pub const HASH_SUITE: &str = RFC9380_SUITE;
+#[cfg(not(feature = "rfc9380"))]
+pub const HASH_SUITE: &str = AMCL_SUITE;
+/// Suite of `Params::new_legacy`
+pub const LEGACY_HASH_SUITE: &str = AMCL_SUITE;
+
+const G_SUFFIX: &[u8] = b" : g";
+const G_TILDE_SUFFIX: &[u8] = b" : g_tilde";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GeneratorDerivation {
+    pub name: String,
+    /// Hashed message when the counter is 0
+    pub input: Vec<u8>,
+    /// Domain separation tag, empty for suites without tags
+    #[serde(default)]
+    pub dst: Vec<u8>,
+    /// Number of retries needed, the counter is appended to `input` as 4 big endian bytes when non-zero
+    pub counter: u32,
+    pub output: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ParamsTranscript {
+    pub label: Vec<u8>,
+    pub hash_suite: String,
+    pub generators: Vec<GeneratorDerivation>,
+}
+
+fn hash_input(input: &[u8], counter: u32) -> Vec<u8> {
+    if counter == 0 {
+        input.to_vec()
+    } else {
+        [input, &counter.to_be_bytes()].concat()
+    }
+}
+
+/// Returns the generator and the counter used
+pub fn derive_generator<G: HashableGroup, H: HashToCurve + ?Sized>(
+    hasher: &H,
+    input: &[u8],
+) -> (G, u32) {
+    let mut counter = 0;
+    loop {
+        let g = G::hash_generator_with(hasher, &hash_input(input, counter));
+        if !g.is_identity() {
+            return (g, counter);
+        }
+        counter += 1;
+    }
+}
+
+/// The label when the suite separates the generators by tags, else the label and `suffix`
+fn generator_input<G: HashableGroup, H: HashToCurve + ?Sized>(
+    hasher: &H,
+    label: &[u8],
+    suffix: &[u8],
+) -> Vec<u8> {
+    match G::generator_dst(hasher) {
+        Some(_) => label.to_vec(),
+        None => [label, suffix].concat(),
+    }
+}
+
+fn derivation<G: HashableGroup, H: HashToCurve + ?Sized>(
+    hasher: &H,
+    name: &str,
+    label: &[u8],
+    suffix: &[u8],
+) -> (G, GeneratorDerivation) {
+    let input = generator_input::<G, H>(hasher, label, suffix);
+    let (g, counter) = derive_generator::<G, H>(hasher, &input);
+    let d = GeneratorDerivation {
+        name: name.to_string(),
+        input,
+        dst: G::generator_dst(hasher).unwrap_or_default().to_vec(),
+        counter,
+        output: g.to_bytes(),
+    };
+    (g, d)
+}
+
+impl GeneratorDerivation {
+    fn verify<G: HashableGroup, H: HashToCurve + ?Sized>(
+        &self,
+        hasher: &H,
+        label: &[u8],
+        suffix: &[u8],
+    ) -> Result<G, PSError> {
+        if self.input != generator_input::<G, H>(hasher, label, suffix)
+            || self.dst != G::generator_dst(hasher).unwrap_or_default()
+        {
+            return Err(PSError::GeneralError {
+                msg: format!(
+                    "Generator {} is not derived from the label with the suite's tag",
+                    self.name
+                ),
+            });
+        }
+        let g = G::hash_generator_with(hasher, &hash_input(&self.input, self.counter));
+        if g.is_identity() || g.to_bytes() != self.output {
+            return Err(PSError::GeneralError {
+                msg: format!("Generator {} does not match its derivation", self.name),
+            });
+        }
+        // Every smaller counter must have given the identity
+        for c in 0..self.counter {
+            if !G::hash_generator_with(hasher, &hash_input(&self.input, c)).is_identity() {
+                return Err(PSError::GeneralError {
+                    msg: format!("Generator {} skipped counter {}", self.name, c),
+                });
+            }
+        }
+        Ok(g)
+    }
+}
+
+impl ParamsTranscript {
+    /// Derive params from `label` and record the derivation
+    pub fn generate(label: &[u8]) -> (Params, Self) {
+        Self::generate_with_hasher(
+            label,
+            hasher_for_suite(HASH_SUITE).expect("suite is built in"),
+        )
+    }
+
+    /// Same as `generate` with amcl's map, giving the params of earlier versions
+    pub fn generate_legacy(label: &[u8]) -> (Params, Self) {
+        Self::generate_with_hasher(label, &AmclMapIt)
+    }
+
+    pub fn generate_with_hasher<H: HashToCurve + ?Sized>(
+        label: &[u8],
+        hasher: &H,
+    ) -> (Params, Self) {
+        let (g, d_g) = derivation::<SignatureGroup, H>(hasher, "g", label, G_SUFFIX);
+        let (g_tilde, d_g_tilde) =
+            derivation::<OtherGroup, H>(hasher, "g_tilde", label, G_TILDE_SUFFIX);
+        let transcript = Self {
+            label: label.to_vec(),
+            hash_suite: hasher.suite_id().to_string(),
+            generators: vec![d_g, d_g_tilde],
+        };
+        let params = Params {
+            g,
+            g_tilde,
+            hash_suite: transcript.hash_suite.clone(),
+        };
+        (params, transcript)
+    }
+
+    /// Recompute all derivations and return the params they give. The suite must be built in, see
+    /// `hasher_for_suite`.
+    pub fn verify(&self) -> Result<Params, PSError> {
+        self.verify_with_hasher(hasher_for_suite(&self.hash_suite)?)
+    }
+
+    /// Same as `verify` with the implementation of the recorded suite given
+    pub fn verify_with_hasher<H: HashToCurve + ?Sized>(
+        &self,
+        hasher: &H,
+    ) -> Result<Params, PSError> {
+        if self.hash_suite != hasher.suite_id() {
+            return Err(PSError::GeneralError {
+                msg: format!(
+                    "Transcript is for hash suite {} but {} is given",
+                    self.hash_suite,
+                    hasher.suite_id()
+                ),
+            });
+        }
+        if self.generators.len() != 2
+            || self.generators[0].name != "g"
+            || self.generators[1].name != "g_tilde"
+        {
+            return Err(PSError::GeneralError {
+                msg: String::from("Transcript should derive g and g_tilde"),
+            });
+        }
+        let g = self.generators[0].verify::<SignatureGroup, H>(hasher, &self.label, G_SUFFIX)?;
+        let g_tilde =
+            self.generators[1].verify::<OtherGroup, H>(hasher, &self.label, G_TILDE_SUFFIX)?;
+        Ok(Params {
+            g,
+            g_tilde,
+            hash_suite: self.hash_suite.clone(),
+        })
+    }
+
+    /// Digest of the transcript for publishing alongside the params
+    pub fn hash(&self) -> [u8; HASH_SIZE] {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&(self.label.len() as u64).to_be_bytes());
+        bytes.extend_from_slice(&self.label);
+        bytes.extend_from_slice(self.hash_suite.as_bytes());
+        for d in &self.generators {
+            for field in &[d.name.as_bytes(), &d.input, &d.output] {
+                bytes.extend_from_slice(&(field.len() as u64).to_be_bytes());
+                bytes.extend_from_slice(field);
+            }
+            bytes.extend_from_slice(&d.counter.to_be_bytes());
+            // Absent for suites without tags so digests of legacy transcripts are unchanged
+            if !d.dst.is_empty() {
+                bytes.extend_from_slice(&(d.dst.len() as u64).to_be_bytes());
+                bytes.extend_from_slice(&d.dst);
+            }
+        }
+        hash_with_domain(b"PS params transcript", &bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amcl_wrapper::group_elem::GroupElement;
+
+    #[test]
+    fn test_params_transcript() {
+        let (params, transcript) = ParamsTranscript::generate(b"test");
+        assert_eq!(params.hash_suite, HASH_SUITE);
+        #[cfg(feature = "rfc9380")]
+        {
+            assert_eq!(transcript.generators[0].input, b"test".to_vec());
+            assert!(!transcript.generators[0].dst.is_empty());
+        }
+        assert!(params.verify_derivation(&transcript));
+        let params_1 = transcript.verify().unwrap();
+        assert_eq!(params_1.g, params.g);
+
+        // Params from another label
+        assert!(!Params::new(b"other").verify_derivation(&transcript));
+
+        // Tampered transcripts
+        let mut t = transcript.clone();
+        t.label = b"other".to_vec();
+        assert!(t.verify().is_err());
+        let mut t = transcript.clone();
+        t.generators[1].output = SignatureGroup::random().to_bytes();
+        assert!(t.verify().is_err());
+        let mut t = transcript.clone();
+        t.generators[0].counter = 1;
+        assert!(t.verify().is_err());
+        assert_ne!(t.hash(), transcript.hash());
+        #[cfg(feature = "rfc9380")]
+        {
+            let mut t = transcript.clone();
+            t.generators[1].dst = t.generators[0].dst.clone();
+            assert!(t.verify().is_err());
+        }
+
+        // Legacy params keep the generators of earlier versions
+        let (legacy, legacy_transcript) = ParamsTranscript::generate_legacy(b"test");
+        assert_eq!(legacy.hash_suite, LEGACY_HASH_SUITE);
+        assert_eq!(legacy.g, SignatureGroup::from_msg_hash(b"test : g"));
+        assert_eq!(legacy.g_tilde, OtherGroup::from_msg_hash(b"test : g_tilde"));
+        assert!(legacy_transcript.generators[0].dst.is_empty());
+        assert!(legacy.verify_derivation(&legacy_transcript));
+        #[cfg(feature = "rfc9380")]
+        assert!(!params.verify_derivation(&legacy_transcript));
+    }
+}
@@ -0,0 +1,322 @@
+// Credential chaining. A holder of a parent credential requests a derived credential from another (or the
+// same) issuer where some attributes are carried over from the parent without revealing them. The
+// carried attributes are the committed messages of a blind signature request, in the order given, and
+// the new attributes are signed in the clear after them. A proof of knowledge of the parent signature
+// and a proof of knowledge of the opening of the commitment share the challenge and use the same
+// blindings for carried attributes, so equal responses prove the committed values are the parent's.
+
+use crate::blind_signature::{BlindingKey, ProofSignatureGroup, ProverCommittingSignatureGroup};
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::pok_sig::{PoKOfSignature, PoKOfSignatureProof};
+use crate::signature::Signature;
+use crate::signer::PsSigner;
+use crate::types::{Blinding, Challenge, Message};
+use crate::validation::deserialize_point;
+use crate::{SignatureGroup, SignatureGroupVec};
+use amcl_wrapper::field_elem::{FieldElement, FieldElementVector};
+use amcl_wrapper::group_elem::GroupElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::{HashMap, HashSet};
+
+/// The holder's parent credential
+pub struct ParentCredential<'a> {
+    pub sig: &'a Signature,
+    pub messages: &'a [FieldElement],
+    pub vk: &'a Verkey,
+    pub params: &'a Params,
+}
+
+/// Request for a derived credential whose first `carry_over_indices.len()` messages are the parent's
+/// messages at those indices and the rest are `new_attrs`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DerivedCredentialRequest {
+    pub parent_proof: PoKOfSignatureProof,
+    #[serde(deserialize_with = "deserialize_point")]
+    pub commitment: SignatureGroup,
+    pub commitment_proof: ProofSignatureGroup,
+    pub carry_over_indices: Vec<usize>,
+    pub new_attrs: Vec<FieldElement>,
+    pub nonce: Vec<u8>,
+}
+
+/// Holder creates the request. The returned blinding unblinds the issued signature. `nonce` comes from the
+/// issuer so requests cannot be replayed.
+pub fn derive_credential(
+    parent: &ParentCredential,
+    carry_over_indices: &[usize],
+    new_attrs: &[FieldElement],
+    blinding_key: &BlindingKey,
+    params: &Params,
+    nonce: &[u8],
+) -> Result<(DerivedCredentialRequest, Blinding), PSError> {
+    derive_credential_with_rng(
+        parent,
+        carry_over_indices,
+        new_attrs,
+        blinding_key,
+        params,
+        nonce,
+        &mut thread_rng(),
+    )
+}
+
+pub fn derive_credential_with_rng<R: RngCore + CryptoRng>(
+    parent: &ParentCredential,
+    carry_over_indices: &[usize],
+    new_attrs: &[FieldElement],
+    blinding_key: &BlindingKey,
+    params: &Params,
+    nonce: &[u8],
+    rng: &mut R,
+) -> Result<(DerivedCredentialRequest, Blinding), PSError> {
+    check_shape(
+        carry_over_indices,
+        new_attrs.len(),
+        parent.messages.len(),
+        blinding_key,
+    )?;
+    // All parent messages are hidden, the carried ones get the blindings used in the commitment proof
+    let mut blindings: Vec<FieldElement> = (0..parent.messages.len())
+        .map(|_| FieldElement::random_using_rng(rng))
+        .collect();
+    let mut committing = ProverCommittingSignatureGroup::new();
+    let mut secrets = vec![];
+    for (j, p) in carry_over_indices.iter().enumerate() {
+        let r = FieldElement::random_using_rng(rng);
+        blindings[*p] = r.clone();
+        committing.commit(&blinding_key.Y[j], Some(&r));
+        secrets.push(parent.messages[*p].clone());
+    }
+    let blinding = Blinding::random_using_rng(rng);
+    committing.commit(&params.g, Some(&FieldElement::random_using_rng(rng)));
+    secrets.push(blinding.0.clone());
+
+    let mut bases = blinding_key.Y[..carry_over_indices.len()].to_vec();
+    bases.push(params.g.clone());
+    let commitment = SignatureGroupVec::from(bases.as_slice())
+        .multi_scalar_mul_const_time(&FieldElementVector::from(secrets.as_slice()))
+        .unwrap();
+
+    let pok = PoKOfSignature::init_with_rng(
+        parent.sig,
+        parent.vk,
+        parent.params,
+        &Message::from_field_elements(parent.messages),
+        Some(&blindings),
+        HashSet::new(),
+        rng,
+    )?;
+    let committed = committing.finish();
+    let mut bytes = pok.to_bytes();
+    bytes.append(&mut committed.to_bytes());
+    bytes.append(&mut challenge_bytes(
+        &commitment,
+        carry_over_indices,
+        new_attrs,
+        nonce,
+    ));
+    let challenge = Challenge::from_msg_hash(&bytes);
+    let request = DerivedCredentialRequest {
+        parent_proof: pok.gen_proof(&challenge)?,
+        commitment,
+        commitment_proof: committed.gen_proof(&challenge, &secrets)?,
+        carry_over_indices: carry_over_indices.to_vec(),
+        new_attrs: new_attrs.to_vec(),
+        nonce: nonce.to_vec(),
+    };
+    Ok((request, blinding))
+}
+
+/// Messages of the derived credential, for the holder to verify the unblinded signature
+pub fn derived_messages(
+    parent_messages: &[FieldElement],
+    carry_over_indices: &[usize],
+    new_attrs: &[FieldElement],
+) -> Vec<FieldElement> {
+    carry_over_indices
+        .iter()
+        .map(|p| parent_messages[*p].clone())
+        .chain(new_attrs.iter().cloned())
+        .collect()
+}
+
+impl DerivedCredentialRequest {
+    /// Issuer side check of the parent proof under `parent_vk` and of equality of the carried attributes
+    pub fn verify(
+        &self,
+        parent_vk: &Verkey,
+        parent_params: &Params,
+        blinding_key: &BlindingKey,
+        params: &Params,
+    ) -> Result<bool, PSError> {
+        let count = self.carry_over_indices.len();
+        if check_shape(
+            &self.carry_over_indices,
+            self.new_attrs.len(),
+            parent_vk.Y_tilde.len(),
+            blinding_key,
+        )
+        .is_err()
+            || self.commitment_proof.responses.len() != count + 1
+        {
+            return Ok(false);
+        }
+        let mut bases = blinding_key.Y[..count].to_vec();
+        bases.push(params.g.clone());
+
+        let none_revealed = HashSet::new();
+        let mut bytes =
+            self.parent_proof
+                .get_bytes_for_challenge(&none_revealed, parent_vk, parent_params);
+        for b in &bases {
+            bytes.append(&mut b.to_bytes());
+        }
+        bytes.append(&mut self.commitment_proof.commitment.to_bytes());
+        bytes.append(&mut challenge_bytes(
+            &self.commitment,
+            &self.carry_over_indices,
+            &self.new_attrs,
+            &self.nonce,
+        ));
+        let challenge = Challenge::from_msg_hash(&bytes);
+
+        for (j, p) in self.carry_over_indices.iter().enumerate() {
+            match self.parent_proof.get_resp_for_message(*p, &none_revealed) {
+                Ok(r) if r.0 == self.commitment_proof.responses[j] => (),
+                _ => return Ok(false),
+            }
+        }
+        if !self
+            .commitment_proof
+            .verify(&bases, &self.commitment, &challenge)?
+        {
+            return Ok(false);
+        }
+        self.parent_proof
+            .verify(parent_vk, parent_params, HashMap::new(), &challenge)
+    }
+
+    /// Verify the request and blind sign it with `signer`
+    pub fn issue<S: PsSigner + ?Sized>(
+        &self,
+        parent_vk: &Verkey,
+        parent_params: &Params,
+        signer: &S,
+        blinding_key: &BlindingKey,
+        params: &Params,
+    ) -> Result<Signature, PSError> {
+        if !self.verify(parent_vk, parent_params, blinding_key, params)? {
+            return Err(PSError::GeneralError {
+                msg: String::from("Invalid derived credential request"),
+            });
+        }
+        signer.blind_sign(&self.commitment, &self.new_attrs, params)
+    }
+}
+
+fn check_shape(
+    carry_over_indices: &[usize],
+    new_attr_count: usize,
+    parent_msg_count: usize,
+    blinding_key: &BlindingKey,
+) -> Result<(), PSError> {
+    if carry_over_indices.is_empty()
+        || carry_over_indices.len() + new_attr_count != blinding_key.msg_count()
+    {
+        return Err(PSError::UnsupportedNoOfMessages {
+            expected: blinding_key.msg_count(),
+            given: carry_over_indices.len() + new_attr_count,
+        });
+    }
+    let mut seen = HashSet::new();
+    for p in carry_over_indices {
+        if *p >= parent_msg_count || !seen.insert(*p) {
+            return Err(PSError::GeneralError {
+                msg: format!("Invalid or repeated parent index {}", p),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn challenge_bytes(
+    commitment: &SignatureGroup,
+    carry_over_indices: &[usize],
+    new_attrs: &[FieldElement],
+    nonce: &[u8],
+) -> Vec<u8> {
+    let mut bytes = commitment.to_bytes();
+    bytes.extend_from_slice(&(carry_over_indices.len() as u64).to_be_bytes());
+    for p in carry_over_indices {
+        bytes.extend_from_slice(&(*p as u64).to_be_bytes());
+    }
+    for m in new_attrs {
+        bytes.append(&mut m.to_bytes());
+    }
+    bytes.extend_from_slice(nonce);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blind_signature::BlindSignature;
+    use crate::keys::keygen;
+
+    #[test]
+    fn test_derive_credential() {
+        let parent_params = Params::new("parent".as_bytes());
+        let (parent_sk, parent_vk) = keygen(4, &parent_params);
+        let parent_msgs = FieldElementVector::random(4);
+        let parent_sig = Signature::new(
+            &Message::from_field_elements(parent_msgs.as_slice()),
+            &parent_sk,
+            &parent_params,
+        )
+        .unwrap();
+        let parent = ParentCredential {
+            sig: &parent_sig,
+            messages: parent_msgs.as_slice(),
+            vk: &parent_vk,
+            params: &parent_params,
+        };
+
+        let params = Params::new("child".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let blinding_key = BlindingKey::new(&sk, &params);
+        let new_attrs = vec![FieldElement::random()];
+        let carried = [3, 1];
+        let (request, blinding) = derive_credential(
+            &parent,
+            &carried,
+            &new_attrs,
+            &blinding_key,
+            &params,
+            b"nonce",
+        )
+        .unwrap();
+        let sig_blinded = request
+            .issue(&parent_vk, &parent_params, &sk, &blinding_key, &params)
+            .unwrap();
+        let sig = BlindSignature::unblind(&sig_blinded, &blinding);
+        let msgs = derived_messages(parent_msgs.as_slice(), &carried, &new_attrs);
+        assert_eq!(msgs[0], parent_msgs[3]);
+        assert!(sig
+            .verify(&Message::from_field_elements(&msgs), &vk, &params)
+            .unwrap());
+
+        // Claiming a different parent index breaks the equality of responses
+        let mut bad = request.clone();
+        bad.carry_over_indices = vec![3, 2];
+        assert!(!bad
+            .verify(&parent_vk, &parent_params, &blinding_key, &params)
+            .unwrap());
+        // Changing the signed attributes breaks the challenge
+        let mut bad = request;
+        bad.new_attrs = vec![FieldElement::random()];
+        assert!(bad
+            .issue(&parent_vk, &parent_params, &sk, &blinding_key, &params)
+            .is_err());
+    }
+}
@@ -0,0 +1,207 @@
+// Compact versioned encodings of signatures, proofs and blinding keys, the counterpart of
+// `Verkey::to_versioned_bytes`. A format version byte is followed by compressed points, see
+// `PointEncoding`, and scalars packed into 32 bytes. Decoding checks the version, the exact length,
+// canonical encodings and every point with `validate_point`, so the same artifact has exactly one
+// encoding and bytes stay readable by later versions of the crate.
+
+use crate::blind_signature::BlindingKey;
+use crate::errors::PSError;
+use crate::limits::DecodeLimits;
+use crate::pok_sig::{PoKOfSignatureProof, ProofOtherGroup};
+use crate::signature::Signature;
+use crate::utils::{
+    pack_field_element, point_from_compressed_bytes, unpack_field_element, ByteReader,
+    PointEncoding, PACKED_FIELD_ELEMENT_SIZE,
+};
+use crate::{OtherGroup, SignatureGroup};
+use amcl_wrapper::field_elem::FieldElementVector;
+
+/// First byte of the compact encodings
+pub const COMPACT_FORMAT_V1: u8 = 1;
+
+fn read_version(reader: &mut ByteReader) -> Result<(), PSError> {
+    match reader.read_u8()? {
+        COMPACT_FORMAT_V1 => Ok(()),
+        v => Err(PSError::InvalidEncoding {
+            msg: format!("Unknown format version {}", v),
+        }),
+    }
+}
+
+fn read_point<G: PointEncoding>(reader: &mut ByteReader) -> Result<G, PSError> {
+    let bytes = reader.take(G::COMPRESSED_SIZE)?;
+    let point: G = point_from_compressed_bytes(bytes)?;
+    if point.to_compressed_bytes() != bytes {
+        return Err(PSError::InvalidEncoding {
+            msg: String::from("Non-canonical group element"),
+        });
+    }
+    Ok(point)
+}
+
+/// Number of elements of `size` bytes in the rest of `reader`, which must be a multiple of `size`
+fn remaining_count(reader: &ByteReader, size: usize) -> Result<usize, PSError> {
+    if !reader.remaining().is_multiple_of(size) {
+        return Err(PSError::InvalidEncoding {
+            msg: format!(
+                "{} bytes are not a multiple of {}",
+                reader.remaining(),
+                size
+            ),
+        });
+    }
+    Ok(reader.remaining() / size)
+}
+
+impl Signature {
+    pub fn to_versioned_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![COMPACT_FORMAT_V1];
+        bytes.append(&mut self.sigma_1.to_compressed_bytes());
+        bytes.append(&mut self.sigma_2.to_compressed_bytes());
+        bytes
+    }
+
+    pub fn from_versioned_bytes(bytes: &[u8]) -> Result<Self, PSError> {
+        let mut reader = ByteReader::new(bytes);
+        read_version(&mut reader)?;
+        let sigma_1 = read_point(&mut reader)?;
+        let sigma_2 = read_point(&mut reader)?;
+        reader.finish()?;
+        Ok(Self { sigma_1, sigma_2 })
+    }
+}
+
+impl PoKOfSignatureProof {
+    /// Signature, J, commitment and the responses
+    pub fn to_versioned_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.sig.to_versioned_bytes();
+        bytes.append(&mut self.J.to_compressed_bytes());
+        bytes.append(&mut self.proof_vc.commitment.to_compressed_bytes());
+        for r in self.proof_vc.responses.iter() {
+            bytes.append(&mut pack_field_element(r));
+        }
+        bytes
+    }
+
+    pub fn from_versioned_bytes(bytes: &[u8]) -> Result<Self, PSError> {
+        let limits = DecodeLimits::current();
+        limits.check_proof_size(bytes.len())?;
+        let mut reader = ByteReader::new(bytes);
+        read_version(&mut reader)?;
+        let sigma_1: SignatureGroup = read_point(&mut reader)?;
+        let sigma_2: SignatureGroup = read_point(&mut reader)?;
+        let J: OtherGroup = read_point(&mut reader)?;
+        let commitment = read_point(&mut reader)?;
+        // One response for `t` and one per hidden message
+        let count = remaining_count(&reader, PACKED_FIELD_ELEMENT_SIZE)?;
+        if count == 0 {
+            return Err(PSError::InvalidEncoding {
+                msg: String::from("Proof has no responses"),
+            });
+        }
+        limits.check_msg_count(count - 1)?;
+        let mut responses = FieldElementVector::with_capacity(count);
+        for _ in 0..count {
+            responses.push(unpack_field_element(
+                reader.take(PACKED_FIELD_ELEMENT_SIZE)?,
+            )?);
+        }
+        reader.finish()?;
+        Ok(Self {
+            sig: Signature { sigma_1, sigma_2 },
+            J,
+            proof_vc: ProofOtherGroup {
+                commitment,
+                responses,
+            },
+        })
+    }
+}
+
+impl BlindingKey {
+    /// X followed by all Y
+    pub fn to_versioned_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![COMPACT_FORMAT_V1];
+        bytes.append(&mut self.X.to_compressed_bytes());
+        for y in &self.Y {
+            bytes.append(&mut y.to_compressed_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_versioned_bytes(bytes: &[u8]) -> Result<Self, PSError> {
+        let mut reader = ByteReader::new(bytes);
+        read_version(&mut reader)?;
+        let X = read_point(&mut reader)?;
+        let count = remaining_count(&reader, SignatureGroup::COMPRESSED_SIZE)?;
+        DecodeLimits::current().check_msg_count(count)?;
+        let Y = (0..count)
+            .map(|_| read_point(&mut reader))
+            .collect::<Result<_, _>>()?;
+        reader.finish()?;
+        Ok(Self { X, Y })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::{keygen, Params};
+    use crate::pok_sig::PoKOfSignature;
+    use crate::types::Message;
+    use amcl_wrapper::group_elem::GroupElement;
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn test_compact_encodings() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let msgs = FieldElementVector::random(3);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+
+        let bytes = sig.to_versioned_bytes();
+        assert_eq!(bytes[0], COMPACT_FORMAT_V1);
+        assert_eq!(bytes.len(), 1 + 2 * SignatureGroup::COMPRESSED_SIZE);
+        let sig_1 = Signature::from_versioned_bytes(&bytes).unwrap();
+        assert_eq!(sig_1.to_bytes(), sig.to_bytes());
+        assert!(Signature::from_versioned_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Signature::from_versioned_bytes(&[bytes.as_slice(), &[0]].concat()).is_err());
+        let mut unknown = bytes.clone();
+        unknown[0] = 9;
+        assert!(Signature::from_versioned_bytes(&unknown).is_err());
+
+        let revealed: HashSet<usize> = vec![0].into_iter().collect();
+        let pok = PoKOfSignature::init(
+            &sig,
+            &vk,
+            &params,
+            &Message::from_field_elements(msgs.as_slice()),
+            None,
+            revealed,
+        )
+        .unwrap();
+        let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
+        let proof = pok.gen_proof(&chal).unwrap();
+        let bytes = proof.to_versioned_bytes();
+        assert!(bytes.len() < proof.to_bytes().len());
+        let proof_1 = PoKOfSignatureProof::from_versioned_bytes(&bytes).unwrap();
+        let mut revealed_msgs = HashMap::new();
+        revealed_msgs.insert(0, msgs[0].clone());
+        assert!(proof_1.verify(&vk, &params, revealed_msgs, &chal).unwrap());
+        assert!(PoKOfSignatureProof::from_versioned_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(PoKOfSignatureProof::from_versioned_bytes(
+            &bytes[..bytes.len() - 3 * PACKED_FIELD_ELEMENT_SIZE]
+        )
+        .is_err());
+
+        let bk = BlindingKey::new(&sk, &params);
+        let bk_1 = BlindingKey::from_versioned_bytes(&bk.to_versioned_bytes()).unwrap();
+        assert_eq!(bk_1.X, bk.X);
+        assert_eq!(bk_1.Y, bk.Y);
+        // Identity elements are rejected
+        let mut with_identity = vec![COMPACT_FORMAT_V1];
+        with_identity.append(&mut SignatureGroup::identity().to_compressed_bytes());
+        assert!(BlindingKey::from_versioned_bytes(&with_identity).is_err());
+    }
+}
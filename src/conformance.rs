@@ -0,0 +1,241 @@
+// Conformance of artifacts produced by other implementations. A `ConformanceCase` names an artifact,
+// its inputs and whether it is expected to verify, all binary values hex encoded: verkeys, signatures
+// and proofs with `ProfileEncoding`, messages, revealed messages and challenges as 32 byte big endian
+// integers. A case passes when decoding and verifying gives the expected outcome, so invalid artifacts
+// that must be rejected are cases too. The `conformance` binary runs a directory of cases and prints
+// the `ConformanceReport` as JSON.
+
+use crate::curve::{curve_info, CurveInfo};
+use crate::errors::PSError;
+use crate::interop::ProfileEncoding;
+use crate::keys::{Params, Verkey};
+use crate::pok_sig::PoKOfSignatureProof;
+use crate::signature::Signature;
+use crate::types::{Challenge, Message};
+use crate::utils::unpack_field_element;
+use crate::validation::ValidationProfile;
+use amcl_wrapper::field_elem::FieldElement;
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    /// The verkey decodes and all its elements are valid
+    Verkey,
+    /// The signature verifies on `messages`
+    Signature,
+    /// The proof verifies with `revealed` and `challenge`
+    Proof,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConformanceCase {
+    pub name: String,
+    pub kind: ArtifactKind,
+    /// Label given to `Params::new`
+    pub params_label: String,
+    pub verkey: String,
+    #[serde(default)]
+    pub messages: Vec<String>,
+    #[serde(default)]
+    pub signature: Option<String>,
+    #[serde(default)]
+    pub proof: Option<String>,
+    /// Indices of revealed messages to their values
+    #[serde(default)]
+    pub revealed: HashMap<usize, String>,
+    #[serde(default)]
+    pub challenge: Option<String>,
+    pub expected: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CaseResult {
+    pub name: String,
+    pub expected: bool,
+    pub verified: bool,
+    /// Why the artifact did not verify, if it failed to decode or is malformed
+    pub error: Option<String>,
+}
+
+impl CaseResult {
+    pub fn passed(&self) -> bool {
+        self.expected == self.verified
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConformanceReport {
+    pub crate_version: String,
+    pub curve: CurveInfo,
+    pub passed: usize,
+    pub failed: usize,
+    pub results: Vec<CaseResult>,
+}
+
+impl ConformanceReport {
+    pub fn all_passed(&self) -> bool {
+        self.failed == 0
+    }
+}
+
+fn decode_hex(field: &str, hex: &str) -> Result<Vec<u8>, PSError> {
+    let invalid = || PSError::InvalidEncoding {
+        msg: format!("Field {} is not hex", field),
+    };
+    let hex = hex.trim_start_matches("0x");
+    if !hex.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|b| u8::from_str_radix(b, 16).ok())
+                .ok_or_else(invalid)
+        })
+        .collect()
+}
+
+fn decode_scalar(field: &str, hex: &str) -> Result<FieldElement, PSError> {
+    unpack_field_element(&decode_hex(field, hex)?)
+}
+
+fn required<'a>(field: &str, value: &'a Option<String>) -> Result<&'a str, PSError> {
+    value.as_deref().ok_or_else(|| PSError::GeneralError {
+        msg: format!("Field {} is missing", field),
+    })
+}
+
+impl ConformanceCase {
+    fn verify(&self) -> Result<bool, PSError> {
+        let params = Params::new(self.params_label.as_bytes());
+        let (vk, _) = Verkey::from_bytes_with_encoding(&decode_hex("verkey", &self.verkey)?)?;
+        if !ValidationProfile::Paranoid.is_valid_verkey(&vk) {
+            return Ok(false);
+        }
+        match self.kind {
+            ArtifactKind::Verkey => Ok(true),
+            ArtifactKind::Signature => {
+                let bytes = decode_hex("signature", required("signature", &self.signature)?)?;
+                let (sig, _) = Signature::from_bytes_with_encoding(&bytes)?;
+                let messages = self
+                    .messages
+                    .iter()
+                    .map(|m| decode_scalar("messages", m).map(Message))
+                    .collect::<Result<Vec<Message>, PSError>>()?;
+                sig.verify(&messages, &vk, &params)
+            }
+            ArtifactKind::Proof => {
+                let bytes = decode_hex("proof", required("proof", &self.proof)?)?;
+                let (proof, _) = PoKOfSignatureProof::from_bytes_with_encoding(&bytes)?;
+                let challenge = Challenge(decode_scalar(
+                    "challenge",
+                    required("challenge", &self.challenge)?,
+                )?);
+                let mut revealed = HashMap::new();
+                for (i, m) in &self.revealed {
+                    revealed.insert(*i, decode_scalar("revealed", m)?);
+                }
+                proof.verify(&vk, &params, revealed, &challenge)
+            }
+        }
+    }
+
+    pub fn run(&self) -> CaseResult {
+        let (verified, error) = match self.verify() {
+            Ok(verified) => (verified, None),
+            Err(e) => (false, Some(e.to_string())),
+        };
+        CaseResult {
+            name: self.name.clone(),
+            expected: self.expected,
+            verified,
+            error,
+        }
+    }
+}
+
+pub fn run_cases(cases: &[ConformanceCase]) -> ConformanceReport {
+    let results: Vec<CaseResult> = cases.iter().map(|c| c.run()).collect();
+    let passed = results.iter().filter(|r| r.passed()).count();
+    ConformanceReport {
+        crate_version: String::from(env!("CARGO_PKG_VERSION")),
+        curve: curve_info(),
+        passed,
+        failed: results.len() - passed,
+        results,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interop::EncodingProfile;
+    use crate::keys::keygen;
+    use crate::pok_sig::PoKOfSignature;
+    use crate::utils::pack_field_element;
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use std::collections::HashSet;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_conformance_cases() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(2, &params);
+        let msgs = FieldElementVector::random(2);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let revealed: HashSet<usize> = vec![1].into_iter().collect();
+        let pok = PoKOfSignature::init(
+            &sig,
+            &vk,
+            &params,
+            &Message::from_field_elements(msgs.as_slice()),
+            None,
+            revealed,
+        )
+        .unwrap();
+        let chal = pok.pok_vc.gen_challenge(vec![]);
+        let proof = pok.gen_proof(&chal).unwrap();
+
+        let profile = EncodingProfile::default();
+        let case = ConformanceCase {
+            name: String::from("signature"),
+            kind: ArtifactKind::Signature,
+            params_label: String::from("test"),
+            verkey: hex(&vk.to_bytes_with_encoding(profile)),
+            messages: msgs.iter().map(|m| hex(&pack_field_element(m))).collect(),
+            signature: Some(hex(&sig.to_bytes_with_encoding(profile))),
+            proof: None,
+            revealed: HashMap::new(),
+            challenge: None,
+            expected: true,
+        };
+        let mut proof_case = case.clone();
+        proof_case.name = String::from("proof");
+        proof_case.kind = ArtifactKind::Proof;
+        proof_case.proof = Some(hex(&proof.to_bytes_with_encoding(profile)));
+        proof_case
+            .revealed
+            .insert(1, hex(&pack_field_element(&msgs[1])));
+        proof_case.challenge = Some(hex(&pack_field_element(chal.as_field())));
+        // Must be rejected, expected to fail verification
+        let mut wrong_messages = case.clone();
+        wrong_messages.messages.reverse();
+        wrong_messages.expected = false;
+        // Fails, claims an undecodable signature verifies
+        let mut not_hex = case.clone();
+        not_hex.signature = Some(String::from("zz"));
+
+        let json = serde_json::to_string(&[case, proof_case, wrong_messages, not_hex]).unwrap();
+        let cases: Vec<ConformanceCase> = serde_json::from_str(&json).unwrap();
+        let report = run_cases(&cases);
+        assert_eq!((report.passed, report.failed), (3, 1));
+        assert!(!report.results[3].passed());
+        assert!(report.results[3].error.is_some());
+    }
+}
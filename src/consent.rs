@@ -0,0 +1,234 @@
+// Consent receipts. The holder states what it consents to, the purpose, the verifier and the time, and
+// which attributes it reveals for it, and the hash of the statement is bound into the challenge of the
+// presentation in place of a disclosure document. A verifier given the statement next to the
+// presentation gets a `ConsentReceipt` from `Presentation::verify_with_consent`. Holder and verifier keep
+// the same receipt and either can show a third party, with the presentation and the issuer's verkey,
+// that exactly these attributes were disclosed with this consent, since the statement cannot be changed
+// without invalidating the proof. The verifier still needs to check that `verifier_id` is its own and
+// that the timestamp is recent.
+
+use crate::context::{verkey_id, VerkeyId};
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::presentation::Presentation;
+use crate::signature::{Fingerprint, Signature};
+use crate::utils::hash_with_domain;
+use amcl_wrapper::field_elem::FieldElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsentStatement {
+    pub purpose: String,
+    pub verifier_id: String,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Indices of the revealed messages in increasing order
+    pub attributes: Vec<usize>,
+}
+
+/// Evidence of a verified presentation made with consent
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConsentReceipt {
+    pub statement: ConsentStatement,
+    pub issuer: VerkeyId,
+    /// `Presentation::fingerprint`
+    pub presentation: Fingerprint,
+    pub revealed_msgs: HashMap<usize, FieldElement>,
+}
+
+impl ConsentStatement {
+    pub fn new(
+        purpose: &str,
+        verifier_id: &str,
+        timestamp: u64,
+        revealed_msg_indices: &HashSet<usize>,
+    ) -> Self {
+        let mut attributes: Vec<usize> = revealed_msg_indices.iter().cloned().collect();
+        attributes.sort_unstable();
+        Self {
+            purpose: purpose.to_string(),
+            verifier_id: verifier_id.to_string(),
+            timestamp,
+            attributes,
+        }
+    }
+
+    /// Unambiguous encoding with length prefixed strings
+    pub fn to_canonical_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        for s in &[&self.purpose, &self.verifier_id] {
+            bytes.extend_from_slice(&(s.len() as u64).to_be_bytes());
+            bytes.extend_from_slice(s.as_bytes());
+        }
+        bytes.extend_from_slice(&self.timestamp.to_be_bytes());
+        bytes.extend_from_slice(&(self.attributes.len() as u64).to_be_bytes());
+        for i in &self.attributes {
+            bytes.extend_from_slice(&(*i as u64).to_be_bytes());
+        }
+        bytes
+    }
+
+    pub fn hash(&self) -> Fingerprint {
+        hash_with_domain(b"PS consent statement", &self.to_canonical_bytes())
+    }
+
+    /// Whether the statement lists exactly the revealed messages of `presentation`
+    fn covers(&self, presentation: &Presentation) -> bool {
+        let mut revealed: Vec<usize> = presentation.revealed_msgs.keys().cloned().collect();
+        revealed.sort_unstable();
+        revealed == self.attributes
+    }
+}
+
+impl ConsentReceipt {
+    /// Check the receipt against the presentation it was issued for
+    pub fn verify(
+        &self,
+        presentation: &Presentation,
+        vk: &Verkey,
+        params: &Params,
+    ) -> Result<bool, PSError> {
+        if self.issuer != verkey_id(vk)
+            || self.presentation != presentation.fingerprint()
+            || self.revealed_msgs != presentation.revealed_msgs
+        {
+            return Ok(false);
+        }
+        Ok(presentation.verify_with_consent(vk, params, &self.statement)? == Some(self.clone()))
+    }
+}
+
+impl Presentation {
+    /// Presentation revealing the attributes of `consent` with its hash bound into the challenge
+    pub fn new_with_consent(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[FieldElement],
+        nonce: &[u8],
+        consent: &ConsentStatement,
+    ) -> Result<Self, PSError> {
+        Self::new_with_consent_with_rng(
+            sig,
+            vk,
+            params,
+            messages,
+            nonce,
+            consent,
+            &mut thread_rng(),
+        )
+    }
+
+    pub fn new_with_consent_with_rng<R: RngCore + CryptoRng>(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[FieldElement],
+        nonce: &[u8],
+        consent: &ConsentStatement,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        if let Some(i) = consent.attributes.iter().find(|i| **i >= messages.len()) {
+            return Err(PSError::GeneralError {
+                msg: format!("Consented attribute {} is not a message", i),
+            });
+        }
+        let revealed_msgs = consent
+            .attributes
+            .iter()
+            .map(|i| (*i, messages[*i].clone()))
+            .collect();
+        Self::create(
+            sig,
+            vk,
+            params,
+            messages,
+            consent.attributes.iter().cloned().collect(),
+            revealed_msgs,
+            nonce,
+            Some(consent.hash()),
+            rng,
+        )
+    }
+
+    /// The receipt if the presentation is valid, bound to `consent` and reveals exactly its attributes
+    pub fn verify_with_consent(
+        &self,
+        vk: &Verkey,
+        params: &Params,
+        consent: &ConsentStatement,
+    ) -> Result<Option<ConsentReceipt>, PSError> {
+        if self.disclosure_hash != Some(consent.hash())
+            || !consent.covers(self)
+            || !self.verify(vk, params)?
+        {
+            return Ok(None);
+        }
+        Ok(Some(ConsentReceipt {
+            statement: consent.clone(),
+            issuer: verkey_id(vk),
+            presentation: self.fingerprint(),
+            revealed_msgs: self.revealed_msgs.clone(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::FieldElementVector;
+
+    #[test]
+    fn test_consent_receipt() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(4, &params);
+        let msgs = FieldElementVector::random(4);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let revealed: HashSet<usize> = vec![1, 3].into_iter().collect();
+        let consent = ConsentStatement::new("age check", "shop.example", 1_700_000_000, &revealed);
+
+        let pres =
+            Presentation::new_with_consent(&sig, &vk, &params, msgs.as_slice(), b"nonce", &consent)
+                .unwrap();
+        let receipt = pres
+            .verify_with_consent(&vk, &params, &consent)
+            .unwrap()
+            .unwrap();
+        assert_eq!(receipt.statement.attributes, vec![1, 3]);
+        assert_eq!(receipt.revealed_msgs[&3], msgs[3]);
+        assert!(receipt.verify(&pres, &vk, &params).unwrap());
+        assert!(pres.verify(&vk, &params).unwrap());
+
+        // Another statement or presentation does not match
+        let mut other = consent.clone();
+        other.purpose = String::from("marketing");
+        assert!(pres
+            .verify_with_consent(&vk, &params, &other)
+            .unwrap()
+            .is_none());
+        let mut forged = receipt.clone();
+        forged.statement = other;
+        assert!(!forged.verify(&pres, &vk, &params).unwrap());
+        let plain =
+            Presentation::new(&sig, &vk, &params, msgs.as_slice(), revealed, b"nonce").unwrap();
+        assert!(plain
+            .verify_with_consent(&vk, &params, &consent)
+            .unwrap()
+            .is_none());
+        assert!(!receipt.verify(&plain, &vk, &params).unwrap());
+        let too_many = ConsentStatement::new("x", "y", 0, &vec![4].into_iter().collect());
+        assert!(Presentation::new_with_consent(
+            &sig,
+            &vk,
+            &params,
+            msgs.as_slice(),
+            b"nonce",
+            &too_many
+        )
+        .is_err());
+    }
+}
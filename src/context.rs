@@ -0,0 +1,228 @@
+// Verifier state reused across verifications. Verifiers usually see the same proof request, i.e. the same
+// verkey and the same set of revealed messages, again and again, so the bases of the proof of knowledge
+// of the hidden messages (g_tilde and the Y_tilde of hidden messages) are cached per verkey and revealed
+// set. The cache is bounded in bytes and evicts the least recently used entry.
+
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::presentation::Presentation;
+use crate::signature::Fingerprint;
+use crate::{OtherGroup, OTHER_GROUP_SIZE};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Identifies a verkey in caches, see `verkey_id`
+pub type VerkeyId = Fingerprint;
+
+pub fn verkey_id(vk: &Verkey) -> VerkeyId {
+    vk.fingerprint()
+}
+
+/// Bitmap with bit i set if message i is revealed
+fn revealed_bitmap<'a, I: Iterator<Item = &'a usize>>(msg_count: usize, revealed: I) -> Vec<u8> {
+    let mut bitmap = vec![0u8; msg_count.div_ceil(8)];
+    for i in revealed {
+        bitmap[i / 8] |= 1 << (i % 8);
+    }
+    bitmap
+}
+
+#[derive(Clone, Debug)]
+struct CacheEntry {
+    bases: Vec<OtherGroup>,
+    last_used: u64,
+}
+
+/// Bases of proofs of knowledge of hidden messages keyed by verkey id and revealed message bitmap. The
+/// bases of a verkey id must always be requested with the same verkey and params.
+#[derive(Clone, Debug)]
+pub struct HiddenBasesCache {
+    max_bytes: usize,
+    used_bytes: usize,
+    clock: u64,
+    entries: HashMap<(VerkeyId, Vec<u8>), CacheEntry>,
+}
+
+impl HiddenBasesCache {
+    /// Cache holding at most `max_bytes` of bases, counted with their serialized size
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            used_bytes: 0,
+            clock: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// g_tilde followed by Y_tilde of the messages not in `revealed` in increasing order of index.
+    /// Revealed indices must be less than the number of messages supported by `vk`.
+    pub fn hidden_bases<'a, I: Iterator<Item = &'a usize>>(
+        &mut self,
+        vk_id: &VerkeyId,
+        vk: &Verkey,
+        params: &Params,
+        revealed: I,
+    ) -> Cow<'_, [OtherGroup]> {
+        let key = (*vk_id, revealed_bitmap(vk.Y_tilde.len(), revealed));
+        self.clock += 1;
+        if self.entries.contains_key(&key) {
+            let entry = self.entries.get_mut(&key).unwrap();
+            entry.last_used = self.clock;
+            return Cow::Borrowed(&entry.bases);
+        }
+
+        let mut bases = vec![params.g_tilde.clone()];
+        for (i, y) in vk.Y_tilde.iter().enumerate() {
+            if key.1[i / 8] & (1 << (i % 8)) == 0 {
+                bases.push(y.clone());
+            }
+        }
+        let size = bases.len() * OTHER_GROUP_SIZE;
+        if size > self.max_bytes {
+            return Cow::Owned(bases);
+        }
+        while self.used_bytes + size > self.max_bytes {
+            self.evict();
+        }
+        self.used_bytes += size;
+        let entry = self.entries.entry(key).or_insert(CacheEntry {
+            bases,
+            last_used: self.clock,
+        });
+        Cow::Borrowed(&entry.bases)
+    }
+
+    fn evict(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, e)| e.last_used)
+            .map(|(k, _)| k.clone());
+        if let Some(k) = oldest {
+            let entry = self.entries.remove(&k).unwrap();
+            self.used_bytes -= entry.bases.len() * OTHER_GROUP_SIZE;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn used_bytes(&self) -> usize {
+        self.used_bytes
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.used_bytes = 0;
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct VerificationContext {
+    pub params: Params,
+    pub cache: HiddenBasesCache,
+}
+
+impl VerificationContext {
+    pub fn new(params: Params, max_cache_bytes: usize) -> Self {
+        Self {
+            params,
+            cache: HiddenBasesCache::new(max_cache_bytes),
+        }
+    }
+
+    /// Same as `Presentation::verify` with the bases of the proof of knowledge of hidden messages taken
+    /// from the cache. `vk_id` must identify `vk`.
+    pub fn verify_presentation(
+        &mut self,
+        presentation: &Presentation,
+        vk_id: &VerkeyId,
+        vk: &Verkey,
+    ) -> Result<bool, PSError> {
+        if presentation
+            .revealed_msgs
+            .keys()
+            .any(|i| *i >= vk.Y_tilde.len())
+        {
+            return Ok(false);
+        }
+        let challenge = presentation.challenge(vk, &self.params);
+        let bases =
+            self.cache
+                .hidden_bases(vk_id, vk, &self.params, presentation.revealed_msgs.keys());
+        presentation.proof.verify_with_hidden_bases(
+            vk,
+            &self.params,
+            &presentation.revealed_msgs,
+            &challenge,
+            &bases,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use crate::signature::Signature;
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::{FieldElement, FieldElementVector};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_verification_context() {
+        let count_msgs = 6;
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(count_msgs, &params);
+        let vk_id = verkey_id(&vk);
+        let msgs = FieldElementVector::random(count_msgs);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let present = |revealed: &[usize]| {
+            let revealed: HashSet<usize> = revealed.iter().cloned().collect();
+            Presentation::new(&sig, &vk, &params, msgs.as_slice(), revealed, b"nonce").unwrap()
+        };
+
+        // Room for 2 entries with 4 hidden messages each, 5 bases including g_tilde
+        let mut ctx = VerificationContext::new(params.clone(), 10 * OTHER_GROUP_SIZE);
+        let p1 = present(&[0, 1]);
+        let p2 = present(&[1, 0]);
+        let p3 = present(&[4, 5]);
+        assert!(ctx.verify_presentation(&p1, &vk_id, &vk).unwrap());
+        assert!(ctx.verify_presentation(&p2, &vk_id, &vk).unwrap());
+        assert_eq!(ctx.cache.len(), 1);
+        assert!(ctx.verify_presentation(&p3, &vk_id, &vk).unwrap());
+        assert_eq!(ctx.cache.len(), 2);
+        assert_eq!(ctx.cache.used_bytes(), 10 * OTHER_GROUP_SIZE);
+
+        // Least recently used entry, for revealed {4, 5}, is evicted
+        assert!(ctx.verify_presentation(&p1, &vk_id, &vk).unwrap());
+        let p4 = present(&[2, 3]);
+        assert!(ctx.verify_presentation(&p4, &vk_id, &vk).unwrap());
+        assert_eq!(ctx.cache.len(), 2);
+        assert!(ctx.cache.used_bytes() <= 10 * OTHER_GROUP_SIZE);
+
+        // Too large for the cache but still verified
+        let mut ctx_1 = VerificationContext::new(params.clone(), OTHER_GROUP_SIZE);
+        assert!(ctx_1.verify_presentation(&p1, &vk_id, &vk).unwrap());
+        assert!(ctx_1.cache.is_empty());
+
+        let mut p6 = p1.clone();
+        p6.revealed_msgs.insert(0, FieldElement::random());
+        assert!(!ctx.verify_presentation(&p6, &vk_id, &vk).unwrap());
+        let mut p7 = p1.clone();
+        p7.nonce = b"other nonce".to_vec();
+        assert!(!ctx.verify_presentation(&p7, &vk_id, &vk).unwrap());
+        let (_, vk_1) = keygen(count_msgs, &params);
+        assert!(!ctx
+            .verify_presentation(&p1, &verkey_id(&vk_1), &vk_1)
+            .unwrap());
+        ctx.cache.clear();
+        assert!(ctx.cache.is_empty());
+    }
+}
@@ -0,0 +1,47 @@
+// Operation counts for verification, for estimating the cost of verifying on constrained platforms
+// like smart contracts before deciding on the number of messages of a schema. Scalar multiplications are
+// counted individually even when done as one multi-scalar multiplication.
+
+use std::ops::Add;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CostModel {
+    /// Miller loops, products of pairings share one final exponentiation
+    pub pairings: usize,
+    pub final_exponentiations: usize,
+    pub g1_scalar_mults: usize,
+    pub g2_scalar_mults: usize,
+    /// Hashes of byte strings to field elements
+    pub hashes_to_field: usize,
+    pub hashed_bytes: usize,
+}
+
+impl CostModel {
+    /// Counts of scalar multiplications in `SignatureGroup` and `OtherGroup` as counts in G1 and G2
+    pub fn from_groups(signature_group_mults: usize, other_group_mults: usize) -> Self {
+        #[cfg(feature = "SignatureG2")]
+        let (g1, g2) = (other_group_mults, signature_group_mults);
+        #[cfg(feature = "SignatureG1")]
+        let (g1, g2) = (signature_group_mults, other_group_mults);
+        Self {
+            g1_scalar_mults: g1,
+            g2_scalar_mults: g2,
+            ..Self::default()
+        }
+    }
+}
+
+impl Add for CostModel {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            pairings: self.pairings + other.pairings,
+            final_exponentiations: self.final_exponentiations + other.final_exponentiations,
+            g1_scalar_mults: self.g1_scalar_mults + other.g1_scalar_mults,
+            g2_scalar_mults: self.g2_scalar_mults + other.g2_scalar_mults,
+            hashes_to_field: self.hashes_to_field + other.hashes_to_field,
+            hashed_bytes: self.hashed_bytes + other.hashed_bytes,
+        }
+    }
+}
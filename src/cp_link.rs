@@ -0,0 +1,356 @@
+// Commit-and-prove links in the style of LegoSNARK. The prover commits to some hidden messages of a
+// signature in a Pedersen commitment C = G_1^m_1 * G_2^m_2 * ... * H^r in G1 and proves that the
+// committed messages are the signed ones. A commit-and-prove SNARK, e.g. LegoGroth16 over BLS12-381,
+// then proves a heavyweight statement about the same C, so the signature never enters the circuit.
+//
+// The generators depend only on a label. G_i is RFC 9380 hash_to_curve of the suite
+// BLS12381G1_XMD:SHA-256_SSWU_RO_ of label || "G" || i as 4 byte big endian and H that of label || "H",
+// both under `CP_GENERATORS_DST`, so the SNARK setup can derive the same ones. The link is a Schnorr
+// proof of the opening of C sharing the blindings of the committed messages with the proof of knowledge
+// of the signature, like the tag of `LinkedPresentation`. The prover hands `CpOpening` to the SNARK
+// prover.
+
+use crate::errors::PSError;
+use crate::hash_to_curve::Rfc9380;
+use crate::keys::{Params, Verkey};
+use crate::pok_sig::{PoKOfSignature, PoKOfSignatureProof};
+use crate::signature::Signature;
+use crate::types::{Challenge, Message};
+use crate::validation::{deserialize_point, deserialize_points};
+use amcl_wrapper::field_elem::{FieldElement, FieldElementVector};
+use amcl_wrapper::group_elem::GroupElement;
+use amcl_wrapper::group_elem_g1::{G1Vector, G1};
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::{HashMap, HashSet};
+
+/// Tag the generators of `CpKey` are hashed with
+pub const CP_GENERATORS_DST: &[u8] = b"PS-SIG-V01-CP-LINK-with-BLS12381G1_XMD:SHA-256_SSWU_RO_";
+
+/// Generators of the commitments, shared with the SNARK
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CpKey {
+    pub label: Vec<u8>,
+    #[serde(deserialize_with = "deserialize_points")]
+    pub G: Vec<G1>,
+    #[serde(deserialize_with = "deserialize_point")]
+    pub H: G1,
+}
+
+impl CpKey {
+    /// Generators for commitments to `count` messages
+    pub fn new(label: &[u8], count: usize) -> Self {
+        let G = (0..count as u32)
+            .map(|i| {
+                Rfc9380::hash_to_g1_with_dst(
+                    &[label, b"G", &i.to_be_bytes()].concat(),
+                    CP_GENERATORS_DST,
+                )
+            })
+            .collect();
+        let H = Rfc9380::hash_to_g1_with_dst(&[label, b"H"].concat(), CP_GENERATORS_DST);
+        Self {
+            label: label.to_vec(),
+            G,
+            H,
+        }
+    }
+
+    pub fn commit(
+        &self,
+        messages: &[FieldElement],
+        blinding: &FieldElement,
+    ) -> Result<G1, PSError> {
+        if messages.len() != self.G.len() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: self.G.len(),
+                given: messages.len(),
+            });
+        }
+        let mut bases = self.G.clone();
+        bases.push(self.H.clone());
+        let mut exponents = messages.to_vec();
+        exponents.push(blinding.clone());
+        fail_point!(Commitment);
+        Ok(G1Vector::from(bases.as_slice())
+            .multi_scalar_mul_const_time(&FieldElementVector::from(exponents.as_slice()))
+            .unwrap())
+    }
+
+    /// Whether `commitment` opens to `opening`
+    pub fn verify_opening(&self, commitment: &G1, opening: &CpOpening) -> bool {
+        self.commit(&opening.messages, &opening.blinding)
+            .map(|c| &c == commitment)
+            .unwrap_or(false)
+    }
+}
+
+/// Opening of the commitment, the witness of the SNARK
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CpOpening {
+    /// In the order of `CpLinkedPresentation::committed_indices`
+    pub messages: Vec<FieldElement>,
+    pub blinding: FieldElement,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CpLinkedPresentation {
+    pub proof: PoKOfSignatureProof,
+    pub revealed_msgs: HashMap<usize, FieldElement>,
+    /// Message indices committed to, in the order of the generators
+    pub committed_indices: Vec<usize>,
+    #[serde(deserialize_with = "deserialize_point")]
+    pub commitment: G1,
+    /// Schnorr commitment G_1^b_1 * G_2^b_2 * ... * H^b_r for the opening of `commitment`
+    #[serde(deserialize_with = "deserialize_point")]
+    pub link_commitment: G1,
+    /// Response for the blinding of `commitment`
+    pub blinding_response: FieldElement,
+    pub nonce: Vec<u8>,
+}
+
+impl CpLinkedPresentation {
+    /// Presentation committing to the messages at `committed_indices` under `cp_key`, which needs one
+    /// generator per committed message. Committed messages must be hidden.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[FieldElement],
+        revealed_msg_indices: HashSet<usize>,
+        committed_indices: &[usize],
+        cp_key: &CpKey,
+        nonce: &[u8],
+    ) -> Result<(Self, CpOpening), PSError> {
+        Self::new_with_rng(
+            sig,
+            vk,
+            params,
+            messages,
+            revealed_msg_indices,
+            committed_indices,
+            cp_key,
+            nonce,
+            &mut thread_rng(),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[FieldElement],
+        revealed_msg_indices: HashSet<usize>,
+        committed_indices: &[usize],
+        cp_key: &CpKey,
+        nonce: &[u8],
+        rng: &mut R,
+    ) -> Result<(Self, CpOpening), PSError> {
+        if committed_indices.len() != cp_key.G.len() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: cp_key.G.len(),
+                given: committed_indices.len(),
+            });
+        }
+        let mut seen = HashSet::new();
+        for i in committed_indices {
+            if *i >= messages.len() || revealed_msg_indices.contains(i) || !seen.insert(*i) {
+                return Err(PSError::GeneralError {
+                    msg: format!(
+                        "Committed message {} should be a distinct hidden message",
+                        i
+                    ),
+                });
+            }
+        }
+
+        let mut blindings = vec![];
+        let mut blindings_by_idx = HashMap::new();
+        for i in 0..messages.len() {
+            if revealed_msg_indices.contains(&i) {
+                continue;
+            }
+            let b = FieldElement::random_using_rng(rng);
+            blindings_by_idx.insert(i, b.clone());
+            blindings.push(b);
+        }
+        let revealed_msgs = revealed_msg_indices
+            .iter()
+            .filter(|i| **i < messages.len())
+            .map(|i| (*i, messages[*i].clone()))
+            .collect();
+
+        let pok = PoKOfSignature::init_with_rng(
+            sig,
+            vk,
+            params,
+            &Message::from_field_elements(messages),
+            Some(&blindings),
+            revealed_msg_indices,
+            rng,
+        )?;
+        let opening = CpOpening {
+            messages: committed_indices
+                .iter()
+                .map(|i| messages[*i].clone())
+                .collect(),
+            blinding: FieldElement::random_using_rng(rng),
+        };
+        let commitment = cp_key.commit(&opening.messages, &opening.blinding)?;
+        let blinding_of_blinding = FieldElement::random_using_rng(rng);
+        let committed_blindings: Vec<FieldElement> = committed_indices
+            .iter()
+            .map(|i| blindings_by_idx[i].clone())
+            .collect();
+        let link_commitment = cp_key.commit(&committed_blindings, &blinding_of_blinding)?;
+
+        let challenge = Self::compute_challenge(
+            pok.to_bytes(),
+            cp_key,
+            committed_indices,
+            &commitment,
+            &link_commitment,
+            nonce,
+        );
+        let blinding_response = &blinding_of_blinding - &(&challenge.0 * &opening.blinding);
+        let presentation = Self {
+            proof: pok.gen_proof(&challenge)?,
+            revealed_msgs,
+            committed_indices: committed_indices.to_vec(),
+            commitment,
+            link_commitment,
+            blinding_response,
+            nonce: nonce.to_vec(),
+        };
+        Ok((presentation, opening))
+    }
+
+    fn compute_challenge(
+        mut bytes: Vec<u8>,
+        cp_key: &CpKey,
+        committed_indices: &[usize],
+        commitment: &G1,
+        link_commitment: &G1,
+        nonce: &[u8],
+    ) -> Challenge {
+        for g in cp_key.G.iter().chain(std::iter::once(&cp_key.H)) {
+            bytes.append(&mut g.to_bytes());
+        }
+        for i in committed_indices {
+            bytes.extend_from_slice(&(*i as u64).to_be_bytes());
+        }
+        bytes.append(&mut commitment.to_bytes());
+        bytes.append(&mut link_commitment.to_bytes());
+        bytes.extend_from_slice(nonce);
+        Challenge::from_msg_hash(&bytes)
+    }
+
+    pub fn revealed_msg_indices(&self) -> HashSet<usize> {
+        self.revealed_msgs.keys().cloned().collect()
+    }
+
+    /// Verify the proof of knowledge of signature and that `commitment` opens under `cp_key` to the
+    /// hidden messages at `committed_indices`. The SNARK is verified separately against `commitment`.
+    pub fn verify(&self, vk: &Verkey, params: &Params, cp_key: &CpKey) -> Result<bool, PSError> {
+        if self.committed_indices.len() != cp_key.G.len()
+            || self.commitment.is_identity()
+            || self
+                .revealed_msgs
+                .keys()
+                .chain(self.committed_indices.iter())
+                .any(|i| *i >= vk.Y_tilde.len())
+        {
+            return Ok(false);
+        }
+        let revealed_msg_indices = self.revealed_msg_indices();
+        let challenge = Self::compute_challenge(
+            self.proof
+                .get_bytes_for_challenge(&revealed_msg_indices, vk, params),
+            cp_key,
+            &self.committed_indices,
+            &self.commitment,
+            &self.link_commitment,
+            &self.nonce,
+        );
+        if !self
+            .proof
+            .verify(vk, params, self.revealed_msgs.clone(), &challenge)?
+        {
+            return Ok(false);
+        }
+        // G_1^resp_1 * G_2^resp_2 * ... * H^blinding_response * commitment^challenge == link_commitment
+        let mut responses = vec![];
+        for i in &self.committed_indices {
+            match self.proof.get_resp_for_message(*i, &revealed_msg_indices) {
+                Ok(r) => responses.push(r.0),
+                Err(_) => return Ok(false),
+            }
+        }
+        let lhs = cp_key.commit(&responses, &self.blinding_response)?;
+        Ok(&lhs + &(&self.commitment * &challenge.0) == self.link_commitment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+
+    #[test]
+    fn test_cp_linked_presentation() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(5, &params);
+        let msgs = FieldElementVector::random(5);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let cp_key = CpKey::new(b"credit score", 2);
+        assert_eq!(cp_key, CpKey::new(b"credit score", 2));
+        assert_ne!(cp_key.G[0], CpKey::new(b"other", 2).G[0]);
+        let mut revealed = HashSet::new();
+        revealed.insert(0);
+
+        let (presentation, opening) = CpLinkedPresentation::new(
+            &sig,
+            &vk,
+            &params,
+            msgs.as_slice(),
+            revealed.clone(),
+            &[3, 1],
+            &cp_key,
+            b"nonce",
+        )
+        .unwrap();
+        assert!(presentation.verify(&vk, &params, &cp_key).unwrap());
+        assert!(cp_key.verify_opening(&presentation.commitment, &opening));
+        assert_eq!(opening.messages, vec![msgs[3].clone(), msgs[1].clone()]);
+
+        // The commitment must be to the signed messages
+        let mut bad = presentation.clone();
+        bad.commitment = cp_key
+            .commit(&[msgs[1].clone(), msgs[3].clone()], &opening.blinding)
+            .unwrap();
+        assert!(!bad.verify(&vk, &params, &cp_key).unwrap());
+        let mut bad = presentation.clone();
+        bad.committed_indices = vec![3, 2];
+        assert!(!bad.verify(&vk, &params, &cp_key).unwrap());
+        assert!(!presentation
+            .verify(&vk, &params, &CpKey::new(b"other", 2))
+            .unwrap());
+
+        // Committed messages must be hidden and match the generators
+        for committed in [&[0, 1][..], &[1, 1], &[1]] {
+            assert!(CpLinkedPresentation::new(
+                &sig,
+                &vk,
+                &params,
+                msgs.as_slice(),
+                revealed.clone(),
+                committed,
+                &cp_key,
+                b"nonce",
+            )
+            .is_err());
+        }
+    }
+}
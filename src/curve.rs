@@ -0,0 +1,66 @@
+// The pairing configuration of this build, for deployments to check that all parties use the same one.
+// Signatures, keys and proofs of a `SignatureG2` build do not decode in a `SignatureG1` build and the
+// other way round. The asserts below fail the build if the group aliases do not match the feature.
+
+use crate::{FIELD_ELEMENT_SIZE, OTHER_GROUP_SIZE, SIGNATURE_GROUP_SIZE};
+use amcl_wrapper::constants::{CurveOrderBitSize, GroupG1_SIZE, GroupG2_SIZE};
+
+const _: () = assert!(
+    FIELD_ELEMENT_SIZE == 48,
+    "amcl_wrapper is not built for BLS12-381"
+);
+#[cfg(feature = "SignatureG2")]
+const _: () = assert!(SIGNATURE_GROUP_SIZE == GroupG2_SIZE && OTHER_GROUP_SIZE == GroupG1_SIZE);
+#[cfg(feature = "SignatureG1")]
+const _: () = assert!(SIGNATURE_GROUP_SIZE == GroupG1_SIZE && OTHER_GROUP_SIZE == GroupG2_SIZE);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurveInfo {
+    pub curve: String,
+    /// "G1" or "G2", the group of signatures
+    pub signature_group: String,
+    /// The group of verkeys
+    pub other_group: String,
+    pub signature_group_size: usize,
+    pub other_group_size: usize,
+    pub field_element_size: usize,
+    pub curve_order_bits: usize,
+    /// Estimated, BLS12-381 targets 128 bits and current estimates are slightly below
+    pub security_bits: usize,
+}
+
+/// The curve and groups this build is configured with
+pub fn curve_info() -> CurveInfo {
+    let (signature_group, other_group) = if cfg!(feature = "SignatureG2") {
+        ("G2", "G1")
+    } else {
+        ("G1", "G2")
+    };
+    CurveInfo {
+        curve: String::from("BLS12-381"),
+        signature_group: String::from(signature_group),
+        other_group: String::from(other_group),
+        signature_group_size: SIGNATURE_GROUP_SIZE,
+        other_group_size: OTHER_GROUP_SIZE,
+        field_element_size: FIELD_ELEMENT_SIZE,
+        curve_order_bits: *CurveOrderBitSize,
+        security_bits: 128,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::Params;
+    use amcl_wrapper::group_elem::GroupElement;
+
+    #[test]
+    fn test_curve_info() {
+        let info = curve_info();
+        assert_eq!(info.curve_order_bits, 255);
+        let params = Params::new("test".as_bytes());
+        assert_eq!(params.g.to_bytes().len(), info.signature_group_size);
+        assert_eq!(params.g_tilde.to_bytes().len(), info.other_group_size);
+        assert_ne!(info.signature_group, info.other_group);
+    }
+}
@@ -0,0 +1,281 @@
+// Presentation keys for handing a credential to a semi-trusted agent, e.g. a browser extension, that
+// may only present a fixed set of revealed attributes. The holder precomputes one-time tokens: each is
+// a proof of knowledge of a randomized signature whose proof of knowledge of the hidden messages in J
+// is completed with a challenge over J and its commitment alone. The agent gets the tokens and the
+// revealed messages but never the hidden ones. For a presentation it randomizes the token's signature
+// once more with r and t', so that J' = J * g_tilde^t', and proves knowledge of t' with a challenge
+// over the verifier's nonce. Since the agent only knows the opening of J' relative to J and the token
+// fixes the hidden messages, it can neither reveal nor hide other attributes. A token presented twice
+// shows the same J, so each token should be used once.
+
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::pok_sig::{PoKOfSignature, PoKOfSignatureProof, ProofOtherGroup};
+use crate::signature::Signature;
+use crate::types::{Challenge, Message};
+use crate::utils::MultiScalarMul;
+use crate::validation::deserialize_point;
+use crate::{ate_2_pairing, OtherGroup};
+use amcl_wrapper::field_elem::FieldElement;
+use amcl_wrapper::group_elem::GroupElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::{HashMap, HashSet};
+
+const DOMAIN: &[u8] = b"PS presentation token";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PresentationKey {
+    pub revealed_msgs: HashMap<usize, FieldElement>,
+    /// Unused tokens, `present` takes the last one
+    pub tokens: Vec<PoKOfSignatureProof>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DelegatedPresentation {
+    /// The token's signature randomized again by the agent
+    pub sig: Signature,
+    /// J of the token
+    #[serde(deserialize_with = "deserialize_point")]
+    pub J: OtherGroup,
+    /// The holder's proof of knowledge of the hidden messages in J
+    pub token_proof: ProofOtherGroup,
+    /// J * g_tilde^t'
+    #[serde(deserialize_with = "deserialize_point")]
+    pub J_prime: OtherGroup,
+    /// Schnorr commitment g_tilde^blinding for t'
+    #[serde(deserialize_with = "deserialize_point")]
+    pub commitment: OtherGroup,
+    pub response: FieldElement,
+    pub revealed_msgs: HashMap<usize, FieldElement>,
+    pub nonce: Vec<u8>,
+}
+
+/// g_tilde followed by Y_tilde of the hidden messages in increasing order of index
+fn hidden_bases(
+    vk: &Verkey,
+    params: &Params,
+    revealed_msgs: &HashMap<usize, FieldElement>,
+) -> Vec<OtherGroup> {
+    let mut bases = vec![params.g_tilde.clone()];
+    for (i, Y_tilde_i) in vk.Y_tilde.iter().enumerate() {
+        if !revealed_msgs.contains_key(&i) {
+            bases.push(Y_tilde_i.clone());
+        }
+    }
+    bases
+}
+
+/// Challenge of the token's proof, same as `ProverCommitted::gen_challenge` with J and the domain
+fn token_challenge(bases: &[OtherGroup], commitment: &OtherGroup, J: &OtherGroup) -> Challenge {
+    let mut bytes = vec![];
+    for b in bases {
+        bytes.append(&mut b.to_bytes());
+    }
+    bytes.append(&mut commitment.to_bytes());
+    bytes.append(&mut J.to_bytes());
+    bytes.extend_from_slice(DOMAIN);
+    Challenge::from_msg_hash(&bytes)
+}
+
+impl PresentationKey {
+    /// Holder side. Creates `count_tokens` tokens for presentations revealing exactly
+    /// `revealed_msg_indices`.
+    pub fn new(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[FieldElement],
+        revealed_msg_indices: HashSet<usize>,
+        count_tokens: usize,
+    ) -> Result<Self, PSError> {
+        Self::new_with_rng(
+            sig,
+            vk,
+            params,
+            messages,
+            revealed_msg_indices,
+            count_tokens,
+            &mut thread_rng(),
+        )
+    }
+
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[FieldElement],
+        revealed_msg_indices: HashSet<usize>,
+        count_tokens: usize,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        let msgs = Message::from_field_elements(messages);
+        let mut tokens = Vec::with_capacity(count_tokens);
+        for _ in 0..count_tokens {
+            let pok = PoKOfSignature::init_with_rng(
+                sig,
+                vk,
+                params,
+                &msgs,
+                None,
+                revealed_msg_indices.clone(),
+                rng,
+            )?;
+            let challenge = pok
+                .pok_vc
+                .gen_challenge([pok.J.to_bytes(), DOMAIN.to_vec()].concat());
+            tokens.push(pok.gen_proof(&challenge)?);
+        }
+        let revealed_msgs = revealed_msg_indices
+            .iter()
+            .map(|i| (*i, messages[*i].clone()))
+            .collect();
+        Ok(Self {
+            revealed_msgs,
+            tokens,
+        })
+    }
+
+    pub fn remaining_tokens(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Agent side. Uses up a token, fails when none is left.
+    pub fn present(
+        &mut self,
+        params: &Params,
+        nonce: &[u8],
+    ) -> Result<DelegatedPresentation, PSError> {
+        self.present_with_rng(params, nonce, &mut thread_rng())
+    }
+
+    pub fn present_with_rng<R: RngCore + CryptoRng>(
+        &mut self,
+        params: &Params,
+        nonce: &[u8],
+        rng: &mut R,
+    ) -> Result<DelegatedPresentation, PSError> {
+        let token = self.tokens.pop().ok_or_else(|| PSError::GeneralError {
+            msg: String::from("No presentation tokens left"),
+        })?;
+        let r = FieldElement::random_using_rng(rng);
+        let t = FieldElement::random_using_rng(rng);
+        // Same randomization as `PoKOfSignature::init`, the signature is now on the extra message t + t'
+        let sig = Signature {
+            sigma_1: &token.sig.sigma_1 * &r,
+            sigma_2: (&token.sig.sigma_2 + (&token.sig.sigma_1 * &t)) * &r,
+        };
+        let J_prime = &token.J + &(&params.g_tilde * &t);
+        let blinding = FieldElement::random_using_rng(rng);
+        let commitment = &params.g_tilde * &blinding;
+        let mut presentation = DelegatedPresentation {
+            sig,
+            J: token.J,
+            token_proof: token.proof_vc,
+            J_prime,
+            commitment,
+            response: FieldElement::zero(),
+            revealed_msgs: self.revealed_msgs.clone(),
+            nonce: nonce.to_vec(),
+        };
+        let challenge = presentation.challenge();
+        presentation.response = &blinding - &(&challenge.0 * &t);
+        Ok(presentation)
+    }
+}
+
+impl DelegatedPresentation {
+    fn challenge(&self) -> Challenge {
+        let mut bytes = self.sig.to_bytes();
+        bytes.append(&mut self.J.to_bytes());
+        bytes.append(&mut self.token_proof.to_bytes());
+        bytes.append(&mut self.J_prime.to_bytes());
+        bytes.append(&mut self.commitment.to_bytes());
+        let mut revealed: Vec<_> = self.revealed_msgs.iter().collect();
+        revealed.sort_by_key(|(i, _)| **i);
+        for (i, m) in revealed {
+            bytes.extend_from_slice(&(*i as u64).to_be_bytes());
+            bytes.append(&mut m.to_bytes());
+        }
+        bytes.extend_from_slice(&self.nonce);
+        Challenge::from_msg_hash(&bytes)
+    }
+
+    pub fn verify(&self, vk: &Verkey, params: &Params) -> Result<bool, PSError> {
+        if self.sig.sigma_1.is_identity()
+            || self.sig.sigma_2.is_identity()
+            || self.revealed_msgs.keys().any(|i| *i >= vk.Y_tilde.len())
+        {
+            return Ok(false);
+        }
+        // The token proves knowledge of t and the hidden messages in J
+        let bases = hidden_bases(vk, params, &self.revealed_msgs);
+        let token_challenge = token_challenge(&bases, &self.token_proof.commitment, &self.J);
+        if !self
+            .token_proof
+            .verify(&bases, &self.J, &token_challenge)
+            .unwrap_or(false)
+        {
+            return Ok(false);
+        }
+        // g_tilde^response * (J' / J)^challenge == commitment
+        let challenge = self.challenge();
+        if &(&params.g_tilde * &self.response) + &(&(&self.J_prime - &self.J) * &challenge.0)
+            != self.commitment
+        {
+            return Ok(false);
+        }
+        let mut msm = MultiScalarMul::new();
+        for (i, m) in &self.revealed_msgs {
+            msm.add(&vk.Y_tilde[*i], m);
+        }
+        let J = &(&self.J_prime + &vk.X_tilde) + &msm.finish();
+        let res = ate_2_pairing(
+            &self.sig.sigma_1,
+            &J,
+            &(-&self.sig.sigma_2),
+            &params.g_tilde,
+        );
+        Ok(res.is_one())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use amcl_wrapper::field_elem::FieldElementVector;
+
+    #[test]
+    fn test_presentation_key() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(4, &params);
+        let msgs = FieldElementVector::random(4);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let mut revealed = HashSet::new();
+        revealed.insert(2);
+        let mut key =
+            PresentationKey::new(&sig, &vk, &params, msgs.as_slice(), revealed, 2).unwrap();
+
+        let p1 = key.present(&params, b"nonce 1").unwrap();
+        let p2 = key.present(&params, b"nonce 2").unwrap();
+        assert!(p1.verify(&vk, &params).unwrap());
+        assert!(p2.verify(&vk, &params).unwrap());
+        assert_ne!(p1.J, p2.J);
+        assert_eq!(key.remaining_tokens(), 0);
+        assert!(key.present(&params, b"nonce 3").is_err());
+
+        // Bound to the nonce
+        let mut replayed = p1.clone();
+        replayed.nonce = b"nonce 3".to_vec();
+        assert!(!replayed.verify(&vk, &params).unwrap());
+        // The revealed set is fixed by the token, revealing a hidden message or hiding a revealed
+        // one fails
+        let mut more = p1.clone();
+        more.revealed_msgs.insert(0, msgs[0].clone());
+        assert!(!more.verify(&vk, &params).unwrap());
+        let mut fewer = p1;
+        fewer.revealed_msgs.clear();
+        assert!(!fewer.verify(&vk, &params).unwrap());
+    }
+}
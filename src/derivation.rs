@@ -0,0 +1,106 @@
+// Deterministic derivation of signing keys so an issuer service stores one master seed instead of a key
+// per schema or tenant. `Sigkey::from_seed` derives the master key and `Sigkey::derive_child` a key
+// along a path of indices, each step hashing the parent key with the index into the seed of the child.
+// Derivation is hardened only: a child key or verkey reveals nothing about its parent or siblings.
+//
+// Children have the message count of their parent. Keys derived from the same node with different
+// counts would share x and y_1, y_2, ... and so accept each other's signatures with trailing zero
+// messages, derive from different masters for different counts.
+
+use crate::errors::PSError;
+use crate::keys::Sigkey;
+use crate::utils::{hash_with_domain, HASH_SIZE};
+use amcl_wrapper::field_elem::FieldElement;
+
+const SEED_DOMAIN: &[u8] = b"PS sigkey from seed : ";
+const CHILD_DOMAIN: &[u8] = b"PS sigkey child : ";
+
+pub const MIN_SEED_SIZE: usize = 32;
+
+impl Sigkey {
+    /// Master key for `count_messages` messages from at least `MIN_SEED_SIZE` bytes of secret `seed`
+    pub fn from_seed(seed: &[u8], count_messages: usize) -> Result<Self, PSError> {
+        if seed.len() < MIN_SEED_SIZE {
+            return Err(PSError::GeneralError {
+                msg: format!(
+                    "Seed needs at least {} bytes but given {}",
+                    MIN_SEED_SIZE,
+                    seed.len()
+                ),
+            });
+        }
+        let scalar = |i: u64| {
+            let mut bytes = SEED_DOMAIN.to_vec();
+            bytes.extend_from_slice(&i.to_be_bytes());
+            bytes.extend_from_slice(seed);
+            FieldElement::from_msg_hash(&bytes)
+        };
+        Ok(Self {
+            x: scalar(0),
+            y: (1..=count_messages as u64).map(scalar).collect(),
+        })
+    }
+
+    /// Key at `path` below this one, e.g. &[tenant, schema]. The empty path gives this key.
+    pub fn derive_child(&self, path: &[u32]) -> Self {
+        let mut key = self.clone();
+        for index in path {
+            let mut bytes = key.to_versioned_bytes();
+            bytes.extend_from_slice(&index.to_be_bytes());
+            let seed: [u8; HASH_SIZE] = hash_with_domain(CHILD_DOMAIN, &bytes);
+            key = Self::from_seed(&seed, self.y.len()).unwrap();
+        }
+        key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::{Keypair, Params};
+    use crate::signature::Signature;
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::FieldElementVector;
+
+    #[test]
+    fn test_derive_child() {
+        let params = Params::new("test".as_bytes());
+        let master = Sigkey::from_seed(&[7; 32], 3).unwrap();
+        assert!(Sigkey::from_seed(&[7; 31], 3).is_err());
+
+        let child = master.derive_child(&[1, 2]);
+        assert_eq!(
+            child.to_versioned_bytes(),
+            master
+                .derive_child(&[1])
+                .derive_child(&[2])
+                .to_versioned_bytes()
+        );
+        assert_eq!(
+            Sigkey::from_seed(&[7; 32], 3)
+                .unwrap()
+                .derive_child(&[1, 2])
+                .to_versioned_bytes(),
+            child.to_versioned_bytes()
+        );
+        for other in &[master.derive_child(&[2, 1]), master.derive_child(&[1])] {
+            assert_ne!(other.to_versioned_bytes(), child.to_versioned_bytes());
+        }
+
+        let keypair = Keypair::from_sigkey(child, &params);
+        let msgs = FieldElementVector::random(3);
+        let sig = Signature::new(
+            &Message::from_field_elements(msgs.as_slice()),
+            &keypair.sigkey,
+            &params,
+        )
+        .unwrap();
+        assert!(sig
+            .verify(
+                &Message::from_field_elements(msgs.as_slice()),
+                &keypair.verkey,
+                &params
+            )
+            .unwrap());
+    }
+}
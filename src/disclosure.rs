@@ -0,0 +1,242 @@
+// Human readable account of what a presentation discloses. The document lists the issuer, the schema,
+// the revealed attributes with their names and values and the checks the verifier performs. Its hash
+// is part of the challenge of a presentation created with `Presentation::new_with_disclosure`, so the
+// document a holder approves is bound to the proof and a verifier can check that the document shown to
+// it is the approved one.
+//
+// A displayed value is only allowed when the revealed message is its canonical encoding, see
+// `encode_attribute`, other messages are shown as hex.
+
+use crate::encoding_proof::encode_attribute;
+use crate::errors::PSError;
+use crate::signature::Fingerprint;
+use crate::utils::hash_with_domain;
+use amcl_wrapper::field_elem::FieldElement;
+use std::collections::HashMap;
+use std::fmt;
+
+/// What the holder knows about the credential but the proof does not carry
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DisclosureContext {
+    pub issuer: String,
+    pub schema: String,
+    /// Names of all messages in index order
+    pub attribute_names: Vec<String>,
+    pub checks: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DisclosedAttribute {
+    pub index: usize,
+    pub name: String,
+    /// Plain value if the message is its canonical encoding
+    pub value: Option<String>,
+    pub message: FieldElement,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DisclosureDocument {
+    pub issuer: String,
+    pub schema: String,
+    /// In increasing order of index
+    pub attributes: Vec<DisclosedAttribute>,
+    pub checks: Vec<String>,
+}
+
+impl DisclosureDocument {
+    /// Document for `revealed_msgs` with optional plain `values` of some of them
+    pub fn new(
+        context: &DisclosureContext,
+        revealed_msgs: &HashMap<usize, FieldElement>,
+        values: &HashMap<usize, String>,
+    ) -> Result<Self, PSError> {
+        let mut indices: Vec<usize> = revealed_msgs.keys().cloned().collect();
+        indices.sort_unstable();
+        let mut attributes = vec![];
+        for i in indices {
+            let name = context
+                .attribute_names
+                .get(i)
+                .ok_or_else(|| PSError::GeneralError {
+                    msg: format!("No attribute name for message {}", i),
+                })?;
+            let message = revealed_msgs[&i].clone();
+            let value = match values.get(&i) {
+                Some(v) if encode_attribute(v) != message => {
+                    return Err(PSError::GeneralError {
+                        msg: format!("Message {} is not the encoding of {:?}", i, v),
+                    })
+                }
+                v => v.cloned(),
+            };
+            attributes.push(DisclosedAttribute {
+                index: i,
+                name: name.clone(),
+                value,
+                message,
+            });
+        }
+        if let Some(i) = values.keys().find(|i| !revealed_msgs.contains_key(i)) {
+            return Err(PSError::GeneralError {
+                msg: format!("Value given for message {} which is not revealed", i),
+            });
+        }
+        Ok(Self {
+            issuer: context.issuer.clone(),
+            schema: context.schema.clone(),
+            attributes,
+            checks: context.checks.clone(),
+        })
+    }
+
+    /// Unambiguous encoding with length prefixed strings
+    pub fn to_canonical_bytes(&self) -> Vec<u8> {
+        fn put_str(bytes: &mut Vec<u8>, s: &str) {
+            bytes.extend_from_slice(&(s.len() as u64).to_be_bytes());
+            bytes.extend_from_slice(s.as_bytes());
+        }
+        let mut bytes = vec![];
+        put_str(&mut bytes, &self.issuer);
+        put_str(&mut bytes, &self.schema);
+        bytes.extend_from_slice(&(self.attributes.len() as u64).to_be_bytes());
+        for a in &self.attributes {
+            bytes.extend_from_slice(&(a.index as u64).to_be_bytes());
+            put_str(&mut bytes, &a.name);
+            match &a.value {
+                Some(v) => {
+                    bytes.push(1);
+                    put_str(&mut bytes, v);
+                }
+                None => bytes.push(0),
+            }
+            bytes.append(&mut a.message.to_bytes());
+        }
+        bytes.extend_from_slice(&(self.checks.len() as u64).to_be_bytes());
+        for c in &self.checks {
+            put_str(&mut bytes, c);
+        }
+        bytes
+    }
+
+    pub fn hash(&self) -> Fingerprint {
+        hash_with_domain(b"PS disclosure document", &self.to_canonical_bytes())
+    }
+
+    /// Whether the document shows exactly `revealed_msgs`
+    pub fn matches(&self, revealed_msgs: &HashMap<usize, FieldElement>) -> bool {
+        self.attributes.len() == revealed_msgs.len()
+            && self.attributes.iter().all(|a| {
+                revealed_msgs.get(&a.index) == Some(&a.message)
+                    && a.value
+                        .as_ref()
+                        .is_none_or(|v| encode_attribute(v) == a.message)
+            })
+    }
+}
+
+impl fmt::Display for DisclosureDocument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Issuer: {}", self.issuer)?;
+        writeln!(f, "Schema: {}", self.schema)?;
+        writeln!(f, "Disclosed attributes:")?;
+        for a in &self.attributes {
+            match &a.value {
+                Some(v) => writeln!(f, "  {}: {}", a.name, v)?,
+                None => writeln!(f, "  {}: 0x{}", a.name, a.message.to_hex())?,
+            }
+        }
+        writeln!(f, "Checks:")?;
+        for c in &self.checks {
+            writeln!(f, "  {}", c)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::{keygen, Params};
+    use crate::presentation::Presentation;
+    use crate::signature::Signature;
+    use crate::types::Message;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_disclosure_document() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(4, &params);
+        let msgs = vec![
+            encode_attribute("Alice"),
+            encode_attribute("alice@example.com"),
+            FieldElement::from(1990u64),
+            FieldElement::random(),
+        ];
+        let sig = Signature::new(&Message::from_field_elements(&msgs), &sk, &params).unwrap();
+        let context = DisclosureContext {
+            issuer: String::from("Example University"),
+            schema: String::from("degree-v1"),
+            attribute_names: vec!["name", "email", "birth year", "student id"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            checks: vec![String::from("Signature by the issuer's key")],
+        };
+        let mut revealed = HashSet::new();
+        revealed.insert(1);
+        revealed.insert(2);
+        let mut values = HashMap::new();
+        values.insert(1, String::from("alice@example.com"));
+
+        let pres = Presentation::new_with_disclosure(
+            &sig,
+            &vk,
+            &params,
+            &msgs,
+            revealed.clone(),
+            b"nonce",
+            &context,
+            &values,
+        )
+        .unwrap();
+        assert!(pres.verify(&vk, &params).unwrap());
+        let doc = pres.render_disclosure(&context, &values).unwrap();
+        assert!(pres.verify_disclosure(&doc));
+        assert_eq!(doc.attributes.len(), 2);
+        let text = doc.to_string();
+        assert!(text.contains("email: alice@example.com"));
+        assert!(text.contains("birth year: 0x"));
+
+        // The document is bound to the proof
+        let bytes = pres.to_bytes_v2(&vk, &params);
+        let pres_1 = Presentation::from_bytes(&bytes, &vk, &params).unwrap();
+        assert_eq!(pres_1.disclosure_hash, pres.disclosure_hash);
+        assert!(pres_1.verify(&vk, &params).unwrap());
+        let mut pres_2 = pres.clone();
+        pres_2.disclosure_hash = None;
+        assert!(!pres_2.verify(&vk, &params).unwrap());
+        // nor can the hash be moved into the nonce
+        pres_2
+            .nonce
+            .extend_from_slice(&pres.disclosure_hash.unwrap());
+        assert!(!pres_2.verify(&vk, &params).unwrap());
+
+        // A different document is not accepted
+        let mut context_1 = context.clone();
+        context_1.issuer = String::from("Other University");
+        assert!(pres.render_disclosure(&context_1, &values).is_err());
+        let mut doc_1 = doc.clone();
+        doc_1.attributes[0].message = msgs[0].clone();
+        assert!(!pres.verify_disclosure(&doc_1));
+
+        // Displayed value must be the encoding of the message
+        values.insert(1, String::from("bob@example.com"));
+        assert!(DisclosureDocument::new(&context, &pres.revealed_msgs, &values).is_err());
+        assert!(
+            Presentation::new(&sig, &vk, &params, &msgs, revealed, b"nonce")
+                .unwrap()
+                .render_disclosure(&context, &HashMap::new())
+                .is_err()
+        );
+    }
+}
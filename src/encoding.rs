@@ -0,0 +1,214 @@
+// Deterministic encodings of application data as messages. Strings are hashed with
+// `encode_attribute`, so they cannot be decoded and are compared by equality only. Integers, booleans,
+// dates and decimals are encoded as integers below 2^64 and decode back to the value. The numeric
+// encodings preserve order: a < b exactly when encode(a) < encode(b) as integers, so predicates like
+// "older than 18" become range checks on the messages. Signed integers are offset by 2^63, dates are
+// days since 1970-01-01 and decimals are integers of a fixed number of fractional digits.
+
+use crate::errors::PSError;
+use crate::FIELD_ELEMENT_SIZE;
+use amcl_wrapper::field_elem::FieldElement;
+use std::convert::TryFrom;
+
+pub use crate::encoding_proof::encode_attribute as encode_string;
+
+const SIGN_BIT: u64 = 1 << 63;
+
+fn encode_u64(value: u64) -> FieldElement {
+    // `FieldElement::from(u64)` goes through `isize` and is wrong for values from 2^63
+    let mut bytes = [0; FIELD_ELEMENT_SIZE];
+    bytes[FIELD_ELEMENT_SIZE - 8..].copy_from_slice(&value.to_be_bytes());
+    FieldElement::from(&bytes)
+}
+
+fn decode_u64(message: &FieldElement) -> Result<u64, PSError> {
+    let bytes = message.to_bytes();
+    let (high, low) = bytes.split_at(bytes.len() - 8);
+    if high.iter().any(|b| *b != 0) {
+        return Err(PSError::InvalidEncoding {
+            msg: String::from("Message is not a numeric encoding"),
+        });
+    }
+    let mut b = [0; 8];
+    b.copy_from_slice(low);
+    Ok(u64::from_be_bytes(b))
+}
+
+pub fn encode_integer(value: i64) -> FieldElement {
+    encode_u64(value as u64 ^ SIGN_BIT)
+}
+
+pub fn decode_integer(message: &FieldElement) -> Result<i64, PSError> {
+    Ok((decode_u64(message)? ^ SIGN_BIT) as i64)
+}
+
+/// false is 0 and true is 1
+pub fn encode_bool(value: bool) -> FieldElement {
+    encode_u64(value as u64)
+}
+
+pub fn decode_bool(message: &FieldElement) -> Result<bool, PSError> {
+    match decode_u64(message)? {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(PSError::InvalidEncoding {
+            msg: String::from("Message is not a boolean encoding"),
+        }),
+    }
+}
+
+/// Days since 1970-01-01 of a date of the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = era * 400 + yoe + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Fails for dates that do not exist, e.g. February 30
+pub fn encode_date(year: i32, month: u32, day: u32) -> Result<FieldElement, PSError> {
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(PSError::InvalidEncoding {
+            msg: format!("Invalid date {}-{:02}-{:02}", year, month, day),
+        });
+    }
+    let days = days_from_civil(year as i64, month, day);
+    if civil_from_days(days) != (year as i64, month, day) {
+        return Err(PSError::InvalidEncoding {
+            msg: format!("Invalid date {}-{:02}-{:02}", year, month, day),
+        });
+    }
+    Ok(encode_integer(days))
+}
+
+/// (year, month, day)
+pub fn decode_date(message: &FieldElement) -> Result<(i32, u32, u32), PSError> {
+    let days = decode_integer(message)?;
+    // Keeps the year within i32
+    if days.unsigned_abs() > 365 * (i32::MAX as u64 - 1) {
+        return Err(PSError::InvalidEncoding {
+            msg: String::from("Message is not a date encoding"),
+        });
+    }
+    let (year, month, day) = civil_from_days(days);
+    Ok((year as i32, month, day))
+}
+
+/// Decimal like "-12.5" with at most `scale` fractional digits, encoded as the integer
+/// value * 10^scale. Values with more fractional digits are rejected rather than rounded.
+pub fn encode_decimal(value: &str, scale: u32) -> Result<FieldElement, PSError> {
+    let invalid = || PSError::InvalidEncoding {
+        msg: format!("Invalid decimal {:?} with scale {}", value, scale),
+    };
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value),
+    };
+    let (int_part, frac_part) = match digits.split_once('.') {
+        Some((i, f)) if !f.is_empty() => (i, f),
+        Some(_) => return Err(invalid()),
+        None => (digits, ""),
+    };
+    if int_part.is_empty()
+        || frac_part.len() > scale as usize
+        || !int_part
+            .bytes()
+            .chain(frac_part.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    let padded = format!("{}{:0<width$}", int_part, frac_part, width = scale as usize);
+    // Parsed as i128 so that i64::MIN is accepted
+    let magnitude: i128 = padded.parse().map_err(|_| invalid())?;
+    let units = if negative { -magnitude } else { magnitude };
+    let units = i64::try_from(units).map_err(|_| invalid())?;
+    Ok(encode_integer(units))
+}
+
+/// Canonical form with exactly `scale` fractional digits, e.g. "-12.50" for scale 2
+pub fn decode_decimal(message: &FieldElement, scale: u32) -> Result<String, PSError> {
+    let units = decode_integer(message)?;
+    let sign = if units < 0 { "-" } else { "" };
+    let digits = format!(
+        "{:0>width$}",
+        units.unsigned_abs(),
+        width = scale as usize + 1
+    );
+    let (int_part, frac_part) = digits.split_at(digits.len() - scale as usize);
+    if scale == 0 {
+        return Ok(format!("{}{}", sign, int_part));
+    }
+    Ok(format!("{}{}.{}", sign, int_part, frac_part))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encodings() {
+        for v in &[i64::MIN, -1, 0, 1, 42, i64::MAX] {
+            assert_eq!(decode_integer(&encode_integer(*v)).unwrap(), *v);
+        }
+        // Order is preserved
+        assert!(encode_integer(-5) < encode_integer(3));
+        assert!(encode_integer(3) < encode_integer(4));
+        assert!(decode_integer(&FieldElement::random()).is_err());
+        assert!(!decode_bool(&encode_bool(false)).unwrap());
+        assert!(decode_bool(&encode_bool(true)).unwrap());
+        assert!(decode_bool(&FieldElement::from(2u64)).is_err());
+
+        assert_eq!(
+            decode_integer(&encode_date(1970, 1, 1).unwrap()).unwrap(),
+            0
+        );
+        assert_eq!(
+            decode_integer(&encode_date(2000, 3, 1).unwrap()).unwrap(),
+            11017
+        );
+        for (y, m, d) in &[(1969, 12, 31), (2000, 2, 29), (2024, 12, 31), (-44, 3, 15)] {
+            let e = encode_date(*y, *m, *d).unwrap();
+            assert_eq!(decode_date(&e).unwrap(), (*y, *m, *d));
+        }
+        assert!(encode_date(1990, 5, 1).unwrap() < encode_date(2006, 10, 14).unwrap());
+        assert!(encode_date(2023, 2, 29).is_err());
+        assert!(encode_date(2023, 13, 1).is_err());
+
+        assert_eq!(
+            decode_decimal(&encode_decimal("-12.5", 2).unwrap(), 2).unwrap(),
+            "-12.50"
+        );
+        assert_eq!(encode_decimal("0.05", 2).unwrap(), encode_integer(5));
+        assert_eq!(
+            decode_decimal(&encode_decimal("7", 0).unwrap(), 0).unwrap(),
+            "7"
+        );
+        assert!(encode_decimal("1.999", 2)
+            .unwrap_err()
+            .to_string()
+            .contains("1.999"));
+        assert!(encode_decimal("1.", 2).is_err());
+        assert!(encode_decimal("1e3", 2).is_err());
+        assert!(encode_decimal("99.5", 1).unwrap() < encode_decimal("100", 1).unwrap());
+
+        assert_eq!(encode_string("NL"), encode_string("NL"));
+        assert_ne!(encode_string("NL"), encode_string("nl"));
+    }
+}
@@ -0,0 +1,243 @@
+// Blind issuance where the holder tells the issuer the plain values of some committed attributes, e.g.
+// strings the issuer has checked out of band, while other committed attributes stay hidden. The request
+// proves that the commitment contains exactly the canonical encodings of the stated values at their
+// indices. The issuer removes Y_i^encode(value_i) for every stated value from the commitment and checks
+// a proof of knowledge of the opening of the rest, so a holder cannot commit a different value than
+// the one shown to the issuer.
+
+use crate::blind_signature::{BlindingKey, ProofSignatureGroup, ProverCommittingSignatureGroup};
+use crate::errors::PSError;
+use crate::hash_to_field::hash_to_field;
+use crate::keys::Params;
+use crate::types::Challenge;
+use crate::validation::deserialize_point;
+use crate::{SignatureGroup, SignatureGroupVec};
+use amcl_wrapper::field_elem::{FieldElement, FieldElementVector};
+use amcl_wrapper::group_elem::GroupElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::BTreeMap;
+
+/// Tag of `hash_to_field` for `encode_attribute`
+pub const ATTRIBUTE_DST: &[u8] = b"PS-SIG-V01-CS01-ATTRIBUTE_XMD:SHA-256_";
+const LEGACY_DOMAIN: &[u8] = b"PS attribute encoding : ";
+
+/// Canonical encoding of a string attribute as a message
+pub fn encode_attribute(value: &str) -> FieldElement {
+    hash_to_field(value.as_bytes(), ATTRIBUTE_DST)
+}
+
+/// The amcl specific encoding of earlier versions, for credentials issued with them
+pub fn encode_attribute_legacy(value: &str) -> FieldElement {
+    FieldElement::from_msg_hash(&[LEGACY_DOMAIN, value.as_bytes()].concat())
+}
+
+/// Request for a blind signature on the first `committed_msg_count` messages where the values at the
+/// indices of `stated` are disclosed to the issuer. The commitment is
+/// Y_1^m_1 * Y_2^m_2 * ... * g^blinding and the proof is of knowledge of the hidden messages and the
+/// blinding in commitment / (product of Y_i^encode(stated_i)).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EncodedCredentialRequest {
+    #[serde(deserialize_with = "deserialize_point")]
+    pub commitment: SignatureGroup,
+    pub committed_msg_count: usize,
+    pub stated: BTreeMap<usize, String>,
+    pub proof: ProofSignatureGroup,
+}
+
+impl EncodedCredentialRequest {
+    /// Fails if a committed message at a stated index is not the encoding of the stated value
+    pub fn new(
+        committed_messages: &[FieldElement],
+        stated: BTreeMap<usize, String>,
+        blinding: &FieldElement,
+        blinding_key: &BlindingKey,
+        params: &Params,
+    ) -> Result<Self, PSError> {
+        Self::new_with_rng(
+            committed_messages,
+            stated,
+            blinding,
+            blinding_key,
+            params,
+            &mut thread_rng(),
+        )
+    }
+
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        committed_messages: &[FieldElement],
+        stated: BTreeMap<usize, String>,
+        blinding: &FieldElement,
+        blinding_key: &BlindingKey,
+        params: &Params,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        let count = committed_messages.len();
+        if count == 0 || count >= blinding_key.msg_count() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: blinding_key.msg_count().saturating_sub(1),
+                given: count,
+            });
+        }
+        for (i, value) in &stated {
+            if *i >= count || committed_messages[*i] != encode_attribute(value) {
+                return Err(PSError::GeneralError {
+                    msg: format!("Committed message {} is not the encoding of {:?}", i, value),
+                });
+            }
+        }
+
+        let mut bases = blinding_key.Y[..count].to_vec();
+        bases.push(params.g.clone());
+        let mut exponents = committed_messages.to_vec();
+        exponents.push(blinding.clone());
+        let commitment = SignatureGroupVec::from(bases.as_slice())
+            .multi_scalar_mul_const_time(&FieldElementVector::from(exponents.as_slice()))
+            .unwrap();
+
+        let mut committing = ProverCommittingSignatureGroup::new();
+        let mut secrets = vec![];
+        for i in (0..count).filter(|i| !stated.contains_key(i)) {
+            committing.commit(
+                &blinding_key.Y[i],
+                Some(&FieldElement::random_using_rng(rng)),
+            );
+            secrets.push(committed_messages[i].clone());
+        }
+        committing.commit(&params.g, Some(&FieldElement::random_using_rng(rng)));
+        secrets.push(blinding.clone());
+        let committed = committing.finish();
+        let challenge = committed.gen_challenge(Self::challenge_bytes(&commitment, count, &stated));
+        let proof = committed.gen_proof(&challenge, &secrets)?;
+        Ok(Self {
+            commitment,
+            committed_msg_count: count,
+            stated,
+            proof,
+        })
+    }
+
+    fn challenge_bytes(
+        commitment: &SignatureGroup,
+        committed_msg_count: usize,
+        stated: &BTreeMap<usize, String>,
+    ) -> Vec<u8> {
+        let mut bytes = commitment.to_bytes();
+        bytes.extend_from_slice(&(committed_msg_count as u64).to_be_bytes());
+        for (i, value) in stated {
+            bytes.extend_from_slice(&(*i as u64).to_be_bytes());
+            bytes.extend_from_slice(&(value.len() as u64).to_be_bytes());
+            bytes.extend_from_slice(value.as_bytes());
+        }
+        bytes
+    }
+
+    /// Issuer side check before blind signing `self.commitment`
+    pub fn verify(&self, blinding_key: &BlindingKey, params: &Params) -> Result<bool, PSError> {
+        let count = self.committed_msg_count;
+        if count == 0
+            || count >= blinding_key.msg_count()
+            || self.stated.keys().any(|i| *i >= count)
+            || self.proof.responses.len() != count - self.stated.len() + 1
+        {
+            return Ok(false);
+        }
+        let mut bases: Vec<SignatureGroup> = (0..count)
+            .filter(|i| !self.stated.contains_key(i))
+            .map(|i| blinding_key.Y[i].clone())
+            .collect();
+        bases.push(params.g.clone());
+
+        // Remove the stated values from the commitment
+        let mut opening = self.commitment.clone();
+        for (i, value) in &self.stated {
+            opening = &opening - &(&blinding_key.Y[*i] * &encode_attribute(value));
+        }
+
+        // Same bytes as `ProverCommitted::to_bytes` followed by `challenge_bytes`
+        let mut bytes = vec![];
+        for b in &bases {
+            bytes.append(&mut b.to_bytes());
+        }
+        bytes.append(&mut self.proof.commitment.to_bytes());
+        bytes.append(&mut Self::challenge_bytes(
+            &self.commitment,
+            count,
+            &self.stated,
+        ));
+        let challenge = Challenge::from_msg_hash(&bytes);
+        self.proof.verify(&bases, &opening, &challenge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blind_signature::BlindSignature;
+    use crate::keys::keygen;
+    use crate::types::{Blinding, Message};
+
+    #[test]
+    fn test_encoded_credential_request() {
+        let count_msgs = 5;
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(count_msgs, &params);
+        let blinding_key = BlindingKey::new(&sk, &params);
+
+        // Messages 0 and 2 are stated, message 1 is hidden from the issuer, 3 and 4 are known
+        let mut stated = BTreeMap::new();
+        stated.insert(0, String::from("Alice"));
+        stated.insert(2, String::from("alice@example.com"));
+        let committed = vec![
+            encode_attribute("Alice"),
+            FieldElement::random(),
+            encode_attribute("alice@example.com"),
+        ];
+        let known = FieldElementVector::random(2);
+        let blinding = FieldElement::random();
+        let request = EncodedCredentialRequest::new(
+            &committed,
+            stated.clone(),
+            &blinding,
+            &blinding_key,
+            &params,
+        )
+        .unwrap();
+        assert!(request.verify(&blinding_key, &params).unwrap());
+
+        let sig_blinded = BlindSignature::new(
+            &request.commitment,
+            &Message::from_field_elements(known.as_slice()),
+            &sk,
+            &blinding_key,
+            &params,
+        )
+        .unwrap();
+        let sig = BlindSignature::unblind(&sig_blinded, &Blinding(blinding.clone()));
+        let mut all_msgs = committed.clone();
+        all_msgs.extend_from_slice(known.as_slice());
+        assert!(sig
+            .verify(&Message::from_field_elements(&all_msgs), &vk, &params)
+            .unwrap());
+
+        // The holder cannot state a value other than the committed one
+        let mut wrong = stated.clone();
+        wrong.insert(0, String::from("Bob"));
+        assert!(EncodedCredentialRequest::new(
+            &committed,
+            wrong.clone(),
+            &blinding,
+            &blinding_key,
+            &params
+        )
+        .is_err());
+        let mut request_1 = request.clone();
+        request_1.stated = wrong;
+        assert!(!request_1.verify(&blinding_key, &params).unwrap());
+
+        // Stated value moved to another index
+        let mut request_2 = request.clone();
+        let value = request_2.stated.remove(&2).unwrap();
+        request_2.stated.insert(1, value);
+        assert!(!request_2.verify(&blinding_key, &params).unwrap());
+    }
+}
@@ -0,0 +1,312 @@
+// Issuer keys of rolling epochs. An `EpochVerkey` is a verkey with the epoch it was created for, e.g.
+// a month number, and signs one message more than the credential has: message 0 is reserved for the
+// epoch so every signature is tied to the epoch of its key. The `_with_epoch` variants of signing,
+// proving and verifying fill and check that slot and take the credential's messages at their own
+// indices, the epoch is always revealed in proofs. Verifiers accept only epochs in an `EpochWindow`,
+// so a rule like "re-issued under a key no older than 6 months" is a window with `max_age` 6.
+// `EpochKeyring` keeps the verkeys of the epochs a verifier accepts.
+
+use crate::errors::PSError;
+use crate::hash_to_field::hash_to_field;
+use crate::keys::{keygen_with_rng, Params, Sigkey, Verkey};
+use crate::pok_sig::{PoKOfSignature, PoKOfSignatureProof};
+use crate::signature::Signature;
+use crate::signer::PsSigner;
+use crate::types::{Challenge, Message};
+use amcl_wrapper::field_elem::FieldElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::{HashMap, HashSet};
+
+/// Tag of `hash_to_field` for the epoch message
+pub const EPOCH_DST: &[u8] = b"PS-SIG-V01-CS01-KEY-EPOCH_XMD:SHA-256_";
+
+/// Index of the message holding the epoch
+pub const EPOCH_INDEX: usize = 0;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EpochVerkey {
+    pub verkey: Verkey,
+    pub epoch: u64,
+}
+
+/// Epochs from `current - max_age` to `current`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochWindow {
+    pub current: u64,
+    pub max_age: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EpochKeyring {
+    keys: Vec<EpochVerkey>,
+}
+
+pub fn epoch_message(epoch: u64) -> FieldElement {
+    hash_to_field(&epoch.to_be_bytes(), EPOCH_DST)
+}
+
+impl EpochWindow {
+    pub fn new(current: u64, max_age: u64) -> Self {
+        Self { current, max_age }
+    }
+
+    pub fn contains(&self, epoch: u64) -> bool {
+        epoch <= self.current && self.current - epoch <= self.max_age
+    }
+}
+
+impl EpochVerkey {
+    /// Keys for `count_messages` messages of the credential and the epoch
+    pub fn keygen(count_messages: usize, epoch: u64, params: &Params) -> (Sigkey, Self) {
+        Self::keygen_with_rng(count_messages, epoch, params, &mut thread_rng())
+    }
+
+    pub fn keygen_with_rng<R: RngCore + CryptoRng>(
+        count_messages: usize,
+        epoch: u64,
+        params: &Params,
+        rng: &mut R,
+    ) -> (Sigkey, Self) {
+        let (sk, verkey) = keygen_with_rng(count_messages + 1, params, rng);
+        (sk, Self { verkey, epoch })
+    }
+
+    /// Keys of the next epoch for credentials of the same number of messages
+    pub fn next_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        params: &Params,
+        rng: &mut R,
+    ) -> (Sigkey, Self) {
+        Self::keygen_with_rng(self.message_count(), self.epoch + 1, params, rng)
+    }
+
+    /// Number of messages of credentials, without the epoch
+    pub fn message_count(&self) -> usize {
+        self.verkey.Y_tilde.len().saturating_sub(1)
+    }
+
+    /// Whether credentials under this key must be re-issued to be accepted in `window`
+    pub fn needs_reissue(&self, window: &EpochWindow) -> bool {
+        !window.contains(self.epoch)
+    }
+
+    /// The epoch message followed by `messages`
+    fn messages(&self, messages: &[FieldElement]) -> Result<Vec<FieldElement>, PSError> {
+        if messages.len() != self.message_count() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: self.message_count(),
+                given: messages.len(),
+            });
+        }
+        let mut msgs = vec![epoch_message(self.epoch)];
+        msgs.extend_from_slice(messages);
+        Ok(msgs)
+    }
+}
+
+impl EpochKeyring {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces a key of the same epoch
+    pub fn add(&mut self, key: EpochVerkey) {
+        self.keys.retain(|k| k.epoch != key.epoch);
+        self.keys.push(key);
+        self.keys.sort_by_key(|k| k.epoch);
+    }
+
+    pub fn get(&self, epoch: u64) -> Option<&EpochVerkey> {
+        self.keys.iter().find(|k| k.epoch == epoch)
+    }
+
+    /// Key of the latest epoch
+    pub fn latest(&self) -> Option<&EpochVerkey> {
+        self.keys.last()
+    }
+
+    /// Keys accepted in `window`
+    pub fn valid(&self, window: &EpochWindow) -> Vec<&EpochVerkey> {
+        self.keys
+            .iter()
+            .filter(|k| window.contains(k.epoch))
+            .collect()
+    }
+
+    /// Drop the keys of epochs older than `window`, returns them
+    pub fn retire(&mut self, window: &EpochWindow) -> Vec<EpochVerkey> {
+        let (valid, retired) = self
+            .keys
+            .drain(..)
+            .partition(|k| k.epoch > window.current || window.contains(k.epoch));
+        self.keys = valid;
+        retired
+    }
+}
+
+impl Signature {
+    /// Signature on the epoch of `vk` and `messages`
+    pub fn new_with_epoch<S: PsSigner + ?Sized>(
+        messages: &[FieldElement],
+        signer: &S,
+        vk: &EpochVerkey,
+        params: &Params,
+    ) -> Result<Self, PSError> {
+        Self::new_with_epoch_with_rng(messages, signer, vk, params, &mut thread_rng())
+    }
+
+    pub fn new_with_epoch_with_rng<S: PsSigner + ?Sized, R: RngCore + CryptoRng>(
+        messages: &[FieldElement],
+        signer: &S,
+        vk: &EpochVerkey,
+        params: &Params,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        signer.sign_with_rng(&vk.messages(messages)?, params, rng)
+    }
+
+    /// False as well when the epoch of `vk` is outside `window`
+    pub fn verify_with_epoch(
+        &self,
+        messages: &[FieldElement],
+        vk: &EpochVerkey,
+        params: &Params,
+        window: &EpochWindow,
+    ) -> Result<bool, PSError> {
+        if !window.contains(vk.epoch) {
+            return Ok(false);
+        }
+        self.verify(
+            &Message::from_field_elements(&vk.messages(messages)?),
+            &vk.verkey,
+            params,
+        )
+    }
+}
+
+impl PoKOfSignature {
+    /// Proof of a signature made with `Signature::new_with_epoch` revealing the epoch and the
+    /// messages at `revealed_msg_indices`
+    pub fn init_with_epoch(
+        sig: &Signature,
+        vk: &EpochVerkey,
+        params: &Params,
+        messages: &[FieldElement],
+        revealed_msg_indices: HashSet<usize>,
+    ) -> Result<Self, PSError> {
+        Self::init_with_epoch_with_rng(
+            sig,
+            vk,
+            params,
+            messages,
+            revealed_msg_indices,
+            &mut thread_rng(),
+        )
+    }
+
+    pub fn init_with_epoch_with_rng<R: RngCore + CryptoRng>(
+        sig: &Signature,
+        vk: &EpochVerkey,
+        params: &Params,
+        messages: &[FieldElement],
+        revealed_msg_indices: HashSet<usize>,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        let mut revealed: HashSet<usize> = revealed_msg_indices.iter().map(|i| i + 1).collect();
+        revealed.insert(EPOCH_INDEX);
+        Self::init_with_rng(
+            sig,
+            &vk.verkey,
+            params,
+            &Message::from_field_elements(&vk.messages(messages)?),
+            None,
+            revealed,
+            rng,
+        )
+    }
+}
+
+impl PoKOfSignatureProof {
+    /// Same as `verify` for `PoKOfSignature::init_with_epoch`, false as well when the epoch of `vk` is
+    /// outside `window`
+    pub fn verify_with_epoch(
+        &self,
+        vk: &EpochVerkey,
+        params: &Params,
+        revealed_msgs: HashMap<usize, FieldElement>,
+        challenge: &Challenge,
+        window: &EpochWindow,
+    ) -> Result<bool, PSError> {
+        if !window.contains(vk.epoch) {
+            return Ok(false);
+        }
+        let mut revealed: HashMap<usize, FieldElement> =
+            revealed_msgs.into_iter().map(|(i, m)| (i + 1, m)).collect();
+        revealed.insert(EPOCH_INDEX, epoch_message(vk.epoch));
+        self.verify(&vk.verkey, params, revealed, challenge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use amcl_wrapper::group_elem::GroupElement;
+
+    #[test]
+    fn test_epoch_keys() {
+        let params = Params::new("test".as_bytes());
+        let mut rng = thread_rng();
+        let (sk, vk) = EpochVerkey::keygen(3, 10, &params);
+        assert_eq!(vk.message_count(), 3);
+        let msgs = FieldElementVector::random(3);
+        let sig = Signature::new_with_epoch(msgs.as_slice(), &sk, &vk, &params).unwrap();
+        let window = EpochWindow::new(12, 6);
+        assert!(sig
+            .verify_with_epoch(msgs.as_slice(), &vk, &params, &window)
+            .unwrap());
+        // Too old
+        assert!(!sig
+            .verify_with_epoch(msgs.as_slice(), &vk, &params, &EpochWindow::new(20, 6))
+            .unwrap());
+        // Another epoch under the same key
+        let other = EpochVerkey {
+            verkey: vk.verkey.clone(),
+            epoch: 11,
+        };
+        assert!(!sig
+            .verify_with_epoch(msgs.as_slice(), &other, &params, &window)
+            .unwrap());
+
+        let revealed: HashSet<usize> = vec![1].into_iter().collect();
+        let pok =
+            PoKOfSignature::init_with_epoch(&sig, &vk, &params, msgs.as_slice(), revealed).unwrap();
+        let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
+        let proof = pok.gen_proof(&chal).unwrap();
+        let mut revealed_msgs = HashMap::new();
+        revealed_msgs.insert(1, msgs[1].clone());
+        assert!(proof
+            .verify_with_epoch(&vk, &params, revealed_msgs.clone(), &chal, &window)
+            .unwrap());
+        assert!(!proof
+            .verify_with_epoch(&other, &params, revealed_msgs.clone(), &chal, &window)
+            .unwrap());
+        assert!(!proof
+            .verify_with_epoch(&vk, &params, revealed_msgs, &chal, &EpochWindow::new(3, 6))
+            .unwrap());
+
+        let mut keyring = EpochKeyring::new();
+        keyring.add(vk.clone());
+        let (_, next) = vk.next_with_rng(&params, &mut rng);
+        assert_eq!(next.epoch, 11);
+        keyring.add(next);
+        assert_eq!(keyring.latest().unwrap().epoch, 11);
+        let later = EpochWindow::new(17, 6);
+        assert!(vk.needs_reissue(&later));
+        assert_eq!(keyring.valid(&later).len(), 1);
+        let retired = keyring.retire(&later);
+        assert_eq!(retired.len(), 1);
+        assert!(keyring.get(10).is_none());
+        assert!(keyring.get(11).is_some());
+    }
+}
@@ -1,4 +1,5 @@
-use failure::Error;
+// `failure_derive` emits its `Display` impl inside an anonymous const
+#![allow(non_local_definitions)]
 
 #[derive(Debug, Fail)]
 pub enum PSError {
@@ -20,6 +21,44 @@ pub enum PSError {
     )]
     UnequalNoOfBasesExponents { bases: usize, exponents: usize },
 
+    #[fail(
+        display = "Expected {} witnesses (secrets or blindings) but got {}",
+        expected, got
+    )]
+    MismatchedWitnessCount { expected: usize, got: usize },
+
+    #[fail(display = "No attribute named {:?}", name)]
+    UnknownAttribute { name: String },
+
+    #[fail(display = "No section named {:?}", name)]
+    UnknownSection { name: String },
+
+    #[fail(display = "Message is not less than the curve order")]
+    MessageOutOfRange,
+
+    #[fail(display = "Invalid encoding: {:?}", msg)]
+    InvalidEncoding { msg: String },
+
+    #[fail(display = "{} is {} but at most {} allowed", limit, given, max)]
+    LimitExceeded {
+        limit: String,
+        max: usize,
+        given: usize,
+    },
+
+    #[fail(display = "Signing refused: {:?}", msg)]
+    SigningRefused { msg: String },
+
+    #[fail(display = "Expected verkey {} but got {}", expected, given)]
+    VerkeyMismatch { expected: String, given: String },
+
+    #[fail(display = "Verkey revoked as of epoch {}", epoch)]
+    RevokedVerkey { epoch: u64 },
+
+    /// Only returned with feature `failpoints`
+    #[fail(display = "Injected failure at {}", point)]
+    InjectedFailure { point: String },
+
     #[fail(display = "Error with message {:?}", msg)]
     GeneralError { msg: String },
 }
@@ -0,0 +1,164 @@
+// Escrow of the blinding of a blind signature request across the holder's devices. The blinding is
+// needed to unblind the signature, so it is Shamir shared with threshold t and any t devices can recover
+// it. Shares are verifiable with Feldman commitments g^a_j to the coefficients of the sharing polynomial.
+// The commitments include g^blinding which, together with the request commitment, allows testing guesses
+// of the committed messages, so they must stay on the holder's devices.
+
+use crate::errors::PSError;
+use crate::keys::Params;
+use crate::shamir::{evaluate, random_polynomial, reconstruct_secret};
+use crate::types::Blinding;
+use crate::validation::deserialize_points;
+use crate::SignatureGroup;
+use amcl_wrapper::field_elem::FieldElement;
+use amcl_wrapper::group_elem::GroupElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BlindingShare {
+    /// Starts from 1
+    pub index: usize,
+    pub value: FieldElement,
+}
+
+/// Feldman commitments to the coefficients of the sharing polynomial
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BlindingShareCommitments {
+    #[serde(deserialize_with = "deserialize_points")]
+    pub coefficients: Vec<SignatureGroup>,
+}
+
+impl BlindingShareCommitments {
+    pub fn threshold(&self) -> usize {
+        self.coefficients.len()
+    }
+
+    /// g^share == product of coefficients[j]^(index^j)
+    pub fn verify_share(&self, share: &BlindingShare, params: &Params) -> bool {
+        if share.index == 0 {
+            return false;
+        }
+        let x = FieldElement::from(share.index as u64);
+        let mut expected = SignatureGroup::identity();
+        for c in self.coefficients.iter().rev() {
+            expected = &(&expected * &x) + c;
+        }
+        &params.g * &share.value == expected
+    }
+}
+
+/// Split `blinding` into `total` shares of which `threshold` recover it
+pub fn split_blinding(
+    blinding: &Blinding,
+    threshold: usize,
+    total: usize,
+    params: &Params,
+) -> Result<(Vec<BlindingShare>, BlindingShareCommitments), PSError> {
+    split_blinding_with_rng(blinding, threshold, total, params, &mut thread_rng())
+}
+
+pub fn split_blinding_with_rng<R: RngCore + CryptoRng>(
+    blinding: &Blinding,
+    threshold: usize,
+    total: usize,
+    params: &Params,
+    rng: &mut R,
+) -> Result<(Vec<BlindingShare>, BlindingShareCommitments), PSError> {
+    let coefficients = random_polynomial(&blinding.0, threshold, total, rng)?;
+    let shares = (1..=total)
+        .map(|i| BlindingShare {
+            index: i,
+            value: evaluate(&coefficients, i),
+        })
+        .collect();
+    let commitments = BlindingShareCommitments {
+        coefficients: coefficients.iter().map(|c| &params.g * c).collect(),
+    };
+    Ok((shares, commitments))
+}
+
+/// Recover the blinding from at least `threshold` shares. Every share is verified and the result is
+/// checked against the commitment to the blinding.
+pub fn recombine_blinding(
+    shares: &[BlindingShare],
+    commitments: &BlindingShareCommitments,
+    params: &Params,
+) -> Result<Blinding, PSError> {
+    if shares.len() < commitments.threshold() {
+        return Err(PSError::GeneralError {
+            msg: format!(
+                "Need {} shares but given {}",
+                commitments.threshold(),
+                shares.len()
+            ),
+        });
+    }
+    if let Some(s) = shares.iter().find(|s| !commitments.verify_share(s, params)) {
+        return Err(PSError::GeneralError {
+            msg: format!("Invalid share {}", s.index),
+        });
+    }
+    let pairs: Vec<(usize, FieldElement)> =
+        shares.iter().map(|s| (s.index, s.value.clone())).collect();
+    let blinding = reconstruct_secret(&pairs)?;
+    if &params.g * &blinding != commitments.coefficients[0] {
+        return Err(PSError::GeneralError {
+            msg: String::from("Recovered blinding does not match the commitment"),
+        });
+    }
+    Ok(Blinding(blinding))
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for BlindingShare {
+    fn zeroize(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blind_signature::{BlindSignature, BlindingKey};
+    use crate::keys::keygen;
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::FieldElementVector;
+
+    #[test]
+    fn test_blinding_escrow() {
+        let count_msgs = 3;
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(count_msgs, &params);
+        let blinding_key = BlindingKey::new(&sk, &params);
+        let msgs = FieldElementVector::random(count_msgs);
+        let blinding = Blinding::random();
+        let commitment = &(&blinding_key.Y[0] * &msgs[0]) + &(&params.g * &blinding.0);
+        let sig_blinded = BlindSignature::new(
+            &commitment,
+            &Message::from_field_elements(&msgs.as_slice()[1..]),
+            &sk,
+            &blinding_key,
+            &params,
+        )
+        .unwrap();
+
+        // 2 of 3 devices
+        let (shares, commitments) = split_blinding(&blinding, 2, 3, &params).unwrap();
+        assert!(shares.iter().all(|s| commitments.verify_share(s, &params)));
+        let recovered = recombine_blinding(&shares[1..], &commitments, &params).unwrap();
+        let sig = BlindSignature::unblind(&sig_blinded, &recovered);
+        assert!(sig
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+
+        assert!(recombine_blinding(&shares[..1], &commitments, &params).is_err());
+        let mut bad = shares.clone();
+        bad[0].value = FieldElement::random();
+        assert!(!commitments.verify_share(&bad[0], &params));
+        assert!(recombine_blinding(&bad[..2], &commitments, &params).is_err());
+        assert_eq!(
+            recombine_blinding(&bad[1..], &commitments, &params).unwrap(),
+            blinding
+        );
+    }
+}
@@ -0,0 +1,224 @@
+// Extending an issuer's keys with more messages so a schema can gain attributes. The extended key has
+// the same x and y_i followed by fresh y_i, so a signature on n messages under the old key is one on
+// the n messages followed by zeros under the new key and verifies with `Signature::verify_padded`. The
+// `VerkeyExtension` proves knowledge of x in X_tilde, so only the old key's issuer can extend it, and
+// of each new y_i in both Y_i and Y_tilde_i with the same blinding as `VerkeyPoP` does, so the new Y_i
+// extend the issuer's `BlindingKey`.
+
+use crate::blind_signature::{ProofSignatureGroup, ProverCommittingSignatureGroup};
+use crate::errors::PSError;
+use crate::keys::{Params, Sigkey, Verkey};
+use crate::pok_sig::{ProofOtherGroup, ProverCommittingOtherGroup};
+use crate::signer::PsSigner;
+use crate::transcript::Transcript;
+use crate::validation::deserialize_points;
+use crate::SignatureGroup;
+use amcl_wrapper::field_elem::FieldElement;
+use amcl_wrapper::group_elem::GroupElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VerkeyExtension {
+    /// g^y_i of the new messages
+    #[serde(deserialize_with = "deserialize_points")]
+    pub Y: Vec<SignatureGroup>,
+    /// Proof for X_tilde
+    pub proof_x: ProofOtherGroup,
+    /// Proofs for the new Y_i
+    pub proofs_signature_group: Vec<ProofSignatureGroup>,
+    /// Proofs for the new Y_tilde_i
+    pub proofs_other_group: Vec<ProofOtherGroup>,
+}
+
+fn transcript_for(old: &Verkey, new: &Verkey, Y: &[SignatureGroup]) -> Transcript {
+    let mut transcript = Transcript::new(b"PS verkey extension");
+    transcript.append_message(b"old verkey", &old.to_bytes());
+    transcript.append_message(b"new verkey", &new.to_bytes());
+    let mut bytes = vec![];
+    for y in Y {
+        bytes.append(&mut y.to_bytes());
+    }
+    transcript.append_message(b"Y", &bytes);
+    transcript
+}
+
+impl Verkey {
+    /// Append `additional` messages to this key and its `signer`. Returns the extended signer and
+    /// verkey and the proof that the verkey extends this one.
+    pub fn extend<S: PsSigner>(
+        &self,
+        additional: usize,
+        signer: &S,
+        params: &Params,
+    ) -> Result<(S, Verkey, VerkeyExtension), PSError> {
+        self.extend_with_rng(additional, signer, params, &mut thread_rng())
+    }
+
+    /// The signer extends its key, see `PsSigner::extend_key`
+    pub fn extend_with_rng<S: PsSigner, R: RngCore + CryptoRng>(
+        &self,
+        additional: usize,
+        signer: &S,
+        params: &Params,
+        rng: &mut R,
+    ) -> Result<(S, Verkey, VerkeyExtension), PSError> {
+        signer.extend_key(self, additional, params, rng)
+    }
+
+    /// Extension of this key and `sigkey`
+    pub(crate) fn extend_sigkey<R: RngCore + CryptoRng>(
+        &self,
+        additional: usize,
+        sigkey: &Sigkey,
+        params: &Params,
+        rng: &mut R,
+    ) -> Result<(Sigkey, Verkey, VerkeyExtension), PSError> {
+        self.check_same(&Verkey::from_sigkey(sigkey, sigkey.msg_count(), params)?)?;
+        let new_y: Vec<FieldElement> = (0..additional)
+            .map(|_| FieldElement::random_using_rng(rng))
+            .collect();
+        let mut sigkey = sigkey.clone();
+        let mut verkey = self.clone();
+        let mut Y = vec![];
+        for y in &new_y {
+            sigkey.y.push(y.clone());
+            verkey.Y_tilde.push(&params.g_tilde * y);
+            Y.push(&params.g * y);
+        }
+
+        let mut transcript = transcript_for(self, &verkey, &Y);
+        let mut committed_x = ProverCommittingOtherGroup::new();
+        committed_x.commit(&params.g_tilde, Some(&FieldElement::random_using_rng(rng)));
+        let committed_x = committed_x.finish();
+        transcript.absorb(&committed_x);
+        let mut committed_sig = vec![];
+        let mut committed_other = vec![];
+        for _ in &new_y {
+            let r = FieldElement::random_using_rng(rng);
+            let mut c = ProverCommittingSignatureGroup::new();
+            c.commit(&params.g, Some(&r));
+            let c = c.finish();
+            transcript.absorb(&c);
+            committed_sig.push(c);
+            let mut c = ProverCommittingOtherGroup::new();
+            c.commit(&params.g_tilde, Some(&r));
+            let c = c.finish();
+            transcript.absorb(&c);
+            committed_other.push(c);
+        }
+        let challenge = transcript.challenge();
+        let proof_x = committed_x.gen_proof(&challenge, std::slice::from_ref(&sigkey.x))?;
+        let mut proofs_signature_group = vec![];
+        let mut proofs_other_group = vec![];
+        for ((y, cs), co) in new_y.iter().zip(committed_sig).zip(committed_other) {
+            proofs_signature_group.push(cs.gen_proof(&challenge, std::slice::from_ref(y))?);
+            proofs_other_group.push(co.gen_proof(&challenge, std::slice::from_ref(y))?);
+        }
+        let extension = VerkeyExtension {
+            Y,
+            proof_x,
+            proofs_signature_group,
+            proofs_other_group,
+        };
+        Ok((sigkey, verkey, extension))
+    }
+}
+
+impl VerkeyExtension {
+    /// Whether `new` is `old` extended by its issuer
+    pub fn verify(&self, old: &Verkey, new: &Verkey, params: &Params) -> Result<bool, PSError> {
+        let additional = new.Y_tilde.len().saturating_sub(old.Y_tilde.len());
+        if new.Y_tilde.len() < old.Y_tilde.len()
+            || new.X_tilde != old.X_tilde
+            || new.Y_tilde[..old.Y_tilde.len()] != old.Y_tilde[..]
+            || self.Y.len() != additional
+            || self.proofs_signature_group.len() != additional
+            || self.proofs_other_group.len() != additional
+            || self.proof_x.responses.len() != 1
+        {
+            return Ok(false);
+        }
+        let sig_base = std::slice::from_ref(&params.g);
+        let other_base = std::slice::from_ref(&params.g_tilde);
+
+        let mut transcript = transcript_for(old, new, &self.Y);
+        self.proof_x.absorb_with_bases(other_base, &mut transcript);
+        for (ps, po) in self
+            .proofs_signature_group
+            .iter()
+            .zip(&self.proofs_other_group)
+        {
+            if ps.responses.len() != 1 || po.responses.len() != 1 {
+                return Ok(false);
+            }
+            ps.absorb_with_bases(sig_base, &mut transcript);
+            po.absorb_with_bases(other_base, &mut transcript);
+        }
+        let challenge = transcript.challenge();
+        if !self.proof_x.verify(other_base, &new.X_tilde, &challenge)? {
+            return Ok(false);
+        }
+        for (i, Y_tilde_i) in new.Y_tilde[old.Y_tilde.len()..].iter().enumerate() {
+            let ps = &self.proofs_signature_group[i];
+            let po = &self.proofs_other_group[i];
+            if self.Y[i].is_identity()
+                || Y_tilde_i.is_identity()
+                || ps.responses[0] != po.responses[0]
+                || !ps.verify(sig_base, &self.Y[i], &challenge)?
+                || !po.verify(other_base, Y_tilde_i, &challenge)?
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use crate::signature::Signature;
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::FieldElementVector;
+
+    #[test]
+    fn test_extend_verkey() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let msgs = FieldElementVector::random(3);
+        let old_sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+
+        let (sk_1, vk_1, extension) = vk.extend(2, &sk, &params).unwrap();
+        assert_eq!(vk_1.Y_tilde.len(), 5);
+        assert!(extension.verify(&vk, &vk_1, &params).unwrap());
+        // Credentials issued before the extension still verify
+        assert!(old_sig
+            .verify_padded(msgs.as_slice(), &vk_1, &params)
+            .unwrap());
+        let msgs_1 = FieldElementVector::random(5);
+        let sig = Signature::new(
+            &Message::from_field_elements(msgs_1.as_slice()),
+            &sk_1,
+            &params,
+        )
+        .unwrap();
+        assert!(sig
+            .verify(
+                &Message::from_field_elements(msgs_1.as_slice()),
+                &vk_1,
+                &params
+            )
+            .unwrap());
+        assert_eq!(extension.Y, sk_1.Y(&params)[3..].to_vec());
+
+        // Not an extension of another key, nor with a replaced generator
+        let (sk_2, vk_2) = keygen(3, &params);
+        assert!(!extension.verify(&vk_2, &vk_1, &params).unwrap());
+        let mut replaced = vk_1.clone();
+        replaced.Y_tilde[4] = vk_2.Y_tilde[0].clone();
+        assert!(!extension.verify(&vk, &replaced, &params).unwrap());
+        assert!(vk.extend(2, &sk_2, &params).is_err());
+    }
+}
@@ -0,0 +1,123 @@
+// Injected failures for testing how callers handle errors, enabled with feature `failpoints`. Fallible
+// operations check a failpoint before committing to proof randomness, computing multi-scalar
+// multiplications or pairings, and fail with `PSError::InjectedFailure` while it is armed. Failpoints
+// are armed per thread for a number of hits, e.g. arming `Commitment` once makes the next
+// `PoKOfSignature::init` on the thread fail and the one after succeed, so retry logic can be tested.
+// Work moved to other threads, e.g. by feature `rayon`, does not see them.
+
+use crate::errors::PSError;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Failpoint {
+    /// Commitments of proofs and issuance requests
+    Commitment,
+    MultiScalarMul,
+    Pairing,
+}
+
+#[derive(Default)]
+struct State {
+    armed: HashMap<Failpoint, usize>,
+    hits: HashMap<Failpoint, usize>,
+}
+
+thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State::default());
+}
+
+/// Make the next `times` hits of `point` on this thread fail
+pub fn arm(point: Failpoint, times: usize) {
+    STATE.with(|s| s.borrow_mut().armed.insert(point, times));
+}
+
+pub fn disarm(point: Failpoint) {
+    STATE.with(|s| s.borrow_mut().armed.remove(&point));
+}
+
+/// Disarm all failpoints and reset the hit counts of this thread
+pub fn reset() {
+    STATE.with(|s| *s.borrow_mut() = State::default());
+}
+
+/// Number of times `point` was reached on this thread, failed or not
+pub fn hit_count(point: Failpoint) -> usize {
+    STATE.with(|s| s.borrow().hits.get(&point).cloned().unwrap_or(0))
+}
+
+pub(crate) fn hit(point: Failpoint) -> Result<(), PSError> {
+    STATE.with(|s| {
+        let mut s = s.borrow_mut();
+        *s.hits.entry(point).or_insert(0) += 1;
+        match s.armed.get_mut(&point) {
+            Some(remaining) if *remaining > 0 => {
+                *remaining -= 1;
+                Err(PSError::InjectedFailure {
+                    point: format!("{:?}", point),
+                })
+            }
+            _ => Ok(()),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::{keygen, Params};
+    use crate::pok_sig::PoKOfSignature;
+    use crate::signature::Signature;
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_failpoints() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let msgs = FieldElementVector::random(3);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+
+        reset();
+        arm(Failpoint::Pairing, 1);
+        match sig.verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params) {
+            Err(PSError::InjectedFailure { point }) => assert_eq!(point, "Pairing"),
+            r => panic!("Expected an injected failure, got {:?}", r),
+        }
+        assert!(sig
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+        assert_eq!(hit_count(Failpoint::Pairing), 2);
+
+        // A retry loop succeeds once the injected failures are used up
+        arm(Failpoint::Commitment, 2);
+        let mut attempts = 0;
+        let pok = loop {
+            attempts += 1;
+            match PoKOfSignature::init(
+                &sig,
+                &vk,
+                &params,
+                &Message::from_field_elements(msgs.as_slice()),
+                None,
+                HashSet::new(),
+            ) {
+                Ok(pok) => break pok,
+                Err(PSError::InjectedFailure { .. }) => continue,
+                Err(e) => panic!("{:?}", e),
+            }
+        };
+        assert_eq!(attempts, 3);
+        assert_eq!(pok.secrets.len(), 4);
+
+        arm(Failpoint::MultiScalarMul, 5);
+        disarm(Failpoint::MultiScalarMul);
+        assert!(sig
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+        reset();
+        assert_eq!(hit_count(Failpoint::Pairing), 0);
+    }
+}
@@ -0,0 +1,198 @@
+// Golden files guarding the serialization of deployed artifacts. `golden_artifacts` builds keys, a
+// signature, a proof request and the presentation answering it from the fixed RNG of `testing` and
+// encodes each the way it is sent over the wire. `check_golden` compares them with the checked in
+// files under `tests/golden/<signature group>` and also decodes the checked in presentation and
+// verifies it against the checked in verkey and proof request, so a change of either encoding or
+// verification fails. After an intended format change rerun the tests with `UPDATE_GOLDEN=1` to
+// rewrite the files and review the diff. Enabled with feature `golden`.
+
+use crate::errors::PSError;
+use crate::keys::{Keypair, Params, Verkey};
+use crate::pok_sig::PoKOfSignature;
+use crate::policy::{CompiledPolicy, Policy, Predicate};
+use crate::presentation::Presentation;
+use crate::signature::Signature;
+use crate::testing::{test_rng, DEFAULT_SEED};
+use crate::types::Message;
+use amcl_wrapper::field_elem::FieldElement;
+use amcl_wrapper::group_elem::GroupElement;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const PARAMS_LABEL: &[u8] = b"golden";
+const ATTRIBUTES: [&str; 4] = ["secret", "name", "age", "country"];
+const NONCE: &[u8] = b"golden nonce";
+
+#[cfg(feature = "SignatureG2")]
+const GROUP_DIR: &str = "signature_g2";
+#[cfg(feature = "SignatureG1")]
+const GROUP_DIR: &str = "signature_g1";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GoldenArtifact {
+    /// File name under the golden directory
+    pub name: &'static str,
+    pub contents: String,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut s: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    s.push('\n');
+    s
+}
+
+fn unhex(name: &str, s: &str) -> Result<Vec<u8>, PSError> {
+    let s = s.trim();
+    let invalid = || PSError::InvalidEncoding {
+        msg: format!("Golden file {} is not hex", name),
+    };
+    if !s.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|_| invalid()))
+        .collect()
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> String {
+    let mut s = serde_json::to_string_pretty(value).unwrap();
+    s.push('\n');
+    s
+}
+
+/// Directory of the golden files of this build's signature group
+pub fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(GROUP_DIR)
+}
+
+/// The proof request of the golden presentation, attribute names are `ATTRIBUTES`
+fn proof_request(messages: &[FieldElement]) -> Vec<Policy> {
+    vec![
+        Policy::require_revealed("name"),
+        Policy::require_predicate("country", Predicate::Equals(messages[3].clone())),
+    ]
+}
+
+pub fn golden_artifacts() -> Vec<GoldenArtifact> {
+    let mut rng = test_rng(DEFAULT_SEED);
+    let params = Params::new(PARAMS_LABEL);
+    let keypair = Keypair::new_with_rng(ATTRIBUTES.len(), &params, &mut rng);
+    let messages: Vec<FieldElement> = (0..ATTRIBUTES.len())
+        .map(|_| FieldElement::random_using_rng(&mut rng))
+        .collect();
+    let msgs = Message::from_field_elements(&messages);
+    let sig = Signature::new_with_rng(&msgs, &keypair.sigkey, &params, &mut rng).unwrap();
+    let revealed: HashSet<usize> = vec![1, 3].into_iter().collect();
+    let pok = PoKOfSignature::init_with_rng(
+        &sig,
+        &keypair.verkey,
+        &params,
+        &msgs,
+        None,
+        revealed.clone(),
+        &mut rng,
+    )
+    .unwrap();
+    let challenge = pok.pok_vc.gen_challenge(pok.J.to_bytes());
+    let proof = pok.gen_proof(&challenge).unwrap();
+    let presentation = Presentation::new_with_rng(
+        &sig,
+        &keypair.verkey,
+        &params,
+        &messages,
+        revealed,
+        NONCE,
+        &mut rng,
+    )
+    .unwrap();
+    let policy = CompiledPolicy::compile(&proof_request(&messages), &ATTRIBUTES).unwrap();
+
+    let artifact = |name, contents| GoldenArtifact { name, contents };
+    vec![
+        artifact("params.json", to_json(&params)),
+        artifact("sigkey.hex", hex(&keypair.sigkey.to_versioned_bytes())),
+        artifact("verkey.hex", hex(&keypair.verkey.to_versioned_bytes())),
+        artifact("verkey_fingerprint.hex", hex(&keypair.verkey.fingerprint())),
+        artifact("signature.hex", hex(&sig.to_bytes())),
+        artifact("proof.hex", hex(&proof.to_bytes())),
+        artifact("proof_request.json", to_json(&policy)),
+        artifact("presentation_v1.hex", hex(&presentation.to_bytes())),
+        artifact(
+            "presentation_v2.hex",
+            hex(&presentation.to_bytes_v2(&keypair.verkey, &params)),
+        ),
+    ]
+}
+
+/// The checked in presentation still verifies and satisfies the checked in proof request
+fn verify_stored(dir: &Path) -> Result<bool, PSError> {
+    let read = |name: &str| {
+        fs::read_to_string(dir.join(name)).map_err(|e| PSError::GeneralError {
+            msg: format!("Cannot read golden file {}: {}", name, e),
+        })
+    };
+    let params: Params =
+        serde_json::from_str(&read("params.json")?).map_err(|e| PSError::InvalidEncoding {
+            msg: format!("Golden params: {}", e),
+        })?;
+    let policy: CompiledPolicy =
+        serde_json::from_str(&read("proof_request.json")?).map_err(|e| {
+            PSError::InvalidEncoding {
+                msg: format!("Golden proof request: {}", e),
+            }
+        })?;
+    let vk = Verkey::from_versioned_bytes(&unhex("verkey.hex", &read("verkey.hex")?)?)?;
+    let presentation = Presentation::from_bytes(
+        &unhex("presentation_v2.hex", &read("presentation_v2.hex")?)?,
+        &vk,
+        &params,
+    )?;
+    Ok(presentation.nonce == NONCE
+        && presentation.verify(&vk, &params)?
+        && policy.evaluate(&vk, &presentation.revealed_msgs).is_empty())
+}
+
+/// Names of the artifacts that differ from or are missing in `dir`, plus `presentation_v2.hex` if
+/// the stored presentation does not verify. With `update` the files are rewritten instead.
+pub fn check_golden(dir: &Path, update: bool) -> Result<Vec<String>, PSError> {
+    let mut drifted = vec![];
+    for a in golden_artifacts() {
+        let path = dir.join(a.name);
+        if update {
+            fs::create_dir_all(dir)
+                .and_then(|_| fs::write(&path, &a.contents))
+                .map_err(|e| PSError::GeneralError {
+                    msg: format!("Cannot write golden file {}: {}", a.name, e),
+                })?;
+        } else if fs::read_to_string(&path).ok().as_deref() != Some(a.contents.as_str()) {
+            drifted.push(a.name.to_string());
+        }
+    }
+    if !verify_stored(dir).unwrap_or(false) {
+        drifted.push(String::from("presentation_v2.hex"));
+    }
+    drifted.dedup();
+    Ok(drifted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_golden_files() {
+        assert_eq!(golden_artifacts(), golden_artifacts());
+        let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+        let drifted = check_golden(&golden_dir(), update).unwrap();
+        assert!(
+            drifted.is_empty(),
+            "Serialization changed for {:?}, rerun with UPDATE_GOLDEN=1 if intended",
+            drifted
+        );
+    }
+}
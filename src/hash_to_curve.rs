@@ -0,0 +1,299 @@
+// Hashing to the groups behind a trait so params and points derived from messages can use the
+// RFC 9380 suites shared with other implementations, e.g. arkworks based verifiers, instead of amcl's
+// own map. `Rfc9380` is hash_to_curve with expand_message_xmd over SHA-256 and the simplified SWU map
+// of the BLS12381G1_XMD:SHA-256_SSWU_RO_ and BLS12381G2_XMD:SHA-256_SSWU_RO_ suites and derives the
+// generators of `Params::new` under tags of their own. It needs feature `rfc9380` for the bls12_381
+// crate. `AmclMapIt` is the map of `GroupElement::from_msg_hash` used by earlier versions, kept for
+// `Params::new_legacy`. `Params` record the id of the suite they were derived with.
+
+use crate::errors::PSError;
+use amcl_wrapper::group_elem::GroupElement;
+use amcl_wrapper::group_elem_g1::G1;
+use amcl_wrapper::group_elem_g2::G2;
+
+/// Id of `AmclMapIt`
+pub const AMCL_SUITE: &str = "BLS12-381 SHAKE256 amcl-mapit";
+/// Id of `Rfc9380`
+pub const RFC9380_SUITE: &str = "BLS12381_XMD:SHA-256_SSWU_RO_";
+/// Domain separation tags of `Rfc9380` as recommended by RFC 9380 section 3.1
+pub const RFC9380_DST_G1: &[u8] = b"PS-SIG-V01-CS01-with-BLS12381G1_XMD:SHA-256_SSWU_RO_";
+pub const RFC9380_DST_G2: &[u8] = b"PS-SIG-V01-CS01-with-BLS12381G2_XMD:SHA-256_SSWU_RO_";
+/// Domain separation tags of the generators of `Params` derived with `Rfc9380`
+pub const PARAMS_DST_G1: &[u8] = b"PS-SIG-V01-PARAMS-with-BLS12381G1_XMD:SHA-256_SSWU_RO_";
+pub const PARAMS_DST_G2: &[u8] = b"PS-SIG-V01-PARAMS-with-BLS12381G2_XMD:SHA-256_SSWU_RO_";
+
+pub trait HashToCurve {
+    /// Recorded in `Params` and `ParamsTranscript`
+    fn suite_id(&self) -> &'static str;
+
+    fn hash_to_g1(&self, msg: &[u8]) -> G1;
+
+    fn hash_to_g2(&self, msg: &[u8]) -> G2;
+
+    /// Tag the generators of `Params` in G1 are hashed with, None for suites without tags
+    fn generator_dst_g1(&self) -> Option<&'static [u8]> {
+        None
+    }
+
+    fn generator_dst_g2(&self) -> Option<&'static [u8]> {
+        None
+    }
+
+    /// Hash to a generator of `Params` in G1, like `hash_to_g1` for suites without tags
+    fn hash_generator_g1(&self, msg: &[u8]) -> G1 {
+        self.hash_to_g1(msg)
+    }
+
+    fn hash_generator_g2(&self, msg: &[u8]) -> G2 {
+        self.hash_to_g2(msg)
+    }
+
+    /// Hash to G1 under the tag `dst` of a protocol. Suites without tags hash `dst` followed by `msg`.
+    fn hash_to_g1_with_tag(&self, msg: &[u8], dst: &[u8]) -> G1 {
+        self.hash_to_g1(&[dst, msg].concat())
+    }
+
+    fn hash_to_g2_with_tag(&self, msg: &[u8], dst: &[u8]) -> G2 {
+        self.hash_to_g2(&[dst, msg].concat())
+    }
+}
+
+/// Groups that can be hashed to with a `HashToCurve`
+pub trait HashableGroup: GroupElement {
+    fn hash_with<H: HashToCurve + ?Sized>(hasher: &H, msg: &[u8]) -> Self;
+
+    fn hash_generator_with<H: HashToCurve + ?Sized>(hasher: &H, msg: &[u8]) -> Self;
+
+    fn generator_dst<H: HashToCurve + ?Sized>(hasher: &H) -> Option<&'static [u8]>;
+
+    fn hash_with_tag<H: HashToCurve + ?Sized>(hasher: &H, msg: &[u8], dst: &[u8]) -> Self;
+}
+
+impl HashableGroup for G1 {
+    fn hash_with<H: HashToCurve + ?Sized>(hasher: &H, msg: &[u8]) -> Self {
+        hasher.hash_to_g1(msg)
+    }
+
+    fn hash_generator_with<H: HashToCurve + ?Sized>(hasher: &H, msg: &[u8]) -> Self {
+        hasher.hash_generator_g1(msg)
+    }
+
+    fn generator_dst<H: HashToCurve + ?Sized>(hasher: &H) -> Option<&'static [u8]> {
+        hasher.generator_dst_g1()
+    }
+
+    fn hash_with_tag<H: HashToCurve + ?Sized>(hasher: &H, msg: &[u8], dst: &[u8]) -> Self {
+        hasher.hash_to_g1_with_tag(msg, dst)
+    }
+}
+
+impl HashableGroup for G2 {
+    fn hash_with<H: HashToCurve + ?Sized>(hasher: &H, msg: &[u8]) -> Self {
+        hasher.hash_to_g2(msg)
+    }
+
+    fn hash_generator_with<H: HashToCurve + ?Sized>(hasher: &H, msg: &[u8]) -> Self {
+        hasher.hash_generator_g2(msg)
+    }
+
+    fn generator_dst<H: HashToCurve + ?Sized>(hasher: &H) -> Option<&'static [u8]> {
+        hasher.generator_dst_g2()
+    }
+
+    fn hash_with_tag<H: HashToCurve + ?Sized>(hasher: &H, msg: &[u8], dst: &[u8]) -> Self {
+        hasher.hash_to_g2_with_tag(msg, dst)
+    }
+}
+
+/// amcl's SHAKE256 followed by try-and-increment, not compatible with other libraries
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AmclMapIt;
+
+impl HashToCurve for AmclMapIt {
+    fn suite_id(&self) -> &'static str {
+        AMCL_SUITE
+    }
+
+    fn hash_to_g1(&self, msg: &[u8]) -> G1 {
+        G1::from_msg_hash(msg)
+    }
+
+    fn hash_to_g2(&self, msg: &[u8]) -> G2 {
+        G2::from_msg_hash(msg)
+    }
+}
+
+#[cfg(feature = "rfc9380")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Rfc9380;
+
+#[cfg(feature = "rfc9380")]
+impl Rfc9380 {
+    /// hash_to_curve of BLS12381G1_XMD:SHA-256_SSWU_RO_ with tag `dst`
+    pub fn hash_to_g1_with_dst(msg: &[u8], dst: &[u8]) -> G1 {
+        use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve as Suite};
+        let p =
+            <bls12_381::G1Projective as Suite<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(msg, dst);
+        let affine = bls12_381::G1Affine::from(p);
+        if bool::from(affine.is_identity()) {
+            return G1::identity();
+        }
+        // Without the flags for a finite point the zcash encoding is x || y like amcl's uncompressed one
+        let mut bytes = vec![0x04];
+        bytes.extend_from_slice(&affine.to_uncompressed());
+        amcl_wrapper::types::GroupG1::frombytes(&bytes).into()
+    }
+
+    /// hash_to_curve of BLS12381G2_XMD:SHA-256_SSWU_RO_ with tag `dst`
+    pub fn hash_to_g2_with_dst(msg: &[u8], dst: &[u8]) -> G2 {
+        use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve as Suite};
+        let p =
+            <bls12_381::G2Projective as Suite<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(msg, dst);
+        let affine = bls12_381::G2Affine::from(p);
+        if bool::from(affine.is_identity()) {
+            return G2::identity();
+        }
+        // zcash orders the coefficients of Fp2 elements as c1, c0 and amcl as c0, c1
+        let zcash = affine.to_uncompressed();
+        let mut bytes = Vec::with_capacity(zcash.len());
+        for coordinate in zcash.chunks(96) {
+            bytes.extend_from_slice(&coordinate[48..]);
+            bytes.extend_from_slice(&coordinate[..48]);
+        }
+        G2::from_bytes(&bytes).expect("hash_to_curve gives a point of G2")
+    }
+}
+
+#[cfg(feature = "rfc9380")]
+impl HashToCurve for Rfc9380 {
+    fn suite_id(&self) -> &'static str {
+        RFC9380_SUITE
+    }
+
+    fn hash_to_g1(&self, msg: &[u8]) -> G1 {
+        Self::hash_to_g1_with_dst(msg, RFC9380_DST_G1)
+    }
+
+    fn hash_to_g2(&self, msg: &[u8]) -> G2 {
+        Self::hash_to_g2_with_dst(msg, RFC9380_DST_G2)
+    }
+
+    fn generator_dst_g1(&self) -> Option<&'static [u8]> {
+        Some(PARAMS_DST_G1)
+    }
+
+    fn generator_dst_g2(&self) -> Option<&'static [u8]> {
+        Some(PARAMS_DST_G2)
+    }
+
+    fn hash_generator_g1(&self, msg: &[u8]) -> G1 {
+        Self::hash_to_g1_with_dst(msg, PARAMS_DST_G1)
+    }
+
+    fn hash_generator_g2(&self, msg: &[u8]) -> G2 {
+        Self::hash_to_g2_with_dst(msg, PARAMS_DST_G2)
+    }
+
+    fn hash_to_g1_with_tag(&self, msg: &[u8], dst: &[u8]) -> G1 {
+        Self::hash_to_g1_with_dst(msg, dst)
+    }
+
+    fn hash_to_g2_with_tag(&self, msg: &[u8], dst: &[u8]) -> G2 {
+        Self::hash_to_g2_with_dst(msg, dst)
+    }
+}
+
+/// The built in implementation of the suite `suite_id`, fails for unknown suites
+pub fn hasher_for_suite(suite_id: &str) -> Result<&'static dyn HashToCurve, PSError> {
+    match suite_id {
+        AMCL_SUITE => Ok(&AmclMapIt),
+        #[cfg(feature = "rfc9380")]
+        RFC9380_SUITE => Ok(&Rfc9380),
+        _ => Err(PSError::GeneralError {
+            msg: format!("Unsupported hash suite {}", suite_id),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::Params;
+
+    #[test]
+    fn test_hasher_for_suite() {
+        let hasher = hasher_for_suite(AMCL_SUITE).unwrap();
+        assert_eq!(hasher.suite_id(), AMCL_SUITE);
+        assert_eq!(G1::hash_with(hasher, b"msg"), G1::from_msg_hash(b"msg"));
+        assert!(hasher_for_suite("BLS12381G1_XMD:SHA-256_SVDW_RO_").is_err());
+        #[cfg(feature = "rfc9380")]
+        assert_eq!(
+            hasher_for_suite(RFC9380_SUITE).unwrap().generator_dst_g1(),
+            Some(PARAMS_DST_G1)
+        );
+        #[cfg(not(feature = "rfc9380"))]
+        assert!(hasher_for_suite(RFC9380_SUITE).is_err());
+        assert!(hasher.generator_dst_g2().is_none());
+        assert_eq!(
+            G1::hash_with_tag(hasher, b"msg", b"tag"),
+            G1::from_msg_hash(b"tagmsg")
+        );
+
+        // Params serialized without a suite are amcl's
+        let params = Params::new_legacy(b"test");
+        let mut json: serde_json::Value = serde_json::to_value(&params).unwrap();
+        assert_eq!(json["hash_suite"], AMCL_SUITE);
+        json.as_object_mut().unwrap().remove("hash_suite");
+        let params_1: Params = serde_json::from_value(json).unwrap();
+        assert_eq!(params_1.hash_suite, AMCL_SUITE);
+    }
+
+    #[cfg(feature = "rfc9380")]
+    #[test]
+    fn test_rfc9380_vectors() {
+        use amcl_wrapper::types::GroupG1;
+        // RFC 9380 appendix J.9.1, msg ""
+        let p = Rfc9380::hash_to_g1_with_dst(
+            b"",
+            b"QUUX-V01-CS02-with-BLS12381G1_XMD:SHA-256_SSWU_RO_",
+        );
+        let expected = "04\
+            052926add2207b76ca4fa57a8734416c8dc95e24501772c814278700eed6d1e4e8cf62d9c09db0fac349612b759e79a1\
+            08ba738453bfed09cb546dbb0783dbb3a5f1f566ed67bb6be0e8c67e2e81a4cc68ee29813bb7994998f3eae0c9c6a265";
+        let bytes: Vec<u8> = (0..expected.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&expected[i..i + 2], 16).unwrap())
+            .collect();
+        assert_eq!(p, G1::from(GroupG1::frombytes(&bytes)));
+
+        // The coordinates are reordered correctly for amcl, the generator of either library is the other's
+        let g2 = bls12_381::G2Affine::generator().to_uncompressed();
+        let mut bytes = vec![];
+        for coordinate in g2.chunks(96) {
+            bytes.extend_from_slice(&coordinate[48..]);
+            bytes.extend_from_slice(&coordinate[..48]);
+        }
+        assert_eq!(G2::from_bytes(&bytes).unwrap(), G2::generator());
+        let h = Rfc9380.hash_to_g2(b"msg");
+        assert!(!h.is_identity());
+        assert_ne!(h, AmclMapIt.hash_to_g2(b"msg"));
+
+        let (params, transcript) =
+            crate::ceremony::ParamsTranscript::generate_with_hasher(b"test", &Rfc9380);
+        assert_eq!(params.hash_suite, RFC9380_SUITE);
+        assert!(params.verify_derivation(&transcript));
+        assert!(!Params::new_legacy(b"test").verify_derivation(&transcript));
+        assert!(transcript.verify_with_hasher(&AmclMapIt).is_err());
+        // Generators are hashed under their own tags
+        use crate::SignatureGroup;
+        assert_eq!(
+            params.g,
+            SignatureGroup::hash_generator_with(&Rfc9380, b"test")
+        );
+        assert_ne!(params.g, SignatureGroup::hash_with(&Rfc9380, b"test"));
+        assert_eq!(
+            G1::hash_with_tag(&Rfc9380, b"test", RFC9380_DST_G1),
+            G1::hash_with(&Rfc9380, b"test")
+        );
+    }
+}
@@ -0,0 +1,125 @@
+// hash_to_field of RFC 9380 section 5 for the scalar field of BLS12-381, with expand_message_xmd over
+// SHA-256 and L = 48 bytes per element (k = 128), so messages derived from byte strings can be
+// recomputed by other implementations. Every use has its own domain separation tag. Unlike
+// `FieldElement::from_msg_hash`, which is amcl specific, this is used for attribute encodings, binding
+// secrets and the messages of key statements.
+
+use crate::errors::PSError;
+use amcl_wrapper::constants::MODBYTES;
+use amcl_wrapper::field_elem::FieldElement;
+use sha2::{Digest, Sha256};
+
+/// ceil((ceil(log2(r)) + k) / 8) for the 255 bit group order and k = 128
+const L: usize = 48;
+const B_IN_BYTES: usize = 32;
+const S_IN_BYTES: usize = 64;
+
+/// expand_message_xmd of RFC 9380 section 5.3.1 with SHA-256. Tags longer than 255 bytes are hashed
+/// as in section 5.3.3.
+pub fn expand_message_xmd(msg: &[u8], dst: &[u8], len_in_bytes: usize) -> Result<Vec<u8>, PSError> {
+    let ell = len_in_bytes.div_ceil(B_IN_BYTES);
+    if ell > 255 || len_in_bytes > u16::MAX as usize {
+        return Err(PSError::GeneralError {
+            msg: format!("Cannot expand to {} bytes", len_in_bytes),
+        });
+    }
+    let long_dst;
+    let dst = if dst.len() > 255 {
+        long_dst = Sha256::new()
+            .chain(b"H2C-OVERSIZE-DST-")
+            .chain(dst)
+            .finalize();
+        &long_dst[..]
+    } else {
+        dst
+    };
+    let dst_prime = [dst, &[dst.len() as u8]].concat();
+
+    let b_0 = Sha256::new()
+        .chain([0u8; S_IN_BYTES])
+        .chain(msg)
+        .chain((len_in_bytes as u16).to_be_bytes())
+        .chain([0u8])
+        .chain(&dst_prime)
+        .finalize();
+    let mut b_i = Sha256::new()
+        .chain(b_0)
+        .chain([1u8])
+        .chain(&dst_prime)
+        .finalize();
+    let mut uniform_bytes = b_i.to_vec();
+    for i in 2..=ell {
+        let xored: Vec<u8> = b_0.iter().zip(b_i.iter()).map(|(a, b)| a ^ b).collect();
+        b_i = Sha256::new()
+            .chain(xored)
+            .chain([i as u8])
+            .chain(&dst_prime)
+            .finalize();
+        uniform_bytes.extend_from_slice(&b_i);
+    }
+    uniform_bytes.truncate(len_in_bytes);
+    Ok(uniform_bytes)
+}
+
+/// `count` field elements from `msg` under tag `dst`
+pub fn hash_to_field_elements(
+    msg: &[u8],
+    dst: &[u8],
+    count: usize,
+) -> Result<Vec<FieldElement>, PSError> {
+    let uniform_bytes = expand_message_xmd(msg, dst, count * L)?;
+    Ok(uniform_bytes
+        .chunks(L)
+        .map(|chunk| {
+            // OS2IP(chunk) mod r, L equals the size of amcl's big numbers
+            let mut bytes = [0; MODBYTES];
+            bytes.copy_from_slice(chunk);
+            FieldElement::from(&bytes)
+        })
+        .collect())
+}
+
+/// One field element from `msg` under tag `dst`
+pub fn hash_to_field(msg: &[u8], dst: &[u8]) -> FieldElement {
+    hash_to_field_elements(msg, dst, 1)
+        .expect("48 bytes can be expanded")
+        .remove(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::to_hex;
+
+    #[test]
+    fn test_hash_to_field() {
+        // RFC 9380 appendix K.1
+        let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+        assert_eq!(
+            to_hex(&expand_message_xmd(b"", dst, 0x20).unwrap()),
+            "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235"
+        );
+        assert_eq!(
+            to_hex(&expand_message_xmd(b"abc", dst, 0x20).unwrap()),
+            "d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615"
+        );
+        assert_eq!(expand_message_xmd(b"abc", dst, 0x80).unwrap().len(), 0x80);
+        assert!(expand_message_xmd(b"abc", dst, 256 * 32).is_err());
+
+        let e = hash_to_field_elements(b"abc", dst, 2).unwrap();
+        assert_eq!(e.len(), 2);
+        assert_ne!(e[0], e[1]);
+        // The length is an input of the expansion
+        assert_ne!(e[0], hash_to_field(b"abc", dst));
+        assert_eq!(hash_to_field(b"abc", dst), hash_to_field(b"abc", dst));
+        assert_ne!(
+            hash_to_field(b"abc", b"other tag"),
+            hash_to_field(b"abc", dst)
+        );
+        let long_dst = [7u8; 300];
+        assert_ne!(
+            hash_to_field(b"abc", &long_dst),
+            hash_to_field(b"abc", &long_dst[..299])
+        );
+    }
+}
@@ -0,0 +1,138 @@
+// Verification of many statements, each a presentation under an issuer's verkey, one at a time so a UI
+// can show progress and which statements failed. `verify_incremental` returns an iterator that verifies
+// the next statement only when asked for its result, a failed statement does not stop the others.
+
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::presentation::Presentation;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Statement<'a> {
+    pub presentation: &'a Presentation,
+    pub vk: &'a Verkey,
+}
+
+/// Result of the statement at `index`
+#[derive(Debug)]
+pub struct StatementResult {
+    pub index: usize,
+    pub result: Result<bool, PSError>,
+}
+
+impl StatementResult {
+    pub fn is_valid(&self) -> bool {
+        matches!(self.result, Ok(true))
+    }
+}
+
+/// Iterator over the results of `verify_incremental`
+pub struct IncrementalVerification<'a> {
+    statements: &'a [Statement<'a>],
+    params: &'a Params,
+    next: usize,
+}
+
+impl<'a> IncrementalVerification<'a> {
+    /// Number of statements verified so far
+    pub fn verified_count(&self) -> usize {
+        self.next
+    }
+}
+
+impl<'a> Iterator for IncrementalVerification<'a> {
+    type Item = StatementResult;
+
+    fn next(&mut self) -> Option<StatementResult> {
+        let statement = self.statements.get(self.next)?;
+        let index = self.next;
+        self.next += 1;
+        Some(StatementResult {
+            index,
+            result: statement.presentation.verify(statement.vk, self.params),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.statements.len() - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for IncrementalVerification<'a> {}
+
+pub fn verify_incremental<'a>(
+    statements: &'a [Statement<'a>],
+    params: &'a Params,
+) -> IncrementalVerification<'a> {
+    IncrementalVerification {
+        statements,
+        params,
+        next: 0,
+    }
+}
+
+/// Same as `verify_incremental` but `on_result` is called with each result as it completes. Returns
+/// whether all statements are valid.
+pub fn verify_incremental_with_callback<F: FnMut(&StatementResult)>(
+    statements: &[Statement],
+    params: &Params,
+    mut on_result: F,
+) -> bool {
+    let mut all_valid = true;
+    for r in verify_incremental(statements, params) {
+        all_valid &= r.is_valid();
+        on_result(&r);
+    }
+    all_valid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use crate::signature::Signature;
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_incremental_verification() {
+        let params = Params::new("test".as_bytes());
+        let mut keys = vec![];
+        let mut presentations = vec![];
+        for _ in 0..4 {
+            let (sk, vk) = keygen(2, &params);
+            let msgs = FieldElementVector::random(2);
+            let sig = Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params)
+                .unwrap();
+            presentations.push(
+                Presentation::new(&sig, &vk, &params, msgs.as_slice(), HashSet::new(), b"n")
+                    .unwrap(),
+            );
+            keys.push(vk);
+        }
+        // Statement 2 claims the wrong issuer
+        let statements: Vec<Statement> = (0..4)
+            .map(|i| Statement {
+                presentation: &presentations[i],
+                vk: &keys[if i == 2 { 3 } else { i }],
+            })
+            .collect();
+
+        let mut verification = verify_incremental(&statements, &params);
+        assert_eq!(verification.len(), 4);
+        let first = verification.next().unwrap();
+        assert!(first.is_valid() && first.index == 0);
+        assert_eq!(verification.verified_count(), 1);
+        let rest: Vec<bool> = verification.map(|r| r.is_valid()).collect();
+        assert_eq!(rest, vec![true, false, true]);
+
+        let mut seen = vec![];
+        assert!(!verify_incremental_with_callback(
+            &statements,
+            &params,
+            |r| seen.push(r.index)
+        ));
+        assert_eq!(seen, vec![0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,338 @@
+// Encodings for interop with stacks using other serialization conventions. An `EncodingProfile` picks
+// the byte order of scalars and lengths, whether points are compressed and whether scalars take the
+// full 48 bytes of amcl or are packed into 32 bytes. The profile is sent as the first byte of every
+// artifact encoded with `ProfileEncoding::to_bytes_with_encoding` so the reader needs no negotiation.
+// Point encodings are those of amcl in either byte order, only G1 elements can be compressed.
+
+use crate::errors::PSError;
+use crate::keys::Verkey;
+use crate::limits::DecodeLimits;
+use crate::pok_sig::{PoKOfSignatureProof, ProofOtherGroup};
+use crate::signature::Signature;
+use crate::utils::{
+    pack_field_element, point_from_compressed_bytes, unpack_field_element, ByteReader,
+    PointEncoding,
+};
+use crate::validation::ValidationProfile;
+use crate::{OtherGroup, SignatureGroup, OTHER_GROUP_SIZE, SIGNATURE_GROUP_SIZE};
+use amcl_wrapper::constants::MODBYTES;
+use amcl_wrapper::field_elem::{FieldElement, FieldElementVector};
+use amcl_wrapper::group_elem::GroupElement;
+
+const LITTLE_ENDIAN_FLAG: u8 = 1;
+const COMPRESSED_FLAG: u8 = 1 << 1;
+const PACKED_SCALAR_FLAG: u8 = 1 << 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Endianness {
+    Big,
+    Little,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScalarWidth {
+    /// 48 bytes as amcl encodes them
+    Full,
+    /// 32 bytes, enough for scalars of BLS12-381
+    Packed,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncodingProfile {
+    pub endianness: Endianness,
+    pub compressed_points: bool,
+    pub scalar_width: ScalarWidth,
+}
+
+impl Default for EncodingProfile {
+    /// Same conventions as `to_bytes` of the artifacts
+    fn default() -> Self {
+        Self {
+            endianness: Endianness::Big,
+            compressed_points: false,
+            scalar_width: ScalarWidth::Full,
+        }
+    }
+}
+
+impl EncodingProfile {
+    pub fn to_byte(&self) -> u8 {
+        let mut b = 0;
+        if self.endianness == Endianness::Little {
+            b |= LITTLE_ENDIAN_FLAG;
+        }
+        if self.compressed_points {
+            b |= COMPRESSED_FLAG;
+        }
+        if self.scalar_width == ScalarWidth::Packed {
+            b |= PACKED_SCALAR_FLAG;
+        }
+        b
+    }
+
+    pub fn from_byte(b: u8) -> Result<Self, PSError> {
+        if b & !(LITTLE_ENDIAN_FLAG | COMPRESSED_FLAG | PACKED_SCALAR_FLAG) != 0 {
+            return Err(PSError::InvalidEncoding {
+                msg: format!("Unknown encoding profile {:#04x}", b),
+            });
+        }
+        Ok(Self {
+            endianness: if b & LITTLE_ENDIAN_FLAG != 0 {
+                Endianness::Little
+            } else {
+                Endianness::Big
+            },
+            compressed_points: b & COMPRESSED_FLAG != 0,
+            scalar_width: if b & PACKED_SCALAR_FLAG != 0 {
+                ScalarWidth::Packed
+            } else {
+                ScalarWidth::Full
+            },
+        })
+    }
+
+    fn ordered(&self, mut bytes: Vec<u8>) -> Vec<u8> {
+        if self.endianness == Endianness::Little {
+            bytes.reverse();
+        }
+        bytes
+    }
+
+    fn scalar_size(&self) -> usize {
+        match self.scalar_width {
+            ScalarWidth::Full => MODBYTES,
+            ScalarWidth::Packed => crate::utils::PACKED_FIELD_ELEMENT_SIZE,
+        }
+    }
+}
+
+/// Appends values encoded as per the profile
+pub struct ProfileWriter {
+    pub profile: EncodingProfile,
+    pub bytes: Vec<u8>,
+}
+
+impl ProfileWriter {
+    pub fn new(profile: EncodingProfile) -> Self {
+        Self {
+            profile,
+            bytes: vec![profile.to_byte()],
+        }
+    }
+
+    pub fn write_u32(&mut self, v: u32) {
+        let b = self.profile.ordered(v.to_be_bytes().to_vec());
+        self.bytes.extend_from_slice(&b);
+    }
+
+    pub fn write_scalar(&mut self, e: &FieldElement) {
+        let b = match self.profile.scalar_width {
+            ScalarWidth::Full => e.to_bytes(),
+            ScalarWidth::Packed => pack_field_element(e),
+        };
+        let mut b = self.profile.ordered(b);
+        self.bytes.append(&mut b);
+    }
+
+    pub fn write_point<G: PointEncoding>(&mut self, p: &G) {
+        let b = if self.profile.compressed_points {
+            p.to_compressed_bytes()
+        } else {
+            p.to_bytes()
+        };
+        let mut b = self.profile.ordered(b);
+        self.bytes.append(&mut b);
+    }
+}
+
+/// Reads values encoded as per the profile in the first byte
+pub struct ProfileReader<'a> {
+    pub profile: EncodingProfile,
+    reader: ByteReader<'a>,
+}
+
+impl<'a> ProfileReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Result<Self, PSError> {
+        let mut reader = ByteReader::new(bytes);
+        let profile = EncodingProfile::from_byte(reader.read_u8()?)?;
+        Ok(Self { profile, reader })
+    }
+
+    fn take_ordered(&mut self, n: usize) -> Result<Vec<u8>, PSError> {
+        Ok(self.profile.ordered(self.reader.take(n)?.to_vec()))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, PSError> {
+        let mut b = [0; 4];
+        b.copy_from_slice(&self.take_ordered(4)?);
+        Ok(u32::from_be_bytes(b))
+    }
+
+    pub fn read_scalar(&mut self) -> Result<FieldElement, PSError> {
+        let b = self.take_ordered(self.profile.scalar_size())?;
+        match self.profile.scalar_width {
+            ScalarWidth::Full => ValidationProfile::Strict.field_element_from_bytes(&b),
+            ScalarWidth::Packed => unpack_field_element(&b),
+        }
+    }
+
+    /// `uncompressed_size` is the size of `G::to_bytes`
+    pub fn read_point<G: PointEncoding>(&mut self, uncompressed_size: usize) -> Result<G, PSError> {
+        if self.profile.compressed_points {
+            let b = self.take_ordered(G::COMPRESSED_SIZE)?;
+            point_from_compressed_bytes(&b)
+        } else {
+            let b = self.take_ordered(uncompressed_size)?;
+            ValidationProfile::default().point_from_bytes(&b)
+        }
+    }
+
+    pub fn finish(self) -> Result<(), PSError> {
+        self.reader.finish()
+    }
+}
+
+/// Artifacts that can be encoded with any `EncodingProfile`
+pub trait ProfileEncoding: Sized {
+    fn write(&self, writer: &mut ProfileWriter);
+
+    fn read(reader: &mut ProfileReader) -> Result<Self, PSError>;
+
+    fn to_bytes_with_encoding(&self, profile: EncodingProfile) -> Vec<u8> {
+        let mut writer = ProfileWriter::new(profile);
+        self.write(&mut writer);
+        writer.bytes
+    }
+
+    /// Returns the profile the bytes were encoded with as well
+    fn from_bytes_with_encoding(bytes: &[u8]) -> Result<(Self, EncodingProfile), PSError> {
+        let mut reader = ProfileReader::new(bytes)?;
+        let profile = reader.profile;
+        let v = Self::read(&mut reader)?;
+        reader.finish()?;
+        Ok((v, profile))
+    }
+}
+
+impl ProfileEncoding for Signature {
+    fn write(&self, writer: &mut ProfileWriter) {
+        writer.write_point(&self.sigma_1);
+        writer.write_point(&self.sigma_2);
+    }
+
+    fn read(reader: &mut ProfileReader) -> Result<Self, PSError> {
+        let sigma_1: SignatureGroup = reader.read_point(SIGNATURE_GROUP_SIZE)?;
+        let sigma_2: SignatureGroup = reader.read_point(SIGNATURE_GROUP_SIZE)?;
+        if sigma_1.is_identity() || sigma_2.is_identity() {
+            return Err(PSError::InvalidEncoding {
+                msg: String::from("Signature has identity elements"),
+            });
+        }
+        Ok(Self { sigma_1, sigma_2 })
+    }
+}
+
+impl ProfileEncoding for Verkey {
+    fn write(&self, writer: &mut ProfileWriter) {
+        writer.write_u32(self.Y_tilde.len() as u32);
+        writer.write_point(&self.X_tilde);
+        for y in &self.Y_tilde {
+            writer.write_point(y);
+        }
+    }
+
+    fn read(reader: &mut ProfileReader) -> Result<Self, PSError> {
+        let count = reader.read_u32()? as usize;
+        DecodeLimits::current().check_msg_count(count)?;
+        let X_tilde = reader.read_point(OTHER_GROUP_SIZE)?;
+        let mut Y_tilde = vec![];
+        for _ in 0..count {
+            Y_tilde.push(reader.read_point(OTHER_GROUP_SIZE)?);
+        }
+        Ok(Self { X_tilde, Y_tilde })
+    }
+}
+
+impl ProfileEncoding for PoKOfSignatureProof {
+    fn write(&self, writer: &mut ProfileWriter) {
+        self.sig.write(writer);
+        writer.write_point(&self.J);
+        writer.write_point(&self.proof_vc.commitment);
+        writer.write_u32(self.proof_vc.responses.len() as u32);
+        for r in self.proof_vc.responses.as_slice() {
+            writer.write_scalar(r);
+        }
+    }
+
+    fn read(reader: &mut ProfileReader) -> Result<Self, PSError> {
+        let sig = Signature::read(reader)?;
+        let J: OtherGroup = reader.read_point(OTHER_GROUP_SIZE)?;
+        let commitment = reader.read_point(OTHER_GROUP_SIZE)?;
+        let count = reader.read_u32()? as usize;
+        DecodeLimits::current().check_msg_count(count.saturating_sub(1))?;
+        // The count is not trusted for allocation
+        let mut responses = FieldElementVector::with_capacity(count.min(256));
+        for _ in 0..count {
+            responses.push(reader.read_scalar()?);
+        }
+        Ok(Self {
+            sig,
+            J,
+            proof_vc: ProofOtherGroup {
+                commitment,
+                responses,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::{keygen, Params};
+    use crate::pok_sig::PoKOfSignature;
+    use crate::types::Message;
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn test_encoding_profiles() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let msgs = FieldElementVector::random(3);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let pok = PoKOfSignature::init(
+            &sig,
+            &vk,
+            &params,
+            &Message::from_field_elements(msgs.as_slice()),
+            None,
+            HashSet::new(),
+        )
+        .unwrap();
+        let chal = pok.pok_vc.gen_challenge(vec![]);
+        let proof = pok.gen_proof(&chal).unwrap();
+
+        assert_eq!(
+            sig.to_bytes_with_encoding(EncodingProfile::default()),
+            [vec![0], sig.to_bytes()].concat()
+        );
+        for b in 0..8 {
+            let profile = EncodingProfile::from_byte(b).unwrap();
+            assert_eq!(profile.to_byte(), b);
+            let (s, p) =
+                Signature::from_bytes_with_encoding(&sig.to_bytes_with_encoding(profile)).unwrap();
+            assert_eq!((s.to_bytes(), p), (sig.to_bytes(), profile));
+            let (v, _) =
+                Verkey::from_bytes_with_encoding(&vk.to_bytes_with_encoding(profile)).unwrap();
+            assert_eq!(v, vk);
+            let bytes = proof.to_bytes_with_encoding(profile);
+            let (pr, _) = PoKOfSignatureProof::from_bytes_with_encoding(&bytes).unwrap();
+            assert!(pr.verify(&vk, &params, HashMap::new(), &chal).unwrap());
+            assert!(
+                PoKOfSignatureProof::from_bytes_with_encoding(&bytes[..bytes.len() - 1]).is_err()
+            );
+        }
+        assert!(EncodingProfile::from_byte(8).is_err());
+    }
+}
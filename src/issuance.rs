@@ -0,0 +1,336 @@
+// Blind issuance requests with an issuer-side dry run. The holder commits to the first messages as
+// Y_1^m_1 * Y_2^m_2 * ... * g^t, proves knowledge of the opening bound to the issuer's nonce, and sends
+// the remaining messages in the clear. `CredentialRequest::validate` runs every structural, proof and
+// policy check without the signing key and reports all problems found, so frontends can reject bad
+// requests before they reach an HSM. `issue` signs only requests that validate.
+
+use crate::blind_signature::{BlindingKey, ProofSignatureGroup, ProverCommittingSignatureGroup};
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::policy::{CompiledPolicy, PolicyViolation};
+use crate::precomputed::PrecomputedBlindingKey;
+use crate::signature::Signature;
+use crate::signer::PsSigner;
+use crate::types::{Blinding, Challenge};
+use crate::validation::deserialize_point;
+use crate::{SignatureGroup, SignatureGroupVec};
+use amcl_wrapper::field_elem::{FieldElement, FieldElementVector};
+use amcl_wrapper::group_elem::GroupElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CredentialRequest {
+    #[serde(deserialize_with = "deserialize_point")]
+    pub commitment: SignatureGroup,
+    pub proof: ProofSignatureGroup,
+    /// Messages after the committed ones
+    pub known_messages: Vec<FieldElement>,
+    pub nonce: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RequestIssue {
+    /// Verkey and blinding key are for different message counts
+    KeyMismatch,
+    MessageCountMismatch {
+        expected: usize,
+        given: usize,
+    },
+    NoCommittedMessages,
+    IdentityCommitment,
+    NonceMismatch,
+    InvalidProof,
+    Policy(PolicyViolation),
+}
+
+/// Result of `CredentialRequest::validate`. The proof is not checked when the request is malformed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RequestReport {
+    pub issues: Vec<RequestIssue>,
+}
+
+impl RequestReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Y_1, Y_2, ..., Y_count, g
+fn commitment_bases(
+    count: usize,
+    blinding_key: &BlindingKey,
+    params: &Params,
+) -> Vec<SignatureGroup> {
+    let mut bases = blinding_key.Y[..count].to_vec();
+    bases.push(params.g.clone());
+    bases
+}
+
+fn challenge_bytes(
+    commitment: &SignatureGroup,
+    known_messages: &[FieldElement],
+    nonce: &[u8],
+) -> Vec<u8> {
+    let mut bytes = commitment.to_bytes();
+    for m in known_messages {
+        bytes.append(&mut m.to_bytes());
+    }
+    bytes.extend_from_slice(nonce);
+    bytes
+}
+
+impl CredentialRequest {
+    /// Holder side. The returned blinding unblinds the issued signature.
+    pub fn new(
+        committed_messages: &[FieldElement],
+        known_messages: &[FieldElement],
+        blinding_key: &BlindingKey,
+        params: &Params,
+        nonce: &[u8],
+    ) -> Result<(Self, Blinding), PSError> {
+        Self::new_with_rng(
+            committed_messages,
+            known_messages,
+            blinding_key,
+            params,
+            nonce,
+            &mut thread_rng(),
+        )
+    }
+
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        committed_messages: &[FieldElement],
+        known_messages: &[FieldElement],
+        blinding_key: &BlindingKey,
+        params: &Params,
+        nonce: &[u8],
+        rng: &mut R,
+    ) -> Result<(Self, Blinding), PSError> {
+        Self::new_with_tables(
+            committed_messages,
+            known_messages,
+            blinding_key,
+            params,
+            nonce,
+            None,
+            rng,
+        )
+    }
+
+    /// With `tables` of `blinding_key`, the commitments are computed from fixed-base tables instead
+    /// of multi-scalar multiplications
+    pub(crate) fn new_with_tables<R: RngCore + CryptoRng>(
+        committed_messages: &[FieldElement],
+        known_messages: &[FieldElement],
+        blinding_key: &BlindingKey,
+        params: &Params,
+        nonce: &[u8],
+        tables: Option<&PrecomputedBlindingKey>,
+        rng: &mut R,
+    ) -> Result<(Self, Blinding), PSError> {
+        let count = committed_messages.len() + known_messages.len();
+        if committed_messages.is_empty() || count != blinding_key.msg_count() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: blinding_key.msg_count(),
+                given: count,
+            });
+        }
+        let blinding = Blinding::random_using_rng(rng);
+        let mut secrets = committed_messages.to_vec();
+        secrets.push(blinding.0.clone());
+        let bases = commitment_bases(committed_messages.len(), blinding_key, params);
+        fail_point!(Commitment);
+        let commitment = match tables {
+            Some(tables) => tables.mul_committed(&secrets),
+            None => SignatureGroupVec::from(bases.as_slice())
+                .multi_scalar_mul_const_time(&FieldElementVector::from(secrets.as_slice()))
+                .unwrap(),
+        };
+
+        let blindings: Vec<FieldElement> = bases
+            .iter()
+            .map(|_| FieldElement::random_using_rng(rng))
+            .collect();
+        let mut committing = ProverCommittingSignatureGroup::new();
+        for (b, blinding) in bases.iter().zip(blindings.iter()) {
+            committing.commit(b, Some(blinding));
+        }
+        let committed = match tables {
+            Some(tables) => committing.finish_with_commitment(tables.mul_committed(&blindings)),
+            None => committing.finish(),
+        };
+        let challenge =
+            committed.gen_challenge(challenge_bytes(&commitment, known_messages, nonce));
+        let proof = committed.gen_proof(&challenge, &secrets)?;
+        let request = Self {
+            commitment,
+            proof,
+            known_messages: known_messages.to_vec(),
+            nonce: nonce.to_vec(),
+        };
+        Ok((request, blinding))
+    }
+
+    /// Issuer side dry run. `policy` is evaluated on the known messages as if revealed, with
+    /// attribute names for all messages of the credential. `nonce` is the one given to the holder.
+    pub fn validate(
+        &self,
+        vk: &Verkey,
+        blinding_key: &BlindingKey,
+        params: &Params,
+        policy: &CompiledPolicy,
+        nonce: &[u8],
+    ) -> RequestReport {
+        let mut issues = vec![];
+        let count = blinding_key.msg_count();
+        let count_committed = self.proof.responses.len().saturating_sub(1);
+        if vk.Y_tilde.len() != count {
+            issues.push(RequestIssue::KeyMismatch);
+        }
+        if count_committed + self.known_messages.len() != count {
+            issues.push(RequestIssue::MessageCountMismatch {
+                expected: count,
+                given: count_committed + self.known_messages.len(),
+            });
+        }
+        if count_committed == 0 {
+            issues.push(RequestIssue::NoCommittedMessages);
+        }
+        if self.commitment.is_identity() {
+            issues.push(RequestIssue::IdentityCommitment);
+        }
+        if self.nonce != nonce {
+            issues.push(RequestIssue::NonceMismatch);
+        }
+
+        if issues.is_empty() {
+            // Same bytes as `ProverCommitted::gen_challenge`
+            let bases = commitment_bases(count_committed, blinding_key, params);
+            let mut bytes = vec![];
+            for b in &bases {
+                bytes.append(&mut b.to_bytes());
+            }
+            bytes.append(&mut self.proof.commitment.to_bytes());
+            bytes.append(&mut challenge_bytes(
+                &self.commitment,
+                &self.known_messages,
+                &self.nonce,
+            ));
+            let challenge = Challenge::from_msg_hash(&bytes);
+            if !self
+                .proof
+                .verify(&bases, &self.commitment, &challenge)
+                .unwrap_or(false)
+            {
+                issues.push(RequestIssue::InvalidProof);
+            }
+        }
+
+        let offset = count.saturating_sub(self.known_messages.len());
+        let known: HashMap<usize, FieldElement> = self
+            .known_messages
+            .iter()
+            .enumerate()
+            .map(|(i, m)| (offset + i, m.clone()))
+            .collect();
+        issues.extend(
+            policy
+                .evaluate(vk, &known)
+                .into_iter()
+                .map(RequestIssue::Policy),
+        );
+        RequestReport { issues }
+    }
+
+    /// Validate the request and blind sign it with `signer`
+    pub fn issue<S: PsSigner + ?Sized>(
+        &self,
+        signer: &S,
+        vk: &Verkey,
+        blinding_key: &BlindingKey,
+        params: &Params,
+        policy: &CompiledPolicy,
+        nonce: &[u8],
+    ) -> Result<Signature, PSError> {
+        let report = self.validate(vk, blinding_key, params, policy, nonce);
+        if !report.is_valid() {
+            return Err(PSError::GeneralError {
+                msg: format!("Invalid credential request: {:?}", report.issues),
+            });
+        }
+        signer.blind_sign(&self.commitment, &self.known_messages, params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blind_signature::BlindSignature;
+    use crate::keys::keygen;
+    use crate::policy::{Policy, Predicate};
+    use crate::types::Message;
+
+    #[test]
+    fn test_credential_request_validation() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let blinding_key = BlindingKey::new(&sk, &params);
+        let msgs = FieldElementVector::random(3);
+        let policy = CompiledPolicy::compile(
+            &[Policy::require_predicate(
+                "country",
+                Predicate::Equals(msgs[2].clone()),
+            )],
+            &["secret", "name", "country"],
+        )
+        .unwrap();
+
+        let (request, blinding) = CredentialRequest::new(
+            &msgs.as_slice()[..1],
+            &msgs.as_slice()[1..],
+            &blinding_key,
+            &params,
+            b"n",
+        )
+        .unwrap();
+        assert!(request
+            .validate(&vk, &blinding_key, &params, &policy, b"n")
+            .is_valid());
+        let sig = request
+            .issue(&sk, &vk, &blinding_key, &params, &policy, b"n")
+            .unwrap();
+        let sig = BlindSignature::unblind(&sig, &blinding);
+        assert!(sig
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+
+        // All problems are reported
+        let mut bad = request.clone();
+        bad.known_messages[1] = FieldElement::random();
+        let report = bad.validate(&vk, &blinding_key, &params, &policy, b"other");
+        assert_eq!(
+            report.issues,
+            vec![
+                RequestIssue::NonceMismatch,
+                RequestIssue::Policy(PolicyViolation::PredicateNotSatisfied {
+                    attribute: String::from("country")
+                })
+            ]
+        );
+        let report = bad.validate(&vk, &blinding_key, &params, &policy, b"n");
+        assert_eq!(report.issues[0], RequestIssue::InvalidProof);
+        assert!(bad
+            .issue(&sk, &vk, &blinding_key, &params, &policy, b"n")
+            .is_err());
+        bad.known_messages.pop();
+        let report = bad.validate(&vk, &blinding_key, &params, &policy, b"n");
+        assert_eq!(
+            report.issues[0],
+            RequestIssue::MessageCountMismatch {
+                expected: 3,
+                given: 2
+            }
+        );
+    }
+}
@@ -0,0 +1,314 @@
+// Presentations hiding which of a list of trusted issuers signed the credential, in constant size and
+// time in the length of the list, after Bobolz et al., "Issuer-Hiding Attribute-Based Credentials".
+// Instead of an OR-proof over all verkeys the verifier commits to its list by signing each verkey, as
+// the vector (X_tilde, Y_tilde_1, ..., Y_tilde_n), with a structure-preserving signature on equivalence
+// classes (Fuchsbauer, Hanser, Slamanig, "Structure-Preserving Signatures on Equivalence Classes and
+// Constant-Size Anonymous Credentials"). Such a signature can be adapted to any multiple mu of the
+// vector without the signing key. The holder presents the randomized verkey vk^mu with the adapted
+// signature, and a `Presentation` under vk^mu of its signature (sigma_1, sigma_2^mu), which is a
+// signature on the same messages under vk^mu. Verifiers see neither vk nor its index in the list and
+// only need the `KeyListVerkey`.
+//
+// The randomized verkeys of one issuer are unlinkable if DDH is hard in `OtherGroup`. All verkeys of a
+// list must have the same number of messages.
+
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::presentation::Presentation;
+use crate::signature::Signature;
+use crate::validation::{deserialize_point, deserialize_points};
+use crate::{ate_2_pairing, ate_multi_pairing, OtherGroup, SignatureGroup};
+use amcl_wrapper::field_elem::FieldElement;
+use amcl_wrapper::group_elem::GroupElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::HashSet;
+
+/// Signing key of a key list, one scalar per element of the verkeys in the list
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyListSigkey {
+    pub x: Vec<FieldElement>,
+}
+
+/// Public commitment to a key list, g^x_i
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyListVerkey {
+    #[serde(deserialize_with = "deserialize_points")]
+    pub X: Vec<SignatureGroup>,
+}
+
+/// Signature of the key list on a verkey or on a multiple of it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyListSignature {
+    #[serde(deserialize_with = "deserialize_point")]
+    pub Z: OtherGroup,
+    #[serde(deserialize_with = "deserialize_point")]
+    pub Y: OtherGroup,
+    #[serde(deserialize_with = "deserialize_point")]
+    pub Y_hat: SignatureGroup,
+}
+
+/// A key list's signatures on its verkeys, in the order of the list
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyListCommitment {
+    pub verkey: KeyListVerkey,
+    pub entries: Vec<(Verkey, KeyListSignature)>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HiddenIssuerPresentation {
+    /// vk^mu of the issuer's vk
+    pub verkey: Verkey,
+    pub list_signature: KeyListSignature,
+    pub presentation: Presentation,
+}
+
+/// X_tilde followed by all Y_tilde
+fn elements(vk: &Verkey) -> impl Iterator<Item = &OtherGroup> {
+    std::iter::once(&vk.X_tilde).chain(&vk.Y_tilde)
+}
+
+impl KeyListSigkey {
+    /// Key for lists of verkeys for `msg_count` messages
+    pub fn new(msg_count: usize) -> Self {
+        Self::new_with_rng(msg_count, &mut thread_rng())
+    }
+
+    pub fn new_with_rng<R: RngCore + CryptoRng>(msg_count: usize, rng: &mut R) -> Self {
+        Self {
+            x: (0..=msg_count)
+                .map(|_| FieldElement::random_using_rng(rng))
+                .collect(),
+        }
+    }
+
+    pub fn verkey(&self, params: &Params) -> KeyListVerkey {
+        KeyListVerkey {
+            X: self.x.iter().map(|x| &params.g * x).collect(),
+        }
+    }
+
+    pub fn sign(&self, vk: &Verkey, params: &Params) -> Result<KeyListSignature, PSError> {
+        self.sign_with_rng(vk, params, &mut thread_rng())
+    }
+
+    pub fn sign_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        vk: &Verkey,
+        params: &Params,
+        rng: &mut R,
+    ) -> Result<KeyListSignature, PSError> {
+        if vk.Y_tilde.len() + 1 != self.x.len() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: self.x.len() - 1,
+                given: vk.Y_tilde.len(),
+            });
+        }
+        if elements(vk).any(|e| e.is_identity()) {
+            return Err(PSError::GeneralError {
+                msg: String::from("Verkey has an identity element"),
+            });
+        }
+        let y = FieldElement::random_using_rng(rng);
+        let mut Z = OtherGroup::identity();
+        for (e, x) in elements(vk).zip(&self.x) {
+            Z += e * x;
+        }
+        let y_inv = y.inverse();
+        Ok(KeyListSignature {
+            Z: &Z * &y,
+            Y: &params.g_tilde * &y_inv,
+            Y_hat: &params.g * &y_inv,
+        })
+    }
+
+    /// Sign all `verkeys`
+    pub fn commit(
+        &self,
+        verkeys: &[Verkey],
+        params: &Params,
+    ) -> Result<KeyListCommitment, PSError> {
+        let mut entries = vec![];
+        for vk in verkeys {
+            entries.push((vk.clone(), self.sign(vk, params)?));
+        }
+        Ok(KeyListCommitment {
+            verkey: self.verkey(params),
+            entries,
+        })
+    }
+}
+
+impl KeyListCommitment {
+    /// The signature a holder of a credential under `vk` needs
+    pub fn signature_for(&self, vk: &Verkey) -> Option<&KeyListSignature> {
+        self.entries.iter().find(|(v, _)| v == vk).map(|(_, s)| s)
+    }
+}
+
+impl KeyListSignature {
+    /// Whether this is a signature on `vk`, i.e. on some multiple of a verkey in the list
+    pub fn verify(&self, vk: &Verkey, list_verkey: &KeyListVerkey, params: &Params) -> bool {
+        if vk.Y_tilde.len() + 1 != list_verkey.X.len()
+            || elements(vk).any(|e| e.is_identity())
+            || self.Z.is_identity()
+            || self.Y_hat.is_identity()
+        {
+            return false;
+        }
+        // prod e(X_i, vk_i) == e(Y_hat, Z) and e(g, Y) == e(Y_hat, g_tilde)
+        let neg_Y_hat = -&self.Y_hat;
+        let mut pairs: Vec<(&SignatureGroup, &OtherGroup)> =
+            list_verkey.X.iter().zip(elements(vk)).collect();
+        pairs.push((&neg_Y_hat, &self.Z));
+        ate_multi_pairing(&pairs).is_one()
+            && ate_2_pairing(&params.g, &self.Y, &neg_Y_hat, &params.g_tilde).is_one()
+    }
+
+    /// Signature on `vk^mu` of the same list, also randomized by `psi`
+    fn adapt(&self, mu: &FieldElement, psi: &FieldElement) -> Self {
+        let psi_inv = psi.inverse();
+        Self {
+            Z: &self.Z * &(mu * psi),
+            Y: &self.Y * &psi_inv,
+            Y_hat: &self.Y_hat * &psi_inv,
+        }
+    }
+}
+
+impl HiddenIssuerPresentation {
+    /// `list_signature` is the key list's signature on `vk`
+    pub fn new(
+        sig: &Signature,
+        vk: &Verkey,
+        list_signature: &KeyListSignature,
+        params: &Params,
+        messages: &[FieldElement],
+        revealed_msg_indices: HashSet<usize>,
+        nonce: &[u8],
+    ) -> Result<Self, PSError> {
+        Self::new_with_rng(
+            sig,
+            vk,
+            list_signature,
+            params,
+            messages,
+            revealed_msg_indices,
+            nonce,
+            &mut thread_rng(),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        sig: &Signature,
+        vk: &Verkey,
+        list_signature: &KeyListSignature,
+        params: &Params,
+        messages: &[FieldElement],
+        revealed_msg_indices: HashSet<usize>,
+        nonce: &[u8],
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        let mu = FieldElement::random_using_rng(rng);
+        let psi = FieldElement::random_using_rng(rng);
+        let verkey = Verkey {
+            X_tilde: &vk.X_tilde * &mu,
+            Y_tilde: vk.Y_tilde.iter().map(|y| y * &mu).collect(),
+        };
+        let sig = Signature {
+            sigma_1: sig.sigma_1.clone(),
+            sigma_2: &sig.sigma_2 * &mu,
+        };
+        let presentation = Presentation::new_with_rng(
+            &sig,
+            &verkey,
+            params,
+            messages,
+            revealed_msg_indices,
+            nonce,
+            rng,
+        )?;
+        Ok(Self {
+            verkey,
+            list_signature: list_signature.adapt(&mu, &psi),
+            presentation,
+        })
+    }
+
+    pub fn verify(&self, list_verkey: &KeyListVerkey, params: &Params) -> Result<bool, PSError> {
+        if !self
+            .list_signature
+            .verify(&self.verkey, list_verkey, params)
+        {
+            return Ok(false);
+        }
+        self.presentation.verify(&self.verkey, params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::FieldElementVector;
+
+    #[test]
+    fn test_hidden_issuer_presentation() {
+        let params = Params::new("test".as_bytes());
+        let issuers: Vec<_> = (0..4).map(|_| keygen(3, &params)).collect();
+        let verkeys: Vec<Verkey> = issuers.iter().map(|(_, vk)| vk.clone()).collect();
+        let list_key = KeyListSigkey::new(3);
+        let list = list_key.commit(&verkeys, &params).unwrap();
+
+        let (sk, vk) = &issuers[2];
+        let msgs = FieldElementVector::random(3);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), sk, &params).unwrap();
+        let list_sig = list.signature_for(vk).unwrap();
+        let mut revealed = HashSet::new();
+        revealed.insert(1);
+        let make = |nonce: &[u8]| {
+            HiddenIssuerPresentation::new(
+                &sig,
+                vk,
+                list_sig,
+                &params,
+                msgs.as_slice(),
+                revealed.clone(),
+                nonce,
+            )
+            .unwrap()
+        };
+        let p1 = make(b"nonce 1");
+        let p2 = make(b"nonce 2");
+        assert!(p1.verify(&list.verkey, &params).unwrap());
+        assert!(p2.verify(&list.verkey, &params).unwrap());
+        assert!(!verkeys.contains(&p1.verkey));
+        assert_ne!(p1.verkey, p2.verkey);
+
+        // An issuer outside the list
+        let (sk_out, vk_out) = keygen(3, &params);
+        let sig_out = Signature::new(
+            &Message::from_field_elements(msgs.as_slice()),
+            &sk_out,
+            &params,
+        )
+        .unwrap();
+        let p = HiddenIssuerPresentation::new(
+            &sig_out,
+            &vk_out,
+            list_sig,
+            &params,
+            msgs.as_slice(),
+            revealed.clone(),
+            b"nonce",
+        )
+        .unwrap();
+        assert!(!p.verify(&list.verkey, &params).unwrap());
+        // Another list
+        let other = KeyListSigkey::new(3).verkey(&params);
+        assert!(!p1.verify(&other, &params).unwrap());
+        assert!(list_key.sign(&keygen(2, &params).1, &params).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+// Random attribute jointly derived by holder and issuer, e.g. a lottery number. The holder commits to
+// its contribution s_h as the first message of a blind signature request, Y_1^s_h * g^blinding, and
+// proves knowledge of the opening. Only then the issuer picks its contribution s_i and signs the
+// commitment multiplied by Y_1^s_i, so the signed attribute is s_h + s_i. The issuer does not learn s_h
+// before choosing s_i and the holder cannot change s_h after seeing s_i, so neither can bias the value.
+// The holder checks the correct derivation by verifying the unblinded signature over s_h + s_i.
+// The issuer must accept one request per holder and `nonce`, else a holder can retry until it likes
+// the value.
+
+use crate::blind_signature::{
+    BlindSignature, BlindingKey, ProofSignatureGroup, ProverCommittingSignatureGroup,
+};
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::signature::Signature;
+use crate::signer::PsSigner;
+use crate::types::{Blinding, Challenge, Message};
+use crate::validation::deserialize_point;
+use crate::SignatureGroup;
+use amcl_wrapper::field_elem::FieldElement;
+use amcl_wrapper::group_elem::GroupElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+
+/// Holder's secret state for the request
+#[derive(Clone, Debug)]
+pub struct HolderContribution {
+    pub secret: FieldElement,
+    pub blinding: Blinding,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JointRandomRequest {
+    #[serde(deserialize_with = "deserialize_point")]
+    pub commitment: SignatureGroup,
+    pub proof: ProofSignatureGroup,
+    pub nonce: Vec<u8>,
+}
+
+/// Blind signature and the issuer's contribution sent to the holder
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JointRandomIssuance {
+    pub sig: Signature,
+    pub issuer_contribution: FieldElement,
+}
+
+impl JointRandomRequest {
+    /// `nonce` comes from the issuer
+    pub fn new(
+        blinding_key: &BlindingKey,
+        params: &Params,
+        nonce: &[u8],
+    ) -> (Self, HolderContribution) {
+        Self::new_with_rng(blinding_key, params, nonce, &mut thread_rng())
+    }
+
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        blinding_key: &BlindingKey,
+        params: &Params,
+        nonce: &[u8],
+        rng: &mut R,
+    ) -> (Self, HolderContribution) {
+        let secret = FieldElement::random_using_rng(rng);
+        let blinding = Blinding::random_using_rng(rng);
+        let commitment = &(&blinding_key.Y[0] * &secret) + &(&params.g * &blinding.0);
+        let mut committing = ProverCommittingSignatureGroup::new();
+        committing.commit(
+            &blinding_key.Y[0],
+            Some(&FieldElement::random_using_rng(rng)),
+        );
+        committing.commit(&params.g, Some(&FieldElement::random_using_rng(rng)));
+        let committed = committing.finish();
+        let challenge = committed.gen_challenge(Self::challenge_bytes(&commitment, nonce));
+        let proof = committed
+            .gen_proof(&challenge, &[secret.clone(), blinding.0.clone()])
+            .unwrap();
+        (
+            Self {
+                commitment,
+                proof,
+                nonce: nonce.to_vec(),
+            },
+            HolderContribution { secret, blinding },
+        )
+    }
+
+    fn challenge_bytes(commitment: &SignatureGroup, nonce: &[u8]) -> Vec<u8> {
+        let mut bytes = commitment.to_bytes();
+        bytes.extend_from_slice(nonce);
+        bytes
+    }
+
+    /// Issuer side check of the proof of knowledge of the opening
+    pub fn verify(&self, blinding_key: &BlindingKey, params: &Params) -> Result<bool, PSError> {
+        if blinding_key.msg_count() == 0 || self.proof.responses.len() != 2 {
+            return Ok(false);
+        }
+        let bases = [blinding_key.Y[0].clone(), params.g.clone()];
+        let mut bytes = vec![];
+        for b in &bases {
+            bytes.append(&mut b.to_bytes());
+        }
+        bytes.append(&mut self.proof.commitment.to_bytes());
+        bytes.append(&mut Self::challenge_bytes(&self.commitment, &self.nonce));
+        let challenge = Challenge::from_msg_hash(&bytes);
+        self.proof.verify(&bases, &self.commitment, &challenge)
+    }
+
+    /// Issuer verifies the request, picks its contribution and signs the random attribute as the first
+    /// message followed by `messages`
+    pub fn issue<S: PsSigner + ?Sized>(
+        &self,
+        messages: &[FieldElement],
+        signer: &S,
+        blinding_key: &BlindingKey,
+        params: &Params,
+    ) -> Result<JointRandomIssuance, PSError> {
+        self.issue_with_rng(messages, signer, blinding_key, params, &mut thread_rng())
+    }
+
+    pub fn issue_with_rng<S: PsSigner + ?Sized, R: RngCore + CryptoRng>(
+        &self,
+        messages: &[FieldElement],
+        signer: &S,
+        blinding_key: &BlindingKey,
+        params: &Params,
+        rng: &mut R,
+    ) -> Result<JointRandomIssuance, PSError> {
+        if messages.len() + 1 != blinding_key.msg_count() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: blinding_key.msg_count().saturating_sub(1),
+                given: messages.len(),
+            });
+        }
+        if !self.verify(blinding_key, params)? {
+            return Err(PSError::GeneralError {
+                msg: String::from("Invalid proof of the holder's contribution"),
+            });
+        }
+        let issuer_contribution = FieldElement::random_using_rng(rng);
+        let commitment = &self.commitment + &(&blinding_key.Y[0] * &issuer_contribution);
+        let sig = signer.blind_sign_with_rng(&commitment, messages, params, rng)?;
+        Ok(JointRandomIssuance {
+            sig,
+            issuer_contribution,
+        })
+    }
+}
+
+impl HolderContribution {
+    /// Unblind the signature and check it is over the random attribute s_h + s_i followed by `messages`.
+    /// Returns the signature and the random attribute.
+    pub fn finish(
+        &self,
+        issuance: &JointRandomIssuance,
+        messages: &[FieldElement],
+        vk: &Verkey,
+        params: &Params,
+    ) -> Result<(Signature, FieldElement), PSError> {
+        if messages.len() + 1 != vk.Y_tilde.len() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: vk.Y_tilde.len().saturating_sub(1),
+                given: messages.len(),
+            });
+        }
+        let value = &self.secret + &issuance.issuer_contribution;
+        let sig = BlindSignature::unblind(&issuance.sig, &self.blinding);
+        let mut all = vec![Message(value.clone())];
+        all.extend(Message::from_field_elements(messages));
+        if !sig.verify(&all, vk, params)? {
+            return Err(PSError::GeneralError {
+                msg: String::from("Signature is not over the jointly derived attribute"),
+            });
+        }
+        Ok((sig, value))
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for HolderContribution {
+    fn zeroize(&mut self) {
+        self.secret.zeroize();
+        self.blinding.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+
+    #[test]
+    fn test_joint_random_attribute() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let blinding_key = BlindingKey::new(&sk, &params);
+        let msgs = vec![FieldElement::random(), FieldElement::random()];
+
+        let (request, contribution) = JointRandomRequest::new(&blinding_key, &params, b"nonce");
+        let issuance = request.issue(&msgs, &sk, &blinding_key, &params).unwrap();
+        let (sig, value) = contribution.finish(&issuance, &msgs, &vk, &params).unwrap();
+        assert_ne!(value, contribution.secret);
+        assert_ne!(value, issuance.issuer_contribution);
+        let mut all = vec![value];
+        all.extend_from_slice(&msgs);
+        assert!(sig
+            .verify(&Message::from_field_elements(&all), &vk, &params)
+            .unwrap());
+
+        // Issuer claiming a different contribution than the one signed is caught
+        let mut bad = issuance;
+        bad.issuer_contribution = FieldElement::random();
+        assert!(contribution.finish(&bad, &msgs, &vk, &params).is_err());
+
+        // Request replayed under another nonce
+        let mut replayed = request;
+        replayed.nonce = b"other".to_vec();
+        assert!(replayed.issue(&msgs, &sk, &blinding_key, &params).is_err());
+    }
+}
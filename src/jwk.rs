@@ -0,0 +1,206 @@
+// JWK style JSON objects for keys, to publish verkeys in JWKS based infrastructure. A verkey is
+// `{"kty": "OKP", "crv": "Bls12381G1", "alg": "PS-BLS12381", "kid": ..., "x": ..., "y": [...]}` with
+// X_tilde in `x`, Y_tilde in `y` and the verkey id in `kid`, all base64url without padding. Points are
+// compressed as in `Verkey::to_compressed_bytes` and `crv` names the group of the verkey, so keys of a
+// `SignatureG1` build have `Bls12381G2`. A sigkey adds x as `d` and the y as `dy`, packed in 32 bytes.
+
+use crate::context::verkey_id;
+use crate::errors::PSError;
+use crate::keys::{Params, Sigkey, Verkey};
+use crate::utils::{pack_field_element, unpack_field_element, PointEncoding};
+use crate::validation::ValidationProfile;
+use crate::OtherGroup;
+
+pub const KTY: &str = "OKP";
+pub const ALG: &str = "PS-BLS12381";
+#[cfg(feature = "SignatureG2")]
+pub const CRV: &str = "Bls12381G1";
+#[cfg(feature = "SignatureG1")]
+pub const CRV: &str = "Bls12381G2";
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerkeyJwk {
+    pub kty: String,
+    pub crv: String,
+    pub alg: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+    pub x: String,
+    pub y: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SigkeyJwk {
+    #[serde(flatten)]
+    pub public: VerkeyJwk,
+    pub d: String,
+    pub dy: Vec<String>,
+}
+
+fn encode_base64url(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity((bytes.len() * 4).div_ceil(3));
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..=chunk.len() {
+            s.push(BASE64URL[(n >> (18 - 6 * i) & 63) as usize] as char);
+        }
+    }
+    s
+}
+
+/// Rejects padding and non canonical encodings
+fn decode_base64url(field: &str, s: &str) -> Result<Vec<u8>, PSError> {
+    let invalid = || PSError::InvalidEncoding {
+        msg: format!("JWK field {} is not base64url", field),
+    };
+    let mut bytes = Vec::with_capacity(s.len() * 3 / 4);
+    for chunk in s.as_bytes().chunks(4) {
+        if chunk.len() == 1 {
+            return Err(invalid());
+        }
+        let mut n = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let v = BASE64URL.iter().position(|b| b == c).ok_or_else(invalid)? as u32;
+            n |= v << (18 - 6 * i);
+        }
+        let decoded = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        bytes.extend_from_slice(&decoded[..chunk.len() - 1]);
+    }
+    if encode_base64url(&bytes) != s {
+        return Err(invalid());
+    }
+    Ok(bytes)
+}
+
+impl Verkey {
+    pub fn to_jwk(&self) -> VerkeyJwk {
+        VerkeyJwk {
+            kty: String::from(KTY),
+            crv: String::from(CRV),
+            alg: String::from(ALG),
+            kid: Some(encode_base64url(&verkey_id(self))),
+            x: encode_base64url(&self.X_tilde.to_compressed_bytes()),
+            y: self
+                .Y_tilde
+                .iter()
+                .map(|y| encode_base64url(&y.to_compressed_bytes()))
+                .collect(),
+        }
+    }
+
+    /// Rejects identity elements and elements outside the prime order subgroup. `kid` is checked if
+    /// present.
+    pub fn from_jwk(jwk: &VerkeyJwk) -> Result<Self, PSError> {
+        if jwk.kty != KTY || jwk.crv != CRV || jwk.alg != ALG {
+            return Err(PSError::InvalidEncoding {
+                msg: format!(
+                    "JWK is not a verkey of this build: kty {}, crv {}, alg {}",
+                    jwk.kty, jwk.crv, jwk.alg
+                ),
+            });
+        }
+        let mut bytes = vec![];
+        for (field, value) in std::iter::once(("x", &jwk.x)).chain(jwk.y.iter().map(|y| ("y", y))) {
+            let point = decode_base64url(field, value)?;
+            if point.len() != OtherGroup::COMPRESSED_SIZE {
+                return Err(PSError::InvalidEncoding {
+                    msg: format!("JWK field {} has {} bytes", field, point.len()),
+                });
+            }
+            bytes.extend_from_slice(&point);
+        }
+        let vk = Self::from_compressed_bytes_with_profile(&bytes, ValidationProfile::Paranoid)?;
+        if let Some(kid) = &jwk.kid {
+            if *kid != encode_base64url(&verkey_id(&vk)) {
+                return Err(PSError::InvalidEncoding {
+                    msg: String::from("JWK kid is not the id of the verkey"),
+                });
+            }
+        }
+        Ok(vk)
+    }
+}
+
+impl Sigkey {
+    /// Includes the verkey so the JWK can be used wherever a verkey JWK is expected
+    pub fn to_jwk(&self, params: &Params) -> Result<SigkeyJwk, PSError> {
+        let vk = Verkey::from_sigkey(self, self.y.len(), params)?;
+        Ok(SigkeyJwk {
+            public: vk.to_jwk(),
+            d: encode_base64url(&pack_field_element(&self.x)),
+            dy: self
+                .y
+                .iter()
+                .map(|y| encode_base64url(&pack_field_element(y)))
+                .collect(),
+        })
+    }
+
+    /// Fails if the verkey in the JWK is not the one of the sigkey
+    pub fn from_jwk(jwk: &SigkeyJwk, params: &Params) -> Result<Self, PSError> {
+        let vk = Verkey::from_jwk(&jwk.public)?;
+        let x = unpack_field_element(&decode_base64url("d", &jwk.d)?)?;
+        let y = jwk
+            .dy
+            .iter()
+            .map(|y| unpack_field_element(&decode_base64url("dy", y)?))
+            .collect::<Result<Vec<_>, PSError>>()?;
+        let sigkey = Sigkey { x, y };
+        if sigkey.x.is_zero() || sigkey.y.iter().any(|y| y.is_zero()) {
+            return Err(PSError::InvalidEncoding {
+                msg: String::from("Sigkey has zero scalars"),
+            });
+        }
+        vk.check_same(&Verkey::from_sigkey(&sigkey, vk.Y_tilde.len(), params)?)?;
+        Ok(sigkey)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+
+    #[test]
+    fn test_jwk() {
+        for len in 0..7 {
+            let bytes: Vec<u8> = (0..len).map(|i| (i * 91) as u8).collect();
+            let s = encode_base64url(&bytes);
+            assert_eq!(decode_base64url("t", &s).unwrap(), bytes);
+        }
+        assert_eq!(encode_base64url(b"\xfb\xff"), "-_8");
+        assert!(decode_base64url("t", "-_9").is_err());
+        assert!(decode_base64url("t", "AA==").is_err());
+
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(2, &params);
+        let json = serde_json::to_string(&vk.to_jwk()).unwrap();
+        assert!(json.starts_with(r#"{"kty":"OKP","crv":"Bls12381G"#));
+        let jwk: VerkeyJwk = serde_json::from_str(&json).unwrap();
+        assert_eq!(Verkey::from_jwk(&jwk).unwrap(), vk);
+        let mut wrong = jwk.clone();
+        wrong.y.swap(0, 1);
+        assert!(Verkey::from_jwk(&wrong).is_err());
+        wrong.kid = None;
+        assert!(Verkey::from_jwk(&wrong).is_ok());
+
+        let sk_jwk = sk.to_jwk(&params).unwrap();
+        let json = serde_json::to_string(&sk_jwk).unwrap();
+        let parsed: SigkeyJwk = serde_json::from_str(&json).unwrap();
+        let sk_1 = Sigkey::from_jwk(&parsed, &params).unwrap();
+        assert_eq!(sk_1.to_versioned_bytes(), sk.to_versioned_bytes());
+        // The public part of a sigkey JWK is a verkey JWK
+        let public: VerkeyJwk = serde_json::from_str(&json).unwrap();
+        assert_eq!(Verkey::from_jwk(&public).unwrap(), vk);
+        let mut other = parsed;
+        other.public = keygen(2, &params).1.to_jwk();
+        assert!(Sigkey::from_jwk(&other, &params).is_err());
+    }
+}
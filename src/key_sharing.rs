@@ -0,0 +1,149 @@
+// Threshold sharing of an issuer's signing key, to escrow it across HSMs with `Sigkey::split` or to sign
+// with `PartialSignature`s. Each of x, y_1, y_2, ... is shared with its own polynomial and share i holds
+// all their evaluations at i. A share names the verkey it belongs to, so `Sigkey::reconstruct` rejects
+// mixed shares and checks the recovered key against it.
+
+use crate::context::{verkey_id, VerkeyId};
+use crate::errors::PSError;
+use crate::keys::{Params, Sigkey, Verkey};
+use crate::shamir::{evaluate, random_polynomial, reconstruct_secret};
+use crate::utils::to_hex;
+use amcl_wrapper::field_elem::FieldElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+
+/// Share of a `Sigkey`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SigkeyShare {
+    /// Starts from 1
+    pub index: usize,
+    pub threshold: usize,
+    pub total: usize,
+    /// Id of the verkey of the shared sigkey
+    pub verkey_id: VerkeyId,
+    pub x: FieldElement,
+    pub y: Vec<FieldElement>,
+}
+
+impl Sigkey {
+    /// Split into `total` shares of which `threshold` recover the sigkey
+    pub fn split(
+        &self,
+        threshold: usize,
+        total: usize,
+        params: &Params,
+    ) -> Result<Vec<SigkeyShare>, PSError> {
+        self.split_with_rng(threshold, total, params, &mut thread_rng())
+    }
+
+    pub fn split_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        threshold: usize,
+        total: usize,
+        params: &Params,
+        rng: &mut R,
+    ) -> Result<Vec<SigkeyShare>, PSError> {
+        let vk_id = verkey_id(&Verkey::from_sigkey(self, self.msg_count(), params)?);
+        let x_poly = random_polynomial(&self.x, threshold, total, rng)?;
+        let mut y_polys = vec![];
+        for y in &self.y {
+            y_polys.push(random_polynomial(y, threshold, total, rng)?);
+        }
+        Ok((1..=total)
+            .map(|i| SigkeyShare {
+                index: i,
+                threshold,
+                total,
+                verkey_id: vk_id,
+                x: evaluate(&x_poly, i),
+                y: y_polys.iter().map(|p| evaluate(p, i)).collect(),
+            })
+            .collect())
+    }
+
+    /// Recover the sigkey from at least `threshold` shares of the same key. The recovered key must
+    /// give the verkey named in the shares.
+    pub fn reconstruct(shares: &[SigkeyShare], params: &Params) -> Result<Self, PSError> {
+        let first = match shares.first() {
+            Some(s) => s,
+            None => {
+                return Err(PSError::GeneralError {
+                    msg: String::from("No shares given"),
+                })
+            }
+        };
+        if shares.iter().any(|s| {
+            s.verkey_id != first.verkey_id
+                || s.threshold != first.threshold
+                || s.total != first.total
+                || s.y.len() != first.y.len()
+                || s.index > s.total
+        }) {
+            return Err(PSError::GeneralError {
+                msg: String::from("Shares are not of the same sigkey"),
+            });
+        }
+        if shares.len() < first.threshold {
+            return Err(PSError::GeneralError {
+                msg: format!("Need {} shares but given {}", first.threshold, shares.len()),
+            });
+        }
+        let interpolate = |value: &dyn Fn(&SigkeyShare) -> FieldElement| {
+            let pairs: Vec<(usize, FieldElement)> =
+                shares.iter().map(|s| (s.index, value(s))).collect();
+            reconstruct_secret(&pairs)
+        };
+        let x = interpolate(&|s| s.x.clone())?;
+        let mut y = vec![];
+        for j in 0..first.y.len() {
+            y.push(interpolate(&|s| s.y[j].clone())?);
+        }
+        let sigkey = Self { x, y };
+        let recovered = Verkey::from_sigkey(&sigkey, sigkey.msg_count(), params)?.fingerprint();
+        if recovered != first.verkey_id {
+            return Err(PSError::VerkeyMismatch {
+                expected: to_hex(&first.verkey_id),
+                given: to_hex(&recovered),
+            });
+        }
+        Ok(sigkey)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for SigkeyShare {
+    fn zeroize(&mut self) {
+        self.x.zeroize();
+        self.y.iter_mut().zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+
+    #[test]
+    fn test_sigkey_sharing() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let shares = sk.split(3, 5, &params).unwrap();
+        assert_eq!(shares.len(), 5);
+        let recovered = Sigkey::reconstruct(&shares[1..4], &params).unwrap();
+        assert_eq!(Verkey::from_sigkey(&recovered, 3, &params).unwrap(), vk);
+        assert_eq!(recovered.x, sk.x);
+
+        assert!(Sigkey::reconstruct(&shares[..2], &params).is_err());
+        assert!(Sigkey::reconstruct(&[], &params).is_err());
+        let mut repeated = shares[..3].to_vec();
+        repeated[2] = repeated[1].clone();
+        assert!(Sigkey::reconstruct(&repeated, &params).is_err());
+        let mut tampered = shares[..3].to_vec();
+        tampered[0].y[1] = FieldElement::random();
+        assert!(Sigkey::reconstruct(&tampered, &params).is_err());
+        // Shares of another key
+        let (sk_1, _) = keygen(3, &params);
+        let mut mixed = shares[..2].to_vec();
+        mixed.push(sk_1.split(3, 5, &params).unwrap().remove(2));
+        assert!(Sigkey::reconstruct(&mixed, &params).is_err());
+    }
+}
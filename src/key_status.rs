@@ -0,0 +1,175 @@
+// Revocation of compromised issuer keys. A `KeyRevocation` states that a verkey is revoked as of an
+// epoch and is signed with the revoked key itself, as a signature on the messages
+// (H(verkey, epoch), 0, 0, ...), so anyone holding the verkey can verify it and nobody without the
+// signing key can revoke. Whoever holds a compromised key may revoke it, which is the desired outcome.
+// Verifiers consult a `VerkeyStatusChecker` with `Presentation::verify_with_status`.
+
+use crate::context::{verkey_id, VerkeyId};
+use crate::errors::PSError;
+use crate::hash_to_field::hash_to_field;
+use crate::keys::{Params, Verkey};
+use crate::signature::Signature;
+use crate::signer::PsSigner;
+use crate::types::Message;
+use amcl_wrapper::field_elem::FieldElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::HashMap;
+
+/// Tag of `hash_to_field` for the message of a `KeyRevocation`
+pub const REVOCATION_DST: &[u8] = b"PS-SIG-V01-CS01-VERKEY-REVOCATION_XMD:SHA-256_";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyRevocation {
+    pub verkey_id: VerkeyId,
+    /// First epoch in which the key is revoked
+    pub epoch: u64,
+    pub sig: Signature,
+}
+
+fn revocation_messages(vk_id: &VerkeyId, epoch: u64, count: usize) -> Vec<FieldElement> {
+    let mut msgs = vec![FieldElement::zero(); count];
+    msgs[0] = hash_to_field(&[vk_id, &epoch.to_be_bytes()[..]].concat(), REVOCATION_DST);
+    msgs
+}
+
+impl KeyRevocation {
+    pub fn new<S: PsSigner + ?Sized>(
+        signer: &S,
+        vk: &Verkey,
+        epoch: u64,
+        params: &Params,
+    ) -> Result<Self, PSError> {
+        Self::new_with_rng(signer, vk, epoch, params, &mut thread_rng())
+    }
+
+    pub fn new_with_rng<S: PsSigner + ?Sized, R: RngCore + CryptoRng>(
+        signer: &S,
+        vk: &Verkey,
+        epoch: u64,
+        params: &Params,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        if vk.Y_tilde.is_empty() || signer.msg_count() != vk.Y_tilde.len() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: vk.Y_tilde.len().max(1),
+                given: signer.msg_count(),
+            });
+        }
+        let verkey_id = verkey_id(vk);
+        let msgs = revocation_messages(&verkey_id, epoch, vk.Y_tilde.len());
+        let sig = signer.sign_with_rng(&msgs, params, rng)?;
+        Ok(Self {
+            verkey_id,
+            epoch,
+            sig,
+        })
+    }
+
+    /// Check the statement is about `vk` and signed with its signing key
+    pub fn verify(&self, vk: &Verkey, params: &Params) -> Result<bool, PSError> {
+        if vk.Y_tilde.is_empty() || self.verkey_id != verkey_id(vk) {
+            return Ok(false);
+        }
+        let msgs = revocation_messages(&self.verkey_id, self.epoch, vk.Y_tilde.len());
+        self.sig
+            .verify(&Message::from_field_elements(&msgs), vk, params)
+    }
+}
+
+/// Consulted by verifiers before accepting a proof under a verkey
+pub trait VerkeyStatusChecker {
+    /// Err(`PSError::RevokedVerkey`) if `vk` must not be used
+    fn check(&self, vk: &Verkey) -> Result<(), PSError>;
+}
+
+/// Verified revocations and the current epoch
+#[derive(Clone, Debug, Default)]
+pub struct RevocationList {
+    pub current_epoch: u64,
+    revocations: HashMap<VerkeyId, KeyRevocation>,
+}
+
+impl RevocationList {
+    pub fn new(current_epoch: u64) -> Self {
+        Self {
+            current_epoch,
+            revocations: HashMap::new(),
+        }
+    }
+
+    /// Add `revocation` of `vk` after verifying it. Of several revocations of a key the earliest epoch
+    /// is kept.
+    pub fn add(
+        &mut self,
+        revocation: KeyRevocation,
+        vk: &Verkey,
+        params: &Params,
+    ) -> Result<(), PSError> {
+        if !revocation.verify(vk, params)? {
+            return Err(PSError::GeneralError {
+                msg: String::from("Invalid key revocation"),
+            });
+        }
+        match self.revocations.get(&revocation.verkey_id) {
+            Some(r) if r.epoch <= revocation.epoch => (),
+            _ => {
+                self.revocations.insert(revocation.verkey_id, revocation);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, vk_id: &VerkeyId) -> Option<&KeyRevocation> {
+        self.revocations.get(vk_id)
+    }
+}
+
+impl VerkeyStatusChecker for RevocationList {
+    fn check(&self, vk: &Verkey) -> Result<(), PSError> {
+        match self.revocations.get(&verkey_id(vk)) {
+            Some(r) if r.epoch <= self.current_epoch => {
+                Err(PSError::RevokedVerkey { epoch: r.epoch })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use crate::presentation::Presentation;
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_key_revocation() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let (sk_1, vk_1) = keygen(3, &params);
+        let msgs = FieldElementVector::random(3);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let pres =
+            Presentation::new(&sig, &vk, &params, msgs.as_slice(), HashSet::new(), b"n").unwrap();
+
+        let revocation = KeyRevocation::new(&sk, &vk, 5, &params).unwrap();
+        assert!(revocation.verify(&vk, &params).unwrap());
+        assert!(!revocation.verify(&vk_1, &params).unwrap());
+        // Another key cannot revoke vk
+        let mut forged = KeyRevocation::new(&sk_1, &vk_1, 5, &params).unwrap();
+        forged.verkey_id = verkey_id(&vk);
+        assert!(!forged.verify(&vk, &params).unwrap());
+
+        let mut list = RevocationList::new(4);
+        assert!(list.add(forged, &vk, &params).is_err());
+        list.add(revocation, &vk, &params).unwrap();
+        assert!(pres.verify_with_status(&vk, &params, &list).unwrap());
+        list.current_epoch = 5;
+        match pres.verify_with_status(&vk, &params, &list) {
+            Err(PSError::RevokedVerkey { epoch: 5 }) => (),
+            _ => panic!("Revoked verkey accepted"),
+        }
+    }
+}
@@ -1,46 +1,497 @@
 use amcl_wrapper::field_elem::FieldElement;
 use amcl_wrapper::group_elem::GroupElement;
+use rand::{thread_rng, CryptoRng, RngCore};
 
+use crate::blind_signature::BlindingKey;
+use crate::ceremony::ParamsTranscript;
 use crate::errors::PSError;
-use crate::{OtherGroup, SignatureGroup};
+use crate::hash_to_curve::{hasher_for_suite, HashToCurve, HashableGroup, AMCL_SUITE};
+use crate::limits::DecodeLimits;
+use crate::metadata::Metadata;
+use crate::signature::Fingerprint;
+use crate::utils::{
+    decode_chunks, hash_with_domain, pack_field_element, point_from_compressed_bytes, to_hex,
+    unpack_field_element, ByteReader, PointEncoding, PACKED_FIELD_ELEMENT_SIZE,
+};
+use crate::validation::{deserialize_point, ValidationProfile};
+use crate::{ate_2_pairing, OtherGroup, SignatureGroup, OTHER_GROUP_SIZE};
+use std::convert::TryFrom;
 
+/// First byte of `Sigkey::to_versioned_bytes` and `Verkey::to_versioned_bytes`
+const KEY_FORMAT_V1: u8 = 1;
+/// Same as version 1 with a `Metadata` header before the key
+const KEY_FORMAT_V2: u8 = 2;
+
+/// Deserialization rejects zero scalars
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "SigkeyFields")]
 pub struct Sigkey {
     pub x: FieldElement,
     pub y: Vec<FieldElement>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Deserialize)]
+struct SigkeyFields {
+    x: FieldElement,
+    y: Vec<FieldElement>,
+}
+
+/// Checks done by `Verkey::validate`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerkeyValidation {
+    Lengths,
+    Full,
+}
+
+/// Deserialization rejects identity elements and elements outside the prime order subgroup
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "VerkeyFields")]
 pub struct Verkey {
     pub X_tilde: OtherGroup,
     pub Y_tilde: Vec<OtherGroup>,
 }
 
+#[derive(Deserialize)]
+struct VerkeyFields {
+    X_tilde: OtherGroup,
+    Y_tilde: Vec<OtherGroup>,
+}
+
+fn versioned_payload(bytes: &[u8]) -> Result<(&[u8], Metadata), PSError> {
+    match bytes.split_first() {
+        Some((&KEY_FORMAT_V1, rest)) => Ok((rest, Metadata::default())),
+        Some((&KEY_FORMAT_V2, rest)) => {
+            let mut reader = ByteReader::new(rest);
+            let metadata = Metadata::read(&mut reader)?;
+            Ok((&rest[rest.len() - reader.remaining()..], metadata))
+        }
+        Some((v, _)) => Err(PSError::InvalidEncoding {
+            msg: format!("Unknown key format version {}", v),
+        }),
+        None => Err(PSError::InvalidEncoding {
+            msg: String::from("Empty key encoding"),
+        }),
+    }
+}
+
+impl Sigkey {
+    pub fn msg_count(&self) -> usize {
+        self.y.len()
+    }
+
+    /// g^x, derived on demand since only the scalars are stored
+    pub fn X(&self, params: &Params) -> SignatureGroup {
+        &params.g * &self.x
+    }
+
+    /// g^y_i for all i
+    pub fn Y(&self, params: &Params) -> Vec<SignatureGroup> {
+        self.y.iter().map(|y| &params.g * y).collect()
+    }
+
+    /// Format version followed by x and all y packed in 32 bytes each
+    pub fn to_versioned_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![KEY_FORMAT_V1];
+        bytes.append(&mut pack_field_element(&self.x));
+        for y in &self.y {
+            bytes.append(&mut pack_field_element(y));
+        }
+        bytes
+    }
+
+    pub fn from_versioned_bytes(bytes: &[u8]) -> Result<Self, PSError> {
+        let payload = versioned_payload(bytes)?.0;
+        DecodeLimits::current()
+            .check_msg_count((payload.len() / PACKED_FIELD_ELEMENT_SIZE).saturating_sub(1))?;
+        let mut scalars = decode_chunks(payload, PACKED_FIELD_ELEMENT_SIZE, unpack_field_element)?;
+        if scalars.is_empty() {
+            return Err(PSError::InvalidEncoding {
+                msg: String::from("Sigkey needs at least x"),
+            });
+        }
+        let x = scalars.remove(0);
+        Self::try_from(SigkeyFields { x, y: scalars })
+    }
+}
+
+impl TryFrom<SigkeyFields> for Sigkey {
+    type Error = PSError;
+
+    fn try_from(fields: SigkeyFields) -> Result<Self, PSError> {
+        DecodeLimits::current().check_msg_count(fields.y.len())?;
+        if fields.x.is_zero() || fields.y.iter().any(|y| y.is_zero()) {
+            return Err(PSError::InvalidEncoding {
+                msg: String::from("Sigkey has zero scalars"),
+            });
+        }
+        Ok(Self {
+            x: fields.x,
+            y: fields.y,
+        })
+    }
+}
+
+impl TryFrom<VerkeyFields> for Verkey {
+    type Error = PSError;
+
+    fn try_from(fields: VerkeyFields) -> Result<Self, PSError> {
+        DecodeLimits::current().check_msg_count(fields.Y_tilde.len())?;
+        let vk = Self {
+            X_tilde: fields.X_tilde,
+            Y_tilde: fields.Y_tilde,
+        };
+        if !ValidationProfile::Paranoid.is_valid_verkey(&vk) {
+            return Err(PSError::InvalidEncoding {
+                msg: String::from("Verkey has identity elements or elements of wrong order"),
+            });
+        }
+        Ok(vk)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Keypair {
+    pub sigkey: Sigkey,
+    pub verkey: Verkey,
+}
+
+fn default_hash_suite() -> String {
+    AMCL_SUITE.to_string()
+}
+
 // Parameters generated by random oracle.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Params {
+    #[serde(deserialize_with = "deserialize_point")]
     pub g: SignatureGroup,
+    #[serde(deserialize_with = "deserialize_point")]
     pub g_tilde: OtherGroup,
+    /// Id of the `HashToCurve` the generators were derived with, params serialized before it was
+    /// recorded are amcl's
+    #[serde(default = "default_hash_suite")]
+    pub hash_suite: String,
+}
+
+impl Verkey {
+    /// Recompute the verkey of `sigkey` for `count` messages
+    pub fn from_sigkey(sigkey: &Sigkey, count: usize, params: &Params) -> Result<Self, PSError> {
+        if sigkey.y.len() != count {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: sigkey.y.len(),
+                given: count,
+            });
+        }
+        Ok(Self {
+            X_tilde: &params.g_tilde * &sigkey.x,
+            Y_tilde: sigkey.y.iter().map(|y| &params.g_tilde * y).collect(),
+        })
+    }
+
+    /// Hash of `to_bytes`, which is canonical, so it stays the same for a key across encodings and
+    /// versions of this library. Used as `verkey_id` in protocol messages and in errors.
+    pub fn fingerprint(&self) -> Fingerprint {
+        hash_with_domain(b"PS verkey id", &self.to_bytes())
+    }
+
+    /// Lowercase hex of `fingerprint`
+    pub fn fingerprint_hex(&self) -> String {
+        to_hex(&self.fingerprint())
+    }
+
+    /// `VerkeyMismatch` error unless `self` and `given` are the same key
+    pub fn check_same(&self, given: &Verkey) -> Result<(), PSError> {
+        if self == given {
+            return Ok(());
+        }
+        Err(PSError::VerkeyMismatch {
+            expected: self.fingerprint_hex(),
+            given: given.fingerprint_hex(),
+        })
+    }
+
+    /// Uncompressed encoding, X_tilde followed by all Y_tilde
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.append(&mut self.X_tilde.to_bytes());
+        for y in &self.Y_tilde {
+            bytes.append(&mut y.to_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PSError> {
+        Self::from_bytes_with_profile(bytes, ValidationProfile::default())
+    }
+
+    pub fn from_bytes_with_profile(
+        bytes: &[u8],
+        profile: ValidationProfile,
+    ) -> Result<Self, PSError> {
+        DecodeLimits::current()
+            .check_msg_count((bytes.len() / OTHER_GROUP_SIZE).saturating_sub(1))?;
+        let points = decode_chunks(bytes, OTHER_GROUP_SIZE, |b| profile.point_from_bytes(b))?;
+        Self::from_points(points)
+    }
+
+    /// Parse the output of `to_bytes` skipping all validation. Only for keys cached in trusted local
+    /// storage, keys from elsewhere should be loaded with `from_compressed_bytes` or `from_bytes`.
+    pub fn from_trusted_cache_bytes(bytes: &[u8]) -> Result<Self, PSError> {
+        let points = decode_chunks(bytes, OTHER_GROUP_SIZE, |b| {
+            OtherGroup::from_bytes(b).map_err(|e| PSError::InvalidEncoding {
+                msg: format!("{:?}", e),
+            })
+        })?;
+        Self::from_points(points)
+    }
+
+    /// Compressed encoding, X_tilde followed by all Y_tilde. Only G1 elements can be compressed.
+    pub fn to_compressed_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.append(&mut self.X_tilde.to_compressed_bytes());
+        for y in &self.Y_tilde {
+            bytes.append(&mut y.to_compressed_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_compressed_bytes(bytes: &[u8]) -> Result<Self, PSError> {
+        Self::from_compressed_bytes_with_profile(bytes, ValidationProfile::default())
+    }
+
+    /// Points are decompressed in parallel with feature `rayon`
+    pub fn from_compressed_bytes_with_profile(
+        bytes: &[u8],
+        profile: ValidationProfile,
+    ) -> Result<Self, PSError> {
+        DecodeLimits::current()
+            .check_msg_count((bytes.len() / OtherGroup::COMPRESSED_SIZE).saturating_sub(1))?;
+        let points = decode_chunks(bytes, OtherGroup::COMPRESSED_SIZE, |b| {
+            let p = point_from_compressed_bytes(b)?;
+            if !profile.is_valid_point(&p) {
+                return Err(PSError::InvalidEncoding {
+                    msg: format!("Group element rejected by {:?} profile", profile),
+                });
+            }
+            Ok(p)
+        })?;
+        Self::from_points(points)
+    }
+
+    /// Equivalent verkey with all elements raised to `r`, e.g. to publish distinct looking keys of the
+    /// same issuer on different ledgers. It is the verkey of the sigkey multiplied by `r`. Holders adapt
+    /// their signatures with `Signature::adapt_to_rerandomized_verkey`, so `r` is given to holders but
+    /// not published since it links both keys.
+    pub fn rerandomize(&self, r: &FieldElement) -> Result<Self, PSError> {
+        if r.is_zero() {
+            return Err(PSError::GeneralError {
+                msg: String::from("Cannot rerandomize verkey with zero"),
+            });
+        }
+        Ok(Self {
+            X_tilde: &self.X_tilde * r,
+            Y_tilde: self.Y_tilde.iter().map(|y| y * r).collect(),
+        })
+    }
+
+    /// Format version followed by `to_compressed_bytes`
+    pub fn to_versioned_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![KEY_FORMAT_V1];
+        bytes.append(&mut self.to_compressed_bytes());
+        bytes
+    }
+
+    /// Same as `to_versioned_bytes` with `metadata` before the key
+    pub fn to_versioned_bytes_with_metadata(&self, metadata: &Metadata) -> Vec<u8> {
+        let mut bytes = vec![KEY_FORMAT_V2];
+        bytes.append(&mut metadata.to_bytes());
+        bytes.append(&mut self.to_compressed_bytes());
+        bytes
+    }
+
+    /// Rejects identity elements and elements outside the prime order subgroup. Metadata is ignored.
+    pub fn from_versioned_bytes(bytes: &[u8]) -> Result<Self, PSError> {
+        Ok(Self::from_versioned_bytes_with_metadata(bytes)?.0)
+    }
+
+    /// Empty metadata for keys encoded without
+    pub fn from_versioned_bytes_with_metadata(bytes: &[u8]) -> Result<(Self, Metadata), PSError> {
+        let (payload, metadata) = versioned_payload(bytes)?;
+        let vk = Self::from_compressed_bytes_with_profile(payload, ValidationProfile::Paranoid)?;
+        Ok((vk, metadata))
+    }
+
+    /// Check the verkey against the issuer's `BlindingKey`. `Lengths` only compares the number of
+    /// elements, `Full` also rejects identity elements and checks e(X, g_tilde) == e(g, X_tilde) and
+    /// e(Y_i, g_tilde) == e(g, Y_tilde_i) for all i. The checks of all Y_i are batched with random
+    /// coefficients into one product of 2 pairings.
+    pub fn validate(
+        &self,
+        blinding_key: &BlindingKey,
+        params: &Params,
+        mode: VerkeyValidation,
+    ) -> Result<(), PSError> {
+        if blinding_key.Y.len() != self.Y_tilde.len() {
+            return Err(PSError::InvalidVerkey {
+                y: blinding_key.Y.len(),
+                y_tilde: self.Y_tilde.len(),
+            });
+        }
+        if mode == VerkeyValidation::Lengths {
+            return Ok(());
+        }
+        if self.X_tilde.is_identity()
+            || blinding_key.X.is_identity()
+            || self.Y_tilde.iter().any(|y| y.is_identity())
+            || blinding_key.Y.iter().any(|y| y.is_identity())
+        {
+            return Err(PSError::GeneralError {
+                msg: String::from("Verkey has identity elements"),
+            });
+        }
+        let neg_g = params.g.negation();
+        if !ate_2_pairing(&blinding_key.X, &params.g_tilde, &neg_g, &self.X_tilde).is_one() {
+            return Err(PSError::GeneralError {
+                msg: String::from("X and X_tilde have different exponents"),
+            });
+        }
+        let mut Y_msm = SignatureGroup::identity();
+        let mut Y_tilde_msm = OtherGroup::identity();
+        for (Y_i, Y_tilde_i) in blinding_key.Y.iter().zip(&self.Y_tilde) {
+            let r = FieldElement::random();
+            Y_msm += Y_i * &r;
+            Y_tilde_msm += Y_tilde_i * &r;
+        }
+        if !ate_2_pairing(&Y_msm, &params.g_tilde, &neg_g, &Y_tilde_msm).is_one() {
+            return Err(PSError::GeneralError {
+                msg: String::from("Y and Y_tilde have different exponents"),
+            });
+        }
+        Ok(())
+    }
+
+    fn from_points(mut points: Vec<OtherGroup>) -> Result<Self, PSError> {
+        if points.is_empty() {
+            return Err(PSError::InvalidEncoding {
+                msg: String::from("Verkey needs at least X_tilde"),
+            });
+        }
+        let X_tilde = points.remove(0);
+        Ok(Self {
+            X_tilde,
+            Y_tilde: points,
+        })
+    }
 }
 
 impl Params {
-    /// Generate g1, g2. These are shared by signer and all users.
+    /// Generate g1, g2. These are shared by signer and all users. The generators are hashed to the
+    /// curve with RFC 9380 hash_to_curve under the tags of `dst_g` and `dst_g_tilde`, or with amcl's
+    /// map without feature `rfc9380`.
     pub fn new(label: &[u8]) -> Self {
-        let g = SignatureGroup::from_msg_hash(&[label, " : g".as_bytes()].concat());
-        let g_tilde = OtherGroup::from_msg_hash(&[label, " : g_tilde".as_bytes()].concat());
-        Self { g, g_tilde }
+        ParamsTranscript::generate(label).0
+    }
+
+    /// The params earlier versions derived from `label` with amcl's map
+    pub fn new_legacy(label: &[u8]) -> Self {
+        ParamsTranscript::generate_legacy(label).0
+    }
+
+    /// Same as `new` with the generators hashed with `hasher`
+    pub fn new_with_hasher<H: HashToCurve + ?Sized>(label: &[u8], hasher: &H) -> Self {
+        ParamsTranscript::generate_with_hasher(label, hasher).0
+    }
+
+    /// The built in implementation of the suite of the params
+    pub fn hasher(&self) -> Result<&'static dyn HashToCurve, PSError> {
+        hasher_for_suite(&self.hash_suite)
+    }
+
+    /// Domain separation tag `g` was hashed with, None for legacy params and unknown suites
+    pub fn dst_g(&self) -> Option<&'static [u8]> {
+        self.hasher().ok().and_then(SignatureGroup::generator_dst)
+    }
+
+    /// Domain separation tag `g_tilde` was hashed with
+    pub fn dst_g_tilde(&self) -> Option<&'static [u8]> {
+        self.hasher().ok().and_then(OtherGroup::generator_dst)
+    }
+
+    /// Hash `msg` to the signature group with the suite of the params
+    pub fn hash_to_signature_group(&self, msg: &[u8]) -> Result<SignatureGroup, PSError> {
+        Ok(SignatureGroup::hash_with(self.hasher()?, msg))
+    }
+
+    /// Same as `hash_to_signature_group` under the tag `dst`, see `HashToCurve::hash_to_g1_with_tag`
+    pub fn hash_to_signature_group_with_tag(
+        &self,
+        msg: &[u8],
+        dst: &[u8],
+    ) -> Result<SignatureGroup, PSError> {
+        Ok(SignatureGroup::hash_with_tag(self.hasher()?, msg, dst))
+    }
+
+    /// Check that the generators were derived as recorded in `transcript`
+    pub fn verify_derivation(&self, transcript: &ParamsTranscript) -> bool {
+        match transcript.verify() {
+            Ok(p) => p.g == self.g && p.g_tilde == self.g_tilde && p.hash_suite == self.hash_suite,
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Sigkey {
+    fn zeroize(&mut self) {
+        self.x.zeroize();
+        self.y.iter_mut().zeroize();
+    }
+}
+
+impl Keypair {
+    pub fn new(count_messages: usize, params: &Params) -> Self {
+        Self::new_with_rng(count_messages, params, &mut thread_rng())
+    }
+
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        count_messages: usize,
+        params: &Params,
+        rng: &mut R,
+    ) -> Self {
+        let (sigkey, verkey) = keygen_with_rng(count_messages, params, rng);
+        Self { sigkey, verkey }
+    }
+
+    /// Keypair of a stored `sigkey`
+    pub fn from_sigkey(sigkey: Sigkey, params: &Params) -> Self {
+        let verkey = Verkey::from_sigkey(&sigkey, sigkey.y.len(), params).unwrap();
+        Self { sigkey, verkey }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Keypair {
+    fn zeroize(&mut self) {
+        self.sigkey.zeroize();
     }
 }
 
 /// Generate signing and verification keys
 pub fn keygen(count_messages: usize, params: &Params) -> (Sigkey, Verkey) {
-    // TODO: Take PRNG as argument
-    let x = FieldElement::random();
+    keygen_with_rng(count_messages, params, &mut thread_rng())
+}
+
+/// Generate signing and verification keys using the given random number generator
+pub fn keygen_with_rng<R: RngCore + CryptoRng>(
+    count_messages: usize,
+    params: &Params,
+    rng: &mut R,
+) -> (Sigkey, Verkey) {
+    let x = FieldElement::random_using_rng(rng);
     let X_tilde = &params.g_tilde * &x;
     let mut y = vec![];
     let mut Y_tilde = vec![];
     for _ in 0..count_messages {
-        let y_i = FieldElement::random();
+        let y_i = FieldElement::random_using_rng(rng);
         Y_tilde.push(&params.g_tilde * &y_i);
         y.push(y_i);
     }
@@ -50,8 +501,10 @@ pub fn keygen(count_messages: usize, params: &Params) -> (Sigkey, Verkey) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
     // For benchmarking
-    use std::time::{Duration, Instant};
+    use std::time::Instant;
 
     #[test]
     fn test_keygen() {
@@ -61,4 +514,191 @@ mod tests {
         assert_eq!(sk.y.len(), count_msgs);
         assert_eq!(vk.Y_tilde.len(), count_msgs);
     }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_sigkey_zeroize() {
+        use zeroize::Zeroize;
+        let params = Params::new("test".as_bytes());
+        let (mut sk, _) = keygen(3, &params);
+        sk.zeroize();
+        assert!(sk.x.is_zero());
+        assert!(sk.y.iter().all(|y| y.is_zero()));
+    }
+
+    #[test]
+    fn test_verkey_serialization() {
+        let params = Params::new("test".as_bytes());
+        let (_, vk) = keygen(5, &params);
+        let bytes = vk.to_bytes();
+        assert_eq!(Verkey::from_bytes(&bytes).unwrap(), vk);
+        assert_eq!(Verkey::from_trusted_cache_bytes(&bytes).unwrap(), vk);
+        let compressed = vk.to_compressed_bytes();
+        assert!(compressed.len() <= bytes.len());
+        assert_eq!(Verkey::from_compressed_bytes(&compressed).unwrap(), vk);
+        assert_eq!(
+            Verkey::from_compressed_bytes_with_profile(&compressed, ValidationProfile::Paranoid)
+                .unwrap(),
+            vk
+        );
+
+        assert!(Verkey::from_bytes(&[]).is_err());
+        assert!(Verkey::from_bytes(&bytes[1..]).is_err());
+        assert!(Verkey::from_compressed_bytes(&compressed[1..]).is_err());
+
+        // Identity is rejected by all profiles
+        let mut vk_1 = vk.clone();
+        vk_1.Y_tilde[2] = OtherGroup::identity();
+        let compressed = vk_1.to_compressed_bytes();
+        assert!(Verkey::from_compressed_bytes(&compressed).is_err());
+        assert!(Verkey::from_bytes(&vk_1.to_bytes()).is_err());
+        assert_eq!(
+            Verkey::from_trusted_cache_bytes(&vk_1.to_bytes()).unwrap(),
+            vk_1
+        );
+        assert!(
+            Verkey::from_compressed_bytes_with_profile(&compressed, ValidationProfile::Strict)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_verkey_from_sigkey() {
+        let params = Params::new("test".as_bytes());
+        let keypair = Keypair::new(4, &params);
+        assert_eq!(
+            Verkey::from_sigkey(&keypair.sigkey, 4, &params).unwrap(),
+            keypair.verkey
+        );
+        assert!(Verkey::from_sigkey(&keypair.sigkey, 3, &params).is_err());
+        let stored = Sigkey::from_versioned_bytes(&keypair.sigkey.to_versioned_bytes()).unwrap();
+        assert_eq!(Keypair::from_sigkey(stored, &params).verkey, keypair.verkey);
+    }
+
+    #[test]
+    fn test_verkey_fingerprint() {
+        let params = Params::new("test".as_bytes());
+        let (_, vk) = keygen(3, &params);
+        let (_, other) = keygen(3, &params);
+        // Same for every encoding of the key
+        let decoded = Verkey::from_compressed_bytes(&vk.to_compressed_bytes()).unwrap();
+        assert_eq!(decoded.fingerprint(), vk.fingerprint());
+        assert_eq!(crate::context::verkey_id(&vk), vk.fingerprint());
+        assert_ne!(other.fingerprint(), vk.fingerprint());
+        assert_eq!(vk.fingerprint_hex().len(), 64);
+
+        assert!(vk.check_same(&decoded).is_ok());
+        let err = vk.check_same(&other).unwrap_err().to_string();
+        assert!(err.contains(&vk.fingerprint_hex()) && err.contains(&other.fingerprint_hex()));
+    }
+
+    #[test]
+    fn test_versioned_key_encodings() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(4, &params);
+        let sk_bytes = sk.to_versioned_bytes();
+        let sk_1 = Sigkey::from_versioned_bytes(&sk_bytes).unwrap();
+        assert_eq!((sk_1.x, sk_1.y), (sk.x.clone(), sk.y.clone()));
+        assert_eq!(
+            Verkey::from_versioned_bytes(&vk.to_versioned_bytes()).unwrap(),
+            vk
+        );
+        let mut unknown = sk_bytes.clone();
+        unknown[0] = 0xff;
+        match Sigkey::from_versioned_bytes(&unknown) {
+            Err(PSError::InvalidEncoding { msg }) => assert!(msg.contains("version")),
+            _ => panic!("Unknown version accepted"),
+        }
+        // A V1 body is not a V2 metadata header. Seeded keys, a body starting with 0 would read as
+        // empty metadata.
+        let (sk_s, vk_s) = keygen_with_rng(4, &params, &mut ChaChaRng::seed_from_u64(1));
+        let mut relabelled = sk_s.to_versioned_bytes();
+        relabelled[0] = KEY_FORMAT_V2;
+        assert!(Sigkey::from_versioned_bytes(&relabelled).is_err());
+        let mut relabelled = vk_s.to_versioned_bytes();
+        relabelled[0] = KEY_FORMAT_V2;
+        assert!(Verkey::from_versioned_bytes(&relabelled).is_err());
+        assert!(Sigkey::from_versioned_bytes(&sk_bytes[..sk_bytes.len() - 1]).is_err());
+        assert!(Verkey::from_versioned_bytes(&[]).is_err());
+
+        let json = serde_json::to_string(&vk).unwrap();
+        assert_eq!(serde_json::from_str::<Verkey>(&json).unwrap(), vk);
+        assert!(serde_json::from_str::<Sigkey>(&serde_json::to_string(&sk).unwrap()).is_ok());
+
+        // Identity elements and zero scalars are rejected on load
+        let mut vk_1 = vk.clone();
+        vk_1.Y_tilde[1] = OtherGroup::identity();
+        assert!(Verkey::from_versioned_bytes(&vk_1.to_versioned_bytes()).is_err());
+        assert!(serde_json::from_str::<Verkey>(&serde_json::to_string(&vk_1).unwrap()).is_err());
+        let mut sk_2 = sk.clone();
+        sk_2.y[0] = FieldElement::zero();
+        assert!(Sigkey::from_versioned_bytes(&sk_2.to_versioned_bytes()).is_err());
+        assert!(serde_json::from_str::<Sigkey>(&serde_json::to_string(&sk_2).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_verkey_validate() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(4, &params);
+        let blinding_key = BlindingKey::new(&sk, &params);
+        vk.validate(&blinding_key, &params, VerkeyValidation::Full)
+            .unwrap();
+
+        let (sk_1, _) = keygen(3, &params);
+        let short = BlindingKey::new(&sk_1, &params);
+        assert!(vk
+            .validate(&short, &params, VerkeyValidation::Lengths)
+            .is_err());
+
+        // Mismatched Y_tilde passes the length check only
+        let mut vk_1 = vk.clone();
+        vk_1.Y_tilde.swap(0, 1);
+        vk_1.validate(&blinding_key, &params, VerkeyValidation::Lengths)
+            .unwrap();
+        assert!(vk_1
+            .validate(&blinding_key, &params, VerkeyValidation::Full)
+            .is_err());
+        let mut vk_2 = vk.clone();
+        vk_2.X_tilde = &vk.X_tilde + &params.g_tilde;
+        assert!(vk_2
+            .validate(&blinding_key, &params, VerkeyValidation::Full)
+            .is_err());
+        let mut vk_3 = vk;
+        vk_3.Y_tilde[3] = OtherGroup::identity();
+        assert!(vk_3
+            .validate(&blinding_key, &params, VerkeyValidation::Full)
+            .is_err());
+    }
+
+    #[test]
+    fn timing_verkey_deserialization() {
+        let count_msgs = 100;
+        let iterations = 10;
+        let params = Params::new("test".as_bytes());
+        let (_, vk) = keygen(count_msgs, &params);
+        let bytes = vk.to_bytes();
+        let compressed = vk.to_compressed_bytes();
+
+        let start = Instant::now();
+        for _ in 0..iterations {
+            Verkey::from_compressed_bytes(&compressed).unwrap();
+        }
+        println!(
+            "Time to decompress {} verkeys for {} messages is {:?}",
+            iterations,
+            count_msgs,
+            start.elapsed()
+        );
+
+        let start = Instant::now();
+        for _ in 0..iterations {
+            Verkey::from_trusted_cache_bytes(&bytes).unwrap();
+        }
+        println!(
+            "Time to load {} verkeys for {} messages from trusted cache is {:?}",
+            iterations,
+            count_msgs,
+            start.elapsed()
+        );
+    }
 }
@@ -0,0 +1,243 @@
+// Storage of issuer keys. A `StoredKey` keeps a verkey with its params, the sigkey if the application
+// issues with it, and the label, message count and creation time. Keys are identified by `verkey_id`.
+// `KeyStore` is implemented by `MemoryKeyStore` and, with feature `file-keystore`, by `FileKeyStore`
+// which keeps one JSON file per key in a directory. Sigkeys are stored in plain, seal them with feature
+// `sealing` before storing them where others can read.
+
+use crate::context::{verkey_id, VerkeyId};
+use crate::errors::PSError;
+use crate::keys::{Keypair, Params, Sigkey, Verkey};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StoredKey {
+    pub label: String,
+    pub msg_count: usize,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+    /// Absent for keys of other issuers
+    pub sigkey: Option<Sigkey>,
+    pub verkey: Verkey,
+    pub params: Params,
+}
+
+impl StoredKey {
+    /// Key of this issuer, created now
+    pub fn new(label: &str, keypair: &Keypair, params: &Params) -> Self {
+        let mut key = Self::for_verkey(label, &keypair.verkey, params);
+        key.sigkey = Some(keypair.sigkey.clone());
+        key
+    }
+
+    /// Verkey of another issuer, stored now
+    pub fn for_verkey(label: &str, verkey: &Verkey, params: &Params) -> Self {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self {
+            label: label.to_string(),
+            msg_count: verkey.Y_tilde.len(),
+            created_at,
+            sigkey: None,
+            verkey: verkey.clone(),
+            params: params.clone(),
+        }
+    }
+
+    pub fn id(&self) -> VerkeyId {
+        verkey_id(&self.verkey)
+    }
+
+    pub fn keypair(&self) -> Option<Keypair> {
+        self.sigkey.as_ref().map(|sigkey| Keypair {
+            sigkey: sigkey.clone(),
+            verkey: self.verkey.clone(),
+        })
+    }
+
+    /// The message count matches the keys
+    fn check(&self) -> Result<(), PSError> {
+        let given = match &self.sigkey {
+            Some(sigkey) if sigkey.msg_count() != self.msg_count => sigkey.msg_count(),
+            _ if self.verkey.Y_tilde.len() != self.msg_count => self.verkey.Y_tilde.len(),
+            _ => return Ok(()),
+        };
+        Err(PSError::UnsupportedNoOfMessages {
+            expected: self.msg_count,
+            given,
+        })
+    }
+}
+
+pub trait KeyStore {
+    fn get(&self, id: &VerkeyId) -> Result<Option<StoredKey>, PSError>;
+
+    /// Stores `key` under its id, replacing a key with the same id
+    fn put(&mut self, key: StoredKey) -> Result<VerkeyId, PSError>;
+
+    /// Ids of all stored keys in increasing order
+    fn list(&self) -> Result<Vec<VerkeyId>, PSError>;
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MemoryKeyStore {
+    keys: HashMap<VerkeyId, StoredKey>,
+}
+
+impl MemoryKeyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl KeyStore for MemoryKeyStore {
+    fn get(&self, id: &VerkeyId) -> Result<Option<StoredKey>, PSError> {
+        Ok(self.keys.get(id).cloned())
+    }
+
+    fn put(&mut self, key: StoredKey) -> Result<VerkeyId, PSError> {
+        key.check()?;
+        let id = key.id();
+        self.keys.insert(id, key);
+        Ok(id)
+    }
+
+    fn list(&self) -> Result<Vec<VerkeyId>, PSError> {
+        let mut ids: Vec<VerkeyId> = self.keys.keys().cloned().collect();
+        ids.sort();
+        Ok(ids)
+    }
+}
+
+/// Keys in files `<hex of id>.json` of a directory
+#[cfg(feature = "file-keystore")]
+#[derive(Clone, Debug)]
+pub struct FileKeyStore {
+    dir: std::path::PathBuf,
+}
+
+#[cfg(feature = "file-keystore")]
+impl FileKeyStore {
+    /// Creates `dir` if missing
+    pub fn open<P: AsRef<std::path::Path>>(dir: P) -> Result<Self, PSError> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir).map_err(|e| io_error(&dir, e))?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, id: &VerkeyId) -> std::path::PathBuf {
+        let name: String = id.iter().map(|b| format!("{:02x}", b)).collect();
+        self.dir.join(name + ".json")
+    }
+}
+
+#[cfg(feature = "file-keystore")]
+fn io_error(path: &std::path::Path, e: std::io::Error) -> PSError {
+    PSError::GeneralError {
+        msg: format!("Key store {}: {}", path.display(), e),
+    }
+}
+
+#[cfg(feature = "file-keystore")]
+impl KeyStore for FileKeyStore {
+    fn get(&self, id: &VerkeyId) -> Result<Option<StoredKey>, PSError> {
+        let path = self.path(id);
+        let json = match std::fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(io_error(&path, e)),
+        };
+        let key: StoredKey = serde_json::from_str(&json).map_err(|e| PSError::InvalidEncoding {
+            msg: format!("Key store {}: {}", path.display(), e),
+        })?;
+        // A renamed or edited file is not returned for another id
+        if key.id() != *id {
+            return Err(PSError::InvalidEncoding {
+                msg: format!("Key store {}: key has another id", path.display()),
+            });
+        }
+        Ok(Some(key))
+    }
+
+    fn put(&mut self, key: StoredKey) -> Result<VerkeyId, PSError> {
+        key.check()?;
+        let id = key.id();
+        let path = self.path(&id);
+        let json = serde_json::to_string(&key).map_err(|e| PSError::GeneralError {
+            msg: format!("Key store {}: {}", path.display(), e),
+        })?;
+        // Written to a temporary file first so a crash leaves either the old or the new key
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)
+            .and_then(|_| std::fs::rename(&tmp, &path))
+            .map_err(|e| io_error(&path, e))?;
+        Ok(id)
+    }
+
+    fn list(&self) -> Result<Vec<VerkeyId>, PSError> {
+        let entries = std::fs::read_dir(&self.dir).map_err(|e| io_error(&self.dir, e))?;
+        let mut ids = vec![];
+        for entry in entries {
+            let name = entry.map_err(|e| io_error(&self.dir, e))?.file_name();
+            let hex = match name.to_str().and_then(|n| n.strip_suffix(".json")) {
+                Some(hex) if hex.len() == 2 * std::mem::size_of::<VerkeyId>() => hex,
+                _ => continue,
+            };
+            let mut id = VerkeyId::default();
+            let parsed =
+                (0..id.len()).all(|i| match u8::from_str_radix(&hex[2 * i..2 * i + 2], 16) {
+                    Ok(b) => {
+                        id[i] = b;
+                        true
+                    }
+                    Err(_) => false,
+                });
+            if parsed {
+                ids.push(id);
+            }
+        }
+        ids.sort();
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_store<S: KeyStore>(store: &mut S) {
+        let params = Params::new("test".as_bytes());
+        let issuer = StoredKey::new("issuer", &Keypair::new(3, &params), &params);
+        let other = StoredKey::for_verkey("other", &Keypair::new(2, &params).verkey, &params);
+        let id = store.put(issuer.clone()).unwrap();
+        let other_id = store.put(other).unwrap();
+        let mut expected = vec![id, other_id];
+        expected.sort();
+        assert_eq!(store.list().unwrap(), expected);
+
+        let stored = store.get(&id).unwrap().unwrap();
+        assert_eq!(stored.label, "issuer");
+        assert_eq!(stored.msg_count, 3);
+        assert_eq!(stored.created_at, issuer.created_at);
+        assert_eq!(stored.keypair().unwrap().verkey, issuer.verkey);
+        assert!(store.get(&other_id).unwrap().unwrap().keypair().is_none());
+        assert!(store.get(&[0u8; 32]).unwrap().is_none());
+
+        let mut wrong_count = issuer;
+        wrong_count.msg_count = 4;
+        assert!(store.put(wrong_count).is_err());
+    }
+
+    #[test]
+    fn test_key_stores() {
+        check_store(&mut MemoryKeyStore::new());
+        #[cfg(feature = "file-keystore")]
+        {
+            let dir = std::env::temp_dir().join(format!("ps_sig_keystore_{}", std::process::id()));
+            check_store(&mut FileKeyStore::open(&dir).unwrap());
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+}
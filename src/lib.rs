@@ -2,8 +2,9 @@
 
 #[cfg(all(feature = "SignatureG2", feature = "SignatureG1"))]
 compile_error!("features `SignatureG2` and `SignatureG1` are mutually exclusive");
+#[cfg(not(any(feature = "SignatureG2", feature = "SignatureG1")))]
+compile_error!("one of the features `SignatureG2` and `SignatureG1` is needed");
 
-#[macro_use]
 extern crate amcl_wrapper;
 
 use amcl_wrapper::extension_field_gt::GT;
@@ -17,6 +18,12 @@ pub type OtherGroup = amcl_wrapper::group_elem_g1::G1;
 #[cfg(feature = "SignatureG2")]
 pub type OtherGroupVec = amcl_wrapper::group_elem_g1::G1Vector;
 #[cfg(feature = "SignatureG2")]
+pub const SIGNATURE_GROUP_SIZE: usize = amcl_wrapper::constants::GroupG2_SIZE;
+#[cfg(feature = "SignatureG2")]
+pub const OTHER_GROUP_SIZE: usize = amcl_wrapper::constants::GroupG1_SIZE;
+/// Product of the pairings of (g1, g2) and (h1, h2). amcl computes pairings on the stack without
+/// allocating or keeping state, so there is no pairing context to reuse across calls or threads.
+#[cfg(feature = "SignatureG2")]
 pub fn ate_2_pairing(
     g1: &SignatureGroup,
     g2: &OtherGroup,
@@ -25,6 +32,11 @@ pub fn ate_2_pairing(
 ) -> GT {
     GT::ate_2_pairing(g2, g1, h2, h1)
 }
+/// Product of the pairings of all pairs
+#[cfg(feature = "SignatureG2")]
+pub fn ate_multi_pairing(pairs: &[(&SignatureGroup, &OtherGroup)]) -> GT {
+    GT::ate_multi_pairing(pairs.iter().map(|(g1, g2)| (*g2, *g1)).collect())
+}
 
 #[cfg(feature = "SignatureG1")]
 pub type SignatureGroup = amcl_wrapper::group_elem_g1::G1;
@@ -35,6 +47,11 @@ pub type OtherGroup = amcl_wrapper::group_elem_g2::G2;
 #[cfg(feature = "SignatureG1")]
 pub type OtherGroupVec = amcl_wrapper::group_elem_g2::G2Vector;
 #[cfg(feature = "SignatureG1")]
+pub const SIGNATURE_GROUP_SIZE: usize = amcl_wrapper::constants::GroupG1_SIZE;
+#[cfg(feature = "SignatureG1")]
+pub const OTHER_GROUP_SIZE: usize = amcl_wrapper::constants::GroupG2_SIZE;
+/// See the `SignatureG2` version
+#[cfg(feature = "SignatureG1")]
 pub fn ate_2_pairing(
     g1: &SignatureGroup,
     g2: &OtherGroup,
@@ -43,19 +60,123 @@ pub fn ate_2_pairing(
 ) -> GT {
     GT::ate_2_pairing(g1, g2, h1, h2)
 }
+/// See the `SignatureG2` version
+#[cfg(feature = "SignatureG1")]
+pub fn ate_multi_pairing(pairs: &[(&SignatureGroup, &OtherGroup)]) -> GT {
+    GT::ate_multi_pairing(pairs.to_vec())
+}
+
+pub const FIELD_ELEMENT_SIZE: usize = amcl_wrapper::constants::MODBYTES;
 
 extern crate rand;
 #[macro_use]
 extern crate failure;
 
+#[cfg(feature = "zeroize")]
+extern crate zeroize;
+
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 
+/// Fail with the error of `point` if it is armed, see `failpoints`. Nothing without feature
+/// `failpoints`.
+macro_rules! fail_point {
+    ($point:ident) => {
+        #[cfg(feature = "failpoints")]
+        crate::failpoints::hit(crate::failpoints::Failpoint::$point)?;
+    };
+}
+
+mod utils;
+
 pub mod errors;
 #[macro_use]
 pub mod pok_vc;
 pub mod keys;
+pub mod linking;
+pub mod modified;
 pub mod pok_sig;
 pub mod signature;
 pub mod blind_signature;
+pub mod adaptor;
+pub mod artifact;
+#[cfg(feature = "async")]
+pub mod async_api;
+pub mod attestation;
+pub mod audit;
+pub mod binding;
+pub mod boolean;
+pub mod ceremony;
+pub mod chaining;
+pub mod compact;
+pub mod conformance;
+pub mod consent;
+pub mod context;
+pub mod cost;
+#[cfg(feature = "rfc9380")]
+pub mod cp_link;
+pub mod curve;
+pub mod delegation;
+pub mod derivation;
+pub mod disclosure;
+pub mod encoding;
+pub mod encoding_proof;
+pub mod epoch;
+pub mod escrow;
+pub mod extension;
+#[cfg(feature = "failpoints")]
+pub mod failpoints;
+#[cfg(feature = "golden")]
+pub mod golden;
+pub mod hash_to_curve;
+pub mod hash_to_field;
+pub mod incremental;
+pub mod interop;
+pub mod issuance;
+pub mod issuer_hiding;
+pub mod joint_random;
+#[cfg(feature = "jwk")]
+pub mod jwk;
+pub mod key_sharing;
+pub mod key_status;
+pub mod keystore;
+pub mod limits;
+pub mod metadata;
+pub mod migration;
+pub mod mnemonic;
+pub mod multi_credential;
+pub mod padding;
+pub mod policy;
+pub mod pop;
+pub mod precomputed;
+pub mod prepared;
+pub mod presentation;
+pub mod quickstart;
+pub mod rate_limit;
+pub mod relations;
+pub mod resharing;
+pub mod roles;
+pub mod rotation;
+pub mod salted;
+pub mod schema;
+#[cfg(feature = "sealing")]
+pub mod sealing;
+pub mod sections;
+#[cfg(feature = "session")]
+pub mod session;
+pub mod shamir;
+pub mod signer;
+pub mod stored;
+pub mod stream;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod text;
+pub mod threshold;
+pub mod transcript;
+pub mod transparency;
+pub mod tripwire;
+pub mod types;
+pub mod validation;
+
+pub use crate::curve::{curve_info, CurveInfo};
@@ -0,0 +1,181 @@
+// Limits on the sizes of decoded artifacts, so that length prefixes and element counts supplied by an
+// attacker cannot make a verifier allocate or compute without bound. Serde deserialization cannot take
+// parameters, so the limits are process wide, set with `DecodeLimits::install`, and can be overridden
+// for the current thread with `DecodeLimits::scoped`. They are checked by the `from_bytes` decoders of
+// keys, proofs and presentations, by the interop profiles and by deserialization of keys and
+// presentations, and exceeding one is a `PSError::LimitExceeded`.
+
+use crate::errors::PSError;
+use amcl_wrapper::field_elem::FieldElement;
+use serde::{Deserialize, Deserializer};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub const DEFAULT_MAX_MSG_COUNT: usize = 1024;
+/// Enough for a presentation of `DEFAULT_MAX_MSG_COUNT` messages in either format
+pub const DEFAULT_MAX_PROOF_SIZE: usize = 256 * 1024;
+pub const DEFAULT_MAX_REVEALED: usize = DEFAULT_MAX_MSG_COUNT;
+
+static MAX_MSG_COUNT: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_MSG_COUNT);
+static MAX_PROOF_SIZE: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_PROOF_SIZE);
+static MAX_REVEALED: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_REVEALED);
+
+thread_local! {
+    static SCOPED: Cell<Option<DecodeLimits>> = const { Cell::new(None) };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Messages of a verkey or sigkey
+    pub max_msg_count: usize,
+    /// Encoded size in bytes of a proof or presentation
+    pub max_proof_size: usize,
+    /// Revealed messages of a presentation
+    pub max_revealed: usize,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_msg_count: DEFAULT_MAX_MSG_COUNT,
+            max_proof_size: DEFAULT_MAX_PROOF_SIZE,
+            max_revealed: DEFAULT_MAX_REVEALED,
+        }
+    }
+}
+
+impl DecodeLimits {
+    /// The limits in effect on this thread
+    pub fn current() -> Self {
+        SCOPED.with(|s| s.get()).unwrap_or_else(|| Self {
+            max_msg_count: MAX_MSG_COUNT.load(Ordering::Relaxed),
+            max_proof_size: MAX_PROOF_SIZE.load(Ordering::Relaxed),
+            max_revealed: MAX_REVEALED.load(Ordering::Relaxed),
+        })
+    }
+
+    /// Set the limits of all threads without a scoped override
+    pub fn install(self) {
+        MAX_MSG_COUNT.store(self.max_msg_count, Ordering::Relaxed);
+        MAX_PROOF_SIZE.store(self.max_proof_size, Ordering::Relaxed);
+        MAX_REVEALED.store(self.max_revealed, Ordering::Relaxed);
+    }
+
+    /// Run `f` with these limits on the current thread
+    pub fn scoped<T, F: FnOnce() -> T>(self, f: F) -> T {
+        struct Restore(Option<DecodeLimits>);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                SCOPED.with(|s| s.set(self.0));
+            }
+        }
+        let _restore = Restore(SCOPED.with(|s| s.replace(Some(self))));
+        f()
+    }
+
+    pub fn check_msg_count(&self, count: usize) -> Result<(), PSError> {
+        check("message count", self.max_msg_count, count)
+    }
+
+    pub fn check_proof_size(&self, size: usize) -> Result<(), PSError> {
+        check("proof size", self.max_proof_size, size)
+    }
+
+    pub fn check_revealed(&self, count: usize) -> Result<(), PSError> {
+        check("revealed messages", self.max_revealed, count)
+    }
+}
+
+fn check(limit: &str, max: usize, given: usize) -> Result<(), PSError> {
+    if given > max {
+        return Err(PSError::LimitExceeded {
+            limit: limit.to_string(),
+            max,
+            given,
+        });
+    }
+    Ok(())
+}
+
+/// Deserialize revealed messages of a presentation within `max_revealed`
+pub(crate) fn deserialize_revealed<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<usize, FieldElement>, D::Error> {
+    let revealed = HashMap::<usize, FieldElement>::deserialize(deserializer)?;
+    DecodeLimits::current()
+        .check_revealed(revealed.len())
+        .map_err(serde::de::Error::custom)?;
+    Ok(revealed)
+}
+
+/// Same as `deserialize_revealed` for the revealed messages of several credentials
+pub(crate) fn deserialize_revealed_vec<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<HashMap<usize, FieldElement>>, D::Error> {
+    let revealed = Vec::<HashMap<usize, FieldElement>>::deserialize(deserializer)?;
+    let limits = DecodeLimits::current();
+    for r in &revealed {
+        limits
+            .check_revealed(r.len())
+            .map_err(serde::de::Error::custom)?;
+    }
+    Ok(revealed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::{keygen, Params, Verkey};
+    use crate::presentation::Presentation;
+    use crate::signature::Signature;
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_decode_limits() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(5, &params);
+        let msgs = FieldElementVector::random(5);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let revealed: HashSet<usize> = (0..3).collect();
+        let pres =
+            Presentation::new(&sig, &vk, &params, msgs.as_slice(), revealed, b"nonce").unwrap();
+        let pres_bytes = pres.to_bytes();
+        let pres_json = serde_json::to_string(&pres).unwrap();
+        let vk_json = serde_json::to_string(&vk).unwrap();
+
+        let limits = DecodeLimits {
+            max_msg_count: 4,
+            max_proof_size: pres_bytes.len() - 1,
+            max_revealed: 2,
+        };
+        let limited = |r: Result<(), PSError>| matches!(r, Err(PSError::LimitExceeded { .. }));
+        limits.scoped(|| {
+            assert_eq!(DecodeLimits::current(), limits);
+            assert!(limited(Verkey::from_bytes(&vk.to_bytes()).map(|_| ())));
+            assert!(limited(
+                Verkey::from_compressed_bytes(&vk.to_compressed_bytes()).map(|_| ())
+            ));
+            assert!(limited(
+                crate::keys::Sigkey::from_versioned_bytes(&sk.to_versioned_bytes()).map(|_| ())
+            ));
+            assert!(serde_json::from_str::<Verkey>(&vk_json).is_err());
+            assert!(limited(
+                Presentation::from_bytes(&pres_bytes, &vk, &params).map(|_| ())
+            ));
+            assert!(serde_json::from_str::<Presentation>(&pres_json).is_err());
+        });
+
+        // Outside the scope the defaults apply again
+        assert_eq!(DecodeLimits::current(), DecodeLimits::default());
+        assert_eq!(Verkey::from_bytes(&vk.to_bytes()).unwrap(), vk);
+        assert!(Presentation::from_bytes(&pres_bytes, &vk, &params)
+            .unwrap()
+            .verify(&vk, &params)
+            .unwrap());
+        assert!(serde_json::from_str::<Presentation>(&pres_json).is_ok());
+    }
+}
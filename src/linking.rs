@@ -0,0 +1,278 @@
+// Opt-in linking of presentations. The prover picks a hidden message of the signature as link secret
+// `s` and derives the tag H(scope)^s for a verifier chosen scope. The tag is proven correct with a
+// Schnorr proof sharing the blinding of `s` with the proof of knowledge of the signature, hence its
+// response as well. Presentations with the same tag for the same scope come from the same signature
+// (or at least the same link secret) while tags for different scopes are unlinkable.
+
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::pok_sig::{PoKOfSignature, PoKOfSignatureProof};
+use crate::signature::Signature;
+use crate::types::{Challenge, Message};
+use crate::validation::deserialize_point;
+use crate::SignatureGroup;
+use amcl_wrapper::field_elem::FieldElement;
+use amcl_wrapper::group_elem::GroupElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::{HashMap, HashSet};
+
+const DOMAIN: &[u8] = b"PS linking scope : ";
+
+/// Base of the tag for `scope`, hashed with the suite of `params`
+pub fn scope_base(scope: &[u8], params: &Params) -> Result<SignatureGroup, PSError> {
+    params.hash_to_signature_group(&[DOMAIN, scope].concat())
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LinkedPresentation {
+    pub proof: PoKOfSignatureProof,
+    pub revealed_msgs: HashMap<usize, FieldElement>,
+    pub link_msg_idx: usize,
+    pub scope: Vec<u8>,
+    #[serde(deserialize_with = "deserialize_point")]
+    pub tag: SignatureGroup,
+    /// Schnorr commitment H(scope)^blinding for the tag
+    #[serde(deserialize_with = "deserialize_point")]
+    pub tag_commitment: SignatureGroup,
+    pub nonce: Vec<u8>,
+}
+
+impl LinkedPresentation {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[FieldElement],
+        revealed_msg_indices: HashSet<usize>,
+        link_msg_idx: usize,
+        scope: &[u8],
+        nonce: &[u8],
+    ) -> Result<Self, PSError> {
+        Self::new_with_rng(
+            sig,
+            vk,
+            params,
+            messages,
+            revealed_msg_indices,
+            link_msg_idx,
+            scope,
+            nonce,
+            &mut thread_rng(),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[FieldElement],
+        revealed_msg_indices: HashSet<usize>,
+        link_msg_idx: usize,
+        scope: &[u8],
+        nonce: &[u8],
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        if link_msg_idx >= messages.len() || revealed_msg_indices.contains(&link_msg_idx) {
+            return Err(PSError::GeneralError {
+                msg: format!("Link secret {} should be a hidden message", link_msg_idx),
+            });
+        }
+        let mut blindings = vec![];
+        let mut link_blinding = None;
+        for i in 0..messages.len() {
+            if revealed_msg_indices.contains(&i) {
+                continue;
+            }
+            let b = FieldElement::random_using_rng(rng);
+            if i == link_msg_idx {
+                link_blinding = Some(b.clone());
+            }
+            blindings.push(b);
+        }
+        let link_blinding = link_blinding.unwrap();
+        let revealed_msgs = revealed_msg_indices
+            .iter()
+            .filter(|i| **i < messages.len())
+            .map(|i| (*i, messages[*i].clone()))
+            .collect();
+
+        let pok = PoKOfSignature::init_with_rng(
+            sig,
+            vk,
+            params,
+            &Message::from_field_elements(messages),
+            Some(&blindings),
+            revealed_msg_indices,
+            rng,
+        )?;
+        let base = scope_base(scope, params)?;
+        let tag = &base * &messages[link_msg_idx];
+        let tag_commitment = &base * &link_blinding;
+        let challenge =
+            Self::compute_challenge(pok.to_bytes(), &tag, &tag_commitment, scope, nonce);
+        Ok(Self {
+            proof: pok.gen_proof(&challenge)?,
+            revealed_msgs,
+            link_msg_idx,
+            scope: scope.to_vec(),
+            tag,
+            tag_commitment,
+            nonce: nonce.to_vec(),
+        })
+    }
+
+    fn compute_challenge(
+        mut bytes: Vec<u8>,
+        tag: &SignatureGroup,
+        tag_commitment: &SignatureGroup,
+        scope: &[u8],
+        nonce: &[u8],
+    ) -> Challenge {
+        bytes.append(&mut tag.to_bytes());
+        bytes.append(&mut tag_commitment.to_bytes());
+        bytes.extend_from_slice(&(scope.len() as u64).to_be_bytes());
+        bytes.extend_from_slice(scope);
+        bytes.extend_from_slice(nonce);
+        Challenge::from_msg_hash(&bytes)
+    }
+
+    pub fn revealed_msg_indices(&self) -> HashSet<usize> {
+        self.revealed_msgs.keys().cloned().collect()
+    }
+
+    /// Verify the proof of knowledge of signature and that the tag is derived from the hidden
+    /// message `link_msg_idx` for `expected_scope`
+    pub fn verify(
+        &self,
+        vk: &Verkey,
+        params: &Params,
+        expected_scope: &[u8],
+    ) -> Result<bool, PSError> {
+        if self.scope != expected_scope
+            || self.tag.is_identity()
+            || self.link_msg_idx >= vk.Y_tilde.len()
+            || self.revealed_msgs.keys().any(|i| *i >= vk.Y_tilde.len())
+        {
+            return Ok(false);
+        }
+        let revealed_msg_indices = self.revealed_msg_indices();
+        let challenge = Self::compute_challenge(
+            self.proof
+                .get_bytes_for_challenge(&revealed_msg_indices, vk, params),
+            &self.tag,
+            &self.tag_commitment,
+            &self.scope,
+            &self.nonce,
+        );
+        if !self
+            .proof
+            .verify(vk, params, self.revealed_msgs.clone(), &challenge)?
+        {
+            return Ok(false);
+        }
+        // H^response * tag^challenge == tag_commitment
+        let resp = match self
+            .proof
+            .get_resp_for_message(self.link_msg_idx, &revealed_msg_indices)
+        {
+            Ok(r) => r,
+            Err(_) => return Ok(false),
+        };
+        let base = scope_base(&self.scope, params)?;
+        Ok(&(&base * &resp.0) + &(&self.tag * &challenge.0) == self.tag_commitment)
+    }
+
+    /// Whether both presentations were made with the same link secret for the same scope. Only
+    /// meaningful for verified presentations.
+    pub fn is_linked_to(&self, other: &LinkedPresentation) -> bool {
+        self.scope == other.scope && self.tag == other.tag
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use amcl_wrapper::field_elem::FieldElementVector;
+
+    #[test]
+    fn test_linked_presentations() {
+        let count_msgs = 4;
+        let link_idx = 1;
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(count_msgs, &params);
+        let msgs = FieldElementVector::random(count_msgs);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let mut revealed = HashSet::new();
+        revealed.insert(3);
+
+        let present = |scope: &[u8], nonce: &[u8]| {
+            LinkedPresentation::new(
+                &sig,
+                &vk,
+                &params,
+                msgs.as_slice(),
+                revealed.clone(),
+                link_idx,
+                scope,
+                nonce,
+            )
+            .unwrap()
+        };
+        let p1 = present(b"verifier 1", b"nonce 1");
+        let p2 = present(b"verifier 1", b"nonce 2");
+        let p3 = present(b"verifier 2", b"nonce 3");
+        assert!(p1.verify(&vk, &params, b"verifier 1").unwrap());
+        assert!(p2.verify(&vk, &params, b"verifier 1").unwrap());
+        assert!(p3.verify(&vk, &params, b"verifier 2").unwrap());
+        assert!(!p1.verify(&vk, &params, b"verifier 2").unwrap());
+
+        assert!(p1.is_linked_to(&p2));
+        assert_ne!(p1.tag, p3.tag);
+        assert!(!p1.is_linked_to(&p3));
+
+        // Another signature over different messages gives a different tag
+        let msgs_1 = FieldElementVector::random(count_msgs);
+        let sig_1 = Signature::new(
+            &Message::from_field_elements(msgs_1.as_slice()),
+            &sk,
+            &params,
+        )
+        .unwrap();
+        let p4 = LinkedPresentation::new(
+            &sig_1,
+            &vk,
+            &params,
+            msgs_1.as_slice(),
+            revealed.clone(),
+            link_idx,
+            b"verifier 1",
+            b"nonce 4",
+        )
+        .unwrap();
+        assert!(p4.verify(&vk, &params, b"verifier 1").unwrap());
+        assert!(!p1.is_linked_to(&p4));
+
+        // Tag not matching the link secret
+        let mut p5 = p1.clone();
+        p5.tag = p4.tag.clone();
+        assert!(!p5.verify(&vk, &params, b"verifier 1").unwrap());
+
+        // Link secret must be hidden
+        revealed.insert(link_idx);
+        assert!(LinkedPresentation::new(
+            &sig,
+            &vk,
+            &params,
+            msgs.as_slice(),
+            revealed,
+            link_idx,
+            b"verifier 1",
+            b"nonce"
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,217 @@
+// Metadata for managing keys and signatures without a separate database: the id of the issuer's
+// verkey, the creation time and the hash of the label the params were generated from. It is carried in
+// a header of type-length-value entries before the unchanged encoding of the key or signature, so the
+// cryptographic payload stays canonical and metadata never enters proofs or challenges. Entries are in
+// increasing order of type and each type appears at most once, so equal metadata always has the same
+// encoding. Entries of unknown types are skipped for forward compatibility.
+//
+// Metadata is not authenticated, whoever passes on an artifact can change it.
+
+use crate::context::{verkey_id, VerkeyId};
+use crate::errors::PSError;
+use crate::keys::Verkey;
+use crate::signature::Signature;
+use crate::utils::{hash_with_domain, ByteReader, HASH_SIZE};
+use crate::SIGNATURE_GROUP_SIZE;
+
+/// First byte of `Signature::to_bytes_with_metadata`
+const SIGNATURE_FORMAT_WITH_METADATA: u8 = 1;
+
+const KEY_ID_TYPE: u8 = 1;
+const CREATED_AT_TYPE: u8 = 2;
+const PARAMS_LABEL_HASH_TYPE: u8 = 3;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metadata {
+    key_id: Option<VerkeyId>,
+    created_at: Option<u64>,
+    params_label_hash: Option<[u8; HASH_SIZE]>,
+}
+
+impl Metadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Metadata naming `vk` as the issuer key
+    pub fn for_verkey(vk: &Verkey) -> Self {
+        Self::new().with_key_id(verkey_id(vk))
+    }
+
+    pub fn with_key_id(mut self, key_id: VerkeyId) -> Self {
+        self.key_id = Some(key_id);
+        self
+    }
+
+    /// `created_at` in seconds since the Unix epoch
+    pub fn with_created_at(mut self, created_at: u64) -> Self {
+        self.created_at = Some(created_at);
+        self
+    }
+
+    /// `label` is the one given to `Params::new`
+    pub fn with_params_label(mut self, label: &[u8]) -> Self {
+        self.params_label_hash = Some(Self::hash_params_label(label));
+        self
+    }
+
+    pub fn hash_params_label(label: &[u8]) -> [u8; HASH_SIZE] {
+        hash_with_domain(b"PS params label", label)
+    }
+
+    pub fn key_id(&self) -> Option<&VerkeyId> {
+        self.key_id.as_ref()
+    }
+
+    pub fn created_at(&self) -> Option<u64> {
+        self.created_at
+    }
+
+    pub fn params_label_hash(&self) -> Option<&[u8; HASH_SIZE]> {
+        self.params_label_hash.as_ref()
+    }
+
+    /// Whether the params were generated from `label`. False if the label hash is absent.
+    pub fn is_for_params_label(&self, label: &[u8]) -> bool {
+        self.params_label_hash == Some(Self::hash_params_label(label))
+    }
+
+    /// Number of entries followed by the entries
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut entries: Vec<(u8, Vec<u8>)> = vec![];
+        if let Some(id) = &self.key_id {
+            entries.push((KEY_ID_TYPE, id.to_vec()));
+        }
+        if let Some(t) = self.created_at {
+            entries.push((CREATED_AT_TYPE, t.to_be_bytes().to_vec()));
+        }
+        if let Some(h) = &self.params_label_hash {
+            entries.push((PARAMS_LABEL_HASH_TYPE, h.to_vec()));
+        }
+        let mut bytes = vec![entries.len() as u8];
+        for (t, v) in entries {
+            bytes.push(t);
+            bytes.push(v.len() as u8);
+            bytes.extend_from_slice(&v);
+        }
+        bytes
+    }
+
+    pub(crate) fn read(reader: &mut ByteReader) -> Result<Self, PSError> {
+        let count = reader.read_u8()?;
+        let mut metadata = Self::default();
+        let mut last_type = 0;
+        for _ in 0..count {
+            let t = reader.read_u8()?;
+            if t <= last_type {
+                return Err(PSError::InvalidEncoding {
+                    msg: format!("Metadata entry of type {} out of order", t),
+                });
+            }
+            last_type = t;
+            let len = reader.read_u8()? as usize;
+            let value = reader.take(len)?;
+            let wrong_length = || PSError::InvalidEncoding {
+                msg: format!("Metadata entry of type {} has length {}", t, len),
+            };
+            match t {
+                KEY_ID_TYPE | PARAMS_LABEL_HASH_TYPE => {
+                    if len != HASH_SIZE {
+                        return Err(wrong_length());
+                    }
+                    let mut h = [0; HASH_SIZE];
+                    h.copy_from_slice(value);
+                    if t == KEY_ID_TYPE {
+                        metadata.key_id = Some(h);
+                    } else {
+                        metadata.params_label_hash = Some(h);
+                    }
+                }
+                CREATED_AT_TYPE => {
+                    if len != 8 {
+                        return Err(wrong_length());
+                    }
+                    let mut b = [0; 8];
+                    b.copy_from_slice(value);
+                    metadata.created_at = Some(u64::from_be_bytes(b));
+                }
+                _ => (),
+            }
+        }
+        Ok(metadata)
+    }
+}
+
+impl Signature {
+    /// Format byte, `metadata` and `to_bytes`
+    pub fn to_bytes_with_metadata(&self, metadata: &Metadata) -> Vec<u8> {
+        let mut bytes = vec![SIGNATURE_FORMAT_WITH_METADATA];
+        bytes.append(&mut metadata.to_bytes());
+        bytes.append(&mut self.to_bytes());
+        bytes
+    }
+
+    pub fn from_bytes_with_metadata(bytes: &[u8]) -> Result<(Self, Metadata), PSError> {
+        let mut reader = ByteReader::new(bytes);
+        let format = reader.read_u8()?;
+        if format != SIGNATURE_FORMAT_WITH_METADATA {
+            return Err(PSError::InvalidEncoding {
+                msg: format!("Unknown signature format {}", format),
+            });
+        }
+        let metadata = Metadata::read(&mut reader)?;
+        let sig = Signature::from_bytes(reader.take(2 * SIGNATURE_GROUP_SIZE)?)?;
+        reader.finish()?;
+        Ok((sig, metadata))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::{keygen, Params};
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::FieldElementVector;
+
+    #[test]
+    fn test_metadata() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(2, &params);
+        let metadata = Metadata::for_verkey(&vk)
+            .with_created_at(1_700_000_000)
+            .with_params_label(b"test");
+        assert_eq!(metadata.key_id(), Some(&verkey_id(&vk)));
+        assert!(metadata.is_for_params_label(b"test"));
+        assert!(!metadata.is_for_params_label(b"other"));
+
+        let bytes = vk.to_versioned_bytes_with_metadata(&metadata);
+        let (vk_1, metadata_1) = Verkey::from_versioned_bytes_with_metadata(&bytes).unwrap();
+        assert_eq!((vk_1, metadata_1), (vk.clone(), metadata.clone()));
+        // The payload is the same as without metadata
+        assert!(bytes.ends_with(&vk.to_compressed_bytes()));
+        assert_eq!(Verkey::from_versioned_bytes(&bytes).unwrap(), vk);
+        let (_, none) =
+            Verkey::from_versioned_bytes_with_metadata(&vk.to_versioned_bytes()).unwrap();
+        assert_eq!(none, Metadata::new());
+
+        let msgs = FieldElementVector::random(2);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let bytes = sig.to_bytes_with_metadata(&metadata);
+        let (sig_1, metadata_2) = Signature::from_bytes_with_metadata(&bytes).unwrap();
+        assert_eq!(sig_1.to_bytes(), sig.to_bytes());
+        assert_eq!(metadata_2.created_at(), Some(1_700_000_000));
+
+        // Unknown entries are skipped, misordered ones rejected
+        let mut unknown = Metadata::new().with_created_at(5).to_bytes();
+        unknown[0] = 2;
+        unknown.extend_from_slice(&[9, 1, 0]);
+        let mut reader = ByteReader::new(&unknown);
+        assert_eq!(
+            Metadata::read(&mut reader).unwrap(),
+            Metadata::new().with_created_at(5)
+        );
+        let misordered = [2, 2, 8, 0, 0, 0, 0, 0, 0, 0, 5, 1, 0];
+        assert!(Metadata::read(&mut ByteReader::new(&misordered)).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+// Message indices across schema versions. Adding, removing or reordering attributes of a
+// `MessageSchema` moves the messages of the remaining attributes, so stored revealed index sets,
+// compiled proof requests and relations among messages refer to other attributes under the new
+// schema. A `SchemaMigration` maps the indices of an old schema to a new one, derived from the
+// attribute names or given explicitly, and translates these. Attributes dropped by the new schema
+// have no index and translating anything that refers to them is an error rather than a silent shift.
+
+use crate::errors::PSError;
+use crate::policy::CompiledPolicy;
+use crate::relations::LinearRelation;
+use crate::schema::MessageSchema;
+use amcl_wrapper::field_elem::FieldElement;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaMigration {
+    /// New index of every old index, None for dropped attributes
+    mapping: Vec<Option<usize>>,
+    new_count: usize,
+}
+
+impl SchemaMigration {
+    /// `mapping[i]` is the new index of old index `i`, new indices must be distinct and less than
+    /// `new_count`
+    pub fn new(mapping: Vec<Option<usize>>, new_count: usize) -> Result<Self, PSError> {
+        let mut seen = HashSet::new();
+        for new in mapping.iter().flatten() {
+            if *new >= new_count || !seen.insert(*new) {
+                return Err(PSError::GeneralError {
+                    msg: format!("Invalid or repeated new index {}", new),
+                });
+            }
+        }
+        Ok(Self { mapping, new_count })
+    }
+
+    /// Attributes are matched by name, `renames` lists (old name, new name) of renamed attributes
+    pub fn between(
+        from: &MessageSchema,
+        to: &MessageSchema,
+        renames: &[(&str, &str)],
+    ) -> Result<Self, PSError> {
+        let mapping = from
+            .attribute_names()
+            .iter()
+            .map(|name| {
+                let new_name = renames
+                    .iter()
+                    .find(|(old, _)| old == name)
+                    .map_or(*name, |(_, new)| *new);
+                to.index_of(new_name).ok()
+            })
+            .collect();
+        Self::new(mapping, to.len())
+    }
+
+    /// Migration back from the new schema to the old one
+    pub fn inverse(&self) -> Self {
+        let mut mapping = vec![None; self.new_count];
+        for (old, new) in self.mapping.iter().enumerate() {
+            if let Some(new) = new {
+                mapping[*new] = Some(old);
+            }
+        }
+        Self {
+            mapping,
+            new_count: self.mapping.len(),
+        }
+    }
+
+    /// Fails for indices of dropped attributes and indices outside of the old schema
+    pub fn map_index(&self, old: usize) -> Result<usize, PSError> {
+        match self.mapping.get(old) {
+            Some(Some(new)) => Ok(*new),
+            Some(None) => Err(PSError::GeneralError {
+                msg: format!("Message {} has no index in the new schema", old),
+            }),
+            None => Err(PSError::GeneralError {
+                msg: format!("Index {} should be less than {}", old, self.mapping.len()),
+            }),
+        }
+    }
+
+    pub fn revealed_indices(&self, indices: &HashSet<usize>) -> Result<HashSet<usize>, PSError> {
+        indices.iter().map(|i| self.map_index(*i)).collect()
+    }
+
+    pub fn revealed_msgs(
+        &self,
+        revealed_msgs: &HashMap<usize, FieldElement>,
+    ) -> Result<HashMap<usize, FieldElement>, PSError> {
+        revealed_msgs
+            .iter()
+            .map(|(i, m)| Ok((self.map_index(*i)?, m.clone())))
+            .collect()
+    }
+
+    /// A proof request compiled against the old schema, as if compiled against the new one
+    pub fn compiled_policy(&self, policy: &CompiledPolicy) -> Result<CompiledPolicy, PSError> {
+        policy.map_indices(|i| self.map_index(i))
+    }
+
+    pub fn relations(&self, relations: &[LinearRelation]) -> Result<Vec<LinearRelation>, PSError> {
+        relations
+            .iter()
+            .map(|relation| {
+                Ok(match relation {
+                    LinearRelation::Sum { total, a, b } => LinearRelation::Sum {
+                        total: self.map_index(*total)?,
+                        a: self.map_index(*a)?,
+                        b: self.map_index(*b)?,
+                    },
+                    LinearRelation::Scaled { m, c, m_prime } => LinearRelation::Scaled {
+                        m: self.map_index(*m)?,
+                        c: c.clone(),
+                        m_prime: self.map_index(*m_prime)?,
+                    },
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::{keygen, Params};
+    use crate::policy::Policy;
+
+    #[test]
+    fn test_schema_migration() {
+        let v1 = MessageSchema::new(&["name", "email", "dob", "fax"]).unwrap();
+        let v2 = MessageSchema::new(&["id", "date_of_birth", "name", "email"]).unwrap();
+        let migration = SchemaMigration::between(&v1, &v2, &[("dob", "date_of_birth")]).unwrap();
+        assert_eq!(migration.map_index(0).unwrap(), 2);
+        assert_eq!(migration.map_index(2).unwrap(), 1);
+        assert!(migration.map_index(3).is_err());
+        assert!(migration.map_index(4).is_err());
+
+        let revealed: HashSet<usize> = vec![1, 2].into_iter().collect();
+        let expected: HashSet<usize> = vec![3, 1].into_iter().collect();
+        assert_eq!(migration.revealed_indices(&revealed).unwrap(), expected);
+        assert_eq!(
+            migration.inverse().revealed_indices(&expected).unwrap(),
+            revealed
+        );
+        assert!(migration.inverse().map_index(0).is_err());
+        let fax: HashSet<usize> = vec![3].into_iter().collect();
+        assert!(migration.revealed_indices(&fax).is_err());
+
+        // A proof request of v1 checks the same attribute under v2
+        let params = Params::new("test".as_bytes());
+        let (_, vk) = keygen(4, &params);
+        let policy =
+            CompiledPolicy::compile(&[Policy::require_revealed("email")], &v1.attribute_names())
+                .unwrap();
+        let migrated = migration.compiled_policy(&policy).unwrap();
+        let mut revealed_msgs = HashMap::new();
+        revealed_msgs.insert(1, FieldElement::random());
+        assert!(policy.evaluate(&vk, &revealed_msgs).is_empty());
+        assert!(!migrated.evaluate(&vk, &revealed_msgs).is_empty());
+        let revealed_v2 = migration.revealed_msgs(&revealed_msgs).unwrap();
+        assert!(migrated.evaluate(&vk, &revealed_v2).is_empty());
+
+        let relations = migration
+            .relations(&[LinearRelation::Sum {
+                total: 0,
+                a: 1,
+                b: 2,
+            }])
+            .unwrap();
+        assert_eq!(relations[0].indices(), vec![2, 3, 1]);
+        assert!(SchemaMigration::new(vec![Some(0), Some(0)], 2).is_err());
+        assert!(SchemaMigration::new(vec![Some(2)], 2).is_err());
+    }
+}
@@ -0,0 +1,160 @@
+// Paper backup of an issuer's keys. A mnemonic encodes a 32 byte secret seed as 33 words, a word of
+// `WORDS` for each byte followed by a checksum word, in the style of BIP39 but with its own list of 256
+// words, so it is not compatible with BIP39 tools. No two words share their first 4 letters and those
+// are enough when importing. `keygen_from_mnemonic` derives the keys from the seed, the message count
+// and the params label, so one mnemonic gives unrelated keys for different counts or params.
+
+use crate::errors::PSError;
+use crate::keys::{Params, Sigkey, Verkey};
+use crate::utils::hash_with_domain;
+use rand::{thread_rng, CryptoRng, RngCore};
+
+pub const SEED_SIZE: usize = 32;
+pub const MNEMONIC_WORD_COUNT: usize = SEED_SIZE + 1;
+
+const CHECKSUM_DOMAIN: &[u8] = b"PS mnemonic checksum : ";
+const KEYGEN_DOMAIN: &[u8] = b"PS keygen from mnemonic : ";
+
+pub const WORDS: [&str; 256] = [
+    "acorn", "actor", "agent", "alarm", "album", "amber", "angle", "apple", "apron", "arena",
+    "armor", "arrow", "atlas", "attic", "autumn", "avocado", "bacon", "badge", "bagel", "baker",
+    "bamboo", "banjo", "barrel", "basket", "beach", "beaver", "berry", "bicycle", "biscuit",
+    "blanket", "blossom", "bottle", "bridge", "broom", "bubble", "bucket", "buffalo", "butter",
+    "cabin", "cactus", "camel", "candle", "canoe", "canyon", "carpet", "carrot", "castle",
+    "celery", "cherry", "chimney", "circle", "citrus", "clover", "cobra", "cocoa", "coffee",
+    "comet", "copper", "cotton", "coyote", "crayon", "cricket", "crystal", "daisy", "delta",
+    "desert", "diamond", "dinner", "dolphin", "donkey", "dragon", "drum", "duck", "dune", "eagle",
+    "easel", "echo", "eclipse", "elbow", "elm", "ember", "engine", "falcon", "feather", "fence",
+    "ferry", "fiddle", "flame", "flute", "forest", "fossil", "fox", "frog", "galaxy", "garden",
+    "garlic", "gecko", "ginger", "giraffe", "glacier", "glove", "goat", "gold", "gorilla", "grape",
+    "gravel", "guitar", "hammer", "harbor", "hazel", "helmet", "heron", "honey", "hornet", "husky",
+    "iceberg", "igloo", "indigo", "insect", "iris", "island", "ivory", "jacket", "jaguar", "jelly",
+    "jewel", "jigsaw", "jockey", "juice", "jungle", "kayak", "kernel", "kettle", "kitchen", "kite",
+    "kiwi", "koala", "ladder", "lagoon", "lamp", "lantern", "lava", "lemon", "leopard", "lettuce",
+    "lily", "lizard", "lobster", "lotus", "magnet", "mango", "maple", "marble", "meadow", "melon",
+    "mirror", "mitten", "monkey", "moose", "muffin", "museum", "napkin", "nectar", "needle",
+    "nest", "noodle", "nutmeg", "oasis", "ocean", "octopus", "olive", "onion", "orange", "orbit",
+    "orchid", "otter", "oven", "owl", "oyster", "palace", "panda", "parrot", "peanut", "pebble",
+    "pepper", "piano", "pigeon", "pillow", "pirate", "planet", "plum", "potato", "pumpkin",
+    "puzzle", "quail", "quartz", "quilt", "rabbit", "radish", "raven", "ribbon", "river", "robot",
+    "rocket", "rose", "ruby", "saddle", "salmon", "sandal", "satin", "saucer", "scarf", "shadow",
+    "shell", "silver", "sleigh", "slipper", "spider", "spoon", "squid", "statue", "sugar",
+    "summer", "sunset", "swan", "teapot", "tennis", "thunder", "tiger", "tomato", "torch",
+    "tractor", "trumpet", "tulip", "turtle", "unicorn", "valley", "velvet", "violin", "volcano",
+    "voyage", "wagon", "walnut", "walrus", "whale", "whistle", "willow", "window", "winter",
+    "wizard", "wolf", "yacht", "yellow", "yogurt", "zebra", "zipper",
+];
+
+pub fn generate_mnemonic() -> String {
+    generate_mnemonic_with_rng(&mut thread_rng())
+}
+
+pub fn generate_mnemonic_with_rng<R: RngCore + CryptoRng>(rng: &mut R) -> String {
+    let mut seed = [0; SEED_SIZE];
+    rng.fill_bytes(&mut seed);
+    seed_to_mnemonic(&seed)
+}
+
+pub fn seed_to_mnemonic(seed: &[u8; SEED_SIZE]) -> String {
+    let checksum = hash_with_domain(CHECKSUM_DOMAIN, seed)[0];
+    seed.iter()
+        .chain(std::iter::once(&checksum))
+        .map(|b| WORDS[*b as usize])
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Words are separated by whitespace and case is ignored
+pub fn mnemonic_to_seed(mnemonic: &str) -> Result<[u8; SEED_SIZE], PSError> {
+    let words: Vec<String> = mnemonic
+        .split_whitespace()
+        .map(|w| w.to_lowercase())
+        .collect();
+    if words.len() != MNEMONIC_WORD_COUNT {
+        return Err(PSError::InvalidEncoding {
+            msg: format!(
+                "Mnemonic needs {} words but given {}",
+                MNEMONIC_WORD_COUNT,
+                words.len()
+            ),
+        });
+    }
+    let mut bytes = vec![];
+    for word in &words {
+        match WORDS
+            .iter()
+            .position(|w| w == word || (word.len() >= 4 && w.starts_with(word.as_str())))
+        {
+            Some(i) => bytes.push(i as u8),
+            None => {
+                return Err(PSError::InvalidEncoding {
+                    msg: format!("Unknown mnemonic word {}", word),
+                })
+            }
+        }
+    }
+    let mut seed = [0; SEED_SIZE];
+    seed.copy_from_slice(&bytes[..SEED_SIZE]);
+    if hash_with_domain(CHECKSUM_DOMAIN, &seed)[0] != bytes[SEED_SIZE] {
+        return Err(PSError::InvalidEncoding {
+            msg: String::from("Mnemonic checksum does not match"),
+        });
+    }
+    Ok(seed)
+}
+
+/// Keys for `count_messages` messages from `mnemonic`. `label` is the one given to `Params::new`.
+pub fn keygen_from_mnemonic(
+    mnemonic: &str,
+    count_messages: usize,
+    label: &[u8],
+) -> Result<(Sigkey, Verkey), PSError> {
+    let mut bytes = mnemonic_to_seed(mnemonic)?.to_vec();
+    bytes.extend_from_slice(&(count_messages as u64).to_be_bytes());
+    bytes.extend_from_slice(label);
+    let sigkey = Sigkey::from_seed(&hash_with_domain(KEYGEN_DOMAIN, &bytes), count_messages)?;
+    let verkey = Verkey::from_sigkey(&sigkey, count_messages, &Params::new(label))?;
+    Ok((sigkey, verkey))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mnemonic_keygen() {
+        assert!(mnemonic_to_seed(&generate_mnemonic()).is_ok());
+        let mut seed = [0; SEED_SIZE];
+        for (i, b) in seed.iter_mut().enumerate() {
+            *b = i as u8 * 7;
+        }
+        let mnemonic = seed_to_mnemonic(&seed);
+        let (sk, vk) = keygen_from_mnemonic(&mnemonic, 3, b"test").unwrap();
+        let (sk_1, vk_1) = keygen_from_mnemonic(&mnemonic.to_uppercase(), 3, b"test").unwrap();
+        assert_eq!(sk.to_versioned_bytes(), sk_1.to_versioned_bytes());
+        assert_eq!(vk, vk_1);
+        // Count and label are part of the derivation
+        let (sk_2, _) = keygen_from_mnemonic(&mnemonic, 4, b"test").unwrap();
+        assert_ne!(sk_2.x, sk.x);
+        let (_, vk_3) = keygen_from_mnemonic(&mnemonic, 3, b"other").unwrap();
+        assert_ne!(vk_3.X_tilde, vk.X_tilde);
+
+        // The first 4 letters of each word suffice
+        let short: Vec<String> = mnemonic
+            .split(' ')
+            .map(|w| w.chars().take(4).collect())
+            .collect();
+        assert_eq!(
+            mnemonic_to_seed(&short.join(" ")).unwrap(),
+            mnemonic_to_seed(&mnemonic).unwrap()
+        );
+
+        // A swapped or missing word is detected
+        let mut words: Vec<&str> = mnemonic.split(' ').collect();
+        words.swap(0, 1);
+        assert!(mnemonic_to_seed(&words.join(" ")).is_err());
+        assert!(mnemonic_to_seed(&words[1..].join(" ")).is_err());
+        words[0] = "xylophone";
+        assert!(mnemonic_to_seed(&words.join(" ")).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+// Modified scheme defined in section 6.1. The signer picks a random m' for every signature and signs
+// (m_1, m_2, ..., m_r, m') with a key for r+1 messages, m' is part of the signature. Its security relies
+// on a non-interactive assumption rather than the interactive one of the scheme from section 4.2.
+//
+// A modified signature on r messages is exactly a signature of section 4.2 on r+1 messages with m'
+// as the last message, so it can be converted with `ModifiedSignature::to_basic` and then used with
+// everything that works with `Signature`, like `PoKOfSignature` where m' stays hidden.
+
+use crate::errors::PSError;
+use crate::keys::{keygen_with_rng, Params, Sigkey, Verkey};
+use crate::signature::Signature;
+use crate::signer::PsSigner;
+use crate::validation::deserialize_point;
+use crate::SignatureGroup;
+use amcl_wrapper::field_elem::FieldElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ModifiedSignature {
+    pub m_prime: FieldElement,
+    #[serde(deserialize_with = "deserialize_point")]
+    pub sigma_1: SignatureGroup,
+    #[serde(deserialize_with = "deserialize_point")]
+    pub sigma_2: SignatureGroup,
+}
+
+/// Generate keys for signing `count_messages` messages with the modified scheme. The keys have an
+/// extra element for m'.
+pub fn modified_keygen(count_messages: usize, params: &Params) -> (Sigkey, Verkey) {
+    modified_keygen_with_rng(count_messages, params, &mut thread_rng())
+}
+
+pub fn modified_keygen_with_rng<R: RngCore + CryptoRng>(
+    count_messages: usize,
+    params: &Params,
+    rng: &mut R,
+) -> (Sigkey, Verkey) {
+    keygen_with_rng(count_messages + 1, params, rng)
+}
+
+impl ModifiedSignature {
+    pub fn new<S: PsSigner + ?Sized>(
+        messages: &[FieldElement],
+        signer: &S,
+        params: &Params,
+    ) -> Result<Self, PSError> {
+        Self::new_with_rng(messages, signer, params, &mut thread_rng())
+    }
+
+    pub fn new_with_rng<S: PsSigner + ?Sized, R: RngCore + CryptoRng>(
+        messages: &[FieldElement],
+        signer: &S,
+        params: &Params,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        if messages.len() + 1 != signer.msg_count() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: signer.msg_count().saturating_sub(1),
+                given: messages.len(),
+            });
+        }
+        let m_prime = FieldElement::random_using_rng(rng);
+        let mut msgs = messages.to_vec();
+        msgs.push(m_prime.clone());
+        let sig = signer.sign_with_rng(&msgs, params, rng)?;
+        Ok(Self {
+            m_prime,
+            sigma_1: sig.sigma_1,
+            sigma_2: sig.sigma_2,
+        })
+    }
+
+    pub fn verify(
+        &self,
+        messages: &[FieldElement],
+        vk: &Verkey,
+        params: &Params,
+    ) -> Result<bool, PSError> {
+        if messages.len() + 1 != vk.Y_tilde.len() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: vk.Y_tilde.len().saturating_sub(1),
+                given: messages.len(),
+            });
+        }
+        let (sig, m_prime) = self.to_basic();
+        sig.verify_from_iter(messages.iter().chain(Some(&m_prime)), vk, params)
+    }
+
+    /// The signature of section 4.2 on `messages` followed by m'
+    pub fn to_basic(&self) -> (Signature, FieldElement) {
+        (
+            Signature {
+                sigma_1: self.sigma_1.clone(),
+                sigma_2: self.sigma_2.clone(),
+            },
+            self.m_prime.clone(),
+        )
+    }
+
+    /// Randomize the signature as described in section 6.1, (m', sigma_1^t, sigma_2^t) for random t
+    pub fn randomize<R: RngCore + CryptoRng>(&self, rng: &mut R) -> Self {
+        let t = FieldElement::random_using_rng(rng);
+        Self {
+            m_prime: self.m_prime.clone(),
+            sigma_1: &self.sigma_1 * &t,
+            sigma_2: &self.sigma_2 * &t,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pok_sig::PoKOfSignature;
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use amcl_wrapper::group_elem::GroupElement;
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn test_modified_signature() {
+        let count_msgs = 4;
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = modified_keygen(count_msgs, &params);
+        assert_eq!(vk.Y_tilde.len(), count_msgs + 1);
+        let msgs = FieldElementVector::random(count_msgs);
+        let sig = ModifiedSignature::new(msgs.as_slice(), &sk, &params).unwrap();
+        assert!(sig.verify(msgs.as_slice(), &vk, &params).unwrap());
+        assert!(sig
+            .randomize(&mut thread_rng())
+            .verify(msgs.as_slice(), &vk, &params)
+            .unwrap());
+
+        let mut msgs_1 = msgs.as_slice().to_vec();
+        msgs_1[1] = FieldElement::random();
+        assert!(!sig.verify(&msgs_1, &vk, &params).unwrap());
+        let mut sig_1 = sig.clone();
+        sig_1.m_prime = FieldElement::random();
+        assert!(!sig_1.verify(msgs.as_slice(), &vk, &params).unwrap());
+        assert!(ModifiedSignature::new(&msgs_1[1..], &sk, &params).is_err());
+        assert!(sig.verify(&msgs_1[1..], &vk, &params).is_err());
+
+        // Prove knowledge of the basic signature with m' hidden
+        let (basic, m_prime) = sig.to_basic();
+        let mut all_msgs = msgs.as_slice().to_vec();
+        all_msgs.push(m_prime);
+        assert!(basic
+            .verify(&Message::from_field_elements(&all_msgs), &vk, &params)
+            .unwrap());
+        let mut revealed = HashSet::new();
+        revealed.insert(0);
+        let pok = PoKOfSignature::init(
+            &basic,
+            &vk,
+            &params,
+            &Message::from_field_elements(&all_msgs),
+            None,
+            revealed,
+        )
+        .unwrap();
+        let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
+        let proof = pok.gen_proof(&chal).unwrap();
+        let mut revealed_msgs = HashMap::new();
+        revealed_msgs.insert(0, msgs[0].clone());
+        assert!(proof.verify(&vk, &params, revealed_msgs, &chal).unwrap());
+    }
+
+    #[test]
+    fn test_modified_signature_deserialize_identity() {
+        let params = Params::new("test".as_bytes());
+        let (sk, _) = modified_keygen(2, &params);
+        let mut sig =
+            ModifiedSignature::new(FieldElementVector::random(2).as_slice(), &sk, &params).unwrap();
+        let json = serde_json::to_string(&sig).unwrap();
+        assert!(serde_json::from_str::<ModifiedSignature>(&json).is_ok());
+        sig.sigma_1 = SignatureGroup::identity();
+        let json = serde_json::to_string(&sig).unwrap();
+        assert!(serde_json::from_str::<ModifiedSignature>(&json).is_err());
+    }
+}
@@ -0,0 +1,369 @@
+// Presentations spanning several credentials, possibly from different issuers. Each credential gets
+// its own proof of knowledge of signature but all share one challenge over all proofs, the revealed
+// messages, the equalities and the nonce. Hidden messages proven equal across credentials, e.g. a link
+// secret carried by all credentials of a holder, share their blinding and hence their response, like
+// in `LinkedPresentation`.
+//
+// With feature `rayon` the per-credential commitments, responses and verifications run in parallel.
+// Only what depends on all credentials is serial: the shared blindings, the challenge and the check of
+// the equalities. The caller's RNG is not shared across threads, each credential gets its own RNG
+// seeded from it in input order, so the output for a seeded RNG does not depend on the feature.
+
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::limits::deserialize_revealed_vec;
+use crate::pok_sig::{PoKOfSignature, PoKOfSignatureProof};
+use crate::signature::Signature;
+use crate::types::{Challenge, Message};
+use amcl_wrapper::field_elem::FieldElement;
+use rand::rngs::StdRng;
+use rand::{thread_rng, CryptoRng, RngCore, SeedableRng};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+const DOMAIN: &[u8] = b"PS multi-credential presentation";
+
+/// Message `.1` of credential `.0`
+pub type MessageRef = (usize, usize);
+
+/// A credential of the holder to present
+#[derive(Clone, Debug)]
+pub struct CredentialInput<'a> {
+    pub sig: &'a Signature,
+    pub vk: &'a Verkey,
+    pub messages: &'a [FieldElement],
+    pub revealed_msg_indices: HashSet<usize>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MultiCredentialPresentation {
+    /// One proof per credential, in the order of the credentials
+    pub proofs: Vec<PoKOfSignatureProof>,
+    #[serde(deserialize_with = "deserialize_revealed_vec")]
+    pub revealed_msgs: Vec<HashMap<usize, FieldElement>>,
+    /// Classes of hidden messages proven equal
+    pub equalities: Vec<Vec<MessageRef>>,
+    pub nonce: Vec<u8>,
+}
+
+impl MultiCredentialPresentation {
+    pub fn new(
+        credentials: &[CredentialInput],
+        params: &Params,
+        equalities: &[Vec<MessageRef>],
+        nonce: &[u8],
+    ) -> Result<Self, PSError> {
+        Self::new_with_rng(credentials, params, equalities, nonce, &mut thread_rng())
+    }
+
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        credentials: &[CredentialInput],
+        params: &Params,
+        equalities: &[Vec<MessageRef>],
+        nonce: &[u8],
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        if credentials.is_empty() {
+            return Err(PSError::GeneralError {
+                msg: String::from("No credentials to present"),
+            });
+        }
+        let mut shared = HashMap::new();
+        for class in equalities {
+            let blinding = FieldElement::random_using_rng(rng);
+            for (c, i) in class {
+                let msg = credentials
+                    .get(*c)
+                    .filter(|cred| {
+                        !cred.revealed_msg_indices.contains(i) && *i < cred.messages.len()
+                    })
+                    .map(|cred| &cred.messages[*i])
+                    .ok_or_else(|| PSError::GeneralError {
+                        msg: format!(
+                            "Message {} of credential {} should be a hidden message",
+                            i, c
+                        ),
+                    })?;
+                if *msg != credentials[class[0].0].messages[class[0].1] {
+                    return Err(PSError::GeneralError {
+                        msg: format!("Message {} of credential {} differs from its class", i, c),
+                    });
+                }
+                if shared.insert((*c, *i), blinding.clone()).is_some() {
+                    return Err(PSError::GeneralError {
+                        msg: format!("Message {} of credential {} is in several classes", i, c),
+                    });
+                }
+            }
+        }
+
+        // Everything drawn from `rng` is drawn here, in the order of the credentials
+        let mut inputs = Vec::with_capacity(credentials.len());
+        for (c, cred) in credentials.iter().enumerate() {
+            let blindings: Vec<FieldElement> = (0..cred.messages.len())
+                .filter(|i| !cred.revealed_msg_indices.contains(i))
+                .map(|i| match shared.get(&(c, i)) {
+                    Some(b) => b.clone(),
+                    None => FieldElement::random_using_rng(rng),
+                })
+                .collect();
+            let cred_rng = StdRng::from_rng(&mut *rng).map_err(|e| PSError::GeneralError {
+                msg: format!("Cannot seed RNG: {}", e),
+            })?;
+            inputs.push((cred, blindings, cred_rng));
+        }
+
+        let init =
+            |(cred, blindings, mut cred_rng): (&CredentialInput, Vec<FieldElement>, StdRng)| {
+                PoKOfSignature::init_with_rng(
+                    cred.sig,
+                    cred.vk,
+                    params,
+                    &Message::from_field_elements(cred.messages),
+                    Some(&blindings),
+                    cred.revealed_msg_indices.clone(),
+                    &mut cred_rng,
+                )
+            };
+        #[cfg(feature = "rayon")]
+        let poks: Vec<PoKOfSignature> =
+            inputs.into_par_iter().map(init).collect::<Result<_, _>>()?;
+        #[cfg(not(feature = "rayon"))]
+        let poks: Vec<PoKOfSignature> = inputs.into_iter().map(init).collect::<Result<_, _>>()?;
+
+        let revealed_msgs: Vec<HashMap<usize, FieldElement>> = credentials
+            .iter()
+            .map(|cred| {
+                cred.revealed_msg_indices
+                    .iter()
+                    .filter(|i| **i < cred.messages.len())
+                    .map(|i| (*i, cred.messages[*i].clone()))
+                    .collect()
+            })
+            .collect();
+        let pok_bytes: Vec<Vec<u8>> = poks.iter().map(|pok| pok.to_bytes()).collect();
+        let challenge = Self::compute_challenge(&pok_bytes, &revealed_msgs, equalities, nonce);
+
+        let gen_proof = |pok: PoKOfSignature| pok.gen_proof(&challenge);
+        #[cfg(feature = "rayon")]
+        let proofs = poks
+            .into_par_iter()
+            .map(gen_proof)
+            .collect::<Result<_, _>>()?;
+        #[cfg(not(feature = "rayon"))]
+        let proofs = poks.into_iter().map(gen_proof).collect::<Result<_, _>>()?;
+        Ok(Self {
+            proofs,
+            revealed_msgs,
+            equalities: equalities.to_vec(),
+            nonce: nonce.to_vec(),
+        })
+    }
+
+    fn compute_challenge(
+        pok_bytes: &[Vec<u8>],
+        revealed_msgs: &[HashMap<usize, FieldElement>],
+        equalities: &[Vec<MessageRef>],
+        nonce: &[u8],
+    ) -> Challenge {
+        let mut bytes = DOMAIN.to_vec();
+        for (b, revealed) in pok_bytes.iter().zip(revealed_msgs) {
+            bytes.extend_from_slice(b);
+            let mut revealed: Vec<_> = revealed.iter().collect();
+            revealed.sort_by_key(|(i, _)| **i);
+            bytes.extend_from_slice(&(revealed.len() as u64).to_be_bytes());
+            for (i, m) in revealed {
+                bytes.extend_from_slice(&(*i as u64).to_be_bytes());
+                bytes.append(&mut m.to_bytes());
+            }
+        }
+        for class in equalities {
+            bytes.extend_from_slice(&(class.len() as u64).to_be_bytes());
+            for (c, i) in class {
+                bytes.extend_from_slice(&(*c as u64).to_be_bytes());
+                bytes.extend_from_slice(&(*i as u64).to_be_bytes());
+            }
+        }
+        bytes.extend_from_slice(nonce);
+        Challenge::from_msg_hash(&bytes)
+    }
+
+    /// Verify all proofs, credential i under `vks[i]`, and the equalities
+    pub fn verify(&self, vks: &[Verkey], params: &Params) -> Result<bool, PSError> {
+        if self.proofs.len() != vks.len() || self.revealed_msgs.len() != vks.len() {
+            return Ok(false);
+        }
+        let revealed_indices: Vec<HashSet<usize>> = self
+            .revealed_msgs
+            .iter()
+            .map(|r| r.keys().cloned().collect())
+            .collect();
+        for (indices, vk) in revealed_indices.iter().zip(vks) {
+            if indices.iter().any(|i| *i >= vk.Y_tilde.len()) {
+                return Ok(false);
+            }
+        }
+        let pok_bytes: Vec<Vec<u8>> = self
+            .proofs
+            .iter()
+            .zip(revealed_indices.iter().zip(vks))
+            .map(|(proof, (indices, vk))| proof.get_bytes_for_challenge(indices, vk, params))
+            .collect();
+        let challenge = Self::compute_challenge(
+            &pok_bytes,
+            &self.revealed_msgs,
+            &self.equalities,
+            &self.nonce,
+        );
+
+        // Equal messages have equal responses
+        for class in &self.equalities {
+            let mut expected = None;
+            for (c, i) in class {
+                let resp = match self
+                    .proofs
+                    .get(*c)
+                    .map(|proof| proof.get_resp_for_message(*i, &revealed_indices[*c]))
+                {
+                    Some(Ok(r)) => r,
+                    _ => return Ok(false),
+                };
+                match &expected {
+                    None => expected = Some(resp),
+                    Some(e) if *e != resp => return Ok(false),
+                    _ => (),
+                }
+            }
+        }
+
+        let verify = |((proof, revealed), vk): (
+            (&PoKOfSignatureProof, &HashMap<usize, FieldElement>),
+            &Verkey,
+        )| proof.verify(vk, params, revealed.clone(), &challenge);
+        #[cfg(feature = "rayon")]
+        let results: Vec<Result<bool, PSError>> = self
+            .proofs
+            .par_iter()
+            .zip(self.revealed_msgs.par_iter())
+            .zip(vks.par_iter())
+            .map(verify)
+            .collect();
+        #[cfg(not(feature = "rayon"))]
+        let results: Vec<Result<bool, PSError>> = self
+            .proofs
+            .iter()
+            .zip(self.revealed_msgs.iter())
+            .zip(vks.iter())
+            .map(verify)
+            .collect();
+        for r in results {
+            if !r? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use amcl_wrapper::field_elem::FieldElementVector;
+
+    #[test]
+    fn test_multi_credential_presentation() {
+        let params = Params::new("test".as_bytes());
+        let link_secret = FieldElement::random();
+        let mut sigs = vec![];
+        let mut vks = vec![];
+        let mut msgs = vec![];
+        for count in 2..8 {
+            let (sk, vk) = keygen(count, &params);
+            let mut m = FieldElementVector::random(count - 1);
+            m.insert(0, link_secret.clone());
+            sigs.push(
+                Signature::new(&Message::from_field_elements(m.as_slice()), &sk, &params).unwrap(),
+            );
+            vks.push(vk);
+            msgs.push(m);
+        }
+        let credentials: Vec<CredentialInput> = (0..6)
+            .map(|c| CredentialInput {
+                sig: &sigs[c],
+                vk: &vks[c],
+                messages: msgs[c].as_slice(),
+                revealed_msg_indices: vec![1].into_iter().collect(),
+            })
+            .collect();
+        let link: Vec<Vec<MessageRef>> = vec![(0..6).map(|c| (c, 0)).collect()];
+        let presentation =
+            MultiCredentialPresentation::new(&credentials, &params, &link, b"nonce").unwrap();
+        assert_eq!(presentation.proofs.len(), 6);
+        assert!(presentation.verify(&vks, &params).unwrap());
+        assert!(!presentation.verify(&vks[1..], &params).unwrap());
+
+        let mut vks_swapped = vks.clone();
+        vks_swapped.swap(2, 3);
+        assert!(!presentation.verify(&vks_swapped, &params).unwrap());
+
+        // The claimed equalities are bound to the challenge
+        let mut tampered = presentation.clone();
+        tampered.equalities.clear();
+        assert!(!tampered.verify(&vks, &params).unwrap());
+        let mut tampered = presentation.clone();
+        tampered.revealed_msgs[4].insert(1, FieldElement::random());
+        assert!(!tampered.verify(&vks, &params).unwrap());
+
+        // Unequal or revealed messages cannot be claimed equal
+        assert!(MultiCredentialPresentation::new(
+            &credentials,
+            &params,
+            &[vec![(0, 0), (1, 1)]],
+            b"nonce"
+        )
+        .is_err());
+        assert!(MultiCredentialPresentation::new(
+            &credentials,
+            &params,
+            &[vec![(0, 0), (1, 2)]],
+            b"nonce"
+        )
+        .is_err());
+        assert!(MultiCredentialPresentation::new(
+            &credentials,
+            &params,
+            &[link[0].clone(), vec![(0, 0)]],
+            b"nonce"
+        )
+        .is_err());
+
+        // Credentials with different link secrets are presented without the equality, which cannot be
+        // added afterwards
+        let mut other = FieldElementVector::random(1);
+        other.insert(0, FieldElement::random());
+        let (sk, vk) = keygen(2, &params);
+        let sig = Signature::new(
+            &Message::from_field_elements(other.as_slice()),
+            &sk,
+            &params,
+        )
+        .unwrap();
+        let mut mixed = credentials.clone();
+        mixed[0] = CredentialInput {
+            sig: &sig,
+            vk: &vk,
+            messages: other.as_slice(),
+            revealed_msg_indices: HashSet::new(),
+        };
+        let presentation =
+            MultiCredentialPresentation::new(&mixed, &params, &[], b"nonce").unwrap();
+        let mut mixed_vks = vks.clone();
+        mixed_vks[0] = vk;
+        assert!(presentation.verify(&mixed_vks, &params).unwrap());
+        let mut forged = presentation.clone();
+        forged.equalities = link;
+        assert!(!forged.verify(&mixed_vks, &params).unwrap());
+    }
+}
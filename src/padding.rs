@@ -0,0 +1,194 @@
+// Signing fewer messages than a key supports, so one issuer key for N attributes serves schema
+// versions with M < N attributes. The M messages are followed by N - M zero messages, the padding,
+// and the signature is an ordinary signature over the N messages. Proofs reveal the padding as zeros
+// and verifiers given the schema's message count insist on it, so a padded credential cannot pass as
+// one of a schema with more attributes unless those attributes are 0.
+
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::pok_sig::{PoKOfSignature, PoKOfSignatureProof};
+use crate::signature::Signature;
+use crate::signer::PsSigner;
+use crate::types::{Challenge, Message};
+use amcl_wrapper::field_elem::FieldElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+/// `messages` followed by zeros up to `capacity` messages
+pub fn pad_messages(
+    messages: &[FieldElement],
+    capacity: usize,
+) -> Result<Vec<FieldElement>, PSError> {
+    if messages.len() > capacity {
+        return Err(PSError::UnsupportedNoOfMessages {
+            expected: capacity,
+            given: messages.len(),
+        });
+    }
+    let mut padded = messages.to_vec();
+    padded.resize(capacity, FieldElement::zero());
+    Ok(padded)
+}
+
+/// Indices of the padding of `msg_count` messages under a key for `capacity` messages
+pub fn padding_indices(msg_count: usize, capacity: usize) -> Range<usize> {
+    msg_count.min(capacity)..capacity
+}
+
+impl Signature {
+    /// Sign `messages` with a key for at least as many messages, padding with zeros
+    pub fn new_padded<S: PsSigner + ?Sized>(
+        messages: &[FieldElement],
+        signer: &S,
+        params: &Params,
+    ) -> Result<Self, PSError> {
+        Self::new_padded_with_rng(messages, signer, params, &mut thread_rng())
+    }
+
+    pub fn new_padded_with_rng<S: PsSigner + ?Sized, R: RngCore + CryptoRng>(
+        messages: &[FieldElement],
+        signer: &S,
+        params: &Params,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        signer.sign_with_rng(&pad_messages(messages, signer.msg_count())?, params, rng)
+    }
+
+    /// Verify a signature created with `new_padded` on `messages`
+    pub fn verify_padded(
+        &self,
+        messages: &[FieldElement],
+        vk: &Verkey,
+        params: &Params,
+    ) -> Result<bool, PSError> {
+        let padded = pad_messages(messages, vk.Y_tilde.len())?;
+        self.verify(&Message::from_field_elements(&padded), vk, params)
+    }
+}
+
+impl PoKOfSignature {
+    /// Same as `init` for a signature created with `Signature::new_padded` on `messages`. The padding
+    /// is revealed in addition to `revealed_msg_indices`, `blindings` are for the hidden messages of
+    /// `messages` only.
+    pub fn init_padded(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[FieldElement],
+        blindings: Option<&[FieldElement]>,
+        revealed_msg_indices: HashSet<usize>,
+    ) -> Result<Self, PSError> {
+        Self::init_padded_with_rng(
+            sig,
+            vk,
+            params,
+            messages,
+            blindings,
+            revealed_msg_indices,
+            &mut thread_rng(),
+        )
+    }
+
+    pub fn init_padded_with_rng<R: RngCore + CryptoRng>(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[FieldElement],
+        blindings: Option<&[FieldElement]>,
+        mut revealed_msg_indices: HashSet<usize>,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        if let Some(idx) = revealed_msg_indices.iter().find(|i| **i >= messages.len()) {
+            return Err(PSError::GeneralError {
+                msg: format!("Index {} should be less than {}", idx, messages.len()),
+            });
+        }
+        let padded = pad_messages(messages, vk.Y_tilde.len())?;
+        revealed_msg_indices.extend(padding_indices(messages.len(), vk.Y_tilde.len()));
+        Self::init_with_rng(
+            sig,
+            vk,
+            params,
+            &Message::from_field_elements(&padded),
+            blindings,
+            revealed_msg_indices,
+            rng,
+        )
+    }
+}
+
+impl PoKOfSignatureProof {
+    /// Verify a proof created with `PoKOfSignature::init_padded` for `msg_count` messages.
+    /// `revealed_msgs` are the revealed messages among the first `msg_count`, the padding is taken
+    /// to be zero.
+    pub fn verify_padded(
+        &self,
+        vk: &Verkey,
+        params: &Params,
+        mut revealed_msgs: HashMap<usize, FieldElement>,
+        challenge: &Challenge,
+        msg_count: usize,
+    ) -> Result<bool, PSError> {
+        if msg_count > vk.Y_tilde.len() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: vk.Y_tilde.len(),
+                given: msg_count,
+            });
+        }
+        if revealed_msgs.keys().any(|i| *i >= msg_count) {
+            return Ok(false);
+        }
+        for i in padding_indices(msg_count, vk.Y_tilde.len()) {
+            revealed_msgs.insert(i, FieldElement::zero());
+        }
+        self.verify(vk, params, revealed_msgs, challenge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use amcl_wrapper::field_elem::FieldElementVector;
+
+    #[test]
+    fn test_padded_messages() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(5, &params);
+        let msgs = FieldElementVector::random(3);
+        let sig = Signature::new_padded(msgs.as_slice(), &sk, &params).unwrap();
+        assert!(sig.verify_padded(msgs.as_slice(), &vk, &params).unwrap());
+        assert!(!sig
+            .verify_padded(&msgs.as_slice()[..2], &vk, &params)
+            .unwrap());
+        assert!(
+            Signature::new_padded(FieldElementVector::random(6).as_slice(), &sk, &params).is_err()
+        );
+
+        let mut revealed = HashSet::new();
+        revealed.insert(1);
+        let pok = PoKOfSignature::init_padded(&sig, &vk, &params, msgs.as_slice(), None, revealed)
+            .unwrap();
+        let chal = pok.pok_vc.gen_challenge(vec![]);
+        let proof = pok.gen_proof(&chal).unwrap();
+        let mut revealed_msgs = HashMap::new();
+        revealed_msgs.insert(1, msgs[1].clone());
+        assert!(proof
+            .verify_padded(&vk, &params, revealed_msgs.clone(), &chal, 3)
+            .unwrap());
+        // Claiming a schema with 2 attributes fails since the 3rd is not 0, claiming one with 4 fails
+        // since the proof reveals the 4th
+        assert!(!proof
+            .verify_padded(&vk, &params, revealed_msgs.clone(), &chal, 2)
+            .unwrap());
+        assert!(!proof
+            .verify_padded(&vk, &params, revealed_msgs.clone(), &chal, 4)
+            .unwrap_or(false));
+        // Revealed messages beyond the schema's are rejected
+        revealed_msgs.insert(4, FieldElement::zero());
+        assert!(!proof
+            .verify_padded(&vk, &params, revealed_msgs, &chal, 3)
+            .unwrap());
+    }
+}
@@ -2,13 +2,22 @@
 
 use crate::errors::PSError;
 use crate::keys::{Params, Verkey};
-use crate::signature::Signature;
-use crate::blind_signature::{BlindingKey, BlindSignature};
-use crate::{ate_2_pairing, OtherGroup, OtherGroupVec, SignatureGroup, SignatureGroupVec};
+use crate::limits::DecodeLimits;
+use crate::policy::{CompiledPolicy, PolicyReport};
+use crate::precomputed::PrecomputedVerkey;
+use crate::relations::{blindings_for_relations, LinearRelation};
+use crate::signature::{Fingerprint, Signature};
+use crate::transcript::{ChallengeContributor, Transcript};
+use crate::types::{Challenge, Message, ProofResponse};
+use crate::utils::{hash_with_domain, ByteReader, MultiScalarMul};
+use crate::validation::{deserialize_point, ValidationProfile};
+use crate::{
+    ate_2_pairing, OtherGroup, OtherGroupVec, FIELD_ELEMENT_SIZE, OTHER_GROUP_SIZE,
+    SIGNATURE_GROUP_SIZE,
+};
 use amcl_wrapper::field_elem::{FieldElement, FieldElementVector};
 use amcl_wrapper::group_elem::{GroupElement, GroupElementVector};
-use amcl_wrapper::group_elem_g1::{G1Vector, G1};
-use amcl_wrapper::group_elem_g2::{G2Vector, G2};
+use rand::{thread_rng, CryptoRng, RngCore};
 use std::collections::{HashMap, HashSet};
 
 // Implement proof of knowledge of committed values in a vector commitment for `SignatureGroup`
@@ -37,6 +46,7 @@ then add the revealed values (raised to the respective generators) to get a fina
 pub struct PoKOfSignature {
     pub secrets: FieldElementVector,
     pub sig: Signature,
+    #[serde(deserialize_with = "deserialize_point")]
     pub J: OtherGroup,
     pub pok_vc: ProverCommittedOtherGroup,
 }
@@ -44,19 +54,83 @@ pub struct PoKOfSignature {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PoKOfSignatureProof {
     pub sig: Signature,
+    #[serde(deserialize_with = "deserialize_point")]
     pub J: OtherGroup,
     pub proof_vc: ProofOtherGroup,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VerificationPhase {
+    /// Proof of knowledge of the messages in J, `PoKOfSignatureProof::verify_schnorr`
+    Schnorr,
+    /// `PoKOfSignatureProof::verify_pairing`
+    Pairing,
+}
+
+/// Result of one phase of a split verification, bound to the proof, verkey and revealed messages it
+/// was computed for so outcomes of different proofs cannot be combined
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseOutcome {
+    pub phase: VerificationPhase,
+    pub inputs: Fingerprint,
+    pub passed: bool,
+}
+
 impl PoKOfSignature {
     /// Section 6.2 of paper
     pub fn init(
         sig: &Signature,
         vk: &Verkey,
         params: &Params,
-        messages: &[FieldElement],
+        messages: &[Message],
+        blindings: Option<&[FieldElement]>,
+        revealed_msg_indices: HashSet<usize>,
+    ) -> Result<Self, PSError> {
+        Self::init_with_rng(
+            sig,
+            vk,
+            params,
+            messages,
+            blindings,
+            revealed_msg_indices,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Same as `init` but all randomness, including blindings not supplied by the caller, comes from `rng`
+    pub fn init_with_rng<R: RngCore + CryptoRng>(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[Message],
+        blindings: Option<&[FieldElement]>,
+        revealed_msg_indices: HashSet<usize>,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        Self::init_with_tables(
+            sig,
+            vk,
+            params,
+            messages,
+            blindings,
+            revealed_msg_indices,
+            None,
+            rng,
+        )
+    }
+
+    /// With `tables` of `vk`, J and the commitment are computed from fixed-base tables instead of
+    /// multi-scalar multiplications
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn init_with_tables<R: RngCore + CryptoRng>(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[Message],
         blindings: Option<&[FieldElement]>,
         revealed_msg_indices: HashSet<usize>,
+        tables: Option<&PrecomputedVerkey>,
+        rng: &mut R,
     ) -> Result<Self, PSError> {
         for idx in &revealed_msg_indices {
             if *idx >= messages.len() {
@@ -66,26 +140,28 @@ impl PoKOfSignature {
             }
         }
         Signature::check_verkey_and_messages_compat(messages, vk)?;
-        let mut blindings: Vec<Option<&FieldElement>> = match blindings {
+        if sig.sigma_1.is_identity() || sig.sigma_2.is_identity() {
+            return Err(PSError::GeneralError {
+                msg: String::from("Signature has identity elements"),
+            });
+        }
+        let mut blindings: Vec<FieldElement> = match blindings {
             Some(b) => {
                 if (messages.len() - revealed_msg_indices.len()) != b.len() {
-                    return Err(PSError::GeneralError {
-                        msg: format!(
-                            "No of blindings {} not equal to number of hidden messages {}",
-                            b.len(),
-                            (messages.len() - revealed_msg_indices.len())
-                        ),
+                    return Err(PSError::MismatchedWitnessCount {
+                        expected: messages.len() - revealed_msg_indices.len(),
+                        got: b.len(),
                     });
                 }
-                b.iter().map(Some).collect()
+                b.to_vec()
             }
             None => (0..(messages.len() - revealed_msg_indices.len()))
-                .map(|_| None)
+                .map(|_| FieldElement::random_using_rng(rng))
                 .collect(),
         };
 
-        let r = FieldElement::random();
-        let t = FieldElement::random();
+        let r = Self::non_zero_random(rng);
+        let t = Self::non_zero_random(rng);
 
         // Transform signature to an aggregate signature on (messages, t)
         let sigma_prime_1 = &sig.sigma_1 * &r;
@@ -95,26 +171,39 @@ impl PoKOfSignature {
         let hidden_msg_count = vk.Y_tilde.len() - revealed_msg_indices.len() + 1;
         let mut bases = OtherGroupVec::with_capacity(hidden_msg_count);
         let mut exponents = FieldElementVector::with_capacity(hidden_msg_count);
+        let mut hidden_msg_indices = Vec::with_capacity(hidden_msg_count - 1);
         bases.push(params.g_tilde.clone());
         exponents.push(t.clone());
-        for i in 0..vk.Y_tilde.len() {
+        for (i, (Y_tilde_i, m)) in vk.Y_tilde.iter().zip(messages).enumerate() {
             if revealed_msg_indices.contains(&i) {
                 continue;
             }
-            bases.push(vk.Y_tilde[i].clone());
-            exponents.push(messages[i].clone());
+            bases.push(Y_tilde_i.clone());
+            exponents.push(m.as_field().clone());
+            hidden_msg_indices.push(i);
         }
         // Prove knowledge of m_1, m_2, ... for all hidden m_i and t in J = Y_tilde_1^m_1 * Y_tilde_2^m_2 * ..... * g_tilde^t
-        let J = bases.multi_scalar_mul_const_time(&exponents).unwrap();
+        fail_point!(MultiScalarMul);
+        let J = match tables {
+            Some(tables) => tables.mul_hidden(&hidden_msg_indices, exponents.as_slice()),
+            None => bases.multi_scalar_mul_const_time(&exponents).unwrap(),
+        };
 
         // For proving knowledge of messages in J.
         // Choose blinding for g_tilde randomly
-        blindings.insert(0, None);
+        blindings.insert(0, FieldElement::random_using_rng(rng));
+        fail_point!(Commitment);
         let mut committing = ProverCommittingOtherGroup::new();
-        for b in bases.as_slice() {
-            committing.commit(b, blindings.remove(0));
+        for (b, blinding) in bases.as_slice().iter().zip(blindings.iter()) {
+            committing.commit(b, Some(blinding));
         }
-        let committed = committing.finish();
+        let committed = match tables {
+            Some(tables) => {
+                let commitment = tables.mul_hidden(&hidden_msg_indices, &blindings);
+                committing.finish_with_commitment(commitment)
+            }
+            None => committing.finish(),
+        };
 
         let sigma_prime = Signature {
             sigma_1: sigma_prime_1,
@@ -128,6 +217,64 @@ impl PoKOfSignature {
         })
     }
 
+    /// Same as `init` but blindings of hidden messages are chosen such that the verifier can check `relations`
+    /// among the hidden messages with `PoKOfSignatureProof::verify_with_relations`.
+    pub fn init_with_relations(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[Message],
+        revealed_msg_indices: HashSet<usize>,
+        relations: &[LinearRelation],
+    ) -> Result<Self, PSError> {
+        Self::init_with_relations_with_rng(
+            sig,
+            vk,
+            params,
+            messages,
+            revealed_msg_indices,
+            relations,
+            &mut thread_rng(),
+        )
+    }
+
+    pub fn init_with_relations_with_rng<R: RngCore + CryptoRng>(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[Message],
+        revealed_msg_indices: HashSet<usize>,
+        relations: &[LinearRelation],
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        let blindings = blindings_for_relations(
+            &Message::to_field_elements(messages),
+            &revealed_msg_indices,
+            relations,
+            rng,
+        )?;
+        Self::init_with_rng(
+            sig,
+            vk,
+            params,
+            messages,
+            Some(&blindings),
+            revealed_msg_indices,
+            rng,
+        )
+    }
+
+    /// A zero `r` makes sigma_prime_1 the identity and a zero `t` makes sigma_prime a plain scaling of
+    /// the original signature (and J the identity when all messages are revealed), so resample on zero.
+    pub(crate) fn non_zero_random<R: RngCore + CryptoRng>(rng: &mut R) -> FieldElement {
+        loop {
+            let e = FieldElement::random_using_rng(rng);
+            if !e.is_zero() {
+                return e;
+            }
+        }
+    }
+
     /// Return byte representation of public elements so they can be used for challenge computation
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut bytes = vec![];
@@ -137,7 +284,7 @@ impl PoKOfSignature {
         bytes
     }
 
-    pub fn gen_proof(self, challenge: &FieldElement) -> Result<PoKOfSignatureProof, PSError> {
+    pub fn gen_proof(self, challenge: &Challenge) -> Result<PoKOfSignatureProof, PSError> {
         let proof_vc = self.pok_vc.gen_proof(challenge, self.secrets.as_slice())?;
         Ok(PoKOfSignatureProof {
             sig: self.sig,
@@ -147,61 +294,390 @@ impl PoKOfSignature {
     }
 }
 
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for PoKOfSignature {
+    fn zeroize(&mut self) {
+        for i in 0..self.secrets.len() {
+            self.secrets[i].zeroize();
+        }
+        self.pok_vc.zeroize();
+    }
+}
+
+impl ChallengeContributor for PoKOfSignature {
+    fn absorb(&self, transcript: &mut Transcript) {
+        transcript.append_message(b"PoK of signature", &self.to_bytes());
+    }
+}
+
 impl PoKOfSignatureProof {
+    /// Verifier side of absorbing `PoKOfSignature` into a transcript, gives the same transcript
+    pub fn absorb_with_key(
+        &self,
+        revealed_msg_indices: &HashSet<usize>,
+        vk: &Verkey,
+        params: &Params,
+        transcript: &mut Transcript,
+    ) {
+        transcript.append_message(
+            b"PoK of signature",
+            &self.get_bytes_for_challenge(revealed_msg_indices, vk, params),
+        );
+    }
+
+    /// Structural checks that need no key material. An honest prover never produces identity elements
+    /// since `r` and `t` are non-zero, an identity `J` means `t` was 0 and all messages were revealed.
+    pub fn is_well_formed(&self) -> bool {
+        !(self.sig.sigma_1.is_identity() || self.sig.sigma_2.is_identity() || self.J.is_identity())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.append(&mut self.sig.to_bytes());
+        bytes.append(&mut self.J.to_bytes());
+        bytes.append(&mut self.proof_vc.to_bytes());
+        bytes
+    }
+
+    /// Parse `to_bytes`, the number of responses follows from the length
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PSError> {
+        Self::from_bytes_with_profile(bytes, ValidationProfile::default())
+    }
+
+    pub fn from_bytes_with_profile(
+        bytes: &[u8],
+        profile: ValidationProfile,
+    ) -> Result<Self, PSError> {
+        DecodeLimits::current().check_proof_size(bytes.len())?;
+        let points_size = 2 * SIGNATURE_GROUP_SIZE + 2 * OTHER_GROUP_SIZE;
+        if bytes.len() < points_size + FIELD_ELEMENT_SIZE
+            || !(bytes.len() - points_size).is_multiple_of(FIELD_ELEMENT_SIZE)
+        {
+            return Err(PSError::InvalidEncoding {
+                msg: format!("Invalid proof size {}", bytes.len()),
+            });
+        }
+        let sig = Signature::from_bytes_with_profile(&bytes[..2 * SIGNATURE_GROUP_SIZE], profile)?;
+        let mut reader = ByteReader::new(&bytes[2 * SIGNATURE_GROUP_SIZE..]);
+        let J = profile.point_from_bytes(reader.take(OTHER_GROUP_SIZE)?)?;
+        let commitment = profile.point_from_bytes(reader.take(OTHER_GROUP_SIZE)?)?;
+        let mut responses =
+            FieldElementVector::with_capacity(reader.remaining() / FIELD_ELEMENT_SIZE);
+        while !reader.is_empty() {
+            responses.push(profile.field_element_from_bytes(reader.take(FIELD_ELEMENT_SIZE)?)?);
+        }
+        Ok(Self {
+            sig,
+            J,
+            proof_vc: ProofOtherGroup {
+                commitment,
+                responses,
+            },
+        })
+    }
+
+    /// Same bytes as `PoKOfSignature::to_bytes` so the verifier can recompute the challenge
+    pub fn get_bytes_for_challenge(
+        &self,
+        revealed_msg_indices: &HashSet<usize>,
+        vk: &Verkey,
+        params: &Params,
+    ) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.append(&mut self.sig.to_bytes());
+        bytes.append(&mut self.J.to_bytes());
+        bytes.append(&mut params.g_tilde.to_bytes());
+        for (i, y) in vk.Y_tilde.iter().enumerate() {
+            if !revealed_msg_indices.contains(&i) {
+                bytes.append(&mut y.to_bytes());
+            }
+        }
+        bytes.append(&mut self.proof_vc.commitment.to_bytes());
+        bytes
+    }
+
+    /// Hash of `to_bytes` for logging and deduplication of received proofs. The signature is
+    /// re-randomized in every proof so proofs of the same credential have unrelated fingerprints and
+    /// they cannot be used to link presentations.
+    pub fn fingerprint(&self) -> Fingerprint {
+        hash_with_domain(b"PS proof fingerprint", &self.to_bytes())
+    }
+
     pub fn verify(
         &self,
         vk: &Verkey,
         params: &Params,
         revealed_msgs: HashMap<usize, FieldElement>,
-        challenge: &FieldElement,
+        challenge: &Challenge,
+    ) -> Result<bool, PSError> {
+        self.verify_with_profile(
+            vk,
+            params,
+            revealed_msgs,
+            challenge,
+            ValidationProfile::default(),
+        )
+    }
+
+    /// Verify the proof with structural checks of proof and verkey as required by `profile`. Checks run
+    /// in increasing order of cost, as in `verify_fast_reject`, so that garbage proofs are rejected
+    /// before computing pairings.
+    pub fn verify_with_profile(
+        &self,
+        vk: &Verkey,
+        params: &Params,
+        revealed_msgs: HashMap<usize, FieldElement>,
+        challenge: &Challenge,
+        profile: ValidationProfile,
     ) -> Result<bool, PSError> {
-        if self.sig.sigma_1.is_identity() || self.sig.sigma_2.is_identity() {
+        if !self.verify_fast_reject(vk, params, &revealed_msgs, challenge)? {
             return Ok(false);
         }
+        self.check_pairing(vk, params, &revealed_msgs, profile)
+    }
 
-        // +1 for `t`
+    /// Same as `verify` but the bases of the proof of knowledge of messages in J, i.e. g_tilde followed
+    /// by Y_tilde of the hidden messages in increasing order of index, are given by the caller, e.g.
+    /// from a `HiddenBasesCache`.
+    pub fn verify_with_hidden_bases(
+        &self,
+        vk: &Verkey,
+        params: &Params,
+        revealed_msgs: &HashMap<usize, FieldElement>,
+        challenge: &Challenge,
+        hidden_bases: &[OtherGroup],
+    ) -> Result<bool, PSError> {
+        if !self.is_well_formed() || revealed_msgs.keys().any(|i| *i >= vk.Y_tilde.len()) {
+            return Ok(false);
+        }
         let hidden_msg_count = vk.Y_tilde.len() - revealed_msgs.len() + 1;
-        let mut bases = OtherGroupVec::with_capacity(hidden_msg_count);
-        bases.push(params.g_tilde.clone());
-        for i in 0..vk.Y_tilde.len() {
-            if revealed_msgs.contains_key(&i) {
-                continue;
-            }
-            bases.push(vk.Y_tilde[i].clone());
+        if self.proof_vc.responses.len() != hidden_msg_count
+            || hidden_bases.len() != hidden_msg_count
+            || !self.proof_vc.verify(hidden_bases, &self.J, challenge)?
+        {
+            return Ok(false);
         }
-        if !self.proof_vc.verify(bases.as_slice(), &self.J, challenge)? {
+        self.check_pairing(vk, params, revealed_msgs, ValidationProfile::default())
+    }
+
+    /// Profile checks and the pairing check, the proof of knowledge of messages in J must already be
+    /// verified
+    fn check_pairing(
+        &self,
+        vk: &Verkey,
+        params: &Params,
+        revealed_msgs: &HashMap<usize, FieldElement>,
+        profile: ValidationProfile,
+    ) -> Result<bool, PSError> {
+        if !(profile.is_valid_point(&self.sig.sigma_1)
+            && profile.is_valid_point(&self.sig.sigma_2)
+            && profile.is_valid_point(&self.J)
+            && profile.is_valid_point(&self.proof_vc.commitment)
+            && profile.is_valid_verkey(vk))
+        {
             return Ok(false);
         }
         // e(sigma_prime_1, J*X_tilde) == e(sigma_prime_2, g_tilde) => e(sigma_prime_1, J*X_tilde) * e(sigma_prime_2^-1, g_tilde) == 1
-        let mut j;
-        let J = if revealed_msgs.is_empty() {
-            &self.J
-        } else {
-            j = self.J.clone();
-            let mut b = OtherGroupVec::with_capacity(revealed_msgs.len());
-            let mut e = FieldElementVector::with_capacity(revealed_msgs.len());
-            for (i, m) in revealed_msgs {
-                b.push(vk.Y_tilde[i].clone());
-                e.push(m.clone());
-            }
-            j += b.multi_scalar_mul_var_time(&e).unwrap();
-            &j
-        };
+        fail_point!(Pairing);
+        let J = self.full_J(vk, revealed_msgs);
         // e(sigma_1, (J + &X_tilde)) == e(sigma_2, g_tilde) => e(sigma_1, (J + &X_tilde)) * e(-sigma_2, g_tilde) == 1
-        // Slight optimization possible by precomputing inverse of g_tilde and storing to avoid inverse of sig.sigma_2
+        // `verify_prepared` has the inverse of g_tilde precomputed to avoid inverse of sig.sigma_2
         let res = ate_2_pairing(
             &self.sig.sigma_1,
-            &(J + &vk.X_tilde),
+            &(&J + &vk.X_tilde),
             &(-&self.sig.sigma_2),
             &params.g_tilde,
         );
         Ok(res.is_one())
     }
+
+    /// J with the revealed messages added, Y_tilde_1^m_1 * Y_tilde_2^m_2 * ..... * g_tilde^t over all
+    /// messages. Revealed indices must be less than the number of messages supported by `vk`.
+    pub fn full_J(&self, vk: &Verkey, revealed_msgs: &HashMap<usize, FieldElement>) -> OtherGroup {
+        if revealed_msgs.is_empty() {
+            return self.J.clone();
+        }
+        let mut msm = MultiScalarMul::new();
+        for (i, m) in revealed_msgs {
+            msm.add(&vk.Y_tilde[*i], m);
+        }
+        &self.J + &msm.finish()
+    }
+
+    /// Only the checks that need no pairing: structure of the proof, indices of revealed messages
+    /// and the proof of knowledge of the messages in J. Returns false if the proof is certainly
+    /// invalid, true means `verify` still has to be called.
+    pub fn verify_fast_reject(
+        &self,
+        vk: &Verkey,
+        params: &Params,
+        revealed_msgs: &HashMap<usize, FieldElement>,
+        challenge: &Challenge,
+    ) -> Result<bool, PSError> {
+        if !self.is_well_formed() || revealed_msgs.keys().any(|i| *i >= vk.Y_tilde.len()) {
+            return Ok(false);
+        }
+        // +1 for `t`
+        let hidden_msg_count = vk.Y_tilde.len() - revealed_msgs.len() + 1;
+        if self.proof_vc.responses.len() != hidden_msg_count {
+            return Ok(false);
+        }
+        // Same check as `ProofOtherGroup::verify` with bases g_tilde and the hidden Y_tilde but
+        // without collecting the bases
+        // g_tilde^responses[0] * Y_tilde_i^responses[i] ... * J^challenge == commitment
+        fail_point!(MultiScalarMul);
+        let mut msm = MultiScalarMul::new();
+        let mut responses = self.proof_vc.responses.iter();
+        msm.add(&params.g_tilde, responses.next().unwrap());
+        let hidden = (0..vk.Y_tilde.len()).filter(|i| !revealed_msgs.contains_key(i));
+        for (i, r) in hidden.zip(responses) {
+            msm.add(&vk.Y_tilde[i], r);
+        }
+        msm.add(&self.J, challenge.as_field());
+        Ok(msm.finish() == self.proof_vc.commitment)
+    }
+
+    /// Hash of the proof, verkey and revealed messages a `PhaseOutcome` is bound to
+    fn phase_inputs(
+        &self,
+        vk: &Verkey,
+        revealed_msgs: &HashMap<usize, FieldElement>,
+    ) -> Fingerprint {
+        let mut bytes = self.to_bytes();
+        bytes.append(&mut vk.to_bytes());
+        let mut revealed: Vec<_> = revealed_msgs.iter().collect();
+        revealed.sort_by_key(|(i, _)| **i);
+        for (i, m) in revealed {
+            bytes.extend_from_slice(&(*i as u64).to_be_bytes());
+            bytes.append(&mut m.to_bytes());
+        }
+        hash_with_domain(b"PS proof verification inputs", &bytes)
+    }
+
+    /// First phase of a split verification, the checks of `verify_fast_reject`. Needs no pairing.
+    pub fn verify_schnorr(
+        &self,
+        vk: &Verkey,
+        params: &Params,
+        revealed_msgs: &HashMap<usize, FieldElement>,
+        challenge: &Challenge,
+    ) -> Result<PhaseOutcome, PSError> {
+        Ok(PhaseOutcome {
+            phase: VerificationPhase::Schnorr,
+            inputs: self.phase_inputs(vk, revealed_msgs),
+            passed: self.verify_fast_reject(vk, params, revealed_msgs, challenge)?,
+        })
+    }
+
+    /// Second phase of a split verification, the pairing check with the revealed messages added to
+    /// J. It does not check the proof of knowledge, so it proves nothing without `verify_schnorr`.
+    pub fn verify_pairing(
+        &self,
+        vk: &Verkey,
+        params: &Params,
+        revealed_msgs: &HashMap<usize, FieldElement>,
+    ) -> Result<PhaseOutcome, PSError> {
+        let passed = self.is_well_formed()
+            && revealed_msgs.keys().all(|i| *i < vk.Y_tilde.len())
+            && self.check_pairing(vk, params, revealed_msgs, ValidationProfile::default())?;
+        Ok(PhaseOutcome {
+            phase: VerificationPhase::Pairing,
+            inputs: self.phase_inputs(vk, revealed_msgs),
+            passed,
+        })
+    }
+
+    /// Whether the outcomes of both phases passed for the same proof, verkey and revealed messages.
+    /// Together they are the checks of `verify`.
+    pub fn combine_phases(schnorr: &PhaseOutcome, pairing: &PhaseOutcome) -> bool {
+        schnorr.phase == VerificationPhase::Schnorr
+            && pairing.phase == VerificationPhase::Pairing
+            && schnorr.inputs == pairing.inputs
+            && schnorr.passed
+            && pairing.passed
+    }
+
+    /// Get the response for the hidden message at index `msg_idx`. The 0th response is for `t`, the rest are
+    /// for hidden messages in increasing order of index.
+    pub fn get_resp_for_message(
+        &self,
+        msg_idx: usize,
+        revealed_msg_indices: &HashSet<usize>,
+    ) -> Result<ProofResponse, PSError> {
+        if revealed_msg_indices.contains(&msg_idx) {
+            return Err(PSError::GeneralError {
+                msg: format!("Message {} is revealed", msg_idx),
+            });
+        }
+        let pos = 1 + msg_idx
+            - revealed_msg_indices
+                .iter()
+                .filter(|i| **i < msg_idx)
+                .count();
+        if pos >= self.proof_vc.responses.len() {
+            return Err(PSError::GeneralError {
+                msg: format!("No response for message {}", msg_idx),
+            });
+        }
+        Ok(ProofResponse(self.proof_vc.responses[pos].clone()))
+    }
+
+    /// Verify the proof and that the hidden messages satisfy `relations`. Relations are checked over responses.
+    pub fn verify_with_relations(
+        &self,
+        vk: &Verkey,
+        params: &Params,
+        revealed_msgs: HashMap<usize, FieldElement>,
+        challenge: &Challenge,
+        relations: &[LinearRelation],
+    ) -> Result<bool, PSError> {
+        let revealed_msg_indices: HashSet<usize> = revealed_msgs.keys().cloned().collect();
+        let mut responses = HashMap::new();
+        for relation in relations {
+            for idx in relation.indices() {
+                if idx >= vk.Y_tilde.len() {
+                    return Err(PSError::GeneralError {
+                        msg: format!("Index {} should be less than {}", idx, vk.Y_tilde.len()),
+                    });
+                }
+                responses.insert(idx, self.get_resp_for_message(idx, &revealed_msg_indices)?);
+            }
+        }
+        if !self.verify(vk, params, revealed_msgs, challenge)? {
+            return Ok(false);
+        }
+        Ok(relations
+            .iter()
+            .all(|relation| relation.holds(|i| responses[&i].0.clone())))
+    }
+
+    /// Verify the proof and, only if it is valid, check the revealed messages and the verkey against `policy`.
+    pub fn verify_with_policy(
+        &self,
+        vk: &Verkey,
+        params: &Params,
+        revealed_msgs: HashMap<usize, FieldElement>,
+        challenge: &Challenge,
+        policy: &CompiledPolicy,
+    ) -> Result<PolicyReport, PSError> {
+        if !self.verify(vk, params, revealed_msgs.clone(), challenge)? {
+            return Ok(PolicyReport {
+                proof_valid: false,
+                violations: vec![],
+            });
+        }
+        Ok(PolicyReport {
+            proof_valid: true,
+            violations: policy.evaluate(vk, &revealed_msgs),
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::policy::Policy;
+    use crate::{SignatureGroup, SignatureGroupVec};
     // For benchmarking
     use crate::keys::keygen;
     use std::time::{Duration, Instant};
@@ -249,10 +725,11 @@ mod tests {
         let (sk, vk) = keygen(count_msgs, &params);
 
         let msgs = FieldElementVector::random(count_msgs);
-        let sig = Signature::new(msgs.as_slice(), &sk, &params).unwrap();
-        assert!(sig.verify(msgs.as_slice(), &vk, &params).unwrap());
+        let messages = Message::from_field_elements(msgs.as_slice());
+        let sig = Signature::new(&messages, &sk, &params).unwrap();
+        assert!(sig.verify(&messages, &vk, &params).unwrap());
 
-        let pok = PoKOfSignature::init(&sig, &vk, &params, msgs.as_slice(), None, HashSet::new()).unwrap();
+        let pok = PoKOfSignature::init(&sig, &vk, &params, &messages, None, HashSet::new()).unwrap();
 
         let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
 
@@ -266,15 +743,155 @@ mod tests {
             &sig,
             &vk,
             &params,
-            msgs.as_slice(),
+            &messages,
             Some(blindings.as_slice()),
             HashSet::new(),
         )
         .unwrap();
-        let chal_1 = FieldElement::from_msg_hash(&pok_1.to_bytes());
+        let chal_1 = Challenge::from_msg_hash(&pok_1.to_bytes());
         let proof_1 = pok_1.gen_proof(&chal_1).unwrap();
 
         assert!(proof_1.verify(&vk, &params, HashMap::new(), &chal_1).unwrap());
+
+        let mut revealed = HashSet::new();
+        revealed.insert(1);
+        let pok_2 =
+            PoKOfSignature::init(&sig, &vk, &params, &messages, None, revealed.clone())
+                .unwrap();
+        let bytes = pok_2.to_bytes();
+        let proof_2 = pok_2.gen_proof(&chal_1).unwrap();
+        assert_eq!(
+            proof_2.get_bytes_for_challenge(&revealed, &vk, &params),
+            bytes
+        );
+    }
+
+    #[test]
+    fn test_PoK_sig_fast_reject() {
+        let count_msgs = 4;
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(count_msgs, &params);
+        let msgs = FieldElementVector::random(count_msgs);
+        let messages = Message::from_field_elements(msgs.as_slice());
+        let sig = Signature::new(&messages, &sk, &params).unwrap();
+        let mut revealed = HashSet::new();
+        revealed.insert(2);
+        let pok =
+            PoKOfSignature::init(&sig, &vk, &params, &messages, None, revealed).unwrap();
+        let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
+        let proof = pok.gen_proof(&chal).unwrap();
+        let mut revealed_msgs = HashMap::new();
+        revealed_msgs.insert(2, msgs[2].clone());
+        assert!(proof
+            .verify_fast_reject(&vk, &params, &revealed_msgs, &chal)
+            .unwrap());
+
+        // Garbage J fails the Schnorr check
+        let mut garbage = proof.clone();
+        garbage.J = OtherGroup::random();
+        assert!(!garbage
+            .verify_fast_reject(&vk, &params, &revealed_msgs, &chal)
+            .unwrap());
+        assert!(!garbage
+            .verify(&vk, &params, revealed_msgs.clone(), &chal)
+            .unwrap());
+
+        // Wrong number of responses or out of range revealed index
+        let mut garbage = proof.clone();
+        garbage.proof_vc.responses.push(FieldElement::random());
+        assert!(!garbage
+            .verify(&vk, &params, revealed_msgs.clone(), &chal)
+            .unwrap());
+        let mut revealed_msgs_1 = revealed_msgs.clone();
+        revealed_msgs_1.insert(count_msgs, FieldElement::random());
+        assert!(!proof.verify(&vk, &params, revealed_msgs_1, &chal).unwrap());
+
+        // Passing the fast checks does not make a proof valid, sigma_prime is only checked by the pairing
+        let mut forged = proof.clone();
+        forged.sig.sigma_1 = SignatureGroup::random();
+        assert!(forged
+            .verify_fast_reject(&vk, &params, &revealed_msgs, &chal)
+            .unwrap());
+        assert!(!forged.verify(&vk, &params, revealed_msgs, &chal).unwrap());
+    }
+
+    #[test]
+    fn test_PoK_sig_split_verification() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(4, &params);
+        let msgs = FieldElementVector::random(4);
+        let messages = Message::from_field_elements(msgs.as_slice());
+        let sig = Signature::new(&messages, &sk, &params).unwrap();
+        let revealed: HashSet<usize> = vec![2].into_iter().collect();
+        let pok =
+            PoKOfSignature::init(&sig, &vk, &params, &messages, None, revealed).unwrap();
+        let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
+        let proof = pok.gen_proof(&chal).unwrap();
+        let mut revealed_msgs = HashMap::new();
+        revealed_msgs.insert(2, msgs[2].clone());
+
+        let schnorr = proof
+            .verify_schnorr(&vk, &params, &revealed_msgs, &chal)
+            .unwrap();
+        let pairing = proof.verify_pairing(&vk, &params, &revealed_msgs).unwrap();
+        assert!(PoKOfSignatureProof::combine_phases(&schnorr, &pairing));
+        // Phases swapped, failed or of other inputs
+        assert!(!PoKOfSignatureProof::combine_phases(&pairing, &schnorr));
+        let wrong_chal = proof
+            .verify_schnorr(
+                &vk,
+                &params,
+                &revealed_msgs,
+                &Challenge(FieldElement::random()),
+            )
+            .unwrap();
+        assert!(!wrong_chal.passed);
+        assert!(!PoKOfSignatureProof::combine_phases(&wrong_chal, &pairing));
+        let mut other_msgs = HashMap::new();
+        other_msgs.insert(2, FieldElement::random());
+        let other_pairing = proof.verify_pairing(&vk, &params, &other_msgs).unwrap();
+        assert!(!other_pairing.passed);
+        let other_schnorr = proof
+            .verify_schnorr(&vk, &params, &other_msgs, &chal)
+            .unwrap();
+        assert!(other_schnorr.passed);
+        assert!(!PoKOfSignatureProof::combine_phases(
+            &other_schnorr,
+            &pairing
+        ));
+        let mut out_of_range = HashMap::new();
+        out_of_range.insert(9, FieldElement::random());
+        assert!(
+            !proof
+                .verify_pairing(&vk, &params, &out_of_range)
+                .unwrap()
+                .passed
+        );
+    }
+
+    #[test]
+    fn test_PoK_sig_fingerprint() {
+        let count_msgs = 3;
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(count_msgs, &params);
+        let msgs = FieldElementVector::random(count_msgs);
+        let messages = Message::from_field_elements(msgs.as_slice());
+        let sig = Signature::new(&messages, &sk, &params).unwrap();
+
+        let proof = |sig: &Signature| {
+            let pok =
+                PoKOfSignature::init(sig, &vk, &params, &messages, None, HashSet::new())
+                    .unwrap();
+            let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
+            pok.gen_proof(&chal).unwrap()
+        };
+        let proof_1 = proof(&sig);
+        let proof_2 = proof(&sig);
+        assert_eq!(proof_1.fingerprint(), proof_1.clone().fingerprint());
+        // Proofs of the same signature are unlinkable
+        assert_ne!(proof_1.fingerprint(), proof_2.fingerprint());
+        assert_ne!(proof_1.fingerprint(), sig.fingerprint());
+        assert_ne!(proof_1.fingerprint(), proof_1.sig.fingerprint());
     }
 
     #[test]
@@ -284,9 +901,10 @@ mod tests {
         let (sk, vk) = keygen(count_msgs, &params);
 
         let msgs = FieldElementVector::random(count_msgs);
+        let messages = Message::from_field_elements(msgs.as_slice());
 
-        let sig = Signature::new(msgs.as_slice(), &sk, &params).unwrap();
-        assert!(sig.verify(msgs.as_slice(), &vk, &params).unwrap());
+        let sig = Signature::new(&messages, &sk, &params).unwrap();
+        assert!(sig.verify(&messages, &vk, &params).unwrap());
 
         let mut revealed_msg_indices = HashSet::new();
         revealed_msg_indices.insert(2);
@@ -297,7 +915,7 @@ mod tests {
             &sig,
             &vk,
             &params,
-            msgs.as_slice(),
+            &messages,
             None,
             revealed_msg_indices.clone(),
         )
@@ -309,7 +927,7 @@ mod tests {
 
         let mut revealed_msgs = HashMap::new();
         for i in &revealed_msg_indices {
-            revealed_msgs.insert(i.clone(), msgs[*i].clone());
+            revealed_msgs.insert(*i, msgs[*i].clone());
         }
         assert!(proof.verify(&vk, &params, revealed_msgs.clone(), &chal).unwrap());
 
@@ -319,6 +937,200 @@ mod tests {
         assert!(!proof.verify(&vk, &params, revealed_msgs_1.clone(), &chal).unwrap());
     }
 
+    #[test]
+    fn test_PoK_sig_degenerate() {
+        let count_msgs = 2;
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(count_msgs, &params);
+        let msgs = FieldElementVector::random(count_msgs);
+        let messages = Message::from_field_elements(msgs.as_slice());
+        let sig = Signature::new(&messages, &sk, &params).unwrap();
+
+        // Signature with identity elements is rejected by the prover
+        let bad_sig = Signature {
+            sigma_1: SignatureGroup::identity(),
+            sigma_2: sig.sigma_2.clone(),
+        };
+        assert!(PoKOfSignature::init(
+            &bad_sig,
+            &vk,
+            &params,
+            &messages,
+            None,
+            HashSet::new()
+        )
+        .is_err());
+
+        // All messages revealed
+        let revealed_msg_indices: HashSet<usize> = (0..count_msgs).collect();
+        let revealed_msgs: HashMap<usize, FieldElement> =
+            (0..count_msgs).map(|i| (i, msgs[i].clone())).collect();
+        let pok = PoKOfSignature::init(
+            &sig,
+            &vk,
+            &params,
+            &messages,
+            None,
+            revealed_msg_indices,
+        )
+        .unwrap();
+        let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
+        let proof = pok.gen_proof(&chal).unwrap();
+        assert!(proof.is_well_formed());
+        assert!(proof
+            .verify(&vk, &params, revealed_msgs.clone(), &chal)
+            .unwrap());
+
+        // Identity J is rejected by the verifier
+        let mut bad_proof = proof.clone();
+        bad_proof.J = OtherGroup::identity();
+        assert!(!bad_proof.is_well_formed());
+        assert!(!bad_proof
+            .verify(&vk, &params, revealed_msgs, &chal)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_PoK_sig_with_policy() {
+        let count_msgs = 3;
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(count_msgs, &params);
+        let msgs = FieldElementVector::random(count_msgs);
+        let messages = Message::from_field_elements(msgs.as_slice());
+        let sig = Signature::new(&messages, &sk, &params).unwrap();
+
+        let mut revealed_msg_indices = HashSet::new();
+        revealed_msg_indices.insert(1);
+        let pok = PoKOfSignature::init(
+            &sig,
+            &vk,
+            &params,
+            &messages,
+            None,
+            revealed_msg_indices,
+        )
+        .unwrap();
+        let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
+        let proof = pok.gen_proof(&chal).unwrap();
+
+        let mut revealed_msgs = HashMap::new();
+        revealed_msgs.insert(1, msgs[1].clone());
+
+        let names = ["name", "dob", "country"];
+        let policy = CompiledPolicy::compile(
+            &[
+                Policy::require_revealed("dob"),
+                Policy::trusted_issuers(std::slice::from_ref(&vk)),
+            ],
+            &names,
+        )
+        .unwrap();
+        let report = proof
+            .verify_with_policy(&vk, &params, revealed_msgs.clone(), &chal, &policy)
+            .unwrap();
+        assert!(report.is_accepted());
+
+        // Valid proof but policy violated
+        let policy =
+            CompiledPolicy::compile(&[Policy::require_revealed("country")], &names).unwrap();
+        let report = proof
+            .verify_with_policy(&vk, &params, revealed_msgs.clone(), &chal, &policy)
+            .unwrap();
+        assert!(report.proof_valid);
+        assert!(!report.is_accepted());
+
+        // Policies are not checked for an invalid proof
+        let report = proof
+            .verify_with_policy(
+                &vk,
+                &params,
+                revealed_msgs,
+                &Challenge(FieldElement::random()),
+                &policy,
+            )
+            .unwrap();
+        assert!(!report.proof_valid);
+        assert!(report.violations.is_empty());
+    }
+
+    #[test]
+    fn test_PoK_sig_with_relations() {
+        let count_msgs = 5;
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(count_msgs, &params);
+
+        // msgs[0] = msgs[2] + msgs[4] and msgs[3] = 3*msgs[2]
+        let three = FieldElement::from(3u64);
+        let mut msgs = FieldElementVector::random(count_msgs);
+        msgs[0] = &msgs[2] + &msgs[4];
+        msgs[3] = &three * &msgs[2];
+        let messages = Message::from_field_elements(msgs.as_slice());
+        let sig = Signature::new(&messages, &sk, &params).unwrap();
+
+        let relations = vec![
+            LinearRelation::Sum {
+                total: 0,
+                a: 2,
+                b: 4,
+            },
+            LinearRelation::Scaled {
+                m: 3,
+                c: three,
+                m_prime: 2,
+            },
+        ];
+        let mut revealed_msg_indices = HashSet::new();
+        revealed_msg_indices.insert(1);
+        let pok = PoKOfSignature::init_with_relations(
+            &sig,
+            &vk,
+            &params,
+            &messages,
+            revealed_msg_indices.clone(),
+            &relations,
+        )
+        .unwrap();
+        let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
+        let proof = pok.gen_proof(&chal).unwrap();
+
+        let mut revealed_msgs = HashMap::new();
+        revealed_msgs.insert(1, msgs[1].clone());
+        assert!(proof
+            .verify_with_relations(&vk, &params, revealed_msgs.clone(), &chal, &relations)
+            .unwrap());
+
+        // A relation the prover did not prove does not hold on responses
+        let other = vec![LinearRelation::Sum {
+            total: 0,
+            a: 2,
+            b: 3,
+        }];
+        assert!(!proof
+            .verify_with_relations(&vk, &params, revealed_msgs.clone(), &chal, &other)
+            .unwrap());
+
+        // Relations are over hidden messages only
+        let over_revealed = vec![LinearRelation::Sum {
+            total: 0,
+            a: 1,
+            b: 3,
+        }];
+        assert!(proof
+            .verify_with_relations(&vk, &params, revealed_msgs, &chal, &over_revealed)
+            .is_err());
+
+        // Prover cannot claim a relation that does not hold
+        assert!(PoKOfSignature::init_with_relations(
+            &sig,
+            &vk,
+            &params,
+            &messages,
+            HashSet::new(),
+            &other
+        )
+        .is_err());
+    }
+
     #[test]
     fn test_PoK_multiple_sigs() {
         // Prove knowledge of multiple signatures together (using the same challenge)
@@ -327,23 +1139,25 @@ mod tests {
         let (sk, vk) = keygen(count_msgs, &params);
 
         let msgs_1 = FieldElementVector::random(count_msgs);
-        let sig_1 = Signature::new(msgs_1.as_slice(), &sk, &params).unwrap();
-        assert!(sig_1.verify(msgs_1.as_slice(), &vk, &params).unwrap());
+        let messages_1 = Message::from_field_elements(msgs_1.as_slice());
+        let sig_1 = Signature::new(&messages_1, &sk, &params).unwrap();
+        assert!(sig_1.verify(&messages_1, &vk, &params).unwrap());
 
         let msgs_2 = FieldElementVector::random(count_msgs);
-        let sig_2 = Signature::new(msgs_2.as_slice(), &sk, &params).unwrap();
-        assert!(sig_2.verify(msgs_2.as_slice(), &vk, &params).unwrap());
+        let messages_2 = Message::from_field_elements(msgs_2.as_slice());
+        let sig_2 = Signature::new(&messages_2, &sk, &params).unwrap();
+        assert!(sig_2.verify(&messages_2, &vk, &params).unwrap());
 
         let pok_1 =
-            PoKOfSignature::init(&sig_1, &vk, &params, msgs_1.as_slice(), None, HashSet::new()).unwrap();
+            PoKOfSignature::init(&sig_1, &vk, &params, &messages_1, None, HashSet::new()).unwrap();
         let pok_2 =
-            PoKOfSignature::init(&sig_2, &vk, &params, msgs_2.as_slice(), None, HashSet::new()).unwrap();
+            PoKOfSignature::init(&sig_2, &vk, &params, &messages_2, None, HashSet::new()).unwrap();
 
         let mut chal_bytes = vec![];
         chal_bytes.append(&mut pok_1.to_bytes());
         chal_bytes.append(&mut pok_2.to_bytes());
 
-        let chal = FieldElement::from_msg_hash(&chal_bytes);
+        let chal = Challenge::from_msg_hash(&chal_bytes);
 
         let proof_1 = pok_1.gen_proof(&chal).unwrap();
         let proof_2 = pok_2.gen_proof(&chal).unwrap();
@@ -365,13 +1179,15 @@ mod tests {
         let same_msg = FieldElement::random();
         let mut msgs_1 = FieldElementVector::random(count_msgs - 1);
         msgs_1.insert(1, same_msg.clone());
-        let sig_1 = Signature::new(msgs_1.as_slice(), &sk, &params).unwrap();
-        assert!(sig_1.verify(msgs_1.as_slice(), &vk, &params).unwrap());
+        let messages_1 = Message::from_field_elements(msgs_1.as_slice());
+        let sig_1 = Signature::new(&messages_1, &sk, &params).unwrap();
+        assert!(sig_1.verify(&messages_1, &vk, &params).unwrap());
 
         let mut msgs_2 = FieldElementVector::random(count_msgs - 1);
         msgs_2.insert(4, same_msg.clone());
-        let sig_2 = Signature::new(msgs_2.as_slice(), &sk, &params).unwrap();
-        assert!(sig_2.verify(msgs_2.as_slice(), &vk, &params).unwrap());
+        let messages_2 = Message::from_field_elements(msgs_2.as_slice());
+        let sig_2 = Signature::new(&messages_2, &sk, &params).unwrap();
+        assert!(sig_2.verify(&messages_2, &vk, &params).unwrap());
 
         // A particular message is same
         assert_eq!(msgs_1[1], msgs_2[4]);
@@ -390,7 +1206,7 @@ mod tests {
         let pok_1 = PoKOfSignature::init(
             &sig_1,
             &vk, &params,
-            msgs_1.as_slice(),
+            &messages_1,
             Some(blindings_1.as_slice()),
             HashSet::new(),
         )
@@ -398,7 +1214,7 @@ mod tests {
         let pok_2 = PoKOfSignature::init(
             &sig_2,
             &vk, &params,
-            msgs_2.as_slice(),
+            &messages_2,
             Some(blindings_2.as_slice()),
             HashSet::new(),
         )
@@ -408,18 +1224,21 @@ mod tests {
         chal_bytes.append(&mut pok_1.to_bytes());
         chal_bytes.append(&mut pok_2.to_bytes());
 
-        let chal = FieldElement::from_msg_hash(&chal_bytes);
+        let chal = Challenge::from_msg_hash(&chal_bytes);
 
         let proof_1 = pok_1.gen_proof(&chal).unwrap();
         let proof_2 = pok_2.gen_proof(&chal).unwrap();
 
         // Response for the same message should be same (this check is made by the verifier)
         // 1 added to the index, since 0th index is reserved for randomization (`t`)
-        // XXX: Does adding a `get_resp_for_message` to `proof` make sense to abstract this detail of +1.
         assert_eq!(
             proof_1.proof_vc.responses[1 + 1],
             proof_2.proof_vc.responses[1 + 4]
         );
+        assert_eq!(
+            proof_1.get_resp_for_message(1, &HashSet::new()).unwrap(),
+            proof_2.get_resp_for_message(4, &HashSet::new()).unwrap()
+        );
 
         assert!(proof_1.verify(&vk, &params, HashMap::new(), &chal).unwrap());
         assert!(proof_2.verify(&vk, &params, HashMap::new(), &chal).unwrap());
@@ -434,7 +1253,8 @@ mod tests {
         let (sk, vk) = keygen(count_msgs, &params);
 
         let msgs = FieldElementVector::random(count_msgs);
-        let sig = Signature::new(msgs.as_slice(), &sk, &params).unwrap();
+        let messages = Message::from_field_elements(msgs.as_slice());
+        let sig = Signature::new(&messages, &sk, &params).unwrap();
 
         let mut total_generating = Duration::new(0, 0);
         let mut total_verifying = Duration::new(0, 0);
@@ -443,7 +1263,7 @@ mod tests {
             let start = Instant::now();
 
             let pok =
-                PoKOfSignature::init(&sig, &vk, &params, msgs.as_slice(), None, HashSet::new()).unwrap();
+                PoKOfSignature::init(&sig, &vk, &params, &messages, None, HashSet::new()).unwrap();
 
             let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
 
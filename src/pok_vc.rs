@@ -75,10 +75,17 @@ macro_rules! impl_PoK_VC {
 
         #[derive(Clone, Debug, Serialize, Deserialize)]
         pub struct $Proof {
+            #[serde(deserialize_with = "crate::validation::deserialize_point")]
             pub commitment: $group_element,
             pub responses: FieldElementVector,
         }
 
+        impl Default for $ProverCommitting {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
         impl $ProverCommitting {
             pub fn new() -> Self {
                 Self {
@@ -116,6 +123,21 @@ macro_rules! impl_PoK_VC {
                 }
             }
 
+            /// Same as `finish` but with the commitment computed by the caller, e.g. from fixed-base
+            /// tables. It must be the pairwise product of (`self.gens`, self.blindings).
+            // Not every instance of the macro has tables
+            #[allow(dead_code)]
+            pub(crate) fn finish_with_commitment(
+                self,
+                commitment: $group_element,
+            ) -> $ProverCommitted {
+                $ProverCommitted {
+                    gens: self.gens,
+                    blindings: self.blindings,
+                    commitment,
+                }
+            }
+
             pub fn get_index(
                 &self,
                 idx: usize,
@@ -130,6 +152,17 @@ macro_rules! impl_PoK_VC {
         }
 
         impl $ProverCommitted {
+            /// The random commitment T, product of bases[i]^blindings[i], for challenges computed
+            /// outside this crate
+            pub fn commitment(&self) -> &$group_element {
+                &self.commitment
+            }
+
+            /// Bases in the order of `commit`
+            pub fn bases(&self) -> &[$group_element] {
+                self.gens.as_slice()
+            }
+
             pub fn to_bytes(&self) -> Vec<u8> {
                 let mut bytes = vec![];
                 for b in self.gens.as_slice() {
@@ -140,27 +173,28 @@ macro_rules! impl_PoK_VC {
             }
 
             /// This step will be done by the main protocol for which this PoK is a sub-protocol
-            pub fn gen_challenge(&self, mut extra: Vec<u8>) -> FieldElement {
+            pub fn gen_challenge(&self, mut extra: Vec<u8>) -> $crate::types::Challenge {
                 let mut bytes = self.to_bytes();
                 bytes.append(&mut extra);
-                FieldElement::from_msg_hash(&bytes)
+                $crate::types::Challenge::from_msg_hash(&bytes)
             }
 
             /// For each secret, generate a response as self.blinding[i] - challenge*secrets[i].
             pub fn gen_proof(
                 self,
-                challenge: &FieldElement,
+                challenge: &$crate::types::Challenge,
                 secrets: &[FieldElement],
             ) -> Result<$Proof, PSError> {
+                // One secret per committed base, in the order of `commit`
                 if secrets.len() != self.gens.len() {
-                    return Err(PSError::UnequalNoOfBasesExponents {
-                        bases: self.gens.len(),
-                        exponents: secrets.len(),
+                    return Err(PSError::MismatchedWitnessCount {
+                        expected: self.gens.len(),
+                        got: secrets.len(),
                     });
                 }
                 let mut responses = FieldElementVector::with_capacity(self.gens.len());
                 for i in 0..self.gens.len() {
-                    responses.push(&self.blindings[i] - (challenge * &secrets[i]));
+                    responses.push(&self.blindings[i] - (&challenge.0 * &secrets[i]));
                 }
                 Ok($Proof {
                     commitment: self.commitment,
@@ -169,13 +203,61 @@ macro_rules! impl_PoK_VC {
             }
         }
 
+        impl $crate::transcript::ChallengeContributor for $ProverCommitted {
+            fn absorb(&self, transcript: &mut $crate::transcript::Transcript) {
+                transcript.append_message(b"PoK VC", &self.to_bytes());
+            }
+        }
+
+        // Blindings give away the secrets once the proof is out. Wiping them on drop is left to amcl's
+        // `FieldElement`, a `Drop` here would prevent `gen_proof` from moving out of `self`.
+        #[cfg(feature = "zeroize")]
+        impl zeroize::Zeroize for $ProverCommitting {
+            fn zeroize(&mut self) {
+                for i in 0..self.blindings.len() {
+                    self.blindings[i].zeroize();
+                }
+            }
+        }
+
+        #[cfg(feature = "zeroize")]
+        impl zeroize::Zeroize for $ProverCommitted {
+            fn zeroize(&mut self) {
+                for i in 0..self.blindings.len() {
+                    self.blindings[i].zeroize();
+                }
+            }
+        }
+
         impl $Proof {
+            /// Verifier side of absorbing `$ProverCommitted` into a transcript, gives the same transcript
+            pub fn absorb_with_bases(
+                &self,
+                bases: &[$group_element],
+                transcript: &mut $crate::transcript::Transcript,
+            ) {
+                let mut bytes = vec![];
+                for b in bases {
+                    bytes.append(&mut b.to_bytes());
+                }
+                bytes.append(&mut self.commitment.to_bytes());
+                transcript.append_message(b"PoK VC", &bytes);
+            }
+
+            pub fn to_bytes(&self) -> Vec<u8> {
+                let mut bytes = self.commitment.to_bytes();
+                for r in self.responses.as_slice() {
+                    bytes.append(&mut r.to_bytes());
+                }
+                bytes
+            }
+
             /// Verify that bases[0]^responses[0] * bases[0]^responses[0] * ... bases[i]^responses[i] * commitment^challenge == random_commitment
             pub fn verify(
                 &self,
                 bases: &[$group_element],
                 commitment: &$group_element,
-                challenge: &FieldElement,
+                challenge: &$crate::types::Challenge,
             ) -> Result<bool, PSError> {
                 // bases[0]^responses[0] * bases[0]^responses[0] * ... bases[i]^responses[i] * commitment^challenge == random_commitment
                 // =>
@@ -189,7 +271,7 @@ macro_rules! impl_PoK_VC {
                 let mut points = $group_element_vec::from(bases);
                 let mut scalars = self.responses.clone();
                 points.push(commitment.clone());
-                scalars.push(challenge.clone());
+                scalars.push(challenge.0.clone());
                 let pr = points.multi_scalar_mul_var_time(&scalars).unwrap() - &self.commitment;
                 Ok(pr.is_identity())
             }
@@ -222,26 +304,48 @@ macro_rules! test_PoK_VC {
         secrets.push(FieldElement::random());
 
         let committed = commiting.finish();
+        assert_eq!(committed.bases(), gens.as_slice());
+        assert!(committed
+            .to_bytes()
+            .ends_with(&committed.commitment().to_bytes()));
+        let mut transcript = $crate::transcript::Transcript::new(b"test");
+        transcript.absorb(&committed);
         let commitment = gens.multi_scalar_mul_const_time(&secrets).unwrap();
         let challenge = committed.gen_challenge(commitment.to_bytes());
+        match committed
+            .clone()
+            .gen_proof(&challenge, &secrets.as_slice()[1..])
+        {
+            Err(PSError::MismatchedWitnessCount { expected, got }) => {
+                assert_eq!((expected, got), ($n, $n - 1))
+            }
+            _ => panic!("Proof generated with a missing secret"),
+        }
         let proof = committed.gen_proof(&challenge, secrets.as_slice()).unwrap();
 
         assert!(proof
             .verify(gens.as_slice(), &commitment, &challenge)
             .unwrap());
+        assert!(proof.to_bytes().starts_with(&proof.commitment.to_bytes()));
+        let mut transcript_1 = $crate::transcript::Transcript::new(b"test");
+        proof.absorb_with_bases(gens.as_slice(), &mut transcript_1);
+        assert_eq!(transcript_1.challenge(), transcript.challenge());
         // Wrong challenge or commitment fails to verify
         assert!(!proof
             .verify(gens.as_slice(), &$group_element::random(), &challenge)
             .unwrap());
         assert!(!proof
-            .verify(gens.as_slice(), &commitment, &FieldElement::random())
+            .verify(
+                gens.as_slice(),
+                &commitment,
+                &$crate::types::Challenge(FieldElement::random())
+            )
             .unwrap());
     };
 }
 
 #[cfg(test)]
 pub(crate) mod tests {
-    use super::*;
     // XXX: Error for VC should be independent of PS
     use crate::errors::PSError;
     use amcl_wrapper::field_elem::{FieldElement, FieldElementVector};
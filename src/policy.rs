@@ -0,0 +1,351 @@
+// Declarative verifier policies. Policies are checked only after the proof of knowledge of signature
+// has been verified cryptographically and report every violation rather than stopping at the first one.
+
+use crate::boolean::FlagStatement;
+use crate::context::verkey_id;
+use crate::errors::PSError;
+use crate::keys::Verkey;
+use crate::signature::Fingerprint;
+use crate::utils::hash_with_domain;
+use amcl_wrapper::field_elem::FieldElement;
+use std::collections::HashMap;
+
+/// Condition that a revealed message must satisfy. `IsBit` and `IsTrue` are also satisfied by a
+/// hidden message with a flag proof, see `FlagPresentation`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Predicate {
+    Equals(FieldElement),
+    OneOf(Vec<FieldElement>),
+    /// The message is 0 or 1
+    IsBit,
+    /// The message is 1
+    IsTrue,
+}
+
+impl Predicate {
+    pub fn is_satisfied_by(&self, value: &FieldElement) -> bool {
+        match self {
+            Predicate::Equals(v) => v == value,
+            Predicate::OneOf(vs) => vs.iter().any(|v| v == value),
+            Predicate::IsBit => value.is_zero() || *value == FieldElement::one(),
+            Predicate::IsTrue => *value == FieldElement::one(),
+        }
+    }
+
+    /// Policy requiring the boolean attribute to be 1, revealed or proven hidden
+    pub fn is_true(attribute: &str) -> Policy {
+        Policy::require_predicate(attribute, Predicate::IsTrue)
+    }
+}
+
+/// A single policy. Attributes are referred to by name and resolved to message indices when the
+/// policies are compiled.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Policy {
+    RequireRevealed {
+        attribute: String,
+    },
+    RequirePredicate {
+        attribute: String,
+        predicate: Predicate,
+    },
+    TrustedIssuers {
+        verkeys: Vec<Verkey>,
+    },
+}
+
+impl Policy {
+    pub fn require_revealed(attribute: &str) -> Self {
+        Policy::RequireRevealed {
+            attribute: attribute.to_string(),
+        }
+    }
+
+    /// The attribute must be revealed and its value must satisfy `predicate`
+    pub fn require_predicate(attribute: &str, predicate: Predicate) -> Self {
+        Policy::RequirePredicate {
+            attribute: attribute.to_string(),
+            predicate,
+        }
+    }
+
+    pub fn trusted_issuers(verkeys: &[Verkey]) -> Self {
+        Policy::TrustedIssuers {
+            verkeys: verkeys.to_vec(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PolicyViolation {
+    NotRevealed { attribute: String },
+    PredicateNotSatisfied { attribute: String },
+    UntrustedIssuer,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum CompiledCheck {
+    Revealed {
+        attribute: String,
+        idx: usize,
+    },
+    Predicate {
+        attribute: String,
+        idx: usize,
+        predicate: Predicate,
+    },
+    Issuer {
+        verkeys: Vec<Verkey>,
+    },
+}
+
+/// Policies with attribute names resolved to message indices.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CompiledPolicy {
+    checks: Vec<CompiledCheck>,
+}
+
+impl CompiledPolicy {
+    /// `attribute_names[i]` is the name of the i-th message of the signature.
+    pub fn compile(policies: &[Policy], attribute_names: &[&str]) -> Result<Self, PSError> {
+        let index_of = |attribute: &str| -> Result<usize, PSError> {
+            attribute_names
+                .iter()
+                .position(|n| *n == attribute)
+                .ok_or_else(|| PSError::UnknownAttribute {
+                    name: attribute.to_string(),
+                })
+        };
+        let mut checks = Vec::with_capacity(policies.len());
+        for policy in policies {
+            let check = match policy {
+                Policy::RequireRevealed { attribute } => CompiledCheck::Revealed {
+                    attribute: attribute.clone(),
+                    idx: index_of(attribute)?,
+                },
+                Policy::RequirePredicate {
+                    attribute,
+                    predicate,
+                } => CompiledCheck::Predicate {
+                    attribute: attribute.clone(),
+                    idx: index_of(attribute)?,
+                    predicate: predicate.clone(),
+                },
+                Policy::TrustedIssuers { verkeys } => CompiledCheck::Issuer {
+                    verkeys: verkeys.clone(),
+                },
+            };
+            checks.push(check);
+        }
+        Ok(Self { checks })
+    }
+
+    /// The same checks with every message index `i` replaced by `f(i)`
+    pub(crate) fn map_indices<F: Fn(usize) -> Result<usize, PSError>>(
+        &self,
+        f: F,
+    ) -> Result<Self, PSError> {
+        let checks = self
+            .checks
+            .iter()
+            .map(|check| {
+                Ok(match check {
+                    CompiledCheck::Revealed { attribute, idx } => CompiledCheck::Revealed {
+                        attribute: attribute.clone(),
+                        idx: f(*idx)?,
+                    },
+                    CompiledCheck::Predicate {
+                        attribute,
+                        idx,
+                        predicate,
+                    } => CompiledCheck::Predicate {
+                        attribute: attribute.clone(),
+                        idx: f(*idx)?,
+                        predicate: predicate.clone(),
+                    },
+                    CompiledCheck::Issuer { verkeys } => CompiledCheck::Issuer {
+                        verkeys: verkeys.clone(),
+                    },
+                })
+            })
+            .collect::<Result<_, PSError>>()?;
+        Ok(Self { checks })
+    }
+
+    /// Unambiguous encoding of the checks in order, verkeys by `verkey_id`
+    pub fn to_canonical_bytes(&self) -> Vec<u8> {
+        let mut bytes = (self.checks.len() as u64).to_be_bytes().to_vec();
+        for check in &self.checks {
+            let mut put_attribute = |tag: u8, attribute: &str, idx: usize| {
+                bytes.push(tag);
+                bytes.extend_from_slice(&(attribute.len() as u64).to_be_bytes());
+                bytes.extend_from_slice(attribute.as_bytes());
+                bytes.extend_from_slice(&(idx as u64).to_be_bytes());
+            };
+            match check {
+                CompiledCheck::Revealed { attribute, idx } => put_attribute(0, attribute, *idx),
+                CompiledCheck::Predicate {
+                    attribute,
+                    idx,
+                    predicate,
+                } => {
+                    put_attribute(1, attribute, *idx);
+                    let values = match predicate {
+                        Predicate::Equals(v) => {
+                            bytes.push(0);
+                            vec![v.clone()]
+                        }
+                        Predicate::OneOf(vs) => {
+                            bytes.push(1);
+                            vs.clone()
+                        }
+                        Predicate::IsBit => {
+                            bytes.push(2);
+                            vec![]
+                        }
+                        Predicate::IsTrue => {
+                            bytes.push(3);
+                            vec![]
+                        }
+                    };
+                    bytes.extend_from_slice(&(values.len() as u64).to_be_bytes());
+                    for v in values {
+                        bytes.append(&mut v.to_bytes());
+                    }
+                }
+                CompiledCheck::Issuer { verkeys } => {
+                    bytes.push(2);
+                    bytes.extend_from_slice(&(verkeys.len() as u64).to_be_bytes());
+                    for vk in verkeys {
+                        bytes.extend_from_slice(&verkey_id(vk));
+                    }
+                }
+            }
+        }
+        bytes
+    }
+
+    pub fn hash(&self) -> Fingerprint {
+        hash_with_domain(b"PS compiled policy", &self.to_canonical_bytes())
+    }
+
+    /// Run all checks against the issuer key and the revealed messages. Returns all violations found.
+    pub fn evaluate(
+        &self,
+        vk: &Verkey,
+        revealed_msgs: &HashMap<usize, FieldElement>,
+    ) -> Vec<PolicyViolation> {
+        self.evaluate_with_flags(vk, revealed_msgs, &HashMap::new())
+    }
+
+    /// Same as `evaluate` where predicates on hidden messages are satisfied by the statements proven
+    /// about them in `flags`
+    pub fn evaluate_with_flags(
+        &self,
+        vk: &Verkey,
+        revealed_msgs: &HashMap<usize, FieldElement>,
+        flags: &HashMap<usize, FlagStatement>,
+    ) -> Vec<PolicyViolation> {
+        let mut violations = vec![];
+        for check in &self.checks {
+            match check {
+                CompiledCheck::Revealed { attribute, idx } => {
+                    if !revealed_msgs.contains_key(idx) {
+                        violations.push(PolicyViolation::NotRevealed {
+                            attribute: attribute.clone(),
+                        });
+                    }
+                }
+                CompiledCheck::Predicate {
+                    attribute,
+                    idx,
+                    predicate,
+                } => match revealed_msgs.get(idx) {
+                    None if flags.get(idx).is_some_and(|f| f.proves(predicate)) => (),
+                    None => violations.push(PolicyViolation::NotRevealed {
+                        attribute: attribute.clone(),
+                    }),
+                    Some(m) if !predicate.is_satisfied_by(m) => {
+                        violations.push(PolicyViolation::PredicateNotSatisfied {
+                            attribute: attribute.clone(),
+                        })
+                    }
+                    Some(_) => (),
+                },
+                CompiledCheck::Issuer { verkeys } => {
+                    if !verkeys.contains(vk) {
+                        violations.push(PolicyViolation::UntrustedIssuer);
+                    }
+                }
+            }
+        }
+        violations
+    }
+}
+
+/// Result of verifying a proof followed by the policy checks. Policies are not evaluated for an
+/// invalid proof so `violations` is empty in that case.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PolicyReport {
+    pub proof_valid: bool,
+    pub violations: Vec<PolicyViolation>,
+}
+
+impl PolicyReport {
+    pub fn is_accepted(&self) -> bool {
+        self.proof_valid && self.violations.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::{keygen, Params};
+    use amcl_wrapper::field_elem::FieldElementVector;
+
+    #[test]
+    fn test_policy_evaluation() {
+        let params = Params::new("test".as_bytes());
+        let (_, vk) = keygen(3, &params);
+        let (_, other_vk) = keygen(3, &params);
+        let msgs = FieldElementVector::random(3);
+        let names = ["name", "dob", "country"];
+
+        let mut revealed_msgs = HashMap::new();
+        revealed_msgs.insert(1, msgs[1].clone());
+        revealed_msgs.insert(2, msgs[2].clone());
+
+        let policies = vec![
+            Policy::require_revealed("dob"),
+            Policy::require_predicate("country", Predicate::OneOf(vec![msgs[2].clone()])),
+            Policy::trusted_issuers(std::slice::from_ref(&vk)),
+        ];
+        let compiled = CompiledPolicy::compile(&policies, &names).unwrap();
+        assert!(compiled.evaluate(&vk, &revealed_msgs).is_empty());
+
+        let violations = compiled.evaluate(&other_vk, &HashMap::new());
+        assert_eq!(
+            violations,
+            vec![
+                PolicyViolation::NotRevealed {
+                    attribute: "dob".to_string()
+                },
+                PolicyViolation::NotRevealed {
+                    attribute: "country".to_string()
+                },
+                PolicyViolation::UntrustedIssuer,
+            ]
+        );
+
+        revealed_msgs.insert(2, FieldElement::random());
+        assert_eq!(
+            compiled.evaluate(&vk, &revealed_msgs),
+            vec![PolicyViolation::PredicateNotSatisfied {
+                attribute: "country".to_string()
+            }]
+        );
+
+        // Attribute names are resolved during compilation
+        assert!(CompiledPolicy::compile(&[Policy::require_revealed("email")], &names).is_err());
+    }
+}
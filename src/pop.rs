@@ -0,0 +1,188 @@
+// Proof of possession of an issuer's keys, for registries to reject rogue keys, e.g. a verkey computed
+// from other issuers' verkeys without knowing its signing key. For each secret s among x, y_1, y_2, ...
+// the issuer proves knowledge of s in both g^s (X or Y_i of the `BlindingKey`) and g_tilde^s (X_tilde
+// or Y_tilde_i of the `Verkey`) with the same blinding, so equal responses also show both elements have
+// the same exponent. All proofs share one challenge bound to a registry chosen `context`.
+
+use crate::blind_signature::{BlindingKey, ProofSignatureGroup, ProverCommittingSignatureGroup};
+use crate::errors::PSError;
+use crate::keys::{Params, Sigkey, Verkey, VerkeyValidation};
+use crate::pok_sig::{ProofOtherGroup, ProverCommittingOtherGroup};
+use crate::signer::PsSigner;
+use crate::transcript::Transcript;
+use crate::{OtherGroup, SignatureGroup};
+use amcl_wrapper::field_elem::FieldElement;
+use amcl_wrapper::group_elem::GroupElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VerkeyPoP {
+    /// Proofs for X, Y_1, Y_2, ...
+    pub proofs_signature_group: Vec<ProofSignatureGroup>,
+    /// Proofs for X_tilde, Y_tilde_1, Y_tilde_2, ...
+    pub proofs_other_group: Vec<ProofOtherGroup>,
+}
+
+fn transcript_for(vk: &Verkey, blinding_key: &BlindingKey, context: &[u8]) -> Transcript {
+    let mut transcript = Transcript::new(b"PS verkey proof of possession");
+    transcript.append_message(b"context", context);
+    transcript.append_message(b"verkey", &vk.to_bytes());
+    let mut bytes = blinding_key.X.to_bytes();
+    for y in &blinding_key.Y {
+        bytes.append(&mut y.to_bytes());
+    }
+    transcript.append_message(b"blinding key", &bytes);
+    transcript
+}
+
+impl VerkeyPoP {
+    pub fn new<S: PsSigner + ?Sized>(
+        signer: &S,
+        vk: &Verkey,
+        blinding_key: &BlindingKey,
+        params: &Params,
+        context: &[u8],
+    ) -> Result<Self, PSError> {
+        Self::new_with_rng(signer, vk, blinding_key, params, context, &mut thread_rng())
+    }
+
+    /// The signer computes the proof, see `PsSigner::prove_possession`
+    pub fn new_with_rng<S: PsSigner + ?Sized, R: RngCore + CryptoRng>(
+        signer: &S,
+        vk: &Verkey,
+        blinding_key: &BlindingKey,
+        params: &Params,
+        context: &[u8],
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        signer.prove_possession(vk, blinding_key, params, context, rng)
+    }
+
+    /// Proof with the secrets of `sigkey`
+    pub(crate) fn from_sigkey<R: RngCore + CryptoRng>(
+        sigkey: &Sigkey,
+        vk: &Verkey,
+        blinding_key: &BlindingKey,
+        params: &Params,
+        context: &[u8],
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        if sigkey.y.len() != vk.Y_tilde.len() {
+            return Err(PSError::MismatchedWitnessCount {
+                expected: vk.Y_tilde.len() + 1,
+                got: sigkey.y.len() + 1,
+            });
+        }
+        vk.validate(blinding_key, params, VerkeyValidation::Lengths)?;
+        let secrets: Vec<&FieldElement> = std::iter::once(&sigkey.x).chain(&sigkey.y).collect();
+        let mut transcript = transcript_for(vk, blinding_key, context);
+        let mut committed_sig = vec![];
+        let mut committed_other = vec![];
+        for _ in &secrets {
+            let r = FieldElement::random_using_rng(rng);
+            let mut c = ProverCommittingSignatureGroup::new();
+            c.commit(&params.g, Some(&r));
+            let c = c.finish();
+            transcript.absorb(&c);
+            committed_sig.push(c);
+            let mut c = ProverCommittingOtherGroup::new();
+            c.commit(&params.g_tilde, Some(&r));
+            let c = c.finish();
+            transcript.absorb(&c);
+            committed_other.push(c);
+        }
+        let challenge = transcript.challenge();
+        let mut proofs_signature_group = vec![];
+        let mut proofs_other_group = vec![];
+        for ((s, cs), co) in secrets.into_iter().zip(committed_sig).zip(committed_other) {
+            proofs_signature_group.push(cs.gen_proof(&challenge, std::slice::from_ref(s))?);
+            proofs_other_group.push(co.gen_proof(&challenge, std::slice::from_ref(s))?);
+        }
+        Ok(Self {
+            proofs_signature_group,
+            proofs_other_group,
+        })
+    }
+
+    pub fn verify(
+        &self,
+        vk: &Verkey,
+        blinding_key: &BlindingKey,
+        params: &Params,
+        context: &[u8],
+    ) -> Result<bool, PSError> {
+        let count = vk.Y_tilde.len() + 1;
+        if blinding_key.Y.len() + 1 != count
+            || self.proofs_signature_group.len() != count
+            || self.proofs_other_group.len() != count
+        {
+            return Ok(false);
+        }
+        let sig_elems: Vec<&SignatureGroup> = std::iter::once(&blinding_key.X)
+            .chain(&blinding_key.Y)
+            .collect();
+        let other_elems: Vec<&OtherGroup> =
+            std::iter::once(&vk.X_tilde).chain(&vk.Y_tilde).collect();
+        let sig_base = std::slice::from_ref(&params.g);
+        let other_base = std::slice::from_ref(&params.g_tilde);
+
+        let mut transcript = transcript_for(vk, blinding_key, context);
+        for (ps, po) in self
+            .proofs_signature_group
+            .iter()
+            .zip(&self.proofs_other_group)
+        {
+            if ps.responses.len() != 1 || po.responses.len() != 1 {
+                return Ok(false);
+            }
+            ps.absorb_with_bases(sig_base, &mut transcript);
+            po.absorb_with_bases(other_base, &mut transcript);
+        }
+        let challenge = transcript.challenge();
+        for i in 0..count {
+            let ps = &self.proofs_signature_group[i];
+            let po = &self.proofs_other_group[i];
+            if sig_elems[i].is_identity()
+                || other_elems[i].is_identity()
+                || ps.responses[0] != po.responses[0]
+                || !ps.verify(sig_base, sig_elems[i], &challenge)?
+                || !po.verify(other_base, other_elems[i], &challenge)?
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+
+    #[test]
+    fn test_verkey_pop() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let blinding_key = BlindingKey::new(&sk, &params);
+        let pop = VerkeyPoP::new(&sk, &vk, &blinding_key, &params, b"registry").unwrap();
+        assert!(pop
+            .verify(&vk, &blinding_key, &params, b"registry")
+            .unwrap());
+        assert!(!pop.verify(&vk, &blinding_key, &params, b"other").unwrap());
+
+        // Rogue key: X_tilde is the sum of 2 honest keys' X_tilde, the issuer knows no signing key for it
+        let (sk_1, vk_1) = keygen(3, &params);
+        let mut rogue = vk.clone();
+        rogue.X_tilde = &vk.X_tilde + &vk_1.X_tilde;
+        assert!(!pop
+            .verify(&rogue, &blinding_key, &params, b"registry")
+            .unwrap());
+        // A proof for other keys does not verify for this one
+        let blinding_key_1 = BlindingKey::new(&sk_1, &params);
+        let pop_1 = VerkeyPoP::new(&sk_1, &vk_1, &blinding_key_1, &params, b"registry").unwrap();
+        assert!(!pop_1
+            .verify(&vk, &blinding_key, &params, b"registry")
+            .unwrap());
+    }
+}
@@ -0,0 +1,294 @@
+// Keys precomputed once for holders creating many proofs or credential requests under the same key.
+// `Verkey::precompute` builds a `FixedBaseTable` for g_tilde and each Y_tilde_i so J and the Schnorr
+// commitment of `PoKOfSignature::init` are sums of table lookups instead of multi-scalar
+// multiplications. `BlindingKey::precompute` does the same for g and each Y_i, used in the commitments
+// of `CredentialRequest::new`. Proofs and requests are the same as without tables, only cheaper.
+//
+// A table holds 1024 group elements, so a precomputed key for n messages holds (n + 1) * 1024 group
+// elements and costs about as much to build as 5 * (n + 1) scalar multiplications. It pays off when
+// the same key is used for more than a handful of proofs.
+
+use crate::blind_signature::BlindingKey;
+use crate::errors::PSError;
+use crate::issuance::CredentialRequest;
+use crate::keys::{Params, Verkey};
+use crate::pok_sig::PoKOfSignature;
+use crate::signature::Signature;
+use crate::types::{Blinding, Message};
+use crate::utils::FixedBaseTable;
+use crate::{OtherGroup, SignatureGroup};
+use amcl_wrapper::field_elem::FieldElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::HashSet;
+
+#[derive(Clone, Debug)]
+pub struct PrecomputedVerkey {
+    vk: Verkey,
+    params: Params,
+    g_tilde: FixedBaseTable<OtherGroup>,
+    Y_tilde: Vec<FixedBaseTable<OtherGroup>>,
+}
+
+impl Verkey {
+    /// Fixed-base tables of g_tilde and Y_tilde for `PoKOfSignature::init_precomputed`
+    pub fn precompute(&self, params: &Params) -> PrecomputedVerkey {
+        PrecomputedVerkey {
+            vk: self.clone(),
+            params: params.clone(),
+            g_tilde: FixedBaseTable::new(&params.g_tilde),
+            Y_tilde: self.Y_tilde.iter().map(FixedBaseTable::new).collect(),
+        }
+    }
+}
+
+impl PrecomputedVerkey {
+    pub fn verkey(&self) -> &Verkey {
+        &self.vk
+    }
+
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    /// g_tilde^scalars[0] * Y_tilde_{hidden[0]}^scalars[1] * Y_tilde_{hidden[1]}^scalars[2] * ...
+    pub(crate) fn mul_hidden(&self, hidden: &[usize], scalars: &[FieldElement]) -> OtherGroup {
+        let mut acc = self.g_tilde.mul(&scalars[0]);
+        for (i, s) in hidden.iter().zip(&scalars[1..]) {
+            acc += self.Y_tilde[*i].mul(s);
+        }
+        acc
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PrecomputedBlindingKey {
+    blinding_key: BlindingKey,
+    params: Params,
+    g: FixedBaseTable<SignatureGroup>,
+    Y: Vec<FixedBaseTable<SignatureGroup>>,
+}
+
+impl BlindingKey {
+    /// Fixed-base tables of g and Y for `CredentialRequest::new_precomputed`
+    pub fn precompute(&self, params: &Params) -> PrecomputedBlindingKey {
+        PrecomputedBlindingKey {
+            blinding_key: self.clone(),
+            params: params.clone(),
+            g: FixedBaseTable::new(&params.g),
+            Y: self.Y.iter().map(FixedBaseTable::new).collect(),
+        }
+    }
+}
+
+impl PrecomputedBlindingKey {
+    pub fn blinding_key(&self) -> &BlindingKey {
+        &self.blinding_key
+    }
+
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    /// Y_1^scalars[0] * Y_2^scalars[1] * ... * g^scalars[n], the last scalar is for g
+    pub(crate) fn mul_committed(&self, scalars: &[FieldElement]) -> SignatureGroup {
+        let (last, rest) = scalars.split_last().unwrap();
+        let mut acc = self.g.mul(last);
+        for (table, s) in self.Y.iter().zip(rest) {
+            acc += table.mul(s);
+        }
+        acc
+    }
+}
+
+impl PoKOfSignature {
+    /// Same as `init` with a verkey precomputed by `Verkey::precompute`
+    pub fn init_precomputed(
+        sig: &Signature,
+        precomputed: &PrecomputedVerkey,
+        messages: &[Message],
+        blindings: Option<&[FieldElement]>,
+        revealed_msg_indices: HashSet<usize>,
+    ) -> Result<Self, PSError> {
+        Self::init_precomputed_with_rng(
+            sig,
+            precomputed,
+            messages,
+            blindings,
+            revealed_msg_indices,
+            &mut thread_rng(),
+        )
+    }
+
+    pub fn init_precomputed_with_rng<R: RngCore + CryptoRng>(
+        sig: &Signature,
+        precomputed: &PrecomputedVerkey,
+        messages: &[Message],
+        blindings: Option<&[FieldElement]>,
+        revealed_msg_indices: HashSet<usize>,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        Self::init_with_tables(
+            sig,
+            &precomputed.vk,
+            &precomputed.params,
+            messages,
+            blindings,
+            revealed_msg_indices,
+            Some(precomputed),
+            rng,
+        )
+    }
+}
+
+impl CredentialRequest {
+    /// Same as `new` with a blinding key precomputed by `BlindingKey::precompute`
+    pub fn new_precomputed(
+        committed_messages: &[FieldElement],
+        known_messages: &[FieldElement],
+        precomputed: &PrecomputedBlindingKey,
+        nonce: &[u8],
+    ) -> Result<(Self, Blinding), PSError> {
+        Self::new_precomputed_with_rng(
+            committed_messages,
+            known_messages,
+            precomputed,
+            nonce,
+            &mut thread_rng(),
+        )
+    }
+
+    pub fn new_precomputed_with_rng<R: RngCore + CryptoRng>(
+        committed_messages: &[FieldElement],
+        known_messages: &[FieldElement],
+        precomputed: &PrecomputedBlindingKey,
+        nonce: &[u8],
+        rng: &mut R,
+    ) -> Result<(Self, Blinding), PSError> {
+        Self::new_with_tables(
+            committed_messages,
+            known_messages,
+            &precomputed.blinding_key,
+            &precomputed.params,
+            nonce,
+            Some(precomputed),
+            rng,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blind_signature::BlindSignature;
+    use crate::keys::keygen;
+    use crate::policy::CompiledPolicy;
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use amcl_wrapper::group_elem::GroupElement;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_precomputed_keys() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(5, &params);
+        let precomputed = vk.precompute(&params);
+        let msgs = FieldElementVector::random(5);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+
+        // Same proof as without tables for the same randomness
+        let revealed: HashSet<usize> = vec![1, 3].into_iter().collect();
+        let pok = PoKOfSignature::init_with_rng(
+            &sig,
+            &vk,
+            &params,
+            &Message::from_field_elements(msgs.as_slice()),
+            None,
+            revealed.clone(),
+            &mut ChaChaRng::seed_from_u64(7),
+        )
+        .unwrap();
+        let pok_pre = PoKOfSignature::init_precomputed_with_rng(
+            &sig,
+            &precomputed,
+            &Message::from_field_elements(msgs.as_slice()),
+            None,
+            revealed.clone(),
+            &mut ChaChaRng::seed_from_u64(7),
+        )
+        .unwrap();
+        assert_eq!(pok.J, pok_pre.J);
+        assert_eq!(pok.to_bytes(), pok_pre.to_bytes());
+
+        let pok = PoKOfSignature::init_precomputed(
+            &sig,
+            &precomputed,
+            &Message::from_field_elements(msgs.as_slice()),
+            None,
+            revealed.clone(),
+        )
+        .unwrap();
+        let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
+        let proof = pok.gen_proof(&chal).unwrap();
+        let mut revealed_msgs = HashMap::new();
+        for i in &revealed {
+            revealed_msgs.insert(*i, msgs[*i].clone());
+        }
+        assert!(proof.verify(&vk, &params, revealed_msgs, &chal).unwrap());
+
+        // All messages revealed, J is g_tilde^t
+        let all: HashSet<usize> = (0..5).collect();
+        let pok = PoKOfSignature::init_precomputed(
+            &sig,
+            &precomputed,
+            &Message::from_field_elements(msgs.as_slice()),
+            None,
+            all,
+        )
+        .unwrap();
+        let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
+        let proof = pok.gen_proof(&chal).unwrap();
+        let all_msgs: HashMap<usize, FieldElement> = (0..5).map(|i| (i, msgs[i].clone())).collect();
+        assert!(proof.verify(&vk, &params, all_msgs, &chal).unwrap());
+
+        // Blind issuance
+        let blinding_key = BlindingKey::new(&sk, &params);
+        let precomputed_bk = blinding_key.precompute(&params);
+        let (request, _) = CredentialRequest::new_with_rng(
+            &msgs.as_slice()[..3],
+            &msgs.as_slice()[3..],
+            &blinding_key,
+            &params,
+            b"nonce",
+            &mut ChaChaRng::seed_from_u64(9),
+        )
+        .unwrap();
+        let (request_pre, blinding) = CredentialRequest::new_precomputed_with_rng(
+            &msgs.as_slice()[..3],
+            &msgs.as_slice()[3..],
+            &precomputed_bk,
+            b"nonce",
+            &mut ChaChaRng::seed_from_u64(9),
+        )
+        .unwrap();
+        assert_eq!(request.commitment, request_pre.commitment);
+        assert_eq!(request.proof.commitment, request_pre.proof.commitment);
+        let policy = CompiledPolicy::compile(&[], &["a", "b", "c", "d", "e"]).unwrap();
+        let sig = request_pre
+            .issue(&sk, &vk, &blinding_key, &params, &policy, b"nonce")
+            .unwrap();
+        let sig = BlindSignature::unblind(&sig, &blinding);
+        assert!(sig
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+
+        assert!(CredentialRequest::new_precomputed(
+            &msgs.as_slice()[..3],
+            &msgs.as_slice()[4..],
+            &precomputed_bk,
+            b"nonce",
+        )
+        .is_err());
+    }
+}
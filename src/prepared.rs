@@ -0,0 +1,170 @@
+// Verkeys prepared once for verifiers checking many signatures or proofs under the same key. amcl has
+// no Miller loop precomputation, see `ate_2_pairing`, so what can be prepared is everything around the
+// pairings: the verkey is validated with `ValidationProfile::Paranoid` once instead of per verification
+// and -g_tilde is precomputed so the pairing check e(sigma_1, Y_m) * e(sigma_2, -g_tilde) == 1 does not
+// negate sigma_2 every time.
+
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::pok_sig::PoKOfSignatureProof;
+use crate::signature::Signature;
+use crate::types::{Challenge, Message};
+use crate::utils::MultiScalarMul;
+use crate::validation::ValidationProfile;
+use crate::{ate_2_pairing, OtherGroup, SignatureGroup};
+use amcl_wrapper::field_elem::FieldElement;
+use amcl_wrapper::group_elem::GroupElement;
+use std::collections::HashMap;
+
+#[derive(Clone, Debug)]
+pub struct PreparedVerkey {
+    vk: Verkey,
+    params: Params,
+    neg_g_tilde: OtherGroup,
+}
+
+impl PreparedVerkey {
+    /// Fails if `vk` has identity elements or elements of wrong order
+    pub fn new(vk: &Verkey, params: &Params) -> Result<Self, PSError> {
+        if !ValidationProfile::Paranoid.is_valid_verkey(vk) {
+            return Err(PSError::InvalidEncoding {
+                msg: String::from("Verkey has identity elements or elements of wrong order"),
+            });
+        }
+        Ok(Self {
+            vk: vk.clone(),
+            params: params.clone(),
+            neg_g_tilde: params.g_tilde.negation(),
+        })
+    }
+
+    pub fn verkey(&self) -> &Verkey {
+        &self.vk
+    }
+
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    /// e(sigma_1, Y_m) * e(sigma_2, -g_tilde) == 1
+    fn check_pairing(
+        &self,
+        sigma_1: &SignatureGroup,
+        Y_m: &OtherGroup,
+        sigma_2: &SignatureGroup,
+    ) -> bool {
+        ate_2_pairing(sigma_1, Y_m, sigma_2, &self.neg_g_tilde).is_one()
+    }
+}
+
+impl Signature {
+    /// Same as `verify` with a verkey prepared by `PreparedVerkey::new`
+    pub fn verify_prepared(
+        &self,
+        messages: &[Message],
+        prepared: &PreparedVerkey,
+    ) -> Result<bool, PSError> {
+        Self::check_verkey_and_messages_compat(messages, &prepared.vk)?;
+        if self.sigma_1.is_identity() || self.sigma_2.is_identity() {
+            return Ok(false);
+        }
+        fail_point!(MultiScalarMul);
+        let mut msm = MultiScalarMul::new();
+        for (Y_tilde_i, m) in prepared.vk.Y_tilde.iter().zip(messages) {
+            msm.add(Y_tilde_i, m.as_field());
+        }
+        let Y_m = &prepared.vk.X_tilde + &msm.finish();
+        fail_point!(Pairing);
+        Ok(prepared.check_pairing(&self.sigma_1, &Y_m, &self.sigma_2))
+    }
+}
+
+impl PoKOfSignatureProof {
+    /// Same as `verify` with a verkey prepared by `PreparedVerkey::new`
+    pub fn verify_prepared(
+        &self,
+        prepared: &PreparedVerkey,
+        revealed_msgs: &HashMap<usize, FieldElement>,
+        challenge: &Challenge,
+    ) -> Result<bool, PSError> {
+        if !self.verify_fast_reject(&prepared.vk, &prepared.params, revealed_msgs, challenge)? {
+            return Ok(false);
+        }
+        fail_point!(Pairing);
+        let J = self.full_J(&prepared.vk, revealed_msgs);
+        Ok(prepared.check_pairing(
+            &self.sig.sigma_1,
+            &(&J + &prepared.vk.X_tilde),
+            &self.sig.sigma_2,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use crate::pok_sig::PoKOfSignature;
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_verify_prepared() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(4, &params);
+        let prepared = PreparedVerkey::new(&vk, &params).unwrap();
+        let msgs = FieldElementVector::random(4);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        assert!(sig
+            .verify_prepared(&Message::from_field_elements(msgs.as_slice()), &prepared)
+            .unwrap());
+        let other_msgs = FieldElementVector::random(4);
+        assert!(!sig
+            .verify_prepared(
+                &Message::from_field_elements(other_msgs.as_slice()),
+                &prepared
+            )
+            .unwrap());
+        assert!(sig
+            .verify_prepared(
+                &Message::from_field_elements(&msgs.as_slice()[1..]),
+                &prepared
+            )
+            .is_err());
+
+        let revealed: HashSet<usize> = vec![1, 3].into_iter().collect();
+        let pok = PoKOfSignature::init(
+            &sig,
+            &vk,
+            &params,
+            &Message::from_field_elements(msgs.as_slice()),
+            None,
+            revealed,
+        )
+        .unwrap();
+        let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
+        let proof = pok.gen_proof(&chal).unwrap();
+        let mut revealed_msgs = HashMap::new();
+        revealed_msgs.insert(1, msgs[1].clone());
+        revealed_msgs.insert(3, msgs[3].clone());
+        assert!(proof
+            .verify_prepared(&prepared, &revealed_msgs, &chal)
+            .unwrap());
+        revealed_msgs.insert(3, msgs[2].clone());
+        assert!(!proof
+            .verify_prepared(&prepared, &revealed_msgs, &chal)
+            .unwrap());
+
+        // Proofs under another key fail
+        let (_, other_vk) = keygen(4, &params);
+        let other = PreparedVerkey::new(&other_vk, &params).unwrap();
+        assert!(!sig
+            .verify_prepared(&Message::from_field_elements(msgs.as_slice()), &other)
+            .unwrap());
+
+        let mut bad_vk = vk.clone();
+        bad_vk.Y_tilde[0] = OtherGroup::identity();
+        assert!(PreparedVerkey::new(&bad_vk, &params).is_err());
+    }
+}
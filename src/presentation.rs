@@ -0,0 +1,608 @@
+// A presentation is a non-interactive proof of knowledge of a signature together with the revealed
+// messages. The challenge is the hash of the proof's public elements and a verifier chosen nonce, so
+// the verifier recomputes it instead of trusting the prover.
+//
+// Two byte formats exist, both starting with a version byte. Version 1 has uncompressed points, the
+// Schnorr commitment and 48 byte field elements. Version 2 compresses points, packs field elements
+// into 32 bytes and replaces the Schnorr commitment with the challenge, from which the verifier
+// reconstructs the commitment. One challenge is shared by all sub-proofs so it is stored only once.
+// Both end with the hash of the disclosure document or consent statement if the presentation has one.
+
+use crate::audit::{AccessContext, AttributeAccess};
+use crate::context::verkey_id;
+use crate::cost::CostModel;
+use crate::disclosure::{DisclosureContext, DisclosureDocument};
+use crate::errors::PSError;
+use crate::key_status::VerkeyStatusChecker;
+use crate::keys::{Params, Verkey};
+use crate::limits::{deserialize_revealed, DecodeLimits};
+use crate::pok_sig::{PoKOfSignature, PoKOfSignatureProof, ProofOtherGroup};
+use crate::signature::{Fingerprint, Signature};
+use crate::types::{Challenge, Message};
+use crate::utils::{
+    pack_field_element, point_from_compressed_bytes, unpack_field_element, ByteReader,
+    MultiScalarMul, PointEncoding, HASH_SIZE, PACKED_FIELD_ELEMENT_SIZE,
+};
+use crate::validation::ValidationProfile;
+use crate::{
+    OtherGroup, SignatureGroup, FIELD_ELEMENT_SIZE, OTHER_GROUP_SIZE, SIGNATURE_GROUP_SIZE,
+};
+use amcl_wrapper::field_elem::{FieldElement, FieldElementVector};
+use amcl_wrapper::group_elem::GroupElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::{HashMap, HashSet};
+
+/// Hashed after the proof's public elements: the length prefixed nonce and the disclosure hash after
+/// a byte telling whether there is one, so neither can be shifted into the other
+pub(crate) fn challenge_suffix(nonce: &[u8], disclosure_hash: Option<&Fingerprint>) -> Vec<u8> {
+    let mut bytes = (nonce.len() as u32).to_be_bytes().to_vec();
+    bytes.extend_from_slice(nonce);
+    match disclosure_hash {
+        Some(h) => {
+            bytes.push(1);
+            bytes.extend_from_slice(h);
+        }
+        None => bytes.push(0),
+    }
+    bytes
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Presentation {
+    pub proof: PoKOfSignatureProof,
+    #[serde(deserialize_with = "deserialize_revealed")]
+    pub revealed_msgs: HashMap<usize, FieldElement>,
+    pub nonce: Vec<u8>,
+    /// Hash of the `DisclosureDocument` or `ConsentStatement` bound into the challenge
+    pub disclosure_hash: Option<Fingerprint>,
+}
+
+impl Presentation {
+    pub fn new(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[FieldElement],
+        revealed_msg_indices: HashSet<usize>,
+        nonce: &[u8],
+    ) -> Result<Self, PSError> {
+        Self::new_with_rng(
+            sig,
+            vk,
+            params,
+            messages,
+            revealed_msg_indices,
+            nonce,
+            &mut thread_rng(),
+        )
+    }
+
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[FieldElement],
+        revealed_msg_indices: HashSet<usize>,
+        nonce: &[u8],
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        let revealed_msgs = revealed_msg_indices
+            .iter()
+            .filter(|i| **i < messages.len())
+            .map(|i| (*i, messages[*i].clone()))
+            .collect();
+        Self::create(
+            sig,
+            vk,
+            params,
+            messages,
+            revealed_msg_indices,
+            revealed_msgs,
+            nonce,
+            None,
+            rng,
+        )
+    }
+
+    /// Presentation bound to the disclosure document rendered from `context` and the plain `values`
+    /// of some revealed messages. The holder shows the document of `render_disclosure` to the user.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_disclosure(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[FieldElement],
+        revealed_msg_indices: HashSet<usize>,
+        nonce: &[u8],
+        context: &DisclosureContext,
+        values: &HashMap<usize, String>,
+    ) -> Result<Self, PSError> {
+        Self::new_with_disclosure_with_rng(
+            sig,
+            vk,
+            params,
+            messages,
+            revealed_msg_indices,
+            nonce,
+            context,
+            values,
+            &mut thread_rng(),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_disclosure_with_rng<R: RngCore + CryptoRng>(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[FieldElement],
+        revealed_msg_indices: HashSet<usize>,
+        nonce: &[u8],
+        context: &DisclosureContext,
+        values: &HashMap<usize, String>,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        let revealed_msgs = revealed_msg_indices
+            .iter()
+            .filter(|i| **i < messages.len())
+            .map(|i| (*i, messages[*i].clone()))
+            .collect();
+        let document = DisclosureDocument::new(context, &revealed_msgs, values)?;
+        Self::create(
+            sig,
+            vk,
+            params,
+            messages,
+            revealed_msg_indices,
+            revealed_msgs,
+            nonce,
+            Some(document.hash()),
+            rng,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn create<R: RngCore + CryptoRng>(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &[FieldElement],
+        revealed_msg_indices: HashSet<usize>,
+        revealed_msgs: HashMap<usize, FieldElement>,
+        nonce: &[u8],
+        disclosure_hash: Option<Fingerprint>,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        let pok = PoKOfSignature::init_with_rng(
+            sig,
+            vk,
+            params,
+            &Message::from_field_elements(messages),
+            None,
+            revealed_msg_indices,
+            rng,
+        )?;
+        let mut bytes = pok.to_bytes();
+        bytes.append(&mut challenge_suffix(nonce, disclosure_hash.as_ref()));
+        let challenge = Challenge::from_msg_hash(&bytes);
+        Ok(Self {
+            proof: pok.gen_proof(&challenge)?,
+            revealed_msgs,
+            nonce: nonce.to_vec(),
+            disclosure_hash,
+        })
+    }
+
+    pub fn revealed_msg_indices(&self) -> HashSet<usize> {
+        self.revealed_msgs.keys().cloned().collect()
+    }
+
+    pub fn challenge(&self, vk: &Verkey, params: &Params) -> Challenge {
+        let mut bytes =
+            self.proof
+                .get_bytes_for_challenge(&self.revealed_msg_indices(), vk, params);
+        bytes.append(&mut challenge_suffix(
+            &self.nonce,
+            self.disclosure_hash.as_ref(),
+        ));
+        Challenge::from_msg_hash(&bytes)
+    }
+
+    /// The disclosure document this presentation is bound to, rendered again from the holder's
+    /// `context` and `values`. Fails if the presentation has no document or the document differs.
+    pub fn render_disclosure(
+        &self,
+        context: &DisclosureContext,
+        values: &HashMap<usize, String>,
+    ) -> Result<DisclosureDocument, PSError> {
+        let document = DisclosureDocument::new(context, &self.revealed_msgs, values)?;
+        if !self.verify_disclosure(&document) {
+            return Err(PSError::GeneralError {
+                msg: String::from("Presentation is not bound to this disclosure document"),
+            });
+        }
+        Ok(document)
+    }
+
+    /// Whether `document` is the one bound into the challenge and shows the revealed messages. Only
+    /// meaningful for a verified presentation.
+    pub fn verify_disclosure(&self, document: &DisclosureDocument) -> bool {
+        self.disclosure_hash == Some(document.hash()) && document.matches(&self.revealed_msgs)
+    }
+
+    /// Checks without pairings, see `PoKOfSignatureProof::verify_fast_reject`
+    pub fn verify_fast_reject(&self, vk: &Verkey, params: &Params) -> Result<bool, PSError> {
+        if self.revealed_msgs.keys().any(|i| *i >= vk.Y_tilde.len()) {
+            return Ok(false);
+        }
+        let challenge = self.challenge(vk, params);
+        self.proof
+            .verify_fast_reject(vk, params, &self.revealed_msgs, &challenge)
+    }
+
+    pub fn verify(&self, vk: &Verkey, params: &Params) -> Result<bool, PSError> {
+        if self.revealed_msgs.keys().any(|i| *i >= vk.Y_tilde.len()) {
+            return Ok(false);
+        }
+        let challenge = self.challenge(vk, params);
+        self.proof
+            .verify(vk, params, self.revealed_msgs.clone(), &challenge)
+    }
+
+    /// Same as `verify` but fails with `PSError::RevokedVerkey` if `checker` reports `vk` revoked
+    pub fn verify_with_status<C: VerkeyStatusChecker + ?Sized>(
+        &self,
+        vk: &Verkey,
+        params: &Params,
+        checker: &C,
+    ) -> Result<bool, PSError> {
+        checker.check(vk)?;
+        self.verify(vk, params)
+    }
+
+    /// Same as `verify` and if the presentation is valid, calls `on_access` for each revealed attribute
+    /// in index order. Fails if a revealed message has no name in `context`.
+    pub fn verify_with_access_log<F: FnMut(&AttributeAccess)>(
+        &self,
+        vk: &Verkey,
+        params: &Params,
+        context: &AccessContext,
+        mut on_access: F,
+    ) -> Result<bool, PSError> {
+        let mut indices: Vec<usize> = self.revealed_msgs.keys().cloned().collect();
+        indices.sort_unstable();
+        if let Some(i) = indices
+            .iter()
+            .find(|i| **i >= context.attribute_names.len())
+        {
+            return Err(PSError::GeneralError {
+                msg: format!("No attribute name for revealed message {}", i),
+            });
+        }
+        if !self.verify(vk, params)? {
+            return Ok(false);
+        }
+        let issuer = verkey_id(vk);
+        for i in indices {
+            on_access(&AttributeAccess {
+                index: i,
+                attribute: context.attribute_names[i],
+                value: &self.revealed_msgs[&i],
+                issuer: &issuer,
+                purpose: context.purpose,
+            });
+        }
+        Ok(true)
+    }
+
+    pub fn fingerprint(&self) -> Fingerprint {
+        self.proof.fingerprint()
+    }
+
+    /// Operations needed by `verify` for a valid presentation
+    pub fn cost_model(&self) -> CostModel {
+        let count_responses = self.proof.proof_vc.responses.len();
+        let hidden_msg_count = count_responses.saturating_sub(1);
+        // sigma_1, sigma_2, J, g_tilde, hidden Y_tilde, commitment followed by nonce and disclosure hash
+        let hashed_bytes = 2 * SIGNATURE_GROUP_SIZE
+            + (hidden_msg_count + 3) * OTHER_GROUP_SIZE
+            + challenge_suffix(&self.nonce, self.disclosure_hash.as_ref()).len();
+        // Proof of knowledge of messages in J with bases g_tilde, hidden Y_tilde and J, then the
+        // revealed messages added to J
+        let other_group_mults = count_responses + 1 + self.revealed_msgs.len();
+        CostModel {
+            pairings: 2,
+            final_exponentiations: 1,
+            hashes_to_field: 1,
+            hashed_bytes,
+            ..CostModel::from_groups(0, other_group_mults)
+        }
+    }
+
+    fn sorted_revealed_msgs(&self) -> Vec<(usize, &FieldElement)> {
+        let mut revealed: Vec<(usize, &FieldElement)> =
+            self.revealed_msgs.iter().map(|(i, m)| (*i, m)).collect();
+        revealed.sort_by_key(|(i, _)| *i);
+        revealed
+    }
+
+    /// Version 1 encoding with uncompressed points
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![FORMAT_V1];
+        bytes.append(&mut self.proof.sig.to_bytes());
+        bytes.append(&mut self.proof.J.to_bytes());
+        bytes.append(&mut self.proof.proof_vc.commitment.to_bytes());
+        bytes.extend_from_slice(&(self.proof.proof_vc.responses.len() as u32).to_be_bytes());
+        for r in self.proof.proof_vc.responses.as_slice() {
+            bytes.append(&mut r.to_bytes());
+        }
+        let revealed = self.sorted_revealed_msgs();
+        bytes.extend_from_slice(&(revealed.len() as u32).to_be_bytes());
+        for (i, m) in revealed {
+            bytes.extend_from_slice(&(i as u32).to_be_bytes());
+            bytes.append(&mut m.to_bytes());
+        }
+        bytes.extend_from_slice(&(self.nonce.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.nonce);
+        if let Some(h) = &self.disclosure_hash {
+            bytes.extend_from_slice(h);
+        }
+        bytes
+    }
+
+    /// Version 2 encoding, needs `vk` and `params` to compute the challenge
+    pub fn to_bytes_v2(&self, vk: &Verkey, params: &Params) -> Vec<u8> {
+        let mut bytes = vec![FORMAT_V2];
+        bytes.append(&mut self.proof.sig.sigma_1.to_compressed_bytes());
+        bytes.append(&mut self.proof.sig.sigma_2.to_compressed_bytes());
+        bytes.append(&mut self.proof.J.to_compressed_bytes());
+        bytes.append(&mut pack_field_element(
+            self.challenge(vk, params).as_field(),
+        ));
+        bytes.extend_from_slice(&(self.proof.proof_vc.responses.len() as u32).to_be_bytes());
+        for r in self.proof.proof_vc.responses.as_slice() {
+            bytes.append(&mut pack_field_element(r));
+        }
+        let revealed = self.sorted_revealed_msgs();
+        bytes.extend_from_slice(&(revealed.len() as u32).to_be_bytes());
+        for (i, m) in revealed {
+            bytes.extend_from_slice(&(i as u32).to_be_bytes());
+            bytes.append(&mut pack_field_element(m));
+        }
+        bytes.extend_from_slice(&(self.nonce.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.nonce);
+        if let Some(h) = &self.disclosure_hash {
+            bytes.extend_from_slice(h);
+        }
+        bytes
+    }
+
+    /// Parse either format. `vk` and `params` are needed to reconstruct the commitment of version 2.
+    pub fn from_bytes(bytes: &[u8], vk: &Verkey, params: &Params) -> Result<Self, PSError> {
+        let limits = DecodeLimits::current();
+        limits.check_proof_size(bytes.len())?;
+        let mut reader = ByteReader::new(bytes);
+        let version = reader.read_u8()?;
+        let profile = ValidationProfile::default();
+        // Version 1 has the commitment, version 2 the challenge
+        let (sigma_1, sigma_2, J, commitment, challenge, field_size) = match version {
+            FORMAT_V1 => (
+                profile.point_from_bytes(reader.take(SIGNATURE_GROUP_SIZE)?)?,
+                profile.point_from_bytes(reader.take(SIGNATURE_GROUP_SIZE)?)?,
+                profile.point_from_bytes(reader.take(OTHER_GROUP_SIZE)?)?,
+                Some(profile.point_from_bytes(reader.take(OTHER_GROUP_SIZE)?)?),
+                None,
+                FIELD_ELEMENT_SIZE,
+            ),
+            FORMAT_V2 => (
+                point_from_compressed_bytes(reader.take(SignatureGroup::COMPRESSED_SIZE)?)?,
+                point_from_compressed_bytes(reader.take(SignatureGroup::COMPRESSED_SIZE)?)?,
+                point_from_compressed_bytes(reader.take(OtherGroup::COMPRESSED_SIZE)?)?,
+                None,
+                Some(unpack_field_element(
+                    reader.take(PACKED_FIELD_ELEMENT_SIZE)?,
+                )?),
+                PACKED_FIELD_ELEMENT_SIZE,
+            ),
+            v => {
+                return Err(PSError::InvalidEncoding {
+                    msg: format!("Unknown presentation format version {}", v),
+                })
+            }
+        };
+        let read_field_element = |b: &[u8]| {
+            if version == FORMAT_V1 {
+                profile.field_element_from_bytes(b)
+            } else {
+                unpack_field_element(b)
+            }
+        };
+
+        let count_responses = reader.read_u32()? as usize;
+        let mut responses =
+            FieldElementVector::with_capacity(count_responses.min(vk.Y_tilde.len() + 1));
+        for _ in 0..count_responses {
+            responses.push(read_field_element(reader.take(field_size)?)?);
+        }
+        let count_revealed = reader.read_u32()? as usize;
+        limits.check_revealed(count_revealed)?;
+        let mut revealed_msgs = HashMap::new();
+        for _ in 0..count_revealed {
+            let idx = reader.read_u32()? as usize;
+            let m = read_field_element(reader.take(field_size)?)?;
+            if idx >= vk.Y_tilde.len() || revealed_msgs.insert(idx, m).is_some() {
+                return Err(PSError::InvalidEncoding {
+                    msg: format!("Invalid or repeated revealed message index {}", idx),
+                });
+            }
+        }
+        let nonce_len = reader.read_u32()? as usize;
+        let nonce = reader.take(nonce_len)?.to_vec();
+        let disclosure_hash = if reader.is_empty() {
+            None
+        } else {
+            let mut h = [0; HASH_SIZE];
+            h.copy_from_slice(reader.take(HASH_SIZE)?);
+            Some(h)
+        };
+        reader.finish()?;
+
+        if responses.len() != vk.Y_tilde.len() - revealed_msgs.len() + 1 {
+            return Err(PSError::UnequalNoOfBasesExponents {
+                bases: vk.Y_tilde.len() - revealed_msgs.len() + 1,
+                exponents: responses.len(),
+            });
+        }
+        let commitment = match (commitment, challenge) {
+            (Some(commitment), _) => commitment,
+            (None, Some(challenge)) => {
+                // commitment = g_tilde^responses[0] * Y_tilde_i^responses[i] ... * J^challenge
+                let mut msm = MultiScalarMul::new();
+                let hidden = (0..vk.Y_tilde.len()).filter(|i| !revealed_msgs.contains_key(i));
+                for (b, r) in Some(&params.g_tilde)
+                    .into_iter()
+                    .chain(hidden.map(|i| &vk.Y_tilde[i]))
+                    .zip(responses.iter())
+                {
+                    msm.add(b, r);
+                }
+                msm.add(&J, &challenge);
+                msm.finish()
+            }
+            (None, None) => unreachable!(),
+        };
+        Ok(Self {
+            proof: PoKOfSignatureProof {
+                sig: Signature { sigma_1, sigma_2 },
+                J,
+                proof_vc: ProofOtherGroup {
+                    commitment,
+                    responses,
+                },
+            },
+            revealed_msgs,
+            nonce,
+            disclosure_hash,
+        })
+    }
+}
+
+const FORMAT_V1: u8 = 1;
+const FORMAT_V2: u8 = 2;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use amcl_wrapper::field_elem::FieldElementVector;
+
+    #[test]
+    fn test_presentation() {
+        let count_msgs = 5;
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(count_msgs, &params);
+        let msgs = FieldElementVector::random(count_msgs);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+
+        let mut revealed = HashSet::new();
+        revealed.insert(1);
+        revealed.insert(3);
+        let pres =
+            Presentation::new(&sig, &vk, &params, msgs.as_slice(), revealed, b"nonce").unwrap();
+        assert_eq!(pres.revealed_msgs[&3], msgs[3]);
+        assert!(pres.verify(&vk, &params).unwrap());
+
+        // Different nonce or revealed message fails
+        let mut pres_1 = pres.clone();
+        pres_1.nonce = b"other nonce".to_vec();
+        assert!(!pres_1.verify(&vk, &params).unwrap());
+        let mut pres_2 = pres.clone();
+        pres_2.revealed_msgs.insert(1, FieldElement::random());
+        assert!(!pres_2.verify(&vk, &params).unwrap());
+        let mut pres_3 = pres.clone();
+        pres_3
+            .revealed_msgs
+            .insert(count_msgs, FieldElement::random());
+        assert!(!pres_3.verify(&vk, &params).unwrap());
+        assert!(!pres_3.verify_fast_reject(&vk, &params).unwrap());
+        assert!(pres.verify_fast_reject(&vk, &params).unwrap());
+        assert!(!pres_1.verify_fast_reject(&vk, &params).unwrap());
+
+        let (_, vk_1) = keygen(count_msgs, &params);
+        assert!(!pres.verify(&vk_1, &params).unwrap());
+
+        let cost = pres.cost_model();
+        assert_eq!(cost.pairings, 2);
+        assert_eq!(cost.g1_scalar_mults + cost.g2_scalar_mults, count_msgs + 2);
+        let mut challenge_bytes =
+            pres.proof
+                .get_bytes_for_challenge(&pres.revealed_msg_indices(), &vk, &params);
+        challenge_bytes.append(&mut challenge_suffix(&pres.nonce, None));
+        assert_eq!(cost.hashed_bytes, challenge_bytes.len());
+    }
+
+    #[test]
+    fn test_presentation_serialization() {
+        let count_msgs = 5;
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(count_msgs, &params);
+        let msgs = FieldElementVector::random(count_msgs);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let mut revealed = HashSet::new();
+        revealed.insert(0);
+        revealed.insert(3);
+        let pres =
+            Presentation::new(&sig, &vk, &params, msgs.as_slice(), revealed, b"nonce").unwrap();
+
+        let v1 = pres.to_bytes();
+        let v2 = pres.to_bytes_v2(&vk, &params);
+        assert!(v2.len() < v1.len());
+        for bytes in &[&v1, &v2] {
+            let p = Presentation::from_bytes(bytes, &vk, &params).unwrap();
+            assert_eq!(p.proof.to_bytes(), pres.proof.to_bytes());
+            assert_eq!(p.revealed_msgs, pres.revealed_msgs);
+            assert!(p.verify(&vk, &params).unwrap());
+            assert!(Presentation::from_bytes(&bytes[..bytes.len() - 1], &vk, &params).is_err());
+            assert!(
+                Presentation::from_bytes(&[bytes.as_slice(), &[0]].concat(), &vk, &params).is_err()
+            );
+        }
+
+        // A wrong challenge reconstructs a commitment that does not verify
+        let chal_offset = 1 + 2 * SignatureGroup::COMPRESSED_SIZE + OtherGroup::COMPRESSED_SIZE;
+        let mut v2_1 = v2.clone();
+        v2_1[chal_offset + PACKED_FIELD_ELEMENT_SIZE - 1] ^= 1;
+        let p = Presentation::from_bytes(&v2_1, &vk, &params).unwrap();
+        assert!(!p.verify(&vk, &params).unwrap());
+
+        let mut v3 = v2.clone();
+        v3[0] = 3;
+        assert!(Presentation::from_bytes(&v3, &vk, &params).is_err());
+    }
+
+    #[test]
+    fn timing_presentation_sizes() {
+        let params = Params::new("test".as_bytes());
+        for count_msgs in &[5, 10, 20] {
+            let (sk, vk) = keygen(*count_msgs, &params);
+            let msgs = FieldElementVector::random(*count_msgs);
+            let sig = Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params)
+                .unwrap();
+            let revealed: HashSet<usize> = (0..count_msgs / 2).collect();
+            let pres =
+                Presentation::new(&sig, &vk, &params, msgs.as_slice(), revealed, b"nonce").unwrap();
+            let v1 = pres.to_bytes().len();
+            let v2 = pres.to_bytes_v2(&vk, &params).len();
+            println!(
+                "Presentation for {} messages with {} revealed is {} bytes in v1 and {} bytes in v2, {:.1}% smaller",
+                count_msgs,
+                count_msgs / 2,
+                v1,
+                v2,
+                100.0 * (v1 - v2) as f64 / v1 as f64
+            );
+        }
+    }
+}
@@ -0,0 +1,113 @@
+// High level API for getting started and for examples. Attributes are strings encoded with
+// `encode_attribute`, all parties use the parameters of `params` and every presentation gets a fresh
+// nonce. A nonce chosen by the prover does not stop a verifier from replaying a presentation to
+// another verifier, anything beyond a demo should use `Presentation::new` with a nonce chosen by the
+// verifier.
+
+use crate::encoding_proof::encode_attribute;
+use crate::errors::PSError;
+use crate::keys::{keygen, Params, Verkey};
+use crate::presentation::Presentation;
+use crate::signature::Signature;
+use crate::types::Message;
+use amcl_wrapper::field_elem::FieldElement;
+use rand::{thread_rng, RngCore};
+
+const PARAMS_LABEL: &[u8] = b"ps_sig quickstart";
+const NONCE_SIZE: usize = 32;
+
+/// Parameters used by `issue`, `present` and `check`
+pub fn params() -> Params {
+    Params::new(PARAMS_LABEL)
+}
+
+/// A signature, the attributes it is over and the issuer's verkey
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Credential {
+    pub sig: Signature,
+    pub attributes: Vec<String>,
+    pub vk: Verkey,
+}
+
+/// Create a new issuer key and sign `attributes` with it. The signing key is not kept.
+pub fn issue(attributes: &[&str]) -> Result<(Verkey, Credential), PSError> {
+    if attributes.is_empty() {
+        return Err(PSError::GeneralError {
+            msg: String::from("Credential needs at least 1 attribute"),
+        });
+    }
+    let params = params();
+    let (sk, vk) = keygen(attributes.len(), &params);
+    let msgs: Vec<Message> = attributes
+        .iter()
+        .map(|a| Message(encode_attribute(a)))
+        .collect();
+    let credential = Credential {
+        sig: Signature::new(&msgs, &sk, &params)?,
+        attributes: attributes.iter().map(|a| a.to_string()).collect(),
+        vk: vk.clone(),
+    };
+    Ok((vk, credential))
+}
+
+/// Presentation revealing the attributes at indices `reveal` and hiding the others
+pub fn present(credential: &Credential, reveal: &[usize]) -> Result<Presentation, PSError> {
+    if let Some(i) = reveal.iter().find(|i| **i >= credential.attributes.len()) {
+        return Err(PSError::GeneralError {
+            msg: format!("No attribute at index {}", i),
+        });
+    }
+    let msgs: Vec<FieldElement> = credential
+        .attributes
+        .iter()
+        .map(|a| encode_attribute(a))
+        .collect();
+    let mut nonce = vec![0; NONCE_SIZE];
+    thread_rng().fill_bytes(&mut nonce);
+    Presentation::new(
+        &credential.sig,
+        &credential.vk,
+        &params(),
+        &msgs,
+        reveal.iter().cloned().collect(),
+        &nonce,
+    )
+}
+
+/// Verify `presentation` under `vk`. The revealed attributes are checked as `encode_attribute` of the
+/// expected values, see `revealed_attribute_is`.
+pub fn check(presentation: &Presentation, vk: &Verkey) -> Result<bool, PSError> {
+    presentation.verify(vk, &params())
+}
+
+/// Whether the attribute at `index` is revealed and is `value`
+pub fn revealed_attribute_is(presentation: &Presentation, index: usize, value: &str) -> bool {
+    presentation.revealed_msgs.get(&index) == Some(&encode_attribute(value))
+}
+
+/// Issue a credential over `attributes`, present it revealing `reveal` and verify the presentation
+pub fn prove_and_verify(attributes: &[&str], reveal: &[usize]) -> Result<bool, PSError> {
+    let (vk, credential) = issue(attributes)?;
+    check(&present(&credential, reveal)?, &vk)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quickstart() {
+        let (vk, credential) = issue(&["Alice", "alice@example.com", "1990"]).unwrap();
+        let presentation = present(&credential, &[2]).unwrap();
+        assert!(check(&presentation, &vk).unwrap());
+        assert!(revealed_attribute_is(&presentation, 2, "1990"));
+        assert!(!revealed_attribute_is(&presentation, 2, "1991"));
+        assert!(!revealed_attribute_is(&presentation, 0, "Alice"));
+
+        let (other_vk, _) = issue(&["Bob", "bob@example.com", "1990"]).unwrap();
+        assert!(!check(&presentation, &other_vk).unwrap());
+        assert!(present(&credential, &[3]).is_err());
+        assert!(issue(&[]).is_err());
+        assert!(prove_and_verify(&["Alice", "1990"], &[0]).unwrap());
+    }
+}
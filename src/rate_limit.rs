@@ -0,0 +1,339 @@
+// Guardrails for issuers. `RateLimitedSigner` owns the signing key and refuses to sign when more than the
+// configured number of signatures were issued in the sliding window, when a blind signature is requested
+// for a commitment that was already signed in the window, or when its RNG fails a health check.
+// Refusals are `PSError::SigningRefused` and do not count towards the limit. Signed commitments are
+// forgotten with their issuance when it leaves the window, so at most `max_signatures` are kept;
+// replays across windows have to be caught by the request's nonce. It is a `PsSigner` so it can be
+// given to the issuance APIs.
+
+use crate::blind_signature::{BlindSignature, BlindingKey};
+use crate::errors::PSError;
+use crate::keys::{Params, Sigkey};
+use crate::signature::{Fingerprint, Signature};
+use crate::signer::PsSigner;
+use crate::utils::hash_with_domain;
+use crate::SignatureGroup;
+use amcl_wrapper::field_elem::FieldElement;
+use amcl_wrapper::group_elem::GroupElement;
+use rand::rngs::ThreadRng;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    pub max_signatures: usize,
+    pub window: Duration,
+}
+
+pub struct RateLimitedSigner<R: RngCore + CryptoRng = ThreadRng> {
+    sigkey: Sigkey,
+    limit: RateLimit,
+    state: Mutex<State<R>>,
+}
+
+struct State<R> {
+    rng: R,
+    /// Time of each issuance in the window and the commitment of blind signatures
+    issued: VecDeque<(Instant, Option<Fingerprint>)>,
+    signed_commitments: HashSet<Fingerprint>,
+}
+
+impl RateLimitedSigner {
+    pub fn new(sigkey: Sigkey, limit: RateLimit) -> Self {
+        Self::new_with_rng(sigkey, limit, thread_rng())
+    }
+}
+
+impl<R: RngCore + CryptoRng> RateLimitedSigner<R> {
+    pub fn new_with_rng(sigkey: Sigkey, limit: RateLimit, rng: R) -> Self {
+        Self {
+            sigkey,
+            limit,
+            state: Mutex::new(State {
+                rng,
+                issued: VecDeque::new(),
+                signed_commitments: HashSet::new(),
+            }),
+        }
+    }
+
+    /// Number of signatures issued in the current window
+    pub fn issued_in_window(&self) -> usize {
+        self.expire_at(Instant::now());
+        self.state().issued.len()
+    }
+
+    /// Number of commitments remembered, at most `max_signatures`
+    pub fn signed_commitments(&self) -> usize {
+        self.expire_at(Instant::now());
+        self.state().signed_commitments.len()
+    }
+
+    /// Forget issuances that are out of the window at `now`
+    pub(crate) fn expire_at(&self, now: Instant) {
+        self.state().expire(now, self.limit.window);
+    }
+
+    pub fn sign(&self, messages: &[FieldElement], params: &Params) -> Result<Signature, PSError> {
+        if messages.len() != self.sigkey.y.len() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: self.sigkey.y.len(),
+                given: messages.len(),
+            });
+        }
+        let mut state = self.state();
+        let now = self.check(&mut state, None)?;
+        let sig = Signature::sign_with_rng(messages, &self.sigkey, params, &mut state.rng)?;
+        state.issued.push_back((now, None));
+        Ok(sig)
+    }
+
+    /// Blind signature on `commitment`, see `BlindSignature::new`. Each commitment is signed at most
+    /// once in the window.
+    pub fn sign_blind(
+        &self,
+        commitment: &SignatureGroup,
+        messages: &[FieldElement],
+        blinding_key: &BlindingKey,
+        params: &Params,
+    ) -> Result<Signature, PSError> {
+        BlindSignature::check_blinding_key_and_messages_compat(messages, blinding_key)?;
+        self.blind_sign_once(commitment, messages, params)
+    }
+
+    fn blind_sign_once(
+        &self,
+        commitment: &SignatureGroup,
+        messages: &[FieldElement],
+        params: &Params,
+    ) -> Result<Signature, PSError> {
+        let commitment_fp = hash_with_domain(b"PS signed commitment", &commitment.to_bytes());
+        let mut state = self.state();
+        let now = self.check(&mut state, Some(&commitment_fp))?;
+        let sig = BlindSignature::sign_with_rng(
+            commitment,
+            messages,
+            &self.sigkey,
+            self.sigkey.y.len(),
+            params,
+            &mut state.rng,
+        )?;
+        state.issued.push_back((now, Some(commitment_fp)));
+        state.signed_commitments.insert(commitment_fp);
+        Ok(sig)
+    }
+
+    fn state(&self) -> MutexGuard<'_, State<R>> {
+        // A panic while holding the lock leaves the state consistent, at worst without the last issuance
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// All checks before signing, returns the time of issuance
+    fn check(
+        &self,
+        state: &mut State<R>,
+        commitment: Option<&Fingerprint>,
+    ) -> Result<Instant, PSError> {
+        let now = Instant::now();
+        state.expire(now, self.limit.window);
+        if state.issued.len() >= self.limit.max_signatures {
+            return Err(PSError::SigningRefused {
+                msg: format!(
+                    "{} signatures issued in the last {:?}",
+                    state.issued.len(),
+                    self.limit.window
+                ),
+            });
+        }
+        if let Some(c) = commitment {
+            if state.signed_commitments.contains(c) {
+                return Err(PSError::SigningRefused {
+                    msg: String::from("Commitment was already signed"),
+                });
+            }
+        }
+        if !rng_is_healthy(&mut state.rng) {
+            return Err(PSError::SigningRefused {
+                msg: String::from("RNG failed health check"),
+            });
+        }
+        Ok(now)
+    }
+}
+
+impl<R> State<R> {
+    /// Drop issuances older than `window` and their commitments
+    fn expire(&mut self, now: Instant, window: Duration) {
+        while let Some((t, _)) = self.issued.front() {
+            if now.duration_since(*t) < window {
+                break;
+            }
+            if let Some((_, Some(c))) = self.issued.pop_front() {
+                self.signed_commitments.remove(&c);
+            }
+        }
+    }
+}
+
+impl<R: RngCore + CryptoRng> PsSigner for RateLimitedSigner<R> {
+    fn msg_count(&self) -> usize {
+        self.sigkey.y.len()
+    }
+
+    fn sign(&self, messages: &[FieldElement], params: &Params) -> Result<Signature, PSError> {
+        RateLimitedSigner::sign(self, messages, params)
+    }
+
+    fn blind_sign(
+        &self,
+        commitment: &SignatureGroup,
+        messages: &[FieldElement],
+        params: &Params,
+    ) -> Result<Signature, PSError> {
+        self.blind_sign_once(commitment, messages, params)
+    }
+}
+
+/// Catches stuck or broken generators: two consecutive outputs must differ and not be constant
+fn rng_is_healthy<R: RngCore>(rng: &mut R) -> bool {
+    let mut a = [0u8; 32];
+    let mut b = [0u8; 32];
+    if rng.try_fill_bytes(&mut a).is_err() || rng.try_fill_bytes(&mut b).is_err() {
+        return false;
+    }
+    a != b && a.iter().any(|x| *x != a[0]) && b.iter().any(|x| *x != b[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::issuance::CredentialRequest;
+    use crate::keys::keygen;
+    use crate::policy::CompiledPolicy;
+    use crate::types::{Blinding, Message};
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use rand::Error;
+
+    // Always returns the same byte
+    struct StuckRng;
+
+    impl RngCore for StuckRng {
+        fn next_u32(&mut self) -> u32 {
+            0
+        }
+        fn next_u64(&mut self) -> u64 {
+            0
+        }
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            dest.iter_mut().for_each(|b| *b = 0);
+        }
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for StuckRng {}
+
+    #[test]
+    fn test_rate_limited_signer() {
+        let count_msgs = 3;
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(count_msgs, &params);
+        let limit = RateLimit {
+            max_signatures: 3,
+            window: Duration::from_secs(3600),
+        };
+        let signer = RateLimitedSigner::new(sk.clone(), limit);
+        let msgs = FieldElementVector::random(count_msgs);
+        let sig = signer.sign(msgs.as_slice(), &params).unwrap();
+        assert!(sig
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+        assert!(signer.sign(&msgs.as_slice()[1..], &params).is_err());
+
+        // Repeated commitment is refused
+        let blinding_key = BlindingKey::new(&sk, &params);
+        let blinding = FieldElement::random();
+        let commitment = &(&blinding_key.Y[0] * &msgs[0]) + &(&params.g * &blinding);
+        let sig_blinded = signer
+            .sign_blind(&commitment, &msgs.as_slice()[1..], &blinding_key, &params)
+            .unwrap();
+        let sig = BlindSignature::unblind(&sig_blinded, &Blinding(blinding.clone()));
+        assert!(sig
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+        match signer.sign_blind(&commitment, &msgs.as_slice()[1..], &blinding_key, &params) {
+            Err(PSError::SigningRefused { .. }) => (),
+            _ => panic!("Commitment signed twice"),
+        }
+        assert_eq!(signer.issued_in_window(), 2);
+
+        // Limit reached
+        signer.sign(msgs.as_slice(), &params).unwrap();
+        match signer.sign(msgs.as_slice(), &params) {
+            Err(PSError::SigningRefused { .. }) => (),
+            _ => panic!("Rate limit not enforced"),
+        }
+
+        // Broken RNG
+        let signer = RateLimitedSigner::new_with_rng(sk, limit, StuckRng);
+        match signer.sign(msgs.as_slice(), &params) {
+            Err(PSError::SigningRefused { .. }) => (),
+            _ => panic!("Signed with a stuck RNG"),
+        }
+    }
+
+    #[test]
+    fn test_rate_limited_ps_signer() {
+        let count_msgs = 3;
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(count_msgs, &params);
+        let blinding_key = BlindingKey::new(&sk, &params);
+        let limit = RateLimit {
+            max_signatures: 2,
+            window: Duration::from_secs(3600),
+        };
+        let signer = RateLimitedSigner::new(sk, limit);
+        let msgs = FieldElementVector::random(count_msgs);
+
+        // Through the issuance API
+        let (request, blinding) = CredentialRequest::new(
+            &msgs.as_slice()[..1],
+            &msgs.as_slice()[1..],
+            &blinding_key,
+            &params,
+            b"n",
+        )
+        .unwrap();
+        let policy = CompiledPolicy::compile(&[], &[]).unwrap();
+        let blinded = request
+            .issue(&signer, &vk, &blinding_key, &params, &policy, b"n")
+            .unwrap();
+        let sig = BlindSignature::unblind(&blinded, &blinding);
+        assert!(sig
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+        match request.issue(&signer, &vk, &blinding_key, &params, &policy, b"n") {
+            Err(PSError::SigningRefused { .. }) => (),
+            _ => panic!("Commitment signed twice"),
+        }
+        assert!(PsSigner::sign(&signer, &msgs.as_slice()[1..], &params).is_err());
+        let sig = PsSigner::sign(&signer, msgs.as_slice(), &params).unwrap();
+        assert!(sig
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+        assert!(PsSigner::sign(&signer, msgs.as_slice(), &params).is_err());
+        assert_eq!(signer.signed_commitments(), 1);
+
+        // Commitments are forgotten with their issuance
+        signer.expire_at(Instant::now() + limit.window);
+        assert_eq!(signer.issued_in_window(), 0);
+        assert_eq!(signer.signed_commitments(), 0);
+        assert!(request
+            .issue(&signer, &vk, &blinding_key, &params, &policy, b"n")
+            .is_ok());
+    }
+}
@@ -0,0 +1,168 @@
+// Linear relations among hidden messages of the same signature, like m_total = m_a + m_b or m = c * m'.
+// Since a response is blinding - challenge*message, the prover chooses the blindings of related messages
+// to satisfy the same relation and then the verifier checks the relation on the responses.
+
+use crate::errors::PSError;
+use amcl_wrapper::field_elem::FieldElement;
+use rand::{CryptoRng, RngCore};
+use std::collections::HashSet;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum LinearRelation {
+    /// messages[total] = messages[a] + messages[b]
+    Sum { total: usize, a: usize, b: usize },
+    /// messages[m] = c * messages[m_prime]
+    Scaled {
+        m: usize,
+        c: FieldElement,
+        m_prime: usize,
+    },
+}
+
+impl LinearRelation {
+    /// Message indices the relation refers to
+    pub fn indices(&self) -> Vec<usize> {
+        match self {
+            LinearRelation::Sum { total, a, b } => vec![*total, *a, *b],
+            LinearRelation::Scaled { m, m_prime, .. } => vec![*m, *m_prime],
+        }
+    }
+
+    /// Check the relation where `value(i)` gives the value for message index `i`. Works for messages
+    /// as well as their blindings or responses.
+    pub fn holds<F: Fn(usize) -> FieldElement>(&self, value: F) -> bool {
+        match self {
+            LinearRelation::Sum { total, a, b } => value(*total) == &value(*a) + &value(*b),
+            LinearRelation::Scaled { m, c, m_prime } => value(*m) == c * &value(*m_prime),
+        }
+    }
+
+    /// Set the value of the derived index (`total` or `m`) from the others
+    fn apply(&self, values: &mut [Option<FieldElement>]) {
+        match self {
+            LinearRelation::Sum { total, a, b } => {
+                values[*total] = Some(values[*a].as_ref().unwrap() + values[*b].as_ref().unwrap())
+            }
+            LinearRelation::Scaled { m, c, m_prime } => {
+                values[*m] = Some(c * values[*m_prime].as_ref().unwrap())
+            }
+        }
+    }
+}
+
+/// Check that every relation refers to hidden messages only and is satisfied by `messages`
+pub fn check_relations(
+    messages: &[FieldElement],
+    revealed_msg_indices: &HashSet<usize>,
+    relations: &[LinearRelation],
+) -> Result<(), PSError> {
+    for relation in relations {
+        for idx in relation.indices() {
+            if idx >= messages.len() {
+                return Err(PSError::GeneralError {
+                    msg: format!("Index {} should be less than {}", idx, messages.len()),
+                });
+            }
+            if revealed_msg_indices.contains(&idx) {
+                return Err(PSError::GeneralError {
+                    msg: format!("Message {} in relation is revealed", idx),
+                });
+            }
+        }
+        if !relation.holds(|i| messages[i].clone()) {
+            return Err(PSError::GeneralError {
+                msg: format!("Messages do not satisfy relation {:?}", relation),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Generate blindings for the hidden messages, in increasing order of message index, such that the
+/// blindings satisfy all `relations`. Relations are applied in order, so a derived value can be
+/// used by later relations. Fails if relations conflict.
+pub fn blindings_for_relations<R: RngCore + CryptoRng>(
+    messages: &[FieldElement],
+    revealed_msg_indices: &HashSet<usize>,
+    relations: &[LinearRelation],
+    rng: &mut R,
+) -> Result<Vec<FieldElement>, PSError> {
+    check_relations(messages, revealed_msg_indices, relations)?;
+    let mut blindings: Vec<Option<FieldElement>> = (0..messages.len())
+        .map(|i| {
+            if revealed_msg_indices.contains(&i) {
+                None
+            } else {
+                Some(FieldElement::random_using_rng(rng))
+            }
+        })
+        .collect();
+    for relation in relations {
+        relation.apply(&mut blindings);
+    }
+    for relation in relations {
+        if !relation.holds(|i| blindings[i].clone().unwrap()) {
+            return Err(PSError::GeneralError {
+                msg: format!("Relation {:?} conflicts with other relations", relation),
+            });
+        }
+    }
+    Ok(blindings.into_iter().flatten().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_blindings_for_relations() {
+        let a = FieldElement::random();
+        let b = FieldElement::random();
+        let c = FieldElement::random();
+        let messages = vec![
+            &a + &b,
+            a.clone(),
+            FieldElement::random(),
+            b.clone(),
+            &c * &a,
+        ];
+        let relations = vec![
+            LinearRelation::Sum {
+                total: 0,
+                a: 1,
+                b: 3,
+            },
+            LinearRelation::Scaled {
+                m: 4,
+                c: c.clone(),
+                m_prime: 1,
+            },
+        ];
+        let mut revealed = HashSet::new();
+        revealed.insert(2);
+        let blindings =
+            blindings_for_relations(&messages, &revealed, &relations, &mut thread_rng()).unwrap();
+        assert_eq!(blindings.len(), 4);
+        // blindings are for messages 0, 1, 3, 4
+        assert_eq!(blindings[0], &blindings[1] + &blindings[2]);
+        assert_eq!(blindings[3], &c * &blindings[1]);
+
+        // Relation over a revealed message
+        revealed.insert(3);
+        assert!(
+            blindings_for_relations(&messages, &revealed, &relations, &mut thread_rng()).is_err()
+        );
+
+        // Relation not satisfied by messages
+        let relations = vec![LinearRelation::Sum {
+            total: 0,
+            a: 1,
+            b: 2,
+        }];
+        assert!(
+            blindings_for_relations(&messages, &HashSet::new(), &relations, &mut thread_rng())
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,272 @@
+// Proactive refresh of `SigkeyShare`s, optionally to a new threshold and number of shares, keeping the
+// verkey. Every old share holder in a set of at least the old threshold shares each of its scalars
+// x_i, y_1_i, y_2_i, ... again with a fresh polynomial of degree new threshold - 1. It broadcasts
+// `RefreshCommitments`, Feldman commitments g_tilde^a_k to the coefficients, and privately sends the
+// `RefreshSubShare` for index j to the new holder j. New share j is the Lagrange combination over the
+// senders of the received sub-shares, so it is a share of the same sigkey while the old shares become
+// useless together with the new ones.
+//
+// The constant commitment of a sender must be its share's verkey, see `SigkeyShare::verkey`, which the
+// new holders know from the last setup or refresh. So a sender cannot reshare anything but its share and
+// a cheating sender is detected and named. Old share verkeys are checked to interpolate to the verkey.
+
+use crate::context::{verkey_id, VerkeyId};
+use crate::errors::PSError;
+use crate::key_sharing::SigkeyShare;
+use crate::keys::{Params, Verkey};
+use crate::shamir::{check_indices, evaluate, lagrange_basis_at_0, random_polynomial};
+use crate::validation::deserialize_point_vecs;
+use crate::OtherGroup;
+use amcl_wrapper::field_elem::FieldElement;
+use amcl_wrapper::group_elem::GroupElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::HashMap;
+
+/// Broadcast by the old share holder `from_index`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RefreshCommitments {
+    pub from_index: usize,
+    pub verkey_id: VerkeyId,
+    pub old_threshold: usize,
+    pub threshold: usize,
+    pub total: usize,
+    /// Commitments to the coefficients of the polynomials of x, y_1, y_2, ... in this order
+    #[serde(deserialize_with = "deserialize_point_vecs")]
+    pub coefficients: Vec<Vec<OtherGroup>>,
+}
+
+/// Sent privately by the old share holder `from_index` to the new share holder `to_index`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RefreshSubShare {
+    pub from_index: usize,
+    pub to_index: usize,
+    pub x: FieldElement,
+    pub y: Vec<FieldElement>,
+}
+
+impl SigkeyShare {
+    /// Refresh messages of this share for `total` new shares with threshold `threshold`
+    pub fn refresh(
+        &self,
+        threshold: usize,
+        total: usize,
+        params: &Params,
+    ) -> Result<(RefreshCommitments, Vec<RefreshSubShare>), PSError> {
+        self.refresh_with_rng(threshold, total, params, &mut thread_rng())
+    }
+
+    pub fn refresh_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        threshold: usize,
+        total: usize,
+        params: &Params,
+        rng: &mut R,
+    ) -> Result<(RefreshCommitments, Vec<RefreshSubShare>), PSError> {
+        let mut polys = vec![random_polynomial(&self.x, threshold, total, rng)?];
+        for y in &self.y {
+            polys.push(random_polynomial(y, threshold, total, rng)?);
+        }
+        let commitments = RefreshCommitments {
+            from_index: self.index,
+            verkey_id: self.verkey_id,
+            old_threshold: self.threshold,
+            threshold,
+            total,
+            coefficients: polys
+                .iter()
+                .map(|p| p.iter().map(|c| &params.g_tilde * c).collect())
+                .collect(),
+        };
+        let sub_shares = (1..=total)
+            .map(|j| RefreshSubShare {
+                from_index: self.index,
+                to_index: j,
+                x: evaluate(&polys[0], j),
+                y: polys[1..].iter().map(|p| evaluate(p, j)).collect(),
+            })
+            .collect();
+        Ok((commitments, sub_shares))
+    }
+}
+
+impl RefreshCommitments {
+    /// Check `sub_share` against the commitments and the commitments against `old_share_vk`, the
+    /// verkey of the sender's share
+    pub fn verify_sub_share(
+        &self,
+        sub_share: &RefreshSubShare,
+        old_share_vk: &Verkey,
+        params: &Params,
+    ) -> bool {
+        let old: Vec<&OtherGroup> = std::iter::once(&old_share_vk.X_tilde)
+            .chain(&old_share_vk.Y_tilde)
+            .collect();
+        let values: Vec<&FieldElement> =
+            std::iter::once(&sub_share.x).chain(&sub_share.y).collect();
+        if sub_share.from_index != self.from_index
+            || sub_share.to_index == 0
+            || sub_share.to_index > self.total
+            || self.coefficients.len() != old.len()
+            || values.len() != old.len()
+        {
+            return false;
+        }
+        let x = FieldElement::from(sub_share.to_index as u64);
+        self.coefficients
+            .iter()
+            .zip(old)
+            .zip(values)
+            .all(|((coefficients, old), value)| {
+                if coefficients.len() != self.threshold || coefficients[0] != *old {
+                    return false;
+                }
+                let mut expected = OtherGroup::identity();
+                for c in coefficients.iter().rev() {
+                    expected = &(&expected * &x) + c;
+                }
+                &params.g_tilde * value == expected
+            })
+    }
+}
+
+/// New share `to_index` from the refresh messages `received` from old share holders, each the
+/// broadcast commitments with the sub-share sent to `to_index`. `old_share_vks` maps the indices of
+/// old shares to their verkeys. Fails naming the sender of an invalid message.
+pub fn combine_refresh(
+    to_index: usize,
+    received: &[(RefreshCommitments, RefreshSubShare)],
+    old_share_vks: &HashMap<usize, Verkey>,
+    vk: &Verkey,
+    params: &Params,
+) -> Result<SigkeyShare, PSError> {
+    let (first, _) = match received.first() {
+        Some(r) => r,
+        None => {
+            return Err(PSError::GeneralError {
+                msg: String::from("No refresh messages given"),
+            })
+        }
+    };
+    let vk_id = verkey_id(vk);
+    let indices: Vec<usize> = received.iter().map(|(c, _)| c.from_index).collect();
+    check_indices(&indices)?;
+    if received.len() < first.old_threshold {
+        return Err(PSError::GeneralError {
+            msg: format!(
+                "Need refresh messages from {} shares but given {}",
+                first.old_threshold,
+                received.len()
+            ),
+        });
+    }
+    for (c, s) in received {
+        let valid = c.verkey_id == vk_id
+            && c.old_threshold == first.old_threshold
+            && c.threshold == first.threshold
+            && c.total == first.total
+            && s.to_index == to_index
+            && old_share_vks.get(&c.from_index).is_some_and(|v| {
+                v.Y_tilde.len() == vk.Y_tilde.len() && c.verify_sub_share(s, v, params)
+            });
+        if !valid {
+            return Err(PSError::GeneralError {
+                msg: format!("Invalid refresh message from share {}", c.from_index),
+            });
+        }
+    }
+
+    // The senders' share verkeys must interpolate to the verkey
+    let mut X_tilde = OtherGroup::identity();
+    let mut Y_tilde = vec![OtherGroup::identity(); vk.Y_tilde.len()];
+    let mut x = FieldElement::zero();
+    let mut y = vec![FieldElement::zero(); vk.Y_tilde.len()];
+    for (c, s) in received {
+        let l = lagrange_basis_at_0(&indices, c.from_index);
+        let old = &old_share_vks[&c.from_index];
+        X_tilde += &old.X_tilde * &l;
+        x += &s.x * &l;
+        for j in 0..vk.Y_tilde.len() {
+            Y_tilde[j] += &old.Y_tilde[j] * &l;
+            y[j] += &s.y[j] * &l;
+        }
+    }
+    if X_tilde != vk.X_tilde || Y_tilde != vk.Y_tilde {
+        return Err(PSError::GeneralError {
+            msg: String::from("Old share verkeys do not interpolate to the verkey"),
+        });
+    }
+    Ok(SigkeyShare {
+        index: to_index,
+        threshold: first.threshold,
+        total: first.total,
+        verkey_id: vk_id,
+        x,
+        y,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::{keygen, Sigkey};
+
+    #[test]
+    fn test_share_refresh() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(2, &params);
+        let shares = sk.split(2, 3, &params).unwrap();
+        let old_share_vks: HashMap<usize, Verkey> = shares
+            .iter()
+            .map(|s| (s.index, s.verkey(&params)))
+            .collect();
+
+        // Old shares 1 and 3 refresh to 3 of 4
+        let messages: Vec<(RefreshCommitments, Vec<RefreshSubShare>)> = [0, 2]
+            .iter()
+            .map(|i| shares[*i].refresh(3, 4, &params).unwrap())
+            .collect();
+        let received_by = |j: usize| -> Vec<(RefreshCommitments, RefreshSubShare)> {
+            messages
+                .iter()
+                .map(|(c, subs)| (c.clone(), subs[j - 1].clone()))
+                .collect()
+        };
+        let new_shares: Vec<SigkeyShare> = (1..=4)
+            .map(|j| combine_refresh(j, &received_by(j), &old_share_vks, &vk, &params).unwrap())
+            .collect();
+        let recovered = Sigkey::reconstruct(&new_shares[1..], &params).unwrap();
+        assert_eq!(Verkey::from_sigkey(&recovered, 2, &params).unwrap(), vk);
+        assert!(Sigkey::reconstruct(&new_shares[..2], &params).is_err());
+
+        // A cheating sender is named
+        let mut cheated = received_by(1);
+        cheated[1].1.y[0] = FieldElement::random();
+        match combine_refresh(1, &cheated, &old_share_vks, &vk, &params) {
+            Err(PSError::GeneralError { msg }) => assert!(msg.ends_with("share 3")),
+            _ => panic!("Invalid refresh accepted"),
+        }
+        // Resharing another secret is detected by the constant commitment
+        let mut other = shares[1].clone();
+        other.index = 3;
+        other.x = FieldElement::random();
+        let (c, subs) = other.refresh(3, 4, &params).unwrap();
+        let mut replaced = received_by(1);
+        replaced[1] = (c, subs[0].clone());
+        assert!(combine_refresh(1, &replaced, &old_share_vks, &vk, &params).is_err());
+        // Fewer senders than the old threshold
+        assert!(combine_refresh(1, &received_by(1)[..1], &old_share_vks, &vk, &params).is_err());
+    }
+
+    #[test]
+    fn test_refresh_commitments_deserialize_identity() {
+        let params = Params::new("test".as_bytes());
+        let (sk, _) = keygen(2, &params);
+        let shares = sk.split(2, 3, &params).unwrap();
+        let (mut commitments, _) = shares[0].refresh(2, 3, &params).unwrap();
+        let json = serde_json::to_string(&commitments).unwrap();
+        assert!(serde_json::from_str::<RefreshCommitments>(&json).is_ok());
+        commitments.coefficients[1][1] = OtherGroup::identity();
+        let json = serde_json::to_string(&commitments).unwrap();
+        assert!(serde_json::from_str::<RefreshCommitments>(&json).is_err());
+    }
+}
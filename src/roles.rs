@@ -0,0 +1,426 @@
+// Issuer, holder and verifier as types owning their keys, credentials and policies, for integrators
+// who want the end to end flow without assembling the primitives. All parties share one `Params` and
+// the credentials of an issuer follow its `MessageSchema`.
+//
+// 1. The issuer makes a `CredentialOffer` with its public keys, schema and a fresh nonce.
+// 2. The holder answers with a `CredentialRequest` committing to the first attributes of the schema,
+//    e.g. a link secret, and revealing the others to the issuer, see `issuance`.
+// 3. The issuer validates the request against its policy and blind signs it, the holder unblinds,
+//    verifies and stores the credential.
+// 4. The verifier makes a `PresentationRequest` with a fresh nonce, the holder proves and the verifier
+//    checks the presentation and its policy.
+//
+// Issuers and verifiers accept each nonce they made once, so requests and presentations cannot be
+// replayed to them. Nonces are kept in memory, deployments with several instances should use
+// `VerifierSession` and `CredentialRequest::issue` directly.
+
+use crate::blind_signature::{BlindSignature, BlindingKey};
+use crate::errors::PSError;
+use crate::issuance::CredentialRequest;
+use crate::keys::{keygen, Params, Sigkey, Verkey};
+use crate::policy::{CompiledPolicy, Policy, PolicyReport};
+use crate::presentation::Presentation;
+use crate::schema::MessageSchema;
+use crate::signature::Signature;
+use crate::types::{Blinding, Message};
+use amcl_wrapper::field_elem::FieldElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::{HashMap, HashSet};
+
+/// Size of the nonces in offers and presentation requests
+pub const ROLE_NONCE_SIZE: usize = 32;
+
+fn fresh_nonce<R: RngCore + CryptoRng>(rng: &mut R) -> Vec<u8> {
+    let mut nonce = vec![0; ROLE_NONCE_SIZE];
+    rng.fill_bytes(&mut nonce);
+    nonce
+}
+
+/// Everything a holder needs to request a credential from the issuer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CredentialOffer {
+    pub vk: Verkey,
+    pub blinding_key: BlindingKey,
+    pub schema: MessageSchema,
+    pub nonce: Vec<u8>,
+}
+
+/// Holder state between sending a request and receiving the signature
+#[derive(Clone, Debug)]
+pub struct PendingCredential {
+    blinding: Blinding,
+    messages: Vec<FieldElement>,
+    vk: Verkey,
+    schema: MessageSchema,
+}
+
+/// A stored credential, the signature is unblinded and verified
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HeldCredential {
+    pub sig: Signature,
+    /// In schema order
+    pub messages: Vec<FieldElement>,
+    pub vk: Verkey,
+    pub schema: MessageSchema,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PresentationRequest {
+    pub nonce: Vec<u8>,
+}
+
+pub struct Issuer {
+    sigkey: Sigkey,
+    vk: Verkey,
+    blinding_key: BlindingKey,
+    schema: MessageSchema,
+    policy: CompiledPolicy,
+    params: Params,
+    pending_nonces: HashSet<Vec<u8>>,
+}
+
+impl Issuer {
+    /// New issuer key for credentials of `schema`. `policies` are checked on the attributes the holder
+    /// reveals in its request.
+    pub fn new(
+        schema: MessageSchema,
+        policies: &[Policy],
+        params: Params,
+    ) -> Result<Self, PSError> {
+        if schema.is_empty() {
+            return Err(PSError::GeneralError {
+                msg: String::from("Schema needs at least 1 attribute"),
+            });
+        }
+        let (sigkey, vk) = keygen(schema.len(), &params);
+        Self::from_keys(sigkey, vk, schema, policies, params)
+    }
+
+    /// Issuer with an existing key, e.g. loaded from a `KeyStore`
+    pub fn from_keys(
+        sigkey: Sigkey,
+        vk: Verkey,
+        schema: MessageSchema,
+        policies: &[Policy],
+        params: Params,
+    ) -> Result<Self, PSError> {
+        if vk.Y_tilde.len() != schema.len() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: vk.Y_tilde.len(),
+                given: schema.len(),
+            });
+        }
+        let policy = CompiledPolicy::compile(policies, &schema.attribute_names())?;
+        let blinding_key = BlindingKey::new(&sigkey, &params);
+        Ok(Self {
+            sigkey,
+            vk,
+            blinding_key,
+            schema,
+            policy,
+            params,
+            pending_nonces: HashSet::new(),
+        })
+    }
+
+    pub fn verkey(&self) -> &Verkey {
+        &self.vk
+    }
+
+    pub fn offer(&mut self) -> CredentialOffer {
+        self.offer_with_rng(&mut thread_rng())
+    }
+
+    pub fn offer_with_rng<R: RngCore + CryptoRng>(&mut self, rng: &mut R) -> CredentialOffer {
+        let nonce = fresh_nonce(rng);
+        self.pending_nonces.insert(nonce.clone());
+        CredentialOffer {
+            vk: self.vk.clone(),
+            blinding_key: self.blinding_key.clone(),
+            schema: self.schema.clone(),
+            nonce,
+        }
+    }
+
+    /// Blind signature for a request answering one of this issuer's offers. The offer is used up only
+    /// when the signature is issued so a rejected holder can correct its request.
+    pub fn issue(&mut self, request: &CredentialRequest) -> Result<Signature, PSError> {
+        if !self.pending_nonces.contains(&request.nonce) {
+            return Err(PSError::GeneralError {
+                msg: String::from("Request is not for an open offer"),
+            });
+        }
+        let sig = request.issue(
+            &self.sigkey,
+            &self.vk,
+            &self.blinding_key,
+            &self.params,
+            &self.policy,
+            &request.nonce,
+        )?;
+        self.pending_nonces.remove(&request.nonce);
+        Ok(sig)
+    }
+}
+
+pub struct Holder {
+    params: Params,
+    credentials: Vec<HeldCredential>,
+}
+
+impl Holder {
+    pub fn new(params: Params) -> Self {
+        Self {
+            params,
+            credentials: vec![],
+        }
+    }
+
+    pub fn credentials(&self) -> &[HeldCredential] {
+        &self.credentials
+    }
+
+    /// Request a credential over `attributes`, which must have every attribute of the offer's schema.
+    /// The first `hidden` attributes in schema order are committed and never seen by the issuer.
+    pub fn request(
+        &self,
+        offer: &CredentialOffer,
+        attributes: &HashMap<String, FieldElement>,
+        hidden: usize,
+    ) -> Result<(CredentialRequest, PendingCredential), PSError> {
+        self.request_with_rng(offer, attributes, hidden, &mut thread_rng())
+    }
+
+    pub fn request_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        offer: &CredentialOffer,
+        attributes: &HashMap<String, FieldElement>,
+        hidden: usize,
+        rng: &mut R,
+    ) -> Result<(CredentialRequest, PendingCredential), PSError> {
+        let messages = offer.schema.messages(attributes)?;
+        if hidden > messages.len() {
+            return Err(PSError::GeneralError {
+                msg: format!("Cannot hide {} of {} attributes", hidden, messages.len()),
+            });
+        }
+        let (request, blinding) = CredentialRequest::new_with_rng(
+            &messages[..hidden],
+            &messages[hidden..],
+            &offer.blinding_key,
+            &self.params,
+            &offer.nonce,
+            rng,
+        )?;
+        let pending = PendingCredential {
+            blinding,
+            messages,
+            vk: offer.vk.clone(),
+            schema: offer.schema.clone(),
+        };
+        Ok((request, pending))
+    }
+
+    /// Unblind and verify the issuer's signature and keep the credential. Returns its index in
+    /// `credentials`.
+    pub fn store(&mut self, pending: PendingCredential, sig: &Signature) -> Result<usize, PSError> {
+        let sig = BlindSignature::unblind(sig, &pending.blinding);
+        if !sig.verify(
+            &Message::from_field_elements(&pending.messages),
+            &pending.vk,
+            &self.params,
+        )? {
+            return Err(PSError::GeneralError {
+                msg: String::from("Issued signature does not verify"),
+            });
+        }
+        self.credentials.push(HeldCredential {
+            sig,
+            messages: pending.messages,
+            vk: pending.vk,
+            schema: pending.schema,
+        });
+        Ok(self.credentials.len() - 1)
+    }
+
+    /// Presentation of the credential at `index` revealing the attributes named in `reveal`
+    pub fn prove(
+        &self,
+        index: usize,
+        reveal: &[&str],
+        request: &PresentationRequest,
+    ) -> Result<Presentation, PSError> {
+        self.prove_with_rng(index, reveal, request, &mut thread_rng())
+    }
+
+    pub fn prove_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        index: usize,
+        reveal: &[&str],
+        request: &PresentationRequest,
+        rng: &mut R,
+    ) -> Result<Presentation, PSError> {
+        let credential = self
+            .credentials
+            .get(index)
+            .ok_or_else(|| PSError::GeneralError {
+                msg: format!("No credential at index {}", index),
+            })?;
+        Presentation::new_with_rng(
+            &credential.sig,
+            &credential.vk,
+            &self.params,
+            &credential.messages,
+            credential.schema.indices(reveal)?,
+            &request.nonce,
+            rng,
+        )
+    }
+}
+
+pub struct Verifier {
+    schema: MessageSchema,
+    policy: CompiledPolicy,
+    params: Params,
+    pending_nonces: HashSet<Vec<u8>>,
+}
+
+impl Verifier {
+    /// Verifier of credentials of `schema`. Restrict the issuers with `Policy::trusted_issuers`.
+    pub fn new(
+        schema: MessageSchema,
+        policies: &[Policy],
+        params: Params,
+    ) -> Result<Self, PSError> {
+        let policy = CompiledPolicy::compile(policies, &schema.attribute_names())?;
+        Ok(Self {
+            schema,
+            policy,
+            params,
+            pending_nonces: HashSet::new(),
+        })
+    }
+
+    pub fn request(&mut self) -> PresentationRequest {
+        self.request_with_rng(&mut thread_rng())
+    }
+
+    pub fn request_with_rng<R: RngCore + CryptoRng>(&mut self, rng: &mut R) -> PresentationRequest {
+        let nonce = fresh_nonce(rng);
+        self.pending_nonces.insert(nonce.clone());
+        PresentationRequest { nonce }
+    }
+
+    /// Verify `presentation` under the issuer key `vk` and evaluate the policies. Fails if the
+    /// presentation is not for an open request, the request is used up otherwise.
+    pub fn verify(
+        &mut self,
+        presentation: &Presentation,
+        vk: &Verkey,
+    ) -> Result<PolicyReport, PSError> {
+        if vk.Y_tilde.len() != self.schema.len() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: self.schema.len(),
+                given: vk.Y_tilde.len(),
+            });
+        }
+        if !self.pending_nonces.remove(&presentation.nonce) {
+            return Err(PSError::GeneralError {
+                msg: String::from("Presentation is not for an open request"),
+            });
+        }
+        if !presentation.verify(vk, &self.params)? {
+            return Ok(PolicyReport {
+                proof_valid: false,
+                violations: vec![],
+            });
+        }
+        Ok(PolicyReport {
+            proof_valid: true,
+            violations: self.policy.evaluate(vk, &presentation.revealed_msgs),
+        })
+    }
+
+    /// Revealed attributes by name of a verified presentation
+    pub fn revealed_attributes(
+        &self,
+        presentation: &Presentation,
+    ) -> Result<HashMap<String, FieldElement>, PSError> {
+        self.schema.named_revealed_msgs(&presentation.revealed_msgs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{PolicyViolation, Predicate};
+
+    #[test]
+    fn test_roles() {
+        let params = Params::new("test".as_bytes());
+        let schema = MessageSchema::new(&["secret", "name", "country"]).unwrap();
+        let country = FieldElement::from(49u64);
+        let mut issuer = Issuer::new(
+            schema.clone(),
+            &[Policy::require_revealed("name")],
+            params.clone(),
+        )
+        .unwrap();
+        let mut holder = Holder::new(params.clone());
+        let mut attributes = HashMap::new();
+        attributes.insert(String::from("secret"), FieldElement::random());
+        attributes.insert(String::from("name"), FieldElement::from(7u64));
+        attributes.insert(String::from("country"), country.clone());
+
+        let offer = issuer.offer();
+        let (request, pending) = holder.request(&offer, &attributes, 1).unwrap();
+        let sig = issuer.issue(&request).unwrap();
+        let index = holder.store(pending, &sig).unwrap();
+        // The offer is used up
+        assert!(issuer.issue(&request).is_err());
+        assert!(holder.request(&offer, &attributes, 4).is_err());
+        // The issuer's policy needs the name revealed
+        let offer = issuer.offer();
+        let (request, _) = holder.request(&offer, &attributes, 2).unwrap();
+        assert!(issuer.issue(&request).is_err());
+
+        let mut verifier = Verifier::new(
+            schema,
+            &[
+                Policy::require_predicate("country", Predicate::Equals(country.clone())),
+                Policy::trusted_issuers(std::slice::from_ref(issuer.verkey())),
+            ],
+            params.clone(),
+        )
+        .unwrap();
+        let presentation_request = verifier.request();
+        let presentation = holder
+            .prove(index, &["country"], &presentation_request)
+            .unwrap();
+        assert!(verifier
+            .verify(&presentation, issuer.verkey())
+            .unwrap()
+            .is_accepted());
+        assert_eq!(
+            verifier.revealed_attributes(&presentation).unwrap()["country"],
+            country
+        );
+        // Replayed presentations are rejected
+        assert!(verifier.verify(&presentation, issuer.verkey()).is_err());
+
+        let presentation_request = verifier.request();
+        let presentation = holder
+            .prove(index, &["name"], &presentation_request)
+            .unwrap();
+        assert_eq!(
+            verifier
+                .verify(&presentation, issuer.verkey())
+                .unwrap()
+                .violations,
+            vec![PolicyViolation::NotRevealed {
+                attribute: String::from("country")
+            }]
+        );
+        assert!(holder.prove(1, &[], &verifier.request()).is_err());
+    }
+}
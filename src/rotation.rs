@@ -0,0 +1,247 @@
+// Rotation of an issuer's keys. A `KeyRotationStatement` states that the old verkey is replaced by the
+// new one as of an epoch. Like a `KeyRevocation` it is a signature on (H(old verkey, new verkey, epoch),
+// 0, 0, ...), here by both keys: the old key's authorizes the new key and the new key's proves its
+// possession, so nobody can rotate someone else's key to their own or to a key they cannot use.
+// Holders then migrate their credentials with a blind re-issuance request, a `DerivedCredentialRequest`
+// carrying over all messages of the old credential in order, so the issuer signs under the new key
+// without learning any message.
+
+use crate::blind_signature::BlindingKey;
+use crate::chaining::{derive_credential_with_rng, DerivedCredentialRequest, ParentCredential};
+use crate::context::{verkey_id, VerkeyId};
+use crate::errors::PSError;
+use crate::hash_to_field::hash_to_field;
+use crate::keys::{Keypair, Params, Verkey};
+use crate::signature::Signature;
+use crate::types::{Blinding, Message};
+use amcl_wrapper::field_elem::FieldElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+
+/// Tag of `hash_to_field` for the message of a `KeyRotationStatement`
+pub const ROTATION_DST: &[u8] = b"PS-SIG-V01-CS01-VERKEY-ROTATION_XMD:SHA-256_";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyRotationStatement {
+    pub old_verkey_id: VerkeyId,
+    pub new_verkey_id: VerkeyId,
+    /// First epoch in which the new key is used
+    pub epoch: u64,
+    pub old_sig: Signature,
+    pub new_sig: Signature,
+}
+
+fn rotation_messages(
+    old_id: &VerkeyId,
+    new_id: &VerkeyId,
+    epoch: u64,
+    count: usize,
+) -> Vec<FieldElement> {
+    let mut msgs = vec![FieldElement::zero(); count];
+    msgs[0] = hash_to_field(
+        &[old_id, new_id, &epoch.to_be_bytes()[..]].concat(),
+        ROTATION_DST,
+    );
+    msgs
+}
+
+impl KeyRotationStatement {
+    pub fn new(old: &Keypair, new: &Keypair, epoch: u64, params: &Params) -> Result<Self, PSError> {
+        Self::new_with_rng(old, new, epoch, params, &mut thread_rng())
+    }
+
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        old: &Keypair,
+        new: &Keypair,
+        epoch: u64,
+        params: &Params,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        for keypair in &[old, new] {
+            if keypair.verkey.Y_tilde.is_empty() {
+                return Err(PSError::UnsupportedNoOfMessages {
+                    expected: 1,
+                    given: 0,
+                });
+            }
+        }
+        let old_verkey_id = verkey_id(&old.verkey);
+        let new_verkey_id = verkey_id(&new.verkey);
+        let sign = |keypair: &Keypair, rng: &mut R| {
+            let msgs = rotation_messages(
+                &old_verkey_id,
+                &new_verkey_id,
+                epoch,
+                keypair.verkey.Y_tilde.len(),
+            );
+            Signature::new_with_rng(
+                &Message::from_field_elements(&msgs),
+                &keypair.sigkey,
+                params,
+                rng,
+            )
+        };
+        let old_sig = sign(old, rng)?;
+        let new_sig = sign(new, rng)?;
+        Ok(Self {
+            old_verkey_id,
+            new_verkey_id,
+            epoch,
+            old_sig,
+            new_sig,
+        })
+    }
+
+    /// Check the statement is about `old_vk` and `new_vk` and signed with both signing keys
+    pub fn verify(
+        &self,
+        old_vk: &Verkey,
+        new_vk: &Verkey,
+        params: &Params,
+    ) -> Result<bool, PSError> {
+        if old_vk.Y_tilde.is_empty()
+            || new_vk.Y_tilde.is_empty()
+            || self.old_verkey_id != verkey_id(old_vk)
+            || self.new_verkey_id != verkey_id(new_vk)
+        {
+            return Ok(false);
+        }
+        for (sig, vk) in &[(&self.old_sig, old_vk), (&self.new_sig, new_vk)] {
+            let msgs = rotation_messages(
+                &self.old_verkey_id,
+                &self.new_verkey_id,
+                self.epoch,
+                vk.Y_tilde.len(),
+            );
+            if !sig.verify(&Message::from_field_elements(&msgs), vk, params)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Issuer side. Verify `request` re-issues a credential under the old key and blind sign it with
+    /// the new key.
+    pub fn reissue(
+        &self,
+        request: &DerivedCredentialRequest,
+        old_vk: &Verkey,
+        new: &Keypair,
+        params: &Params,
+    ) -> Result<Signature, PSError> {
+        if self.old_verkey_id != verkey_id(old_vk) || self.new_verkey_id != verkey_id(&new.verkey) {
+            return Err(PSError::GeneralError {
+                msg: String::from("Rotation statement is for other keys"),
+            });
+        }
+        if !request.new_attrs.is_empty()
+            || request.carry_over_indices != (0..old_vk.Y_tilde.len()).collect::<Vec<usize>>()
+        {
+            return Err(PSError::GeneralError {
+                msg: String::from("Not a re-issuance request carrying over all messages"),
+            });
+        }
+        let blinding_key = BlindingKey::new(&new.sigkey, params);
+        request.issue(old_vk, params, &new.sigkey, &blinding_key, params)
+    }
+}
+
+/// Holder side. Request re-issuance of the credential `sig` on `messages` under `old_vk` with the new
+/// key's `new_blinding_key`. The returned blinding unblinds the issued signature, which is on the same
+/// messages. `nonce` comes from the issuer.
+pub fn request_reissue(
+    sig: &Signature,
+    messages: &[FieldElement],
+    old_vk: &Verkey,
+    new_blinding_key: &BlindingKey,
+    params: &Params,
+    nonce: &[u8],
+) -> Result<(DerivedCredentialRequest, Blinding), PSError> {
+    request_reissue_with_rng(
+        sig,
+        messages,
+        old_vk,
+        new_blinding_key,
+        params,
+        nonce,
+        &mut thread_rng(),
+    )
+}
+
+pub fn request_reissue_with_rng<R: RngCore + CryptoRng>(
+    sig: &Signature,
+    messages: &[FieldElement],
+    old_vk: &Verkey,
+    new_blinding_key: &BlindingKey,
+    params: &Params,
+    nonce: &[u8],
+    rng: &mut R,
+) -> Result<(DerivedCredentialRequest, Blinding), PSError> {
+    let parent = ParentCredential {
+        sig,
+        messages,
+        vk: old_vk,
+        params,
+    };
+    let all: Vec<usize> = (0..messages.len()).collect();
+    derive_credential_with_rng(&parent, &all, &[], new_blinding_key, params, nonce, rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blind_signature::BlindSignature;
+    use amcl_wrapper::field_elem::FieldElementVector;
+
+    #[test]
+    fn test_key_rotation() {
+        let params = Params::new("test".as_bytes());
+        let old = Keypair::new(3, &params);
+        let new = Keypair::new(3, &params);
+        let statement = KeyRotationStatement::new(&old, &new, 7, &params).unwrap();
+        assert!(statement.verify(&old.verkey, &new.verkey, &params).unwrap());
+        assert!(!statement.verify(&new.verkey, &old.verkey, &params).unwrap());
+        let other = Keypair::new(3, &params);
+        assert!(!statement
+            .verify(&old.verkey, &other.verkey, &params)
+            .unwrap());
+
+        let msgs = FieldElementVector::random(3);
+        let sig = Signature::new(
+            &Message::from_field_elements(msgs.as_slice()),
+            &old.sigkey,
+            &params,
+        )
+        .unwrap();
+        let new_blinding_key = BlindingKey::new(&new.sigkey, &params);
+        let (request, blinding) = request_reissue(
+            &sig,
+            msgs.as_slice(),
+            &old.verkey,
+            &new_blinding_key,
+            &params,
+            b"nonce",
+        )
+        .unwrap();
+        let blinded = statement
+            .reissue(&request, &old.verkey, &new, &params)
+            .unwrap();
+        let new_sig = BlindSignature::unblind(&blinded, &blinding);
+        assert!(new_sig
+            .verify(
+                &Message::from_field_elements(msgs.as_slice()),
+                &new.verkey,
+                &params
+            )
+            .unwrap());
+
+        // Requests dropping messages or for another key are rejected
+        let mut partial = request.clone();
+        partial.carry_over_indices = vec![0, 1];
+        assert!(statement
+            .reissue(&partial, &old.verkey, &new, &params)
+            .is_err());
+        assert!(statement
+            .reissue(&request, &old.verkey, &other, &params)
+            .is_err());
+    }
+}
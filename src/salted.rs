@@ -0,0 +1,122 @@
+// Salted encoding for low-entropy attributes like gender or country. The encoding of such a value can be
+// recomputed for every possible value, so the message is instead the encoding of the value together with
+// a random per-credential salt, and the salt is signed as the next message. Revealing a salted attribute
+// reveals both messages so the verifier recomputes the encoding from the disclosed value and salt.
+
+use crate::errors::PSError;
+use crate::hash_to_field::hash_to_field;
+use amcl_wrapper::field_elem::FieldElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::{HashMap, HashSet};
+
+/// Tag of `hash_to_field` for `encode_salted_attribute`
+pub const SALTED_ATTRIBUTE_DST: &[u8] = b"PS-SIG-V01-CS01-SALTED-ATTRIBUTE_XMD:SHA-256_";
+
+/// Encoding of `value` with `salt`, differs from `encode_attribute(value)`
+pub fn encode_salted_attribute(value: &str, salt: &FieldElement) -> FieldElement {
+    hash_to_field(
+        &[&salt.to_bytes(), value.as_bytes()].concat(),
+        SALTED_ATTRIBUTE_DST,
+    )
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SaltedAttribute {
+    pub value: String,
+    pub salt: FieldElement,
+}
+
+impl SaltedAttribute {
+    pub fn new(value: &str) -> Self {
+        Self::new_with_rng(value, &mut thread_rng())
+    }
+
+    pub fn new_with_rng<R: RngCore + CryptoRng>(value: &str, rng: &mut R) -> Self {
+        Self {
+            value: value.to_string(),
+            salt: FieldElement::random_using_rng(rng),
+        }
+    }
+
+    /// The 2 messages to sign at consecutive indices, the encoding followed by the salt
+    pub fn messages(&self) -> [FieldElement; 2] {
+        [
+            encode_salted_attribute(&self.value, &self.salt),
+            self.salt.clone(),
+        ]
+    }
+
+    /// Indices to reveal for disclosing the salted attribute signed at index `idx`
+    pub fn reveal(revealed_msg_indices: &mut HashSet<usize>, idx: usize) {
+        revealed_msg_indices.insert(idx);
+        revealed_msg_indices.insert(idx + 1);
+    }
+
+    /// Check the disclosed `value` against the revealed messages of the attribute signed at `idx` and
+    /// return the attribute
+    pub fn from_revealed(
+        revealed_msgs: &HashMap<usize, FieldElement>,
+        idx: usize,
+        value: &str,
+    ) -> Result<Self, PSError> {
+        let (encoded, salt) = match (revealed_msgs.get(&idx), revealed_msgs.get(&(idx + 1))) {
+            (Some(e), Some(s)) => (e, s),
+            _ => {
+                return Err(PSError::GeneralError {
+                    msg: format!("Salted attribute at {} and its salt must be revealed", idx),
+                })
+            }
+        };
+        if encode_salted_attribute(value, salt) != *encoded {
+            return Err(PSError::GeneralError {
+                msg: format!("Message {} is not the salted encoding of {:?}", idx, value),
+            });
+        }
+        Ok(Self {
+            value: value.to_string(),
+            salt: salt.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding_proof::encode_attribute;
+    use crate::keys::{keygen, Params};
+    use crate::presentation::Presentation;
+    use crate::signature::Signature;
+    use crate::types::Message;
+
+    #[test]
+    fn test_salted_attribute() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(4, &params);
+        // Country at index 1 and its salt at index 2
+        let country = SaltedAttribute::new("NL");
+        let country_1 = SaltedAttribute::new("NL");
+        assert_ne!(country.messages()[0], country_1.messages()[0]);
+        assert_ne!(country.messages()[0], encode_attribute("NL"));
+
+        let mut msgs = vec![encode_attribute("Alice")];
+        msgs.extend_from_slice(&country.messages());
+        msgs.push(FieldElement::random());
+        let sig = Signature::new(&Message::from_field_elements(&msgs), &sk, &params).unwrap();
+
+        let mut revealed = HashSet::new();
+        SaltedAttribute::reveal(&mut revealed, 1);
+        let pres = Presentation::new(&sig, &vk, &params, &msgs, revealed, b"nonce").unwrap();
+        assert!(pres.verify(&vk, &params).unwrap());
+        assert_eq!(
+            SaltedAttribute::from_revealed(&pres.revealed_msgs, 1, "NL").unwrap(),
+            country
+        );
+        assert!(SaltedAttribute::from_revealed(&pres.revealed_msgs, 1, "BE").is_err());
+
+        // Salt not revealed
+        let mut revealed = HashSet::new();
+        revealed.insert(1);
+        let pres = Presentation::new(&sig, &vk, &params, &msgs, revealed, b"nonce").unwrap();
+        assert!(SaltedAttribute::from_revealed(&pres.revealed_msgs, 1, "NL").is_err());
+    }
+}
@@ -0,0 +1,255 @@
+// Attribute names instead of message indices. A `MessageSchema` lists the attribute names of a
+// credential in the order of the messages, and the `_named` variants of signing, proving and verifying
+// take messages and revealed attributes by name and resolve them against the schema. Every attribute
+// of the schema must be given when signing and unknown names are errors, so a misspelled or missing
+// attribute fails instead of shifting the remaining messages.
+
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::pok_sig::{PoKOfSignature, PoKOfSignatureProof};
+use crate::signature::Signature;
+use crate::signer::PsSigner;
+use crate::types::{Challenge, Message};
+use amcl_wrapper::field_elem::FieldElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageSchema {
+    attributes: Vec<String>,
+}
+
+impl MessageSchema {
+    /// Attribute names in the order of the messages, names must be distinct
+    pub fn new(attributes: &[&str]) -> Result<Self, PSError> {
+        let mut seen = HashSet::new();
+        for name in attributes {
+            if !seen.insert(name) {
+                return Err(PSError::GeneralError {
+                    msg: format!("Duplicate attribute {}", name),
+                });
+            }
+        }
+        Ok(Self {
+            attributes: attributes.iter().map(|a| a.to_string()).collect(),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.attributes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+
+    /// Names as expected by `CompiledPolicy::compile`
+    pub fn attribute_names(&self) -> Vec<&str> {
+        self.attributes.iter().map(|a| a.as_str()).collect()
+    }
+
+    pub fn index_of(&self, name: &str) -> Result<usize, PSError> {
+        self.attributes
+            .iter()
+            .position(|a| a == name)
+            .ok_or_else(|| PSError::UnknownAttribute {
+                name: name.to_string(),
+            })
+    }
+
+    pub fn indices(&self, names: &[&str]) -> Result<HashSet<usize>, PSError> {
+        names.iter().map(|n| self.index_of(n)).collect()
+    }
+
+    /// The messages in schema order, `named` must have exactly the attributes of the schema
+    pub fn messages(
+        &self,
+        named: &HashMap<String, FieldElement>,
+    ) -> Result<Vec<FieldElement>, PSError> {
+        if let Some(name) = named.keys().find(|n| !self.attributes.contains(n)) {
+            return Err(PSError::UnknownAttribute { name: name.clone() });
+        }
+        self.attributes
+            .iter()
+            .map(|a| {
+                named.get(a).cloned().ok_or_else(|| PSError::GeneralError {
+                    msg: format!("Missing attribute {}", a),
+                })
+            })
+            .collect()
+    }
+
+    /// Revealed messages by index from revealed messages by name
+    pub fn revealed_msgs(
+        &self,
+        named: &HashMap<String, FieldElement>,
+    ) -> Result<HashMap<usize, FieldElement>, PSError> {
+        named
+            .iter()
+            .map(|(n, m)| Ok((self.index_of(n)?, m.clone())))
+            .collect()
+    }
+
+    /// Revealed messages by name, e.g. for showing a verified presentation
+    pub fn named_revealed_msgs(
+        &self,
+        revealed_msgs: &HashMap<usize, FieldElement>,
+    ) -> Result<HashMap<String, FieldElement>, PSError> {
+        revealed_msgs
+            .iter()
+            .map(|(i, m)| match self.attributes.get(*i) {
+                Some(name) => Ok((name.clone(), m.clone())),
+                None => Err(PSError::GeneralError {
+                    msg: format!("Index {} should be less than {}", i, self.len()),
+                }),
+            })
+            .collect()
+    }
+}
+
+impl Signature {
+    pub fn new_named<S: PsSigner + ?Sized>(
+        messages: &HashMap<String, FieldElement>,
+        schema: &MessageSchema,
+        signer: &S,
+        params: &Params,
+    ) -> Result<Self, PSError> {
+        Self::new_named_with_rng(messages, schema, signer, params, &mut thread_rng())
+    }
+
+    pub fn new_named_with_rng<S: PsSigner + ?Sized, R: RngCore + CryptoRng>(
+        messages: &HashMap<String, FieldElement>,
+        schema: &MessageSchema,
+        signer: &S,
+        params: &Params,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        signer.sign_with_rng(&schema.messages(messages)?, params, rng)
+    }
+
+    pub fn verify_named(
+        &self,
+        messages: &HashMap<String, FieldElement>,
+        schema: &MessageSchema,
+        vk: &Verkey,
+        params: &Params,
+    ) -> Result<bool, PSError> {
+        self.verify(
+            &Message::from_field_elements(&schema.messages(messages)?),
+            vk,
+            params,
+        )
+    }
+}
+
+impl PoKOfSignature {
+    /// Same as `init` revealing the attributes named in `revealed`
+    pub fn init_named(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &HashMap<String, FieldElement>,
+        schema: &MessageSchema,
+        revealed: &[&str],
+    ) -> Result<Self, PSError> {
+        Self::init_named_with_rng(
+            sig,
+            vk,
+            params,
+            messages,
+            schema,
+            revealed,
+            &mut thread_rng(),
+        )
+    }
+
+    pub fn init_named_with_rng<R: RngCore + CryptoRng>(
+        sig: &Signature,
+        vk: &Verkey,
+        params: &Params,
+        messages: &HashMap<String, FieldElement>,
+        schema: &MessageSchema,
+        revealed: &[&str],
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        Self::init_with_rng(
+            sig,
+            vk,
+            params,
+            &Message::from_field_elements(&schema.messages(messages)?),
+            None,
+            schema.indices(revealed)?,
+            rng,
+        )
+    }
+}
+
+impl PoKOfSignatureProof {
+    /// Same as `verify` with the revealed messages by name
+    pub fn verify_named(
+        &self,
+        vk: &Verkey,
+        params: &Params,
+        schema: &MessageSchema,
+        revealed_msgs: &HashMap<String, FieldElement>,
+        challenge: &Challenge,
+    ) -> Result<bool, PSError> {
+        if schema.len() != vk.Y_tilde.len() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: vk.Y_tilde.len(),
+                given: schema.len(),
+            });
+        }
+        self.verify(vk, params, schema.revealed_msgs(revealed_msgs)?, challenge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use amcl_wrapper::group_elem::GroupElement;
+
+    #[test]
+    fn test_named_attributes() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let schema = MessageSchema::new(&["name", "email", "dob"]).unwrap();
+        assert!(MessageSchema::new(&["name", "name"]).is_err());
+        let mut messages = HashMap::new();
+        for a in schema.attribute_names() {
+            messages.insert(a.to_string(), FieldElement::random());
+        }
+        let sig = Signature::new_named(&messages, &schema, &sk, &params).unwrap();
+        assert!(sig.verify_named(&messages, &schema, &vk, &params).unwrap());
+        let in_order = schema.messages(&messages).unwrap();
+        assert!(sig
+            .verify(&Message::from_field_elements(&in_order), &vk, &params)
+            .unwrap());
+
+        let pok =
+            PoKOfSignature::init_named(&sig, &vk, &params, &messages, &schema, &["email"]).unwrap();
+        let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
+        let proof = pok.gen_proof(&chal).unwrap();
+        let mut revealed = HashMap::new();
+        revealed.insert("email".to_string(), messages["email"].clone());
+        assert!(proof
+            .verify_named(&vk, &params, &schema, &revealed, &chal)
+            .unwrap());
+        let indexed = schema.revealed_msgs(&revealed).unwrap();
+        assert_eq!(indexed.keys().collect::<Vec<_>>(), vec![&1]);
+        assert_eq!(schema.named_revealed_msgs(&indexed).unwrap(), revealed);
+
+        // Unknown and missing attributes are errors
+        revealed.insert("mail".to_string(), FieldElement::one());
+        assert!(proof
+            .verify_named(&vk, &params, &schema, &revealed, &chal)
+            .is_err());
+        assert!(
+            PoKOfSignature::init_named(&sig, &vk, &params, &messages, &schema, &["e-mail"])
+                .is_err()
+        );
+        messages.remove("dob");
+        assert!(Signature::new_named(&messages, &schema, &sk, &params).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+// Password encrypted storage of signing keys. The key is derived from the password with Argon2id and
+// `Sigkey::to_versioned_bytes` is encrypted with XChaCha20-Poly1305. The sealed key is the format byte,
+// the Argon2id costs, the salt and the nonce, all authenticated as associated data, followed by the
+// ciphertext. Costs are stored so they can be raised later without breaking old sealed keys.
+
+use crate::errors::PSError;
+use crate::keys::Sigkey;
+use crate::utils::ByteReader;
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand::{thread_rng, CryptoRng, RngCore};
+
+/// First byte of `Sigkey::seal`
+const SEALED_FORMAT_V1: u8 = 1;
+
+const SALT_SIZE: usize = 16;
+const NONCE_SIZE: usize = 24;
+const KEY_SIZE: usize = 32;
+/// Larger costs in a sealed key are rejected so opening an untrusted one cannot exhaust memory
+const MAX_MEMORY_KIB: u32 = 1 << 20;
+const MAX_ITERATIONS: u32 = 64;
+
+/// Argon2id costs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    /// 19 MiB and 2 passes as recommended by OWASP
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    fn derive_key(&self, password: &[u8], salt: &[u8]) -> Result<[u8; KEY_SIZE], PSError> {
+        if self.memory_kib > MAX_MEMORY_KIB || self.iterations > MAX_ITERATIONS {
+            return Err(PSError::InvalidEncoding {
+                msg: format!("Argon2id costs too large: {:?}", self),
+            });
+        }
+        let params = Params::new(
+            self.memory_kib,
+            self.iterations,
+            self.parallelism,
+            Some(KEY_SIZE),
+        )
+        .map_err(|e| PSError::GeneralError {
+            msg: format!("Invalid Argon2id costs: {}", e),
+        })?;
+        let mut key = [0; KEY_SIZE];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(password, salt, &mut key)
+            .map_err(|e| PSError::GeneralError {
+                msg: format!("Argon2id failed: {}", e),
+            })?;
+        Ok(key)
+    }
+}
+
+impl Sigkey {
+    /// Encrypt with a key derived from `password` using the default costs
+    pub fn seal(&self, password: &[u8]) -> Result<Vec<u8>, PSError> {
+        self.seal_with_rng(password, &KdfParams::default(), &mut thread_rng())
+    }
+
+    /// Same as `seal` with costs `kdf`, salt and nonce come from `rng`
+    pub fn seal_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        password: &[u8],
+        kdf: &KdfParams,
+        rng: &mut R,
+    ) -> Result<Vec<u8>, PSError> {
+        let mut salt = [0; SALT_SIZE];
+        rng.fill_bytes(&mut salt);
+        let mut nonce = [0; NONCE_SIZE];
+        rng.fill_bytes(&mut nonce);
+        let key = kdf.derive_key(password, &salt)?;
+
+        let mut bytes = vec![SEALED_FORMAT_V1];
+        for cost in &[kdf.memory_kib, kdf.iterations, kdf.parallelism] {
+            bytes.extend_from_slice(&cost.to_be_bytes());
+        }
+        bytes.extend_from_slice(&salt);
+        bytes.extend_from_slice(&nonce);
+        let mut ciphertext = XChaCha20Poly1305::new(Key::from_slice(&key))
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &self.to_versioned_bytes(),
+                    aad: &bytes,
+                },
+            )
+            .map_err(|_| PSError::GeneralError {
+                msg: String::from("Encryption failed"),
+            })?;
+        bytes.append(&mut ciphertext);
+        Ok(bytes)
+    }
+
+    /// Decrypt a key sealed with `password`. Fails for a wrong password or a modified sealed key.
+    pub fn open(sealed: &[u8], password: &[u8]) -> Result<Self, PSError> {
+        let mut reader = ByteReader::new(sealed);
+        let header_size = 1 + 12 + SALT_SIZE + NONCE_SIZE;
+        let format = reader.read_u8()?;
+        if format != SEALED_FORMAT_V1 {
+            return Err(PSError::InvalidEncoding {
+                msg: format!("Unknown sealed key format {}", format),
+            });
+        }
+        let mut costs = [0; 3];
+        for cost in costs.iter_mut() {
+            let mut b = [0; 4];
+            b.copy_from_slice(reader.take(4)?);
+            *cost = u32::from_be_bytes(b);
+        }
+        let kdf = KdfParams {
+            memory_kib: costs[0],
+            iterations: costs[1],
+            parallelism: costs[2],
+        };
+        let salt = reader.take(SALT_SIZE)?;
+        let nonce = reader.take(NONCE_SIZE)?;
+        let key = kdf.derive_key(password, salt)?;
+        let plaintext = XChaCha20Poly1305::new(Key::from_slice(&key))
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: &sealed[header_size..],
+                    aad: &sealed[..header_size],
+                },
+            )
+            .map_err(|_| PSError::GeneralError {
+                msg: String::from("Wrong password or modified sealed key"),
+            })?;
+        Sigkey::from_versioned_bytes(&plaintext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::{keygen, Params as PSParams};
+
+    #[test]
+    fn test_seal_open() {
+        let params = PSParams::new("test".as_bytes());
+        let (sk, _) = keygen(3, &params);
+        // Low costs to keep the test fast
+        let kdf = KdfParams {
+            memory_kib: 64,
+            iterations: 1,
+            parallelism: 1,
+        };
+        let sealed = sk
+            .seal_with_rng(b"correct horse", &kdf, &mut thread_rng())
+            .unwrap();
+        let opened = Sigkey::open(&sealed, b"correct horse").unwrap();
+        assert_eq!(opened.to_versioned_bytes(), sk.to_versioned_bytes());
+
+        assert!(Sigkey::open(&sealed, b"wrong horse").is_err());
+        // Costs are authenticated
+        let mut modified = sealed.clone();
+        modified[8] += 1;
+        assert!(Sigkey::open(&modified, b"correct horse").is_err());
+        let mut modified = sealed.clone();
+        *modified.last_mut().unwrap() ^= 1;
+        assert!(Sigkey::open(&modified, b"correct horse").is_err());
+        assert!(Sigkey::open(&sealed[..20], b"correct horse").is_err());
+    }
+}
@@ -0,0 +1,264 @@
+// Named sections of a verkey. The Y_tilde bases (and the corresponding signing key elements) are
+// partitioned into consecutive named ranges like "identity", "address" or "employment" so one issuer
+// key can back modular credentials. A credential over only some sections is a signature where the
+// messages of the other sections are 0, those messages are revealed as 0 in every presentation.
+
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::signature::Signature;
+use crate::signer::PsSigner;
+use crate::OtherGroup;
+use amcl_wrapper::field_elem::FieldElement;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Section {
+    pub name: String,
+    pub start: usize,
+    pub len: usize,
+}
+
+impl Section {
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.start + self.len
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SectionLayout {
+    sections: Vec<Section>,
+}
+
+impl SectionLayout {
+    /// Sections in order of the message indices, given as (name, number of messages)
+    pub fn new(sections: &[(&str, usize)]) -> Result<Self, PSError> {
+        let mut start = 0;
+        let mut layout = vec![];
+        for (name, len) in sections {
+            if layout.iter().any(|s: &Section| s.name == *name) {
+                return Err(PSError::GeneralError {
+                    msg: format!("Duplicate section {}", name),
+                });
+            }
+            layout.push(Section {
+                name: name.to_string(),
+                start,
+                len: *len,
+            });
+            start += len;
+        }
+        Ok(Self { sections: layout })
+    }
+
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// Total number of messages, the verkey must support exactly these many
+    pub fn msg_count(&self) -> usize {
+        self.sections.iter().map(|s| s.len).sum()
+    }
+
+    pub fn section(&self, name: &str) -> Result<&Section, PSError> {
+        self.sections
+            .iter()
+            .find(|s| s.name == name)
+            .ok_or_else(|| PSError::UnknownSection {
+                name: name.to_string(),
+            })
+    }
+
+    /// Index of the `offset`th message of section `name`
+    pub fn index(&self, name: &str, offset: usize) -> Result<usize, PSError> {
+        let section = self.section(name)?;
+        if offset >= section.len {
+            return Err(PSError::GeneralError {
+                msg: format!(
+                    "Offset {} should be less than {} in section {}",
+                    offset, section.len, name
+                ),
+            });
+        }
+        Ok(section.start + offset)
+    }
+
+    pub fn check_verkey(&self, vk: &Verkey) -> Result<(), PSError> {
+        if vk.Y_tilde.len() != self.msg_count() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: vk.Y_tilde.len(),
+                given: self.msg_count(),
+            });
+        }
+        Ok(())
+    }
+
+    /// The bases of section `name`
+    pub fn section_bases<'a>(
+        &self,
+        vk: &'a Verkey,
+        name: &str,
+    ) -> Result<&'a [OtherGroup], PSError> {
+        self.check_verkey(vk)?;
+        Ok(&vk.Y_tilde[self.section(name)?.range()])
+    }
+
+    /// Full message vector from messages of some sections, messages of the other sections are 0
+    pub fn messages(
+        &self,
+        sections: &[(&str, &[FieldElement])],
+    ) -> Result<Vec<FieldElement>, PSError> {
+        let mut messages = vec![FieldElement::zero(); self.msg_count()];
+        let mut seen = HashSet::new();
+        for (name, msgs) in sections {
+            let section = self.section(name)?;
+            if !seen.insert(name.to_string()) {
+                return Err(PSError::GeneralError {
+                    msg: format!("Section {} given more than once", name),
+                });
+            }
+            if msgs.len() != section.len {
+                return Err(PSError::UnsupportedNoOfMessages {
+                    expected: section.len,
+                    given: msgs.len(),
+                });
+            }
+            messages[section.range()].clone_from_slice(msgs);
+        }
+        Ok(messages)
+    }
+
+    /// Sign messages of some sections only
+    pub fn sign_sections<S: PsSigner + ?Sized>(
+        &self,
+        sections: &[(&str, &[FieldElement])],
+        signer: &S,
+        params: &Params,
+    ) -> Result<Signature, PSError> {
+        if signer.msg_count() != self.msg_count() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: signer.msg_count(),
+                given: self.msg_count(),
+            });
+        }
+        signer.sign(&self.messages(sections)?, params)
+    }
+
+    /// Message indices of the given sections
+    pub fn indices(&self, names: &[&str]) -> Result<HashSet<usize>, PSError> {
+        let mut indices = HashSet::new();
+        for name in names {
+            indices.extend(self.section(name)?.range());
+        }
+        Ok(indices)
+    }
+
+    /// Revealed messages for disclosing sections `names` of `messages`, for `PoKOfSignatureProof::verify`
+    pub fn revealed_msgs(
+        &self,
+        messages: &[FieldElement],
+        names: &[&str],
+    ) -> Result<HashMap<usize, FieldElement>, PSError> {
+        if messages.len() != self.msg_count() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: self.msg_count(),
+                given: messages.len(),
+            });
+        }
+        Ok(self
+            .indices(names)?
+            .into_iter()
+            .map(|i| (i, messages[i].clone()))
+            .collect())
+    }
+
+    /// Messages of section `name` among `revealed_msgs`, fails unless the whole section is revealed
+    pub fn section_messages(
+        &self,
+        revealed_msgs: &HashMap<usize, FieldElement>,
+        name: &str,
+    ) -> Result<Vec<FieldElement>, PSError> {
+        self.section(name)?
+            .range()
+            .map(|i| {
+                revealed_msgs
+                    .get(&i)
+                    .cloned()
+                    .ok_or_else(|| PSError::GeneralError {
+                        msg: format!("Message {} of section {} not revealed", i, name),
+                    })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use crate::pok_sig::PoKOfSignature;
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use amcl_wrapper::group_elem::GroupElement;
+
+    #[test]
+    fn test_sections() {
+        let layout =
+            SectionLayout::new(&[("identity", 3), ("address", 2), ("employment", 2)]).unwrap();
+        assert_eq!(layout.msg_count(), 7);
+        assert_eq!(layout.index("address", 1).unwrap(), 4);
+        assert!(layout.index("address", 2).is_err());
+        assert!(layout.section("phone").is_err());
+        assert!(SectionLayout::new(&[("identity", 3), ("identity", 2)]).is_err());
+
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(layout.msg_count(), &params);
+        layout.check_verkey(&vk).unwrap();
+        assert_eq!(
+            layout.section_bases(&vk, "employment").unwrap(),
+            &vk.Y_tilde[5..7]
+        );
+
+        // Credential over identity and address only
+        let identity = FieldElementVector::random(3);
+        let address = FieldElementVector::random(2);
+        let sections = [
+            ("identity", identity.as_slice()),
+            ("address", address.as_slice()),
+        ];
+        let sig = layout.sign_sections(&sections, &sk, &params).unwrap();
+        let msgs = layout.messages(&sections).unwrap();
+        assert_eq!(msgs[5], FieldElement::zero());
+        assert!(sig
+            .verify(&Message::from_field_elements(&msgs), &vk, &params)
+            .unwrap());
+        assert!(layout
+            .sign_sections(&[("address", identity.as_slice())], &sk, &params)
+            .is_err());
+
+        // Disclose address, employment is revealed as 0 to show it is not part of the credential
+        let revealed_indices = layout.indices(&["address", "employment"]).unwrap();
+        let pok = PoKOfSignature::init(
+            &sig,
+            &vk,
+            &params,
+            &Message::from_field_elements(&msgs),
+            None,
+            revealed_indices,
+        )
+        .unwrap();
+        let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
+        let proof = pok.gen_proof(&chal).unwrap();
+        let revealed_msgs = layout
+            .revealed_msgs(&msgs, &["address", "employment"])
+            .unwrap();
+        assert!(proof
+            .verify(&vk, &params, revealed_msgs.clone(), &chal)
+            .unwrap());
+        assert_eq!(
+            layout.section_messages(&revealed_msgs, "address").unwrap(),
+            address.as_slice().to_vec()
+        );
+        assert!(layout.section_messages(&revealed_msgs, "identity").is_err());
+    }
+}
@@ -0,0 +1,208 @@
+// Verifier side state of one proof request: the nonce issued to the client, the expected request,
+// the presentation received and the result. So that any instance behind a load balancer can continue a
+// session, the state is sealed into a blob handed to the client, or any untrusted storage, between
+// requests. The blob is the format byte and a nonce, authenticated as associated data, followed by the
+// JSON of the session encrypted with XChaCha20-Poly1305 under a key shared by the instances.
+//
+// A blob cannot be modified but it can be replayed: a client holding a blob from before its
+// presentation was received can submit again. Verifiers needing a single attempt per nonce must record
+// used nonces.
+
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::policy::{CompiledPolicy, PolicyReport};
+use crate::presentation::Presentation;
+use crate::utils::ByteReader;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// First byte of `VerifierSession::seal`
+const SESSION_FORMAT_V1: u8 = 1;
+
+const BLOB_NONCE_SIZE: usize = 24;
+/// Size of the nonces issued to clients
+pub const SESSION_NONCE_SIZE: usize = 32;
+pub const SESSION_KEY_SIZE: usize = 32;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VerifierSession {
+    /// For the client to put in its `Presentation`
+    pub nonce: Vec<u8>,
+    pub request: CompiledPolicy,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+    pub presentation: Option<Presentation>,
+    pub result: Option<PolicyReport>,
+}
+
+impl VerifierSession {
+    /// Session for `request` with a fresh nonce
+    pub fn new(request: CompiledPolicy) -> Self {
+        Self::new_with_rng(request, &mut thread_rng())
+    }
+
+    pub fn new_with_rng<R: RngCore + CryptoRng>(request: CompiledPolicy, rng: &mut R) -> Self {
+        let mut nonce = vec![0; SESSION_NONCE_SIZE];
+        rng.fill_bytes(&mut nonce);
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self {
+            nonce,
+            request,
+            created_at,
+            presentation: None,
+            result: None,
+        }
+    }
+
+    /// Verify the client's presentation against the session's nonce and request. Only one
+    /// presentation is accepted per session.
+    pub fn receive(
+        &mut self,
+        presentation: Presentation,
+        vk: &Verkey,
+        params: &Params,
+    ) -> Result<&PolicyReport, PSError> {
+        if self.result.is_some() {
+            return Err(PSError::GeneralError {
+                msg: String::from("Session already received a presentation"),
+            });
+        }
+        let proof_valid = presentation.nonce == self.nonce && presentation.verify(vk, params)?;
+        let violations = self.request.evaluate(vk, &presentation.revealed_msgs);
+        self.presentation = Some(presentation);
+        Ok(self.result.get_or_insert(PolicyReport {
+            proof_valid,
+            violations,
+        }))
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// Encrypt the session under `key`
+    pub fn seal(&self, key: &[u8; SESSION_KEY_SIZE]) -> Result<Vec<u8>, PSError> {
+        self.seal_with_rng(key, &mut thread_rng())
+    }
+
+    pub fn seal_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        key: &[u8; SESSION_KEY_SIZE],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, PSError> {
+        let json = serde_json::to_vec(self).map_err(|e| PSError::GeneralError {
+            msg: format!("Cannot serialize session: {}", e),
+        })?;
+        let mut nonce = [0; BLOB_NONCE_SIZE];
+        rng.fill_bytes(&mut nonce);
+        let mut bytes = vec![SESSION_FORMAT_V1];
+        bytes.extend_from_slice(&nonce);
+        let mut ciphertext = XChaCha20Poly1305::new(Key::from_slice(key))
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &json,
+                    aad: &bytes,
+                },
+            )
+            .map_err(|_| PSError::GeneralError {
+                msg: String::from("Encryption failed"),
+            })?;
+        bytes.append(&mut ciphertext);
+        Ok(bytes)
+    }
+
+    /// Decrypt a session sealed under `key`. Fails for another key or a modified blob.
+    pub fn open(sealed: &[u8], key: &[u8; SESSION_KEY_SIZE]) -> Result<Self, PSError> {
+        let mut reader = ByteReader::new(sealed);
+        let format = reader.read_u8()?;
+        if format != SESSION_FORMAT_V1 {
+            return Err(PSError::InvalidEncoding {
+                msg: format!("Unknown session format {}", format),
+            });
+        }
+        let nonce = reader.take(BLOB_NONCE_SIZE)?;
+        let header_size = 1 + BLOB_NONCE_SIZE;
+        let json = XChaCha20Poly1305::new(Key::from_slice(key))
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: &sealed[header_size..],
+                    aad: &sealed[..header_size],
+                },
+            )
+            .map_err(|_| PSError::GeneralError {
+                msg: String::from("Wrong key or modified session"),
+            })?;
+        serde_json::from_slice(&json).map_err(|e| PSError::InvalidEncoding {
+            msg: format!("Invalid session: {}", e),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use crate::policy::Policy;
+    use crate::signature::Signature;
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_verifier_session() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let msgs = FieldElementVector::random(3);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let request = CompiledPolicy::compile(
+            &[Policy::require_revealed("dob")],
+            &["name", "dob", "country"],
+        )
+        .unwrap();
+        let key = [7u8; SESSION_KEY_SIZE];
+
+        // Instance 1 starts the session, instance 2 receives the presentation
+        let session = VerifierSession::new(request);
+        let blob = session.seal(&key).unwrap();
+        let mut revealed = HashSet::new();
+        revealed.insert(1);
+        let presentation = Presentation::new(
+            &sig,
+            &vk,
+            &params,
+            msgs.as_slice(),
+            revealed,
+            &session.nonce,
+        )
+        .unwrap();
+        let mut session = VerifierSession::open(&blob, &key).unwrap();
+        assert!(!session.is_complete());
+        assert!(session
+            .receive(presentation.clone(), &vk, &params)
+            .unwrap()
+            .is_accepted());
+        assert!(session.receive(presentation, &vk, &params).is_err());
+        let session = VerifierSession::open(&session.seal(&key).unwrap(), &key).unwrap();
+        assert!(session.result.unwrap().is_accepted());
+
+        // A presentation for another nonce is rejected, blobs are authenticated
+        let mut other = VerifierSession::open(&blob, &key).unwrap();
+        let stale =
+            Presentation::new(&sig, &vk, &params, msgs.as_slice(), HashSet::new(), b"old").unwrap();
+        let report = other.receive(stale, &vk, &params).unwrap();
+        assert!(!report.proof_valid);
+        assert!(!report.violations.is_empty());
+        assert!(VerifierSession::open(&blob, &[8u8; SESSION_KEY_SIZE]).is_err());
+        let mut modified = blob.clone();
+        *modified.last_mut().unwrap() ^= 1;
+        assert!(VerifierSession::open(&modified, &key).is_err());
+    }
+}
@@ -0,0 +1,99 @@
+// Shamir secret sharing over the scalar field. A secret is the constant term of a random polynomial of
+// degree `threshold - 1` and share i is the polynomial evaluated at i, for i in 1..=total. Any
+// `threshold` shares recover the secret by Lagrange interpolation at 0.
+
+use crate::errors::PSError;
+use amcl_wrapper::field_elem::FieldElement;
+use rand::{CryptoRng, RngCore};
+use std::collections::HashSet;
+
+/// Coefficients of the sharing polynomial, the 0th is the secret
+pub fn random_polynomial<R: RngCore + CryptoRng>(
+    secret: &FieldElement,
+    threshold: usize,
+    total: usize,
+    rng: &mut R,
+) -> Result<Vec<FieldElement>, PSError> {
+    if threshold == 0 || threshold > total {
+        return Err(PSError::GeneralError {
+            msg: format!(
+                "Threshold {} should be between 1 and the number of shares {}",
+                threshold, total
+            ),
+        });
+    }
+    let mut coefficients = vec![secret.clone()];
+    for _ in 1..threshold {
+        coefficients.push(FieldElement::random_using_rng(rng));
+    }
+    Ok(coefficients)
+}
+
+/// Evaluate the polynomial with `coefficients` at `x` with Horner's method
+pub fn evaluate(coefficients: &[FieldElement], x: usize) -> FieldElement {
+    let x = FieldElement::from(x as u64);
+    let mut res = FieldElement::zero();
+    for c in coefficients.iter().rev() {
+        res = &(&res * &x) + c;
+    }
+    res
+}
+
+/// Lagrange coefficient at 0 of share `i` among shares `indices`
+pub fn lagrange_basis_at_0(indices: &[usize], i: usize) -> FieldElement {
+    let x_i = FieldElement::from(i as u64);
+    let mut numerator = FieldElement::one();
+    let mut denominator = FieldElement::one();
+    for j in indices.iter().filter(|j| **j != i) {
+        let x_j = FieldElement::from(*j as u64);
+        numerator = &numerator * &x_j;
+        denominator = &denominator * &(&x_j - &x_i);
+    }
+    &numerator * &denominator.inverse()
+}
+
+/// Indices must be non-zero and distinct
+pub fn check_indices(indices: &[usize]) -> Result<(), PSError> {
+    let mut seen = HashSet::new();
+    for i in indices {
+        if *i == 0 || !seen.insert(*i) {
+            return Err(PSError::GeneralError {
+                msg: format!("Invalid or repeated share index {}", i),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Interpolate the secret from (index, share) pairs. Gives a wrong secret for fewer shares than the
+/// threshold.
+pub fn reconstruct_secret(shares: &[(usize, FieldElement)]) -> Result<FieldElement, PSError> {
+    let indices: Vec<usize> = shares.iter().map(|(i, _)| *i).collect();
+    check_indices(&indices)?;
+    let mut secret = FieldElement::zero();
+    for (i, share) in shares {
+        secret += &lagrange_basis_at_0(&indices, *i) * share;
+    }
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_shamir() {
+        let secret = FieldElement::random();
+        let coefficients = random_polynomial(&secret, 3, 5, &mut thread_rng()).unwrap();
+        let shares: Vec<(usize, FieldElement)> =
+            (1..=5).map(|i| (i, evaluate(&coefficients, i))).collect();
+        assert_eq!(reconstruct_secret(&shares[..3]).unwrap(), secret);
+        assert_eq!(reconstruct_secret(&shares[2..]).unwrap(), secret);
+        assert_eq!(reconstruct_secret(&shares).unwrap(), secret);
+        assert_ne!(reconstruct_secret(&shares[..2]).unwrap(), secret);
+        assert!(reconstruct_secret(&[shares[0].clone(), shares[0].clone()]).is_err());
+        assert!(random_polynomial(&secret, 6, 5, &mut thread_rng()).is_err());
+        assert!(random_polynomial(&secret, 0, 5, &mut thread_rng()).is_err());
+    }
+}
@@ -1,16 +1,30 @@
 // Scheme defined in section 4.2. The idea for blind signatures can be taken from Coconut
 
 use crate::errors::PSError;
-use crate::{ate_2_pairing, OtherGroup, OtherGroupVec, SignatureGroup, SignatureGroupVec};
+use crate::{ate_2_pairing, ate_multi_pairing, OtherGroupVec, SignatureGroup, SignatureGroupVec};
 use amcl_wrapper::field_elem::{FieldElement, FieldElementVector};
 use amcl_wrapper::group_elem::{GroupElement, GroupElementVector};
 use crate::keys::{Params, Sigkey, Verkey};
+use crate::validation::{deserialize_point, ValidationProfile};
+use crate::utils::{hash_with_domain, FixedBaseTable, MultiScalarMul, HASH_SIZE};
+use crate::SIGNATURE_GROUP_SIZE;
+use crate::pok_sig::PoKOfSignature;
+use crate::types::Message;
+use rand::{thread_rng, CryptoRng, RngCore};
+
+/// Number of messages buffered by `Signature::verify_from_iter` for each multi-scalar multiplication
+pub const ITER_CHUNK_SIZE: usize = 32;
+
+/// Hash used to identify a signature or a proof, see `Signature::fingerprint`
+pub type Fingerprint = [u8; HASH_SIZE];
 
 /// Created by the signer when no blinded messages. Also the receiver of a blind signature can get
 /// this by unblinding the blind signature.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Signature {
+    #[serde(deserialize_with = "deserialize_point")]
     pub sigma_1: SignatureGroup,
+    #[serde(deserialize_with = "deserialize_point")]
     pub sigma_2: SignatureGroup,
 }
 
@@ -24,10 +38,29 @@ impl_PoK_VC!(
 
 impl Signature {
     /// Signer creates a signature.
-    pub fn new(messages: &[FieldElement], sigkey: &Sigkey, params: &Params) -> Result<Self, PSError> {
-        assert_eq!(sigkey.y.len(), messages.len());
+    pub fn new(messages: &[Message], sigkey: &Sigkey, params: &Params) -> Result<Self, PSError> {
+        Self::new_with_rng(messages, sigkey, params, &mut thread_rng())
+    }
+
+    /// Signer creates a signature using the given random number generator.
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        messages: &[Message],
+        sigkey: &Sigkey,
+        params: &Params,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        Self::sign_with_rng(&Message::to_field_elements(messages), sigkey, params, rng)
+    }
+
+    /// Body of `new_with_rng` for encoded messages, also used by `PsSigner::sign_with_rng` of `Sigkey`
+    pub(crate) fn sign_with_rng<R: RngCore + CryptoRng>(
+        messages: &[FieldElement],
+        sigkey: &Sigkey,
+        params: &Params,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
         // A random h should be generated which is same as generating a random u and then computing h = g^u
-        let u = FieldElement::random();
+        let u = FieldElement::random_using_rng(rng);
         let (sigma_1, sigma_2) = Self::sign_with_sigma_1_generated_from_given_exp(
             messages,
             sigkey,
@@ -46,43 +79,311 @@ impl Signature {
         offset: usize,
         g: &SignatureGroup,
     ) -> Result<(SignatureGroup, SignatureGroup), PSError> {
-        assert_eq!(offset + messages.len(), sigkey.y.len());
+        let exp = Self::signing_exponent(messages, sigkey, offset)?;
         // h = g^u
         let h = g * u;
-        // h^(x + y_j*m_j + y_{j+1}*m_{j+1} + y_{j+2}*m_{j+2} + ...) = g^{u * (x + y_j*m_j + y_{j+1}*m_{j+1} + y_{j+2}*m_{j+2} + ...)}
-        let mut exp = sigkey.x.clone();
-        for i in 0..messages.len() {
-            exp += &sigkey.y[offset + i] * &messages[i];
-        }
         let h_exp = &h * &exp;
         Ok((h, h_exp))
     }
 
+    /// x + y_j*m_j + y_{j+1}*m_{j+1} + ... for messages from index `offset`. Since the sigkey holds
+    /// scalars, h^exp is a single scalar multiplication instead of one per message.
+    fn signing_exponent(
+        messages: &[FieldElement],
+        sigkey: &Sigkey,
+        offset: usize,
+    ) -> Result<FieldElement, PSError> {
+        if offset + messages.len() != sigkey.msg_count() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: sigkey.msg_count().saturating_sub(offset),
+                given: messages.len(),
+            });
+        }
+        let mut exp = sigkey.x.clone();
+        for (y, m) in sigkey.y[offset..].iter().zip(messages) {
+            exp += y * m;
+        }
+        Ok(exp)
+    }
+
+    /// Sign many message vectors. Both elements of every signature are powers of g, (g^u, g^(u*exp)),
+    /// so g is multiplied from a `FixedBaseTable` built once for the batch, which is cheaper than
+    /// calling `new` for each from a few signatures on.
+    pub fn new_batch(
+        messages: &[&[FieldElement]],
+        sigkey: &Sigkey,
+        params: &Params,
+    ) -> Result<Vec<Self>, PSError> {
+        Self::new_batch_with_rng(messages, sigkey, params, &mut thread_rng())
+    }
+
+    pub fn new_batch_with_rng<R: RngCore + CryptoRng>(
+        messages: &[&[FieldElement]],
+        sigkey: &Sigkey,
+        params: &Params,
+        rng: &mut R,
+    ) -> Result<Vec<Self>, PSError> {
+        let exps = messages
+            .iter()
+            .map(|m| Self::signing_exponent(m, sigkey, 0))
+            .collect::<Result<Vec<_>, _>>()?;
+        if exps.is_empty() {
+            return Ok(vec![]);
+        }
+        let table = FixedBaseTable::new(&params.g);
+        Ok(exps
+            .iter()
+            .map(|exp| {
+                let u = FieldElement::random_using_rng(rng);
+                Self {
+                    sigma_1: table.mul(&u),
+                    sigma_2: table.mul(&(&u * exp)),
+                }
+            })
+            .collect())
+    }
+
     /// Verify a signature. Can verify unblinded sig received from a signer and the aggregate sig as well.
     pub fn verify(
         &self,
-        messages: &[FieldElement],
+        messages: &[Message],
         vk: &Verkey,
         params: &Params,
+    ) -> Result<bool, PSError> {
+        self.verify_with_profile(messages, vk, params, ValidationProfile::default())
+    }
+
+    /// Verify a signature with structural checks of signature and verkey as required by `profile`
+    pub fn verify_with_profile(
+        &self,
+        messages: &[Message],
+        vk: &Verkey,
+        params: &Params,
+        profile: ValidationProfile,
     ) -> Result<bool, PSError> {
         assert_eq!(messages.len(), vk.Y_tilde.len());
         if self.sigma_1.is_identity() || self.sigma_2.is_identity() {
             return Ok(false);
         }
-        let mut Y_m_bases = OtherGroupVec::with_capacity(messages.len());
-        let mut Y_m_exps = FieldElementVector::with_capacity(messages.len());
-        for i in 0..messages.len() {
-            Y_m_bases.push(vk.Y_tilde[i].clone());
-            Y_m_exps.push(messages[i].clone());
+        if !(profile.is_valid_point(&self.sigma_1)
+            && profile.is_valid_point(&self.sigma_2)
+            && profile.is_valid_verkey(vk))
+        {
+            return Ok(false);
+        }
+        fail_point!(MultiScalarMul);
+        let mut Y_m_msm = MultiScalarMul::new();
+        for (Y_tilde_i, m) in vk.Y_tilde.iter().zip(messages) {
+            Y_m_msm.add(Y_tilde_i, m.as_field());
         }
         // Y_m = X_tilde * Y_tilde[1]^m_1 * Y_tilde[2]^m_2 * ...Y_tilde[i]^m_i
-        let Y_m = &vk.X_tilde + &(Y_m_bases.multi_scalar_mul_var_time(&Y_m_exps).unwrap());
-        // e(sigma_1, Y_m) == e(sigma_2, g2) => e(sigma_1, Y_m) * e(-sigma_2, g2) == 1, if precomputation can be used, then
-        // inverse in sigma_2 can be avoided since inverse of g_tilde can be precomputed
+        let Y_m = &vk.X_tilde + &Y_m_msm.finish();
+        fail_point!(Pairing);
+        // e(sigma_1, Y_m) == e(sigma_2, g2) => e(sigma_1, Y_m) * e(-sigma_2, g2) == 1, the inverse in sigma_2 is
+        // avoided by `verify_prepared` which has the inverse of g_tilde precomputed
         let e = ate_2_pairing(&self.sigma_1, &Y_m, &(self.sigma_2.negation()), &params.g_tilde);
         Ok(e.is_one())
     }
 
+    /// Verify many signatures under `vk` at once. With random r_i it checks
+    /// e(sum r_i*sigma_1_i, X_tilde) * prod_j e(sum r_i*m_i_j*sigma_1_i, Y_tilde_j) * e(-sum r_i*sigma_2_i, g_tilde) == 1,
+    /// a single multi-pairing of 2 pairs more than the number of messages regardless of the number of
+    /// signatures. False if any signature is invalid, without telling which.
+    pub fn batch_verify(
+        items: &[(&[FieldElement], &Signature)],
+        vk: &Verkey,
+        params: &Params,
+    ) -> Result<bool, PSError> {
+        Self::batch_verify_with_rng(items, vk, params, &mut thread_rng())
+    }
+
+    pub fn batch_verify_with_rng<R: RngCore + CryptoRng>(
+        items: &[(&[FieldElement], &Signature)],
+        vk: &Verkey,
+        params: &Params,
+        rng: &mut R,
+    ) -> Result<bool, PSError> {
+        if let Some((messages, _)) = items.iter().find(|(m, _)| m.len() != vk.Y_tilde.len()) {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: vk.Y_tilde.len(),
+                given: messages.len(),
+            });
+        }
+        let profile = ValidationProfile::default();
+        if !profile.is_valid_verkey(vk)
+            || items.iter().any(|(_, sig)| {
+                sig.sigma_1.is_identity()
+                    || sig.sigma_2.is_identity()
+                    || !profile.is_valid_point(&sig.sigma_1)
+                    || !profile.is_valid_point(&sig.sigma_2)
+            })
+        {
+            return Ok(false);
+        }
+        if items.is_empty() {
+            return Ok(true);
+        }
+        let r: FieldElementVector = (0..items.len())
+            .map(|_| FieldElement::random_using_rng(rng))
+            .collect::<Vec<_>>()
+            .into();
+        let sigma_1s = SignatureGroupVec::from(
+            items
+                .iter()
+                .map(|(_, sig)| sig.sigma_1.clone())
+                .collect::<Vec<_>>()
+                .as_slice(),
+        );
+        let sigma_2s = SignatureGroupVec::from(
+            items
+                .iter()
+                .map(|(_, sig)| sig.sigma_2.clone())
+                .collect::<Vec<_>>()
+                .as_slice(),
+        );
+        // Bases of X_tilde, each Y_tilde_j and g_tilde
+        let mut lhs = vec![sigma_1s.multi_scalar_mul_var_time(&r).unwrap()];
+        for j in 0..vk.Y_tilde.len() {
+            let exps: Vec<FieldElement> = items
+                .iter()
+                .zip(r.iter())
+                .map(|((messages, _), r_i)| r_i * &messages[j])
+                .collect();
+            lhs.push(
+                sigma_1s
+                    .multi_scalar_mul_var_time(&FieldElementVector::from(exps.as_slice()))
+                    .unwrap(),
+            );
+        }
+        lhs.push(-&sigma_2s.multi_scalar_mul_var_time(&r).unwrap());
+        let mut pairs = vec![(&lhs[0], &vk.X_tilde)];
+        for (l, Y_tilde_j) in lhs[1..].iter().zip(vk.Y_tilde.iter()) {
+            pairs.push((l, Y_tilde_j));
+        }
+        pairs.push((&lhs[lhs.len() - 1], &params.g_tilde));
+        fail_point!(Pairing);
+        Ok(ate_multi_pairing(&pairs).is_one())
+    }
+
+    /// Same as `new` but messages are consumed one at a time so they need not be collected first
+    pub fn new_from_iter<'a, I: IntoIterator<Item = &'a FieldElement>>(
+        messages: I,
+        sigkey: &Sigkey,
+        params: &Params,
+    ) -> Result<Self, PSError> {
+        Self::new_from_iter_with_rng(messages, sigkey, params, &mut thread_rng())
+    }
+
+    pub fn new_from_iter_with_rng<
+        'a,
+        I: IntoIterator<Item = &'a FieldElement>,
+        R: RngCore + CryptoRng,
+    >(
+        messages: I,
+        sigkey: &Sigkey,
+        params: &Params,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        let mut exp = sigkey.x.clone();
+        let mut count = 0;
+        for m in messages {
+            if count == sigkey.y.len() {
+                return Err(PSError::UnsupportedNoOfMessages {
+                    expected: sigkey.y.len(),
+                    given: count + 1,
+                });
+            }
+            exp += &sigkey.y[count] * m;
+            count += 1;
+        }
+        if count != sigkey.y.len() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: sigkey.y.len(),
+                given: count,
+            });
+        }
+        let u = FieldElement::random_using_rng(rng);
+        let sigma_1 = &params.g * &u;
+        let sigma_2 = &sigma_1 * &exp;
+        Ok(Self { sigma_1, sigma_2 })
+    }
+
+    /// Same as `verify` but messages are consumed one at a time. Only `ITER_CHUNK_SIZE` messages are held
+    /// at a time for multi-scalar multiplication.
+    pub fn verify_from_iter<'a, I: IntoIterator<Item = &'a FieldElement>>(
+        &self,
+        messages: I,
+        vk: &Verkey,
+        params: &Params,
+    ) -> Result<bool, PSError> {
+        if self.sigma_1.is_identity() || self.sigma_2.is_identity() {
+            return Ok(false);
+        }
+        let mut Y_m = vk.X_tilde.clone();
+        let mut bases = OtherGroupVec::with_capacity(ITER_CHUNK_SIZE);
+        let mut exps = FieldElementVector::with_capacity(ITER_CHUNK_SIZE);
+        let mut count = 0;
+        for m in messages {
+            if count == vk.Y_tilde.len() {
+                return Err(PSError::UnsupportedNoOfMessages {
+                    expected: vk.Y_tilde.len(),
+                    given: count + 1,
+                });
+            }
+            bases.push(vk.Y_tilde[count].clone());
+            exps.push(m.clone());
+            count += 1;
+            if bases.len() == ITER_CHUNK_SIZE {
+                Y_m += bases.multi_scalar_mul_var_time(&exps).unwrap();
+                bases = OtherGroupVec::with_capacity(ITER_CHUNK_SIZE);
+                exps = FieldElementVector::with_capacity(ITER_CHUNK_SIZE);
+            }
+        }
+        if count != vk.Y_tilde.len() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: vk.Y_tilde.len(),
+                given: count,
+            });
+        }
+        if bases.len() > 0 {
+            Y_m += bases.multi_scalar_mul_var_time(&exps).unwrap();
+        }
+        let e = ate_2_pairing(
+            &self.sigma_1,
+            &Y_m,
+            &(self.sigma_2.negation()),
+            &params.g_tilde,
+        );
+        Ok(e.is_one())
+    }
+
+    /// Signature over the same messages under `Verkey::rerandomize` with the same `r`. Since
+    /// e(sigma_1, (X_tilde*Y_tilde_1^m_1*...)^r) == e(sigma_2^r, g_tilde), only sigma_2 changes.
+    pub fn adapt_to_rerandomized_verkey(&self, r: &FieldElement) -> Self {
+        Self {
+            sigma_1: self.sigma_1.clone(),
+            sigma_2: &self.sigma_2 * r,
+        }
+    }
+
+    /// Another signature over the same messages, (sigma_1^r, sigma_2^r), unlinkable to this one. A
+    /// random `r` is used when None is given. Fails for a zero `r` which gives the identity.
+    pub fn rerandomize(&self, r: Option<&FieldElement>) -> Result<Self, PSError> {
+        let r = match r {
+            Some(r) if r.is_zero() => {
+                return Err(PSError::GeneralError {
+                    msg: String::from("Cannot rerandomize signature with zero"),
+                })
+            }
+            Some(r) => r.clone(),
+            None => PoKOfSignature::non_zero_random(&mut thread_rng()),
+        };
+        Ok(Self {
+            sigma_1: &self.sigma_1 * &r,
+            sigma_2: &self.sigma_2 * &r,
+        })
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut bytes = vec![];
         bytes.append(&mut self.sigma_1.to_bytes());
@@ -90,8 +391,38 @@ impl Signature {
         bytes
     }
 
-    pub fn check_verkey_and_messages_compat(
-        messages: &[FieldElement],
+    /// Hash of `to_bytes` for logging and deduplication by the issuer. It identifies the credential, so it
+    /// must never be derived from or sent with presentations. Presentations do not reveal it since the
+    /// signature is re-randomized in every proof.
+    pub fn fingerprint(&self) -> Fingerprint {
+        hash_with_domain(b"PS signature fingerprint", &self.to_bytes())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PSError> {
+        Self::from_bytes_with_profile(bytes, ValidationProfile::default())
+    }
+
+    pub fn from_bytes_with_profile(
+        bytes: &[u8],
+        profile: ValidationProfile,
+    ) -> Result<Self, PSError> {
+        if bytes.len() != 2 * SIGNATURE_GROUP_SIZE {
+            return Err(PSError::InvalidEncoding {
+                msg: format!(
+                    "Signature should be {} bytes but is {} bytes",
+                    2 * SIGNATURE_GROUP_SIZE,
+                    bytes.len()
+                ),
+            });
+        }
+        let sigma_1 = profile.point_from_bytes(&bytes[..SIGNATURE_GROUP_SIZE])?;
+        let sigma_2 = profile.point_from_bytes(&bytes[SIGNATURE_GROUP_SIZE..])?;
+        Ok(Self { sigma_1, sigma_2 })
+    }
+
+    /// Fewer messages than the verkey supports can be signed with `Signature::new_padded`
+    pub fn check_verkey_and_messages_compat<M>(
+        messages: &[M],
         verkey: &Verkey,
     ) -> Result<(), PSError> {
         if messages.len() != verkey.Y_tilde.len() {
@@ -108,8 +439,7 @@ impl Signature {
 mod tests {
     use super::*;
     use crate::keys::keygen;
-    // For benchmarking
-    use std::time::{Duration, Instant};
+    use crate::OtherGroup;
 
     #[test]
     fn test_signature_all_known_messages() {
@@ -118,8 +448,176 @@ mod tests {
             let count_msgs = (i % 5) + 1;
             let (sk, vk) = keygen(count_msgs, &params);
             let msgs = FieldElementVector::random(count_msgs);
-            let sig = Signature::new(msgs.as_slice(), &sk, &params).unwrap();
-            assert!(sig.verify(msgs.as_slice(), &vk, &params).unwrap());
+            let messages = Message::from_field_elements(msgs.as_slice());
+            let sig = Signature::new(&messages, &sk, &params).unwrap();
+            assert!(sig.verify(&messages, &vk, &params).unwrap());
+            assert!(Signature::new(&messages[1..], &sk, &params).is_err());
+        }
+    }
+
+    #[test]
+    fn test_signature_with_profiles() {
+        let params = Params::new("test".as_bytes());
+        let count_msgs = 3;
+        let (sk, vk) = keygen(count_msgs, &params);
+        let msgs = FieldElementVector::random(count_msgs);
+        let messages = Message::from_field_elements(msgs.as_slice());
+        let sig = Signature::new(&messages, &sk, &params).unwrap();
+        for profile in &[
+            ValidationProfile::Compat,
+            ValidationProfile::Strict,
+            ValidationProfile::Paranoid,
+        ] {
+            let sig_1 = Signature::from_bytes_with_profile(&sig.to_bytes(), *profile).unwrap();
+            assert!(sig_1
+                .verify_with_profile(&messages, &vk, &params, *profile)
+                .unwrap());
+        }
+        assert!(Signature::from_bytes(&sig.to_bytes()[1..]).is_err());
+
+        // An identity element in the verkey is only caught by stricter profiles
+        let mut bad_vk = vk.clone();
+        bad_vk.Y_tilde[0] = OtherGroup::identity();
+        let mut bad_sk = sk.clone();
+        bad_sk.y[0] = FieldElement::zero();
+        let sig_1 = Signature::new(&messages, &bad_sk, &params).unwrap();
+        assert!(sig_1
+            .verify_with_profile(&messages, &bad_vk, &params, ValidationProfile::Compat)
+            .unwrap());
+        assert!(!sig_1
+            .verify_with_profile(&messages, &bad_vk, &params, ValidationProfile::Strict)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_signature_from_iter() {
+        let params = Params::new("test".as_bytes());
+        for count_msgs in &[1, 5, ITER_CHUNK_SIZE, ITER_CHUNK_SIZE + 3] {
+            let (sk, vk) = keygen(*count_msgs, &params);
+            let msgs = FieldElementVector::random(*count_msgs);
+            let sig = Signature::new_from_iter(msgs.as_slice(), &sk, &params).unwrap();
+            let messages = Message::from_field_elements(msgs.as_slice());
+            assert!(sig.verify(&messages, &vk, &params).unwrap());
+            assert!(sig.verify_from_iter(msgs.as_slice(), &vk, &params).unwrap());
+
+            let sig_1 = Signature::new(&messages, &sk, &params).unwrap();
+            assert!(sig_1.verify_from_iter(msgs.iter(), &vk, &params).unwrap());
+
+            let mut msgs_1 = msgs.as_slice().to_vec();
+            msgs_1[0] = FieldElement::random();
+            assert!(!sig.verify_from_iter(msgs_1.iter(), &vk, &params).unwrap());
+
+            // Too few or too many messages
+            assert!(Signature::new_from_iter(&msgs.as_slice()[1..], &sk, &params).is_err());
+            msgs_1.push(FieldElement::random());
+            assert!(Signature::new_from_iter(msgs_1.iter(), &sk, &params).is_err());
+            assert!(sig.verify_from_iter(msgs_1.iter(), &vk, &params).is_err());
+            assert!(sig
+                .verify_from_iter(&msgs.as_slice()[1..], &vk, &params)
+                .is_err());
         }
     }
+
+    #[test]
+    fn test_signature_fingerprint() {
+        let params = Params::new("test".as_bytes());
+        let count_msgs = 3;
+        let (sk, _) = keygen(count_msgs, &params);
+        let msgs = FieldElementVector::random(count_msgs);
+        let messages = Message::from_field_elements(msgs.as_slice());
+        let sig = Signature::new(&messages, &sk, &params).unwrap();
+        let sig_1 = Signature::from_bytes(&sig.to_bytes()).unwrap();
+        assert_eq!(sig.fingerprint(), sig_1.fingerprint());
+        // Signing is randomized so even signatures over the same messages differ
+        let sig_2 = Signature::new(&messages, &sk, &params).unwrap();
+        assert_ne!(sig.fingerprint(), sig_2.fingerprint());
+    }
+
+    #[test]
+    fn test_signature_under_rerandomized_verkey() {
+        let params = Params::new("test".as_bytes());
+        let count_msgs = 3;
+        let (sk, vk) = keygen(count_msgs, &params);
+        let msgs = FieldElementVector::random(count_msgs);
+        let messages = Message::from_field_elements(msgs.as_slice());
+        let sig = Signature::new(&messages, &sk, &params).unwrap();
+
+        let r = FieldElement::random();
+        let vk_1 = vk.rerandomize(&r).unwrap();
+        assert_ne!(vk_1, vk);
+        let sig_1 = sig.adapt_to_rerandomized_verkey(&r);
+        assert!(sig_1.verify(&messages, &vk_1, &params).unwrap());
+        assert!(!sig.verify(&messages, &vk_1, &params).unwrap());
+        assert!(!sig_1.verify(&messages, &vk, &params).unwrap());
+        assert!(vk.rerandomize(&FieldElement::zero()).is_err());
+    }
+
+    #[test]
+    fn test_signature_batch_verify() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let msgs: Vec<FieldElementVector> = (0..5).map(|_| FieldElementVector::random(3)).collect();
+        let sigs: Vec<Signature> = msgs
+            .iter()
+            .map(|m| {
+                Signature::new(&Message::from_field_elements(m.as_slice()), &sk, &params).unwrap()
+            })
+            .collect();
+        let mut items: Vec<(&[FieldElement], &Signature)> = msgs
+            .iter()
+            .zip(sigs.iter())
+            .map(|(m, s)| (m.as_slice(), s))
+            .collect();
+        assert!(Signature::batch_verify(&items, &vk, &params).unwrap());
+        assert!(Signature::batch_verify(&[], &vk, &params).unwrap());
+
+        // One signature on other messages
+        let other = FieldElementVector::random(3);
+        items[2].0 = other.as_slice();
+        assert!(!Signature::batch_verify(&items, &vk, &params).unwrap());
+        // Signature of another item
+        items[2].0 = msgs[2].as_slice();
+        items[0].1 = &sigs[1];
+        assert!(!Signature::batch_verify(&items, &vk, &params).unwrap());
+        let short = FieldElementVector::random(2);
+        assert!(Signature::batch_verify(&[(short.as_slice(), &sigs[0])], &vk, &params).is_err());
+    }
+
+    #[test]
+    fn test_signature_new_batch() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let msgs: Vec<FieldElementVector> = (0..4).map(|_| FieldElementVector::random(3)).collect();
+        let slices: Vec<&[FieldElement]> = msgs.iter().map(|m| m.as_slice()).collect();
+        let sigs = Signature::new_batch(&slices, &sk, &params).unwrap();
+        assert_eq!(sigs.len(), 4);
+        for (m, sig) in slices.iter().zip(sigs.iter()) {
+            assert!(sig.verify(&Message::from_field_elements(m), &vk, &params).unwrap());
+        }
+        assert!(Signature::new_batch(&[], &sk, &params).unwrap().is_empty());
+        let short = FieldElementVector::random(2);
+        assert!(Signature::new_batch(&[slices[0], short.as_slice()], &sk, &params).is_err());
+    }
+
+    #[test]
+    fn test_signature_rerandomize() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let msgs = FieldElementVector::random(3);
+        let messages = Message::from_field_elements(msgs.as_slice());
+        let sig = Signature::new(&messages, &sk, &params).unwrap();
+
+        let sig_1 = sig.rerandomize(None).unwrap();
+        assert!(sig_1.verify(&messages, &vk, &params).unwrap());
+        assert_ne!(sig_1.fingerprint(), sig.fingerprint());
+        let r = FieldElement::random();
+        let sig_2 = sig.rerandomize(Some(&r)).unwrap();
+        assert!(sig_2.verify(&messages, &vk, &params).unwrap());
+        assert_eq!(sig_2.sigma_1, &sig.sigma_1 * &r);
+        assert_eq!(
+            sig.rerandomize(Some(&r)).unwrap().to_bytes(),
+            sig_2.to_bytes()
+        );
+        assert!(sig.rerandomize(Some(&FieldElement::zero())).is_err());
+    }
 }
@@ -0,0 +1,238 @@
+// Signing behind a trait so issuers can keep the signing key in an HSM or KMS. `Sigkey` is the in
+// process implementation, other implementations forward the messages or the commitment to the device
+// and return its signature. Issuance APIs such as `CredentialRequest::issue` take any `PsSigner`. Proofs
+// of possession and key extension need the secrets, so they are computed by the signer as well and fail
+// for signers that do not provide them.
+
+use crate::blind_signature::{BlindSignature, BlindingKey};
+use crate::errors::PSError;
+use crate::extension::VerkeyExtension;
+use crate::keys::{Params, Sigkey, Verkey};
+use crate::pop::VerkeyPoP;
+use crate::signature::Signature;
+use crate::SignatureGroup;
+use amcl_wrapper::field_elem::FieldElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+
+/// `RngCore + CryptoRng` as one trait so `PsSigner` can take the RNG as a trait object
+pub trait CryptoRngCore: RngCore + CryptoRng {}
+
+impl<R: RngCore + CryptoRng + ?Sized> CryptoRngCore for R {}
+
+pub trait PsSigner {
+    /// Number of messages signed
+    fn msg_count(&self) -> usize;
+
+    fn sign(&self, messages: &[FieldElement], params: &Params) -> Result<Signature, PSError>;
+
+    /// Blind signature on the messages in `commitment` followed by `messages`, see `BlindSignature::new`
+    fn blind_sign(
+        &self,
+        commitment: &SignatureGroup,
+        messages: &[FieldElement],
+        params: &Params,
+    ) -> Result<Signature, PSError>;
+
+    /// Same as `sign` but randomness comes from `rng`. Signers drawing their own randomness, like a
+    /// device, ignore `rng`.
+    fn sign_with_rng(
+        &self,
+        messages: &[FieldElement],
+        params: &Params,
+        _rng: &mut dyn CryptoRngCore,
+    ) -> Result<Signature, PSError> {
+        self.sign(messages, params)
+    }
+
+    /// Same as `blind_sign` but randomness comes from `rng`, see `sign_with_rng`
+    fn blind_sign_with_rng(
+        &self,
+        commitment: &SignatureGroup,
+        messages: &[FieldElement],
+        params: &Params,
+        _rng: &mut dyn CryptoRngCore,
+    ) -> Result<Signature, PSError> {
+        self.blind_sign(commitment, messages, params)
+    }
+
+    /// Proof of possession of the key, see `VerkeyPoP`. The signer picks the blindings itself so the
+    /// key never leaves it. Fails for signers that cannot prove possession.
+    fn prove_possession(
+        &self,
+        _vk: &Verkey,
+        _blinding_key: &BlindingKey,
+        _params: &Params,
+        _context: &[u8],
+        _rng: &mut dyn CryptoRngCore,
+    ) -> Result<VerkeyPoP, PSError> {
+        Err(PSError::GeneralError {
+            msg: String::from("Signer cannot prove possession of its key"),
+        })
+    }
+
+    /// The key extended with `additional` messages, see `Verkey::extend`. Fails for signers that
+    /// cannot extend their key.
+    fn extend_key(
+        &self,
+        _vk: &Verkey,
+        _additional: usize,
+        _params: &Params,
+        _rng: &mut dyn CryptoRngCore,
+    ) -> Result<(Self, Verkey, VerkeyExtension), PSError>
+    where
+        Self: Sized,
+    {
+        Err(PSError::GeneralError {
+            msg: String::from("Signer cannot extend its key"),
+        })
+    }
+}
+
+impl PsSigner for Sigkey {
+    fn msg_count(&self) -> usize {
+        self.y.len()
+    }
+
+    fn sign(&self, messages: &[FieldElement], params: &Params) -> Result<Signature, PSError> {
+        self.sign_with_rng(messages, params, &mut thread_rng())
+    }
+
+    fn blind_sign(
+        &self,
+        commitment: &SignatureGroup,
+        messages: &[FieldElement],
+        params: &Params,
+    ) -> Result<Signature, PSError> {
+        self.blind_sign_with_rng(commitment, messages, params, &mut thread_rng())
+    }
+
+    fn sign_with_rng(
+        &self,
+        messages: &[FieldElement],
+        params: &Params,
+        mut rng: &mut dyn CryptoRngCore,
+    ) -> Result<Signature, PSError> {
+        Signature::sign_with_rng(messages, self, params, &mut rng)
+    }
+
+    fn blind_sign_with_rng(
+        &self,
+        commitment: &SignatureGroup,
+        messages: &[FieldElement],
+        params: &Params,
+        mut rng: &mut dyn CryptoRngCore,
+    ) -> Result<Signature, PSError> {
+        BlindSignature::sign_with_rng(
+            commitment,
+            messages,
+            self,
+            self.msg_count(),
+            params,
+            &mut rng,
+        )
+    }
+
+    fn prove_possession(
+        &self,
+        vk: &Verkey,
+        blinding_key: &BlindingKey,
+        params: &Params,
+        context: &[u8],
+        mut rng: &mut dyn CryptoRngCore,
+    ) -> Result<VerkeyPoP, PSError> {
+        VerkeyPoP::from_sigkey(self, vk, blinding_key, params, context, &mut rng)
+    }
+
+    fn extend_key(
+        &self,
+        vk: &Verkey,
+        additional: usize,
+        params: &Params,
+        mut rng: &mut dyn CryptoRngCore,
+    ) -> Result<(Self, Verkey, VerkeyExtension), PSError> {
+        vk.extend_sigkey(additional, self, params, &mut rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blind_signature::{BlindSignature, BlindingKey};
+    use crate::issuance::CredentialRequest;
+    use crate::keys::keygen;
+    use crate::policy::CompiledPolicy;
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use std::cell::Cell;
+
+    /// Stands in for a device holding the key
+    struct Device {
+        key: Sigkey,
+        calls: Cell<usize>,
+    }
+
+    impl PsSigner for Device {
+        fn msg_count(&self) -> usize {
+            self.key.msg_count()
+        }
+
+        fn sign(&self, messages: &[FieldElement], params: &Params) -> Result<Signature, PSError> {
+            self.calls.set(self.calls.get() + 1);
+            self.key.sign(messages, params)
+        }
+
+        fn blind_sign(
+            &self,
+            commitment: &SignatureGroup,
+            messages: &[FieldElement],
+            params: &Params,
+        ) -> Result<Signature, PSError> {
+            self.calls.set(self.calls.get() + 1);
+            self.key.blind_sign(commitment, messages, params)
+        }
+    }
+
+    #[test]
+    fn test_external_signer() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let blinding_key = BlindingKey::new(&sk, &params);
+        let device = Device {
+            key: sk,
+            calls: Cell::new(0),
+        };
+        let msgs = FieldElementVector::random(3);
+        let sig = device.sign(msgs.as_slice(), &params).unwrap();
+        assert!(sig
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+
+        let (request, blinding) = CredentialRequest::new(
+            &msgs.as_slice()[..2],
+            &msgs.as_slice()[2..],
+            &blinding_key,
+            &params,
+            b"n",
+        )
+        .unwrap();
+        let policy = CompiledPolicy::compile(&[], &[]).unwrap();
+        let blinded = request
+            .issue(&device, &vk, &blinding_key, &params, &policy, b"n")
+            .unwrap();
+        let sig = BlindSignature::unblind(&blinded, &blinding);
+        assert!(sig
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+        assert_eq!(device.calls.get(), 2);
+
+        let sig = Signature::new_padded(&msgs.as_slice()[..2], &device, &params).unwrap();
+        assert!(sig
+            .verify_padded(&msgs.as_slice()[..2], &vk, &params)
+            .unwrap());
+        assert_eq!(device.calls.get(), 3);
+
+        // The device keeps its secrets
+        assert!(VerkeyPoP::new(&device, &vk, &blinding_key, &params, b"ctx").is_err());
+        assert!(vk.extend(1, &device, &params).is_err());
+    }
+}
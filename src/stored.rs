@@ -0,0 +1,178 @@
+// Signatures blinded for storage at rest, so a copy of a wallet database neither yields presentable
+// signatures nor records that can be matched with the signatures the issuer sent. The signature is
+// randomized to (sigma_1^t, sigma_2^t) and sigma_2 is blinded to sigma_2 * sigma_1^r, which is not a
+// signature on the messages. The unblinding factor r is stored encrypted as r + H(storage key, sigma_1)
+// with a tag binding the record to the storage key, so a wrong key is detected instead of producing an
+// invalid signature.
+
+use crate::errors::PSError;
+use crate::signature::{Fingerprint, Signature};
+use crate::utils::{hash_with_domain, ByteReader, HASH_SIZE};
+use crate::validation::{deserialize_point, ValidationProfile};
+use crate::{SignatureGroup, FIELD_ELEMENT_SIZE, SIGNATURE_GROUP_SIZE};
+use amcl_wrapper::field_elem::FieldElement;
+use amcl_wrapper::group_elem::GroupElement;
+use rand::{thread_rng, CryptoRng, RngCore};
+
+pub const STORAGE_KEY_SIZE: usize = 32;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StoredSignature {
+    #[serde(deserialize_with = "deserialize_point")]
+    pub sigma_1: SignatureGroup,
+    /// sigma_2 * sigma_1^r
+    #[serde(deserialize_with = "deserialize_point")]
+    pub blinded_sigma_2: SignatureGroup,
+    pub encrypted_factor: FieldElement,
+    pub tag: Fingerprint,
+}
+
+fn key_stream(storage_key: &[u8; STORAGE_KEY_SIZE], sigma_1: &SignatureGroup) -> FieldElement {
+    FieldElement::from_msg_hash(
+        &[
+            b"PS stored signature factor".as_ref(),
+            storage_key,
+            &sigma_1.to_bytes(),
+        ]
+        .concat(),
+    )
+}
+
+impl StoredSignature {
+    fn compute_tag(&self, storage_key: &[u8; STORAGE_KEY_SIZE]) -> Fingerprint {
+        let mut bytes = storage_key.to_vec();
+        bytes.append(&mut self.to_bytes());
+        hash_with_domain(b"PS stored signature tag", &bytes)
+    }
+
+    /// sigma_1, blinded sigma_2 and encrypted factor, without the tag
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.sigma_1.to_bytes();
+        bytes.append(&mut self.blinded_sigma_2.to_bytes());
+        bytes.append(&mut self.encrypted_factor.to_bytes());
+        bytes
+    }
+
+    /// `to_bytes` followed by the tag, for storage
+    pub fn to_record(&self) -> Vec<u8> {
+        let mut bytes = self.to_bytes();
+        bytes.extend_from_slice(&self.tag);
+        bytes
+    }
+
+    pub fn from_record(bytes: &[u8]) -> Result<Self, PSError> {
+        let profile = ValidationProfile::Strict;
+        let mut reader = ByteReader::new(bytes);
+        let sigma_1 = profile.point_from_bytes(reader.take(SIGNATURE_GROUP_SIZE)?)?;
+        let blinded_sigma_2 = profile.point_from_bytes(reader.take(SIGNATURE_GROUP_SIZE)?)?;
+        let encrypted_factor =
+            profile.field_element_from_bytes(reader.take(FIELD_ELEMENT_SIZE)?)?;
+        let mut tag = Fingerprint::default();
+        tag.copy_from_slice(reader.take(HASH_SIZE)?);
+        reader.finish()?;
+        Ok(Self {
+            sigma_1,
+            blinded_sigma_2,
+            encrypted_factor,
+            tag,
+        })
+    }
+}
+
+impl Signature {
+    pub fn store_blinded(&self, storage_key: &[u8; STORAGE_KEY_SIZE]) -> StoredSignature {
+        self.store_blinded_with_rng(storage_key, &mut thread_rng())
+    }
+
+    pub fn store_blinded_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        storage_key: &[u8; STORAGE_KEY_SIZE],
+        rng: &mut R,
+    ) -> StoredSignature {
+        let t = FieldElement::random_using_rng(rng);
+        let r = FieldElement::random_using_rng(rng);
+        let sigma_1 = &self.sigma_1 * &t;
+        let blinded_sigma_2 = &(&self.sigma_2 * &t) + &(&sigma_1 * &r);
+        let encrypted_factor = &r + &key_stream(storage_key, &sigma_1);
+        let mut stored = StoredSignature {
+            sigma_1,
+            blinded_sigma_2,
+            encrypted_factor,
+            tag: Fingerprint::default(),
+        };
+        stored.tag = stored.compute_tag(storage_key);
+        stored
+    }
+
+    /// Fails for another storage key or a modified record
+    pub fn load_unblinded(
+        stored: &StoredSignature,
+        storage_key: &[u8; STORAGE_KEY_SIZE],
+    ) -> Result<Self, PSError> {
+        if stored.compute_tag(storage_key) != stored.tag {
+            return Err(PSError::GeneralError {
+                msg: String::from("Wrong storage key or modified stored signature"),
+            });
+        }
+        let r = &stored.encrypted_factor - &key_stream(storage_key, &stored.sigma_1);
+        Ok(Self {
+            sigma_1: stored.sigma_1.clone(),
+            sigma_2: &stored.blinded_sigma_2 - &(&stored.sigma_1 * &r),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::{keygen, Params};
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::FieldElementVector;
+
+    #[test]
+    fn test_stored_signature() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let msgs = FieldElementVector::random(3);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let key = [3u8; STORAGE_KEY_SIZE];
+
+        let stored = sig.store_blinded(&key);
+        // Neither presentable nor matching the issued signature
+        let blinded = Signature {
+            sigma_1: stored.sigma_1.clone(),
+            sigma_2: stored.blinded_sigma_2.clone(),
+        };
+        assert!(!blinded
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+        assert_ne!(stored.sigma_1, sig.sigma_1);
+
+        let stored = StoredSignature::from_record(&stored.to_record()).unwrap();
+        let loaded = Signature::load_unblinded(&stored, &key).unwrap();
+        assert!(loaded
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+        assert!(Signature::load_unblinded(&stored, &[4u8; STORAGE_KEY_SIZE]).is_err());
+        let mut modified = stored.clone();
+        modified.encrypted_factor = FieldElement::random();
+        assert!(Signature::load_unblinded(&modified, &key).is_err());
+        assert!(StoredSignature::from_record(&stored.to_record()[1..]).is_err());
+    }
+
+    #[test]
+    fn test_stored_signature_deserialize_identity() {
+        let params = Params::new("test".as_bytes());
+        let (sk, _) = keygen(2, &params);
+        let msgs = FieldElementVector::random(2);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let mut stored = sig.store_blinded(&[3u8; STORAGE_KEY_SIZE]);
+        let json = serde_json::to_string(&stored).unwrap();
+        assert!(serde_json::from_str::<StoredSignature>(&json).is_ok());
+        stored.blinded_sigma_2 = SignatureGroup::identity();
+        let json = serde_json::to_string(&stored).unwrap();
+        assert!(serde_json::from_str::<StoredSignature>(&json).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+// Verification of a continuous stream of presentations, like records consumed from a message queue.
+// Bundles are pulled from the input in batches of fixed size and verified together, in parallel with
+// feature `rayon`, so memory use is bounded by the batch size no matter how long the stream is.
+// Outcomes are yielded in input order.
+
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::presentation::Presentation;
+use crate::signature::Fingerprint;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::collections::VecDeque;
+
+pub const DEFAULT_BATCH_SIZE: usize = 64;
+
+/// A presentation with the verkey of its issuer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PresentationBundle {
+    pub presentation: Presentation,
+    pub verkey: Verkey,
+}
+
+#[derive(Debug)]
+pub struct VerifyOutcome {
+    /// Position of the bundle in the input stream
+    pub index: usize,
+    pub fingerprint: Fingerprint,
+    pub result: Result<bool, PSError>,
+}
+
+impl VerifyOutcome {
+    pub fn is_valid(&self) -> bool {
+        matches!(self.result, Ok(true))
+    }
+}
+
+pub struct VerifyStream<'a, I: Iterator<Item = PresentationBundle>> {
+    bundles: I,
+    params: &'a Params,
+    batch_size: usize,
+    next_index: usize,
+    pending: VecDeque<VerifyOutcome>,
+}
+
+impl<'a, I: Iterator<Item = PresentationBundle>> VerifyStream<'a, I> {
+    fn verify_next_batch(&mut self) {
+        let start = self.next_index;
+        let batch: Vec<(usize, PresentationBundle)> = (&mut self.bundles)
+            .take(self.batch_size)
+            .enumerate()
+            .map(|(i, b)| (start + i, b))
+            .collect();
+        self.next_index += batch.len();
+        let params = self.params;
+        let verify = |(index, bundle): &(usize, PresentationBundle)| VerifyOutcome {
+            index: *index,
+            fingerprint: bundle.presentation.fingerprint(),
+            result: bundle.presentation.verify(&bundle.verkey, params),
+        };
+        #[cfg(feature = "rayon")]
+        let outcomes: Vec<VerifyOutcome> = batch.par_iter().map(verify).collect();
+        #[cfg(not(feature = "rayon"))]
+        let outcomes: Vec<VerifyOutcome> = batch.iter().map(verify).collect();
+        self.pending.extend(outcomes);
+    }
+}
+
+impl<'a, I: Iterator<Item = PresentationBundle>> Iterator for VerifyStream<'a, I> {
+    type Item = VerifyOutcome;
+
+    fn next(&mut self) -> Option<VerifyOutcome> {
+        if self.pending.is_empty() {
+            self.verify_next_batch();
+        }
+        self.pending.pop_front()
+    }
+}
+
+/// Verify bundles lazily in batches of `DEFAULT_BATCH_SIZE`
+pub fn verify_stream<I: Iterator<Item = PresentationBundle>>(
+    bundles: I,
+    params: &Params,
+) -> VerifyStream<'_, I> {
+    verify_stream_with_batch_size(bundles, params, DEFAULT_BATCH_SIZE)
+}
+
+/// At most `batch_size` bundles, and their outcomes, are held in memory at a time. A batch size of 0 is
+/// treated as 1.
+pub fn verify_stream_with_batch_size<I: Iterator<Item = PresentationBundle>>(
+    bundles: I,
+    params: &Params,
+    batch_size: usize,
+) -> VerifyStream<'_, I> {
+    VerifyStream {
+        bundles,
+        params,
+        batch_size: batch_size.max(1),
+        next_index: 0,
+        pending: VecDeque::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use crate::signature::Signature;
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::{FieldElement, FieldElementVector};
+    use std::cell::Cell;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_verify_stream() {
+        let count_msgs = 3;
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(count_msgs, &params);
+        let mut bundles = vec![];
+        for i in 0..7 {
+            let msgs = FieldElementVector::random(count_msgs);
+            let sig = Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params)
+                .unwrap();
+            let mut revealed = HashSet::new();
+            revealed.insert(0);
+            let mut presentation =
+                Presentation::new(&sig, &vk, &params, msgs.as_slice(), revealed, b"nonce").unwrap();
+            // Tamper with every third presentation
+            if i % 3 == 2 {
+                presentation.revealed_msgs.insert(0, FieldElement::random());
+            }
+            bundles.push(PresentationBundle {
+                presentation,
+                verkey: vk.clone(),
+            });
+        }
+
+        // Input is consumed lazily, one batch at a time
+        let pulled = Cell::new(0);
+        let input = bundles
+            .clone()
+            .into_iter()
+            .inspect(|_| pulled.set(pulled.get() + 1));
+        let mut outcomes = verify_stream_with_batch_size(input, &params, 3);
+        assert_eq!(pulled.get(), 0);
+        let first = outcomes.next().unwrap();
+        assert_eq!(pulled.get(), 3);
+        assert_eq!(first.index, 0);
+        assert_eq!(first.fingerprint, bundles[0].presentation.fingerprint());
+
+        let rest: Vec<VerifyOutcome> = outcomes.collect();
+        assert_eq!(pulled.get(), 7);
+        assert_eq!(rest.len(), 6);
+        for (i, outcome) in rest.iter().enumerate() {
+            assert_eq!(outcome.index, i + 1);
+            assert_eq!(outcome.is_valid(), outcome.index % 3 != 2);
+        }
+
+        let valid = verify_stream(bundles.into_iter(), &params)
+            .filter(|o| o.is_valid())
+            .count();
+        assert_eq!(valid, 5);
+    }
+}
@@ -0,0 +1,252 @@
+// Deterministic randomness, fixtures and a proof simulator for tests. Enabled with feature `testing`.
+// Never use the RNG here for real keys or proofs, the seed is public.
+//
+// `PoKOfSignatureProof::simulate` is the honest-verifier zero-knowledge simulator: given the challenge
+// in advance it outputs a proof that verifies for any verkey and revealed messages without any
+// signature. It picks sigma_1 = g^a, sigma_2 = g^b and sets J so that J * X_tilde * prod Y_tilde_i^m_i
+// = g_tilde^(b/a), then picks the responses and derives the commitment from them. It is no forgery
+// since a real challenge is computed from the commitment, the proof only verifies against the given
+// challenge.
+
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::pok_sig::{PoKOfSignatureProof, ProofOtherGroup};
+use crate::signature::Signature;
+use crate::types::Challenge;
+use crate::utils::MultiScalarMul;
+use amcl_wrapper::field_elem::{FieldElement, FieldElementVector};
+use rand::{thread_rng, CryptoRng, RngCore, SeedableRng};
+pub use rand_chacha::ChaChaRng;
+use std::collections::HashMap;
+
+/// Seed used by the fixture builders that do not take an RNG
+pub const DEFAULT_SEED: u64 = 0;
+
+/// Seedable ChaCha RNG which can be passed to all `_with_rng` APIs
+pub fn test_rng(seed: u64) -> ChaChaRng {
+    ChaChaRng::seed_from_u64(seed)
+}
+
+impl PoKOfSignatureProof {
+    /// Proof that verifies for `challenge` with `revealed_msgs` under `vk`, for testing verifiers
+    pub fn simulate(
+        vk: &Verkey,
+        params: &Params,
+        revealed_msgs: &HashMap<usize, FieldElement>,
+        challenge: &Challenge,
+    ) -> Result<Self, PSError> {
+        Self::simulate_with_rng(vk, params, revealed_msgs, challenge, &mut thread_rng())
+    }
+
+    pub fn simulate_with_rng<R: RngCore + CryptoRng>(
+        vk: &Verkey,
+        params: &Params,
+        revealed_msgs: &HashMap<usize, FieldElement>,
+        challenge: &Challenge,
+        rng: &mut R,
+    ) -> Result<Self, PSError> {
+        if let Some(i) = revealed_msgs.keys().find(|i| **i >= vk.Y_tilde.len()) {
+            return Err(PSError::GeneralError {
+                msg: format!("Index {} should be less than {}", i, vk.Y_tilde.len()),
+            });
+        }
+        let a = FieldElement::random_using_rng(rng);
+        let b = FieldElement::random_using_rng(rng);
+        let sig = Signature {
+            sigma_1: &params.g * &a,
+            sigma_2: &params.g * &b,
+        };
+        let mut revealed = MultiScalarMul::new();
+        for (i, m) in revealed_msgs {
+            revealed.add(&vk.Y_tilde[*i], m);
+        }
+        let J = &(&params.g_tilde * &(&b * &a.inverse())) - &(&vk.X_tilde + &revealed.finish());
+
+        // commitment = g_tilde^responses[0] * prod Y_tilde_i^responses[i] * J^challenge
+        let mut responses = FieldElementVector::new(0);
+        let mut commitment = MultiScalarMul::new();
+        let hidden = (0..vk.Y_tilde.len()).filter(|i| !revealed_msgs.contains_key(i));
+        for base in std::iter::once(&params.g_tilde).chain(hidden.map(|i| &vk.Y_tilde[i])) {
+            let r = FieldElement::random_using_rng(rng);
+            commitment.add(base, &r);
+            responses.push(r);
+        }
+        commitment.add(&J, challenge.as_field());
+        Ok(Self {
+            sig,
+            J,
+            proof_vc: ProofOtherGroup {
+                commitment: commitment.finish(),
+                responses,
+            },
+        })
+    }
+}
+
+pub mod fixtures {
+    use super::{test_rng, ChaChaRng, DEFAULT_SEED};
+    use crate::keys::{keygen_with_rng, Params, Sigkey, Verkey};
+    use crate::signature::Signature;
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::FieldElement;
+
+    /// Label used for `Params` of all fixtures
+    pub const PARAMS_LABEL: &[u8] = b"test";
+
+    /// Keys, messages and a signature over those messages
+    #[derive(Clone, Debug)]
+    pub struct Credential {
+        pub params: Params,
+        pub sigkey: Sigkey,
+        pub verkey: Verkey,
+        pub messages: Vec<Message>,
+        pub sig: Signature,
+    }
+
+    /// Credential over `count_msgs` random messages generated from `DEFAULT_SEED`
+    pub fn credential(count_msgs: usize) -> Credential {
+        credential_with_rng(count_msgs, &mut test_rng(DEFAULT_SEED))
+    }
+
+    pub fn credential_with_rng(count_msgs: usize, rng: &mut ChaChaRng) -> Credential {
+        let params = Params::new(PARAMS_LABEL);
+        let (sigkey, verkey) = keygen_with_rng(count_msgs, &params, rng);
+        let messages: Vec<Message> = (0..count_msgs)
+            .map(|_| Message(FieldElement::random_using_rng(rng)))
+            .collect();
+        let sig = Signature::new_with_rng(&messages, &sigkey, &params, rng).unwrap();
+        Credential {
+            params,
+            sigkey,
+            verkey,
+            messages,
+            sig,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixtures::*;
+    use super::*;
+    use crate::blind_signature::{BlindSignature, BlindingKey};
+    use crate::pok_sig::PoKOfSignature;
+    use amcl_wrapper::group_elem::GroupElement;
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn test_fixtures_are_deterministic() {
+        let c1 = credential(3);
+        let c2 = credential(3);
+        assert_eq!(c1.verkey, c2.verkey);
+        assert_eq!(c1.messages, c2.messages);
+        assert_eq!(c1.sig.to_bytes(), c2.sig.to_bytes());
+        assert!(c1.sig.verify(&c1.messages, &c1.verkey, &c1.params).unwrap());
+
+        let c3 = credential_with_rng(3, &mut test_rng(1));
+        assert_ne!(c1.verkey, c3.verkey);
+
+        // Proofs are deterministic as well
+        let pok_bytes = |seed| {
+            let pok = PoKOfSignature::init_with_rng(
+                &c1.sig,
+                &c1.verkey,
+                &c1.params,
+                &c1.messages,
+                None,
+                HashSet::new(),
+                &mut test_rng(seed),
+            )
+            .unwrap();
+            pok.to_bytes()
+        };
+        assert_eq!(pok_bytes(5), pok_bytes(5));
+        assert_ne!(pok_bytes(5), pok_bytes(6));
+
+        let pok = PoKOfSignature::init_with_rng(
+            &c1.sig,
+            &c1.verkey,
+            &c1.params,
+            &c1.messages,
+            None,
+            HashSet::new(),
+            &mut test_rng(5),
+        )
+        .unwrap();
+        let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
+        let proof = pok.gen_proof(&chal).unwrap();
+        assert!(proof
+            .verify(&c1.verkey, &c1.params, HashMap::new(), &chal)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_blind_signing_is_deterministic() {
+        let c = credential(3);
+        let blinding_key = BlindingKey::new(&c.sigkey, &c.params);
+        let commitment = &blinding_key.Y[0] * c.messages[0].as_field();
+        let sign = |seed| {
+            BlindSignature::new_with_rng(
+                &commitment,
+                &c.messages[1..],
+                &c.sigkey,
+                &blinding_key,
+                &c.params,
+                &mut test_rng(seed),
+            )
+            .unwrap()
+            .to_bytes()
+        };
+        assert_eq!(sign(7), sign(7));
+        assert_ne!(sign(7), sign(8));
+        let paper = BlindSignature::new_from_paper_with_rng(
+            &commitment,
+            &c.messages[1..],
+            &blinding_key.X,
+            &blinding_key,
+            &c.params,
+            &mut test_rng(7),
+        )
+        .unwrap();
+        assert_eq!(paper.to_bytes(), sign(7));
+        assert!(paper.verify(&c.messages, &c.verkey, &c.params).unwrap());
+    }
+
+    #[test]
+    fn test_simulated_proof() {
+        let c = credential(4);
+        let mut revealed_msgs = HashMap::new();
+        revealed_msgs.insert(1, c.messages[1].as_field().clone());
+        // Claims a revealed message the credential does not have
+        revealed_msgs.insert(3, FieldElement::one());
+        let chal = Challenge(FieldElement::random_using_rng(&mut test_rng(3)));
+        let proof = PoKOfSignatureProof::simulate_with_rng(
+            &c.verkey,
+            &c.params,
+            &revealed_msgs,
+            &chal,
+            &mut test_rng(4),
+        )
+        .unwrap();
+        assert!(proof
+            .verify(&c.verkey, &c.params, revealed_msgs.clone(), &chal)
+            .unwrap());
+        // Only for the given challenge and revealed messages
+        assert!(!proof
+            .verify(
+                &c.verkey,
+                &c.params,
+                revealed_msgs.clone(),
+                &Challenge(FieldElement::one())
+            )
+            .unwrap());
+        revealed_msgs.insert(3, FieldElement::zero());
+        assert!(!proof
+            .verify(&c.verkey, &c.params, revealed_msgs.clone(), &chal)
+            .unwrap());
+        revealed_msgs.insert(4, FieldElement::zero());
+        assert!(
+            PoKOfSignatureProof::simulate(&c.verkey, &c.params, &revealed_msgs, &chal).is_err()
+        );
+    }
+}
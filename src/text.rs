@@ -0,0 +1,239 @@
+// Text encodings of keys, signatures and proofs for CLI tools and config files. Each type is encoded
+// as the bytes it is stored as, `to_versioned_bytes` for keys and `to_bytes` otherwise, in lowercase
+// hex or in base58 with the Bitcoin alphabet. `Display` writes hex and `FromStr` parses it. Parsing
+// validates group elements with `ValidationProfile::Paranoid`, so a string that round trips is a
+// valid artifact. Note that `Display` of a `Sigkey` prints the secret key.
+
+use crate::errors::PSError;
+use crate::keys::{Sigkey, Verkey};
+use crate::pok_sig::PoKOfSignatureProof;
+use crate::signature::Signature;
+use crate::utils::to_hex;
+use crate::validation::ValidationProfile;
+use std::fmt;
+use std::str::FromStr;
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+pub fn encode_base58(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|b| **b == 0).count();
+    // Base 58 digits, least significant first
+    let mut digits: Vec<u8> = vec![];
+    for b in &bytes[zeros..] {
+        let mut carry = *b as u32;
+        for d in digits.iter_mut() {
+            carry += (*d as u32) << 8;
+            *d = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let mut s = String::with_capacity(zeros + digits.len());
+    s.extend(std::iter::repeat_n('1', zeros));
+    s.extend(
+        digits
+            .iter()
+            .rev()
+            .map(|d| BASE58_ALPHABET[*d as usize] as char),
+    );
+    s
+}
+
+pub fn decode_base58(s: &str) -> Result<Vec<u8>, PSError> {
+    let zeros = s.bytes().take_while(|c| *c == b'1').count();
+    // Bytes, least significant first
+    let mut bytes: Vec<u8> = vec![];
+    for c in s.bytes().skip(zeros) {
+        let mut carry = BASE58_ALPHABET
+            .iter()
+            .position(|a| *a == c)
+            .ok_or_else(|| PSError::InvalidEncoding {
+                msg: format!("Invalid base58 character {:?}", c as char),
+            })? as u32;
+        for b in bytes.iter_mut() {
+            carry += (*b as u32) * 58;
+            *b = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    let mut decoded = vec![0; zeros];
+    decoded.extend(bytes.iter().rev());
+    Ok(decoded)
+}
+
+pub fn decode_hex(s: &str) -> Result<Vec<u8>, PSError> {
+    let invalid = || PSError::InvalidEncoding {
+        msg: String::from("Invalid hex string"),
+    };
+    if !s.len().is_multiple_of(2) || !s.is_ascii() {
+        return Err(invalid());
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|_| invalid()))
+        .collect()
+}
+
+pub trait TextEncoding: Sized {
+    /// The bytes the text encodes
+    fn to_text_bytes(&self) -> Vec<u8>;
+
+    /// Parses `to_text_bytes` with full validation
+    fn from_text_bytes(bytes: &[u8]) -> Result<Self, PSError>;
+
+    fn to_hex(&self) -> String {
+        to_hex(&self.to_text_bytes())
+    }
+
+    /// Accepts upper and lower case
+    fn from_hex(s: &str) -> Result<Self, PSError> {
+        Self::from_text_bytes(&decode_hex(s)?)
+    }
+
+    fn to_base58(&self) -> String {
+        encode_base58(&self.to_text_bytes())
+    }
+
+    fn from_base58(s: &str) -> Result<Self, PSError> {
+        Self::from_text_bytes(&decode_base58(s)?)
+    }
+}
+
+impl TextEncoding for Verkey {
+    fn to_text_bytes(&self) -> Vec<u8> {
+        self.to_versioned_bytes()
+    }
+
+    fn from_text_bytes(bytes: &[u8]) -> Result<Self, PSError> {
+        Self::from_versioned_bytes(bytes)
+    }
+}
+
+impl TextEncoding for Sigkey {
+    fn to_text_bytes(&self) -> Vec<u8> {
+        self.to_versioned_bytes()
+    }
+
+    fn from_text_bytes(bytes: &[u8]) -> Result<Self, PSError> {
+        Self::from_versioned_bytes(bytes)
+    }
+}
+
+impl TextEncoding for Signature {
+    fn to_text_bytes(&self) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    fn from_text_bytes(bytes: &[u8]) -> Result<Self, PSError> {
+        Self::from_bytes_with_profile(bytes, ValidationProfile::Paranoid)
+    }
+}
+
+impl TextEncoding for PoKOfSignatureProof {
+    fn to_text_bytes(&self) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    fn from_text_bytes(bytes: &[u8]) -> Result<Self, PSError> {
+        Self::from_bytes_with_profile(bytes, ValidationProfile::Paranoid)
+    }
+}
+
+macro_rules! impl_text_traits {
+    ( $($t:ty),* ) => {
+        $(
+            impl fmt::Display for $t {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str(&self.to_hex())
+                }
+            }
+
+            impl FromStr for $t {
+                type Err = PSError;
+
+                fn from_str(s: &str) -> Result<Self, PSError> {
+                    Self::from_hex(s)
+                }
+            }
+        )*
+    };
+}
+
+impl_text_traits!(Verkey, Sigkey, Signature, PoKOfSignatureProof);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::{keygen, Params};
+    use crate::pok_sig::PoKOfSignature;
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use amcl_wrapper::group_elem::GroupElement;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_text_encodings() {
+        assert_eq!(encode_base58(&[]), "");
+        assert_eq!(encode_base58(&[0, 0, 1]), "112");
+        assert_eq!(encode_base58(b"hello world"), "StV1DL6CwTryKyV");
+        assert_eq!(decode_base58("StV1DL6CwTryKyV").unwrap(), b"hello world");
+        assert_eq!(decode_base58("112").unwrap(), vec![0, 0, 1]);
+        assert!(decode_base58("0OIl").is_err());
+
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let msgs = FieldElementVector::random(3);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let pok = PoKOfSignature::init(
+            &sig,
+            &vk,
+            &params,
+            &Message::from_field_elements(msgs.as_slice()),
+            None,
+            HashSet::new(),
+        )
+        .unwrap();
+        let chal = pok.pok_vc.gen_challenge(pok.J.to_bytes());
+        let proof = pok.gen_proof(&chal).unwrap();
+
+        assert_eq!(vk.to_string().parse::<Verkey>().unwrap(), vk);
+        assert_eq!(Verkey::from_base58(&vk.to_base58()).unwrap(), vk);
+        assert_eq!(Verkey::from_hex(&vk.to_hex().to_uppercase()).unwrap(), vk);
+        assert_eq!(
+            sk.to_string()
+                .parse::<Sigkey>()
+                .unwrap()
+                .to_versioned_bytes(),
+            sk.to_versioned_bytes()
+        );
+        let sig_1: Signature = sig.to_string().parse().unwrap();
+        assert!(sig_1
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+        assert_eq!(
+            Signature::from_base58(&sig.to_base58()).unwrap().to_bytes(),
+            sig.to_bytes()
+        );
+        let proof_1: PoKOfSignatureProof = proof.to_string().parse().unwrap();
+        assert!(proof_1
+            .verify(&vk, &params, Default::default(), &chal)
+            .unwrap());
+
+        // Points are validated
+        let identity = Signature {
+            sigma_1: sig.sigma_1.clone(),
+            sigma_2: crate::SignatureGroup::identity(),
+        };
+        assert!(identity.to_string().parse::<Signature>().is_err());
+        assert!(vk.to_string()[1..].parse::<Verkey>().is_err());
+        assert!("zz".parse::<Signature>().is_err());
+    }
+}
@@ -0,0 +1,220 @@
+// Threshold issuance as in Coconut. The signing key is shared with `Sigkey::split` and each signer
+// creates a `PartialSignature` with its `SigkeyShare`. All signers need the same sigma_1, so instead of
+// g^u it is h, a hash of the messages to the signature group with the suite of the params under
+// `THRESHOLD_DST`, and partial signature i is
+// (h, h^(x_i + y_1_i*m_1 + y_2_i*m_2 + ...)). Any `threshold` partial signatures are aggregated by
+// Lagrange interpolation in the exponent into a signature under the verkey of the shared sigkey,
+// which works with `PoKOfSignature` like any other signature.
+// A partial signature is a signature under the verkey of its share, see `SigkeyShare::verkey`, so an
+// aggregator can check each before aggregating.
+
+use crate::errors::PSError;
+use crate::key_sharing::SigkeyShare;
+use crate::keys::{Params, Verkey};
+use crate::shamir::{check_indices, lagrange_basis_at_0};
+use crate::signature::Signature;
+use crate::types::Message;
+use crate::validation::deserialize_point;
+use crate::SignatureGroup;
+use amcl_wrapper::field_elem::FieldElement;
+use amcl_wrapper::group_elem::GroupElement;
+
+/// Domain separation tag h is hashed with, suites without tags hash it as a prefix
+pub const THRESHOLD_DST: &[u8] = b"PS-SIG-V01-THRESHOLD-H_XMD:SHA-256_SSWU_RO_";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartialSignature {
+    #[serde(deserialize_with = "deserialize_point")]
+    pub sigma_1: SignatureGroup,
+    #[serde(deserialize_with = "deserialize_point")]
+    pub sigma_2: SignatureGroup,
+}
+
+/// h for a signature over `messages` by the key with the shares' verkey id
+fn message_base(
+    share: &SigkeyShare,
+    messages: &[FieldElement],
+    params: &Params,
+) -> Result<SignatureGroup, PSError> {
+    let mut bytes = share.verkey_id.to_vec();
+    for m in messages {
+        bytes.append(&mut m.to_bytes());
+    }
+    params.hash_to_signature_group_with_tag(&bytes, THRESHOLD_DST)
+}
+
+impl SigkeyShare {
+    /// Verkey of this share, partial signatures by the share verify under it
+    pub fn verkey(&self, params: &Params) -> Verkey {
+        Verkey {
+            X_tilde: &params.g_tilde * &self.x,
+            Y_tilde: self.y.iter().map(|y| &params.g_tilde * y).collect(),
+        }
+    }
+}
+
+impl PartialSignature {
+    pub fn new(
+        messages: &[FieldElement],
+        share: &SigkeyShare,
+        params: &Params,
+    ) -> Result<Self, PSError> {
+        if messages.len() != share.y.len() {
+            return Err(PSError::UnsupportedNoOfMessages {
+                expected: share.y.len(),
+                given: messages.len(),
+            });
+        }
+        let h = message_base(share, messages, params)?;
+        let mut exp = share.x.clone();
+        for (y, m) in share.y.iter().zip(messages) {
+            exp += y * m;
+        }
+        let sigma_2 = &h * &exp;
+        Ok(Self {
+            sigma_1: h,
+            sigma_2,
+        })
+    }
+
+    /// Check the partial signature against the verkey of the signer's share
+    pub fn verify(
+        &self,
+        messages: &[FieldElement],
+        share_vk: &Verkey,
+        params: &Params,
+    ) -> Result<bool, PSError> {
+        Signature {
+            sigma_1: self.sigma_1.clone(),
+            sigma_2: self.sigma_2.clone(),
+        }
+        .verify(&Message::from_field_elements(messages), share_vk, params)
+    }
+}
+
+impl Signature {
+    /// Aggregate the partial signatures of the shares with `indices`, `partials[i]` is by the share
+    /// with index `indices[i]`. Gives an invalid signature for fewer partial signatures than the
+    /// threshold.
+    pub fn aggregate(partials: &[PartialSignature], indices: &[usize]) -> Result<Self, PSError> {
+        if partials.is_empty() || partials.len() != indices.len() {
+            return Err(PSError::GeneralError {
+                msg: format!(
+                    "Need as many indices as partial signatures but given {} and {}",
+                    indices.len(),
+                    partials.len()
+                ),
+            });
+        }
+        check_indices(indices)?;
+        let sigma_1 = partials[0].sigma_1.clone();
+        if partials.iter().any(|p| p.sigma_1 != sigma_1) {
+            return Err(PSError::GeneralError {
+                msg: String::from("Partial signatures are over different messages"),
+            });
+        }
+        let mut sigma_2 = SignatureGroup::identity();
+        for (p, i) in partials.iter().zip(indices) {
+            sigma_2 += &p.sigma_2 * &lagrange_basis_at_0(indices, *i);
+        }
+        Ok(Self { sigma_1, sigma_2 })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::keygen;
+    use crate::pok_sig::PoKOfSignature;
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn test_threshold_signature() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let shares = sk.split(3, 5, &params).unwrap();
+        let msgs = FieldElementVector::random(3);
+        let partials: Vec<PartialSignature> = shares
+            .iter()
+            .map(|s| PartialSignature::new(msgs.as_slice(), s, &params).unwrap())
+            .collect();
+        for (p, s) in partials.iter().zip(&shares) {
+            assert!(p
+                .verify(msgs.as_slice(), &s.verkey(&params), &params)
+                .unwrap());
+        }
+
+        let sig = Signature::aggregate(&partials[1..4], &[2, 3, 4]).unwrap();
+        assert!(sig
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+        let sig_1 = Signature::aggregate(
+            &[
+                partials[0].clone(),
+                partials[4].clone(),
+                partials[2].clone(),
+            ],
+            &[1, 5, 3],
+        )
+        .unwrap();
+        assert!(sig_1
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+
+        // Works with proofs of knowledge
+        let pok = PoKOfSignature::init(
+            &sig,
+            &vk,
+            &params,
+            &Message::from_field_elements(msgs.as_slice()),
+            None,
+            HashSet::new(),
+        )
+        .unwrap();
+        let chal = pok.pok_vc.gen_challenge(vec![]);
+        let proof = pok.gen_proof(&chal).unwrap();
+        assert!(proof.verify(&vk, &params, HashMap::new(), &chal).unwrap());
+
+        // Below threshold, wrong indices or partials over other messages
+        let sig_2 = Signature::aggregate(&partials[..2], &[1, 2]).unwrap();
+        assert!(!sig_2
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+        let sig_3 = Signature::aggregate(&partials[..3], &[1, 2, 4]).unwrap();
+        assert!(!sig_3
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+        assert!(Signature::aggregate(&partials[..3], &[1, 2]).is_err());
+        assert!(Signature::aggregate(&partials[..2], &[1, 1]).is_err());
+        let other = PartialSignature::new(
+            FieldElementVector::random(3).as_slice(),
+            &shares[2],
+            &params,
+        )
+        .unwrap();
+        assert!(Signature::aggregate(
+            &[partials[0].clone(), partials[1].clone(), other],
+            &[1, 2, 3]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_partial_signature_deserialize_identity() {
+        let params = Params::new("test".as_bytes());
+        let (sk, _) = keygen(2, &params);
+        let shares = sk.split(2, 3, &params).unwrap();
+        let mut partial = PartialSignature::new(
+            FieldElementVector::random(2).as_slice(),
+            &shares[0],
+            &params,
+        )
+        .unwrap();
+        let json = serde_json::to_string(&partial).unwrap();
+        assert!(serde_json::from_str::<PartialSignature>(&json).is_ok());
+        partial.sigma_2 = SignatureGroup::identity();
+        let json = serde_json::to_string(&partial).unwrap();
+        assert!(serde_json::from_str::<PartialSignature>(&json).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+// Fiat-Shamir transcript shared by several sub-proofs. Every statement of a hybrid proof, e.g. a proof of
+// knowledge of a signature, a Schnorr proof of a custom statement and a range proof from another
+// library, absorbs its public values into one `Transcript` so all of them are answered with the same
+// challenge. Prover and verifier must absorb the same statements in the same order.
+//
+// Messages are appended with length prefixed labels so different sequences of messages never give the
+// same transcript.
+
+use crate::types::Challenge;
+use amcl_wrapper::group_elem::GroupElement;
+use amcl_wrapper::group_elem_g1::G1;
+use amcl_wrapper::group_elem_g2::G2;
+
+/// A statement or commitment whose public values go into the challenge
+pub trait ChallengeContributor {
+    fn absorb(&self, transcript: &mut Transcript);
+}
+
+#[derive(Clone, Debug)]
+pub struct Transcript {
+    bytes: Vec<u8>,
+}
+
+impl Transcript {
+    /// `label` names the protocol
+    pub fn new(label: &[u8]) -> Self {
+        let mut transcript = Self { bytes: vec![] };
+        transcript.append_message(b"protocol", label);
+        transcript
+    }
+
+    pub fn append_message(&mut self, label: &[u8], msg: &[u8]) {
+        self.bytes
+            .extend_from_slice(&(label.len() as u64).to_be_bytes());
+        self.bytes.extend_from_slice(label);
+        self.bytes
+            .extend_from_slice(&(msg.len() as u64).to_be_bytes());
+        self.bytes.extend_from_slice(msg);
+    }
+
+    pub fn absorb<C: ChallengeContributor + ?Sized>(&mut self, contributor: &C) {
+        contributor.absorb(self)
+    }
+
+    pub fn challenge(&self) -> Challenge {
+        Challenge::from_msg_hash(&self.bytes)
+    }
+}
+
+// Commitments are absorbed as points
+impl ChallengeContributor for G1 {
+    fn absorb(&self, transcript: &mut Transcript) {
+        transcript.append_message(b"G1", &self.to_bytes());
+    }
+}
+
+impl ChallengeContributor for G2 {
+    fn absorb(&self, transcript: &mut Transcript) {
+        transcript.append_message(b"G2", &self.to_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blind_signature::ProverCommittingSignatureGroup;
+    use crate::keys::{keygen, Params};
+    use crate::pok_sig::PoKOfSignature;
+    use crate::signature::Signature;
+    use crate::types::Message;
+    use crate::SignatureGroup;
+    use amcl_wrapper::field_elem::{FieldElement, FieldElementVector};
+    use std::collections::{HashMap, HashSet};
+
+    // A user defined statement joining the transcript
+    struct Context<'a>(&'a [u8]);
+
+    impl<'a> ChallengeContributor for Context<'a> {
+        fn absorb(&self, transcript: &mut Transcript) {
+            transcript.append_message(b"context", self.0);
+        }
+    }
+
+    #[test]
+    fn test_shared_challenge() {
+        let count_msgs = 4;
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(count_msgs, &params);
+        let msgs = FieldElementVector::random(count_msgs);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let mut revealed = HashSet::new();
+        revealed.insert(0);
+
+        // Prove knowledge of the signature and of x in h = g^x with one challenge
+        let pok = PoKOfSignature::init(
+            &sig,
+            &vk,
+            &params,
+            &Message::from_field_elements(msgs.as_slice()),
+            None,
+            revealed.clone(),
+        )
+        .unwrap();
+        let x = FieldElement::random();
+        let h = &params.g * &x;
+        let mut committing = ProverCommittingSignatureGroup::new();
+        committing.commit(&params.g, None);
+        let committed = committing.finish();
+
+        let mut transcript = Transcript::new(b"hybrid proof");
+        transcript.absorb(&Context(b"session 1"));
+        transcript.absorb(&pok);
+        transcript.absorb(&h);
+        transcript.absorb(&committed);
+        let challenge = transcript.challenge();
+        let proof_sig = pok.gen_proof(&challenge).unwrap();
+        let proof_x = committed
+            .gen_proof(&challenge, std::slice::from_ref(&x))
+            .unwrap();
+
+        // Verifier builds the same transcript from public values
+        let bases: Vec<SignatureGroup> = vec![params.g.clone()];
+        let verifier_transcript = |context: &[u8]| {
+            let mut transcript = Transcript::new(b"hybrid proof");
+            transcript.absorb(&Context(context));
+            proof_sig.absorb_with_key(&revealed, &vk, &params, &mut transcript);
+            transcript.absorb(&h);
+            proof_x.absorb_with_bases(&bases, &mut transcript);
+            transcript.challenge()
+        };
+        let challenge_1 = verifier_transcript(b"session 1");
+        assert_eq!(challenge_1, challenge);
+        let mut revealed_msgs = HashMap::new();
+        revealed_msgs.insert(0, msgs[0].clone());
+        assert!(proof_sig
+            .verify(&vk, &params, revealed_msgs.clone(), &challenge_1)
+            .unwrap());
+        assert!(proof_x.verify(&bases, &h, &challenge_1).unwrap());
+
+        // Another context gives another challenge under which the proofs fail
+        let challenge_2 = verifier_transcript(b"session 2");
+        assert!(!proof_sig
+            .verify(&vk, &params, revealed_msgs, &challenge_2)
+            .unwrap());
+        assert!(!proof_x.verify(&bases, &h, &challenge_2).unwrap());
+    }
+}
@@ -0,0 +1,441 @@
+// Issuance audit trail in the style of certificate transparency. Each issuance appends an entry to an
+// append-only log. The log is a Merkle tree hashed as in RFC 9162 (leaves prefixed with 0x00, nodes
+// with 0x01) so monitors can check that an entry is included in a published root.
+
+use crate::blind_signature::BlindingKey;
+use crate::errors::PSError;
+use crate::keys::{Params, Verkey};
+use crate::pop::VerkeyPoP;
+use crate::signature::Signature;
+use crate::signer::{CryptoRngCore, PsSigner};
+use crate::utils::{hash_with_domain, HASH_SIZE};
+use crate::SignatureGroup;
+use amcl_wrapper::field_elem::FieldElement;
+use amcl_wrapper::group_elem::GroupElement;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DOMAIN: &[u8] = b"PS issuance log";
+
+pub type LogHash = [u8; HASH_SIZE];
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IssuanceLogEntry {
+    pub verkey_hash: LogHash,
+    pub schema_id: Vec<u8>,
+    pub commitment_hash: LogHash,
+    pub timestamp: u64,
+}
+
+impl IssuanceLogEntry {
+    /// Issuances without a commitment, i.e. when all messages are known to the signer, use the identity as commitment.
+    pub fn new(vk: &Verkey, schema_id: &[u8], commitment: &SignatureGroup, timestamp: u64) -> Self {
+        Self {
+            verkey_hash: hash_with_domain(DOMAIN, &vk.to_bytes()),
+            schema_id: schema_id.to_vec(),
+            commitment_hash: hash_with_domain(DOMAIN, &commitment.to_bytes()),
+            timestamp,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&self.verkey_hash);
+        bytes.extend_from_slice(&self.commitment_hash);
+        bytes.extend_from_slice(&self.timestamp.to_be_bytes());
+        bytes.extend_from_slice(&self.schema_id);
+        bytes
+    }
+
+    pub fn leaf_hash(&self) -> LogHash {
+        let mut bytes = vec![0];
+        bytes.append(&mut self.to_bytes());
+        hash_with_domain(DOMAIN, &bytes)
+    }
+}
+
+fn node_hash(left: &LogHash, right: &LogHash) -> LogHash {
+    let mut bytes = vec![1];
+    bytes.extend_from_slice(left);
+    bytes.extend_from_slice(right);
+    hash_with_domain(DOMAIN, &bytes)
+}
+
+/// Largest power of 2 smaller than `n`, `n` > 1
+fn split_point(n: usize) -> usize {
+    let mut k = 1;
+    while k << 1 < n {
+        k <<= 1;
+    }
+    k
+}
+
+fn merkle_root(leaves: &[LogHash]) -> LogHash {
+    match leaves.len() {
+        0 => hash_with_domain(DOMAIN, &[]),
+        1 => leaves[0],
+        n => {
+            let k = split_point(n);
+            node_hash(&merkle_root(&leaves[..k]), &merkle_root(&leaves[k..]))
+        }
+    }
+}
+
+fn merkle_path(idx: usize, leaves: &[LogHash]) -> Vec<LogHash> {
+    let n = leaves.len();
+    if n <= 1 {
+        return vec![];
+    }
+    let k = split_point(n);
+    if idx < k {
+        let mut path = merkle_path(idx, &leaves[..k]);
+        path.push(merkle_root(&leaves[k..]));
+        path
+    } else {
+        let mut path = merkle_path(idx - k, &leaves[k..]);
+        path.push(merkle_root(&leaves[..k]));
+        path
+    }
+}
+
+/// Receives an entry for every issuance
+pub trait IssuanceLog {
+    /// Append the entry and return its index in the log
+    fn append(&mut self, entry: IssuanceLogEntry) -> Result<usize, PSError>;
+}
+
+/// In-memory log
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MerkleIssuanceLog {
+    entries: Vec<IssuanceLogEntry>,
+    leaves: Vec<LogHash>,
+}
+
+impl IssuanceLog for MerkleIssuanceLog {
+    fn append(&mut self, entry: IssuanceLogEntry) -> Result<usize, PSError> {
+        self.leaves.push(entry.leaf_hash());
+        self.entries.push(entry);
+        Ok(self.entries.len() - 1)
+    }
+}
+
+impl MerkleIssuanceLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entry(&self, idx: usize) -> Option<&IssuanceLogEntry> {
+        self.entries.get(idx)
+    }
+
+    /// Root of the tree over all entries, this is what the log publishes
+    pub fn root(&self) -> LogHash {
+        merkle_root(&self.leaves)
+    }
+
+    pub fn inclusion_proof(&self, idx: usize) -> Result<InclusionProof, PSError> {
+        if idx >= self.leaves.len() {
+            return Err(PSError::GeneralError {
+                msg: format!("Index {} should be less than {}", idx, self.leaves.len()),
+            });
+        }
+        Ok(InclusionProof {
+            leaf_index: idx,
+            tree_size: self.leaves.len(),
+            path: merkle_path(idx, &self.leaves),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InclusionProof {
+    pub leaf_index: usize,
+    pub tree_size: usize,
+    pub path: Vec<LogHash>,
+}
+
+impl InclusionProof {
+    /// Verify that `entry` is in the tree of size `self.tree_size` with root `root`. Section 2.1.3.2 of RFC 9162
+    pub fn verify(&self, entry: &IssuanceLogEntry, root: &LogHash) -> bool {
+        if self.leaf_index >= self.tree_size {
+            return false;
+        }
+        let mut f_n = self.leaf_index;
+        let mut s_n = self.tree_size - 1;
+        let mut r = entry.leaf_hash();
+        for p in &self.path {
+            if s_n == 0 {
+                return false;
+            }
+            if f_n & 1 == 1 || f_n == s_n {
+                r = node_hash(p, &r);
+                while f_n & 1 == 0 && f_n != 0 {
+                    f_n >>= 1;
+                    s_n >>= 1;
+                }
+            } else {
+                r = node_hash(&r, p);
+            }
+            f_n >>= 1;
+            s_n >>= 1;
+        }
+        s_n == 0 && r == *root
+    }
+}
+
+/// Signer that appends an entry to `log` for every signature `signer` creates. The entry is appended
+/// only after signing succeeded. It is a `PsSigner` so it can be given to the issuance APIs, which log
+/// with the current time.
+pub struct LoggingSigner<'a, S: PsSigner + ?Sized, L: IssuanceLog> {
+    signer: &'a S,
+    verkey: &'a Verkey,
+    schema_id: Vec<u8>,
+    log: Mutex<L>,
+}
+
+impl<'a, S: PsSigner + ?Sized, L: IssuanceLog> LoggingSigner<'a, S, L> {
+    pub fn new(signer: &'a S, verkey: &'a Verkey, schema_id: &[u8], log: L) -> Self {
+        Self {
+            signer,
+            verkey,
+            schema_id: schema_id.to_vec(),
+            log: Mutex::new(log),
+        }
+    }
+
+    pub fn log(&self) -> MutexGuard<'_, L> {
+        // Entries are appended in one call so a panic while holding the lock leaves the log consistent
+        self.log.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn into_log(self) -> L {
+        self.log.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Sign known messages. Returns the signature and the index of the log entry.
+    pub fn sign_at(
+        &self,
+        messages: &[FieldElement],
+        params: &Params,
+        timestamp: u64,
+    ) -> Result<(Signature, usize), PSError> {
+        let sig = self.signer.sign(messages, params)?;
+        let idx = self.append(&SignatureGroup::identity(), timestamp)?;
+        Ok((sig, idx))
+    }
+
+    /// Sign committed and known messages. Returns the blinded signature and the index of the log entry.
+    pub fn blind_sign_at(
+        &self,
+        commitment: &SignatureGroup,
+        messages: &[FieldElement],
+        params: &Params,
+        timestamp: u64,
+    ) -> Result<(Signature, usize), PSError> {
+        let sig = self.signer.blind_sign(commitment, messages, params)?;
+        let idx = self.append(commitment, timestamp)?;
+        Ok((sig, idx))
+    }
+
+    fn append(&self, commitment: &SignatureGroup, timestamp: u64) -> Result<usize, PSError> {
+        let entry = IssuanceLogEntry::new(self.verkey, &self.schema_id, commitment, timestamp);
+        self.log().append(entry)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl<'a, S: PsSigner + ?Sized, L: IssuanceLog> PsSigner for LoggingSigner<'a, S, L> {
+    fn msg_count(&self) -> usize {
+        self.signer.msg_count()
+    }
+
+    fn sign(&self, messages: &[FieldElement], params: &Params) -> Result<Signature, PSError> {
+        self.sign_at(messages, params, now()).map(|(sig, _)| sig)
+    }
+
+    fn blind_sign(
+        &self,
+        commitment: &SignatureGroup,
+        messages: &[FieldElement],
+        params: &Params,
+    ) -> Result<Signature, PSError> {
+        self.blind_sign_at(commitment, messages, params, now())
+            .map(|(sig, _)| sig)
+    }
+
+    fn sign_with_rng(
+        &self,
+        messages: &[FieldElement],
+        params: &Params,
+        rng: &mut dyn CryptoRngCore,
+    ) -> Result<Signature, PSError> {
+        let sig = self.signer.sign_with_rng(messages, params, rng)?;
+        self.append(&SignatureGroup::identity(), now())?;
+        Ok(sig)
+    }
+
+    fn blind_sign_with_rng(
+        &self,
+        commitment: &SignatureGroup,
+        messages: &[FieldElement],
+        params: &Params,
+        rng: &mut dyn CryptoRngCore,
+    ) -> Result<Signature, PSError> {
+        let sig = self
+            .signer
+            .blind_sign_with_rng(commitment, messages, params, rng)?;
+        self.append(commitment, now())?;
+        Ok(sig)
+    }
+
+    fn prove_possession(
+        &self,
+        vk: &Verkey,
+        blinding_key: &BlindingKey,
+        params: &Params,
+        context: &[u8],
+        rng: &mut dyn CryptoRngCore,
+    ) -> Result<VerkeyPoP, PSError> {
+        self.signer
+            .prove_possession(vk, blinding_key, params, context, rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blind_signature::BlindSignature;
+    use crate::issuance::CredentialRequest;
+    use crate::keys::keygen;
+    use crate::policy::CompiledPolicy;
+    use crate::rate_limit::{RateLimit, RateLimitedSigner};
+    use crate::types::{Blinding, Message};
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use std::time::Duration;
+
+    #[test]
+    fn test_inclusion_proofs() {
+        let params = Params::new("test".as_bytes());
+        let (_, vk) = keygen(2, &params);
+        let mut log = MerkleIssuanceLog::new();
+        for size in 1..10 {
+            let entry = IssuanceLogEntry::new(&vk, b"schema", &SignatureGroup::random(), size);
+            log.append(entry).unwrap();
+            let root = log.root();
+            for i in 0..log.len() {
+                let proof = log.inclusion_proof(i).unwrap();
+                assert!(proof.verify(log.entry(i).unwrap(), &root));
+                // Proof for one entry does not work for another
+                if log.len() > 1 {
+                    assert!(!proof.verify(log.entry((i + 1) % log.len()).unwrap(), &root));
+                }
+            }
+        }
+        assert!(log.inclusion_proof(log.len()).is_err());
+
+        // Root changes on append, old proofs do not verify against the new root
+        let old_proof = log.inclusion_proof(0).unwrap();
+        log.append(IssuanceLogEntry::new(
+            &vk,
+            b"schema",
+            &SignatureGroup::random(),
+            0,
+        ))
+        .unwrap();
+        assert!(!old_proof.verify(log.entry(0).unwrap(), &log.root()));
+    }
+
+    #[test]
+    fn test_logging_signer() {
+        let count_msgs = 3;
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(count_msgs, &params);
+        let blinding_key = BlindingKey::new(&sk, &params);
+        let signer = LoggingSigner::new(&sk, &vk, b"schema", MerkleIssuanceLog::new());
+
+        let msgs = FieldElementVector::random(count_msgs);
+        let (sig, idx) = signer.sign_at(msgs.as_slice(), &params, 1).unwrap();
+        assert_eq!(idx, 0);
+        assert!(sig
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+
+        let blinding = FieldElement::random();
+        let comm = &(&blinding_key.Y[0] * &msgs[0]) + &(&params.g * &blinding);
+        let (sig_blinded, idx) = signer
+            .blind_sign_at(&comm, &msgs.as_slice()[1..], &params, 2)
+            .unwrap();
+        assert_eq!(idx, 1);
+        let sig = BlindSignature::unblind(&sig_blinded, &Blinding(blinding.clone()));
+        assert!(sig
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+
+        let expected = IssuanceLogEntry::new(&vk, b"schema", &comm, 2);
+        assert_eq!(signer.log().entry(1).unwrap(), &expected);
+        let proof = signer.log().inclusion_proof(1).unwrap();
+        assert!(proof.verify(&expected, &signer.log().root()));
+
+        // Failed issuance is not logged
+        assert!(signer
+            .blind_sign_at(&comm, msgs.as_slice(), &params, 3)
+            .is_err());
+        assert_eq!(signer.log().len(), 2);
+    }
+
+    #[test]
+    fn test_logging_signer_composes() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let blinding_key = BlindingKey::new(&sk, &params);
+        let limited = RateLimitedSigner::new(
+            sk,
+            RateLimit {
+                max_signatures: 1,
+                window: Duration::from_secs(3600),
+            },
+        );
+        let signer = LoggingSigner::new(&limited, &vk, b"schema", MerkleIssuanceLog::new());
+
+        // Issuance through the trait is logged
+        let msgs = FieldElementVector::random(3);
+        let (request, blinding) = CredentialRequest::new(
+            &msgs.as_slice()[..1],
+            &msgs.as_slice()[1..],
+            &blinding_key,
+            &params,
+            b"n",
+        )
+        .unwrap();
+        let policy = CompiledPolicy::compile(&[], &[]).unwrap();
+        let blinded = request
+            .issue(&signer, &vk, &blinding_key, &params, &policy, b"n")
+            .unwrap();
+        let sig = BlindSignature::unblind(&blinded, &blinding);
+        assert!(sig
+            .verify(&Message::from_field_elements(msgs.as_slice()), &vk, &params)
+            .unwrap());
+        assert_eq!(signer.log().len(), 1);
+
+        // Refusals of the inner signer are not logged
+        assert!(matches!(
+            PsSigner::sign(&signer, msgs.as_slice(), &params),
+            Err(PSError::SigningRefused { .. })
+        ));
+        assert_eq!(signer.into_log().len(), 1);
+    }
+}
@@ -0,0 +1,106 @@
+// Linkability tripwire for verifiers. Every proof carries a freshly randomized signature sigma_prime so
+// the same sigma_prime in two proofs means a prover reused its randomness, which links the presentations
+// and can leak the hidden messages. `ReuseMonitor` remembers the fingerprints of the last `window`
+// randomized signatures and flags exact repeats. It is a health monitor for the ecosystem, a flagged
+// proof can still be valid.
+
+use crate::pok_sig::PoKOfSignatureProof;
+use crate::presentation::Presentation;
+use crate::signature::Fingerprint;
+use std::collections::{HashSet, VecDeque};
+
+#[derive(Clone, Debug)]
+pub struct ReuseMonitor {
+    window: usize,
+    order: VecDeque<Fingerprint>,
+    seen: HashSet<Fingerprint>,
+    repeats: u64,
+}
+
+impl ReuseMonitor {
+    /// Remember the last `window` randomized signatures
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            order: VecDeque::with_capacity(window),
+            seen: HashSet::with_capacity(window),
+            repeats: 0,
+        }
+    }
+
+    /// Record the randomized signature of `proof`, returns true if it is a repeat within the window
+    pub fn observe(&mut self, proof: &PoKOfSignatureProof) -> bool {
+        self.observe_fingerprint(proof.sig.fingerprint())
+    }
+
+    pub fn observe_presentation(&mut self, presentation: &Presentation) -> bool {
+        self.observe(&presentation.proof)
+    }
+
+    fn observe_fingerprint(&mut self, fp: Fingerprint) -> bool {
+        if self.seen.contains(&fp) {
+            self.repeats += 1;
+            return true;
+        }
+        if self.window == 0 {
+            return false;
+        }
+        if self.order.len() == self.window {
+            if let Some(old) = self.order.pop_front() {
+                self.seen.remove(&old);
+            }
+        }
+        self.order.push_back(fp);
+        self.seen.insert(fp);
+        false
+    }
+
+    /// Number of repeats flagged so far
+    pub fn repeats(&self) -> u64 {
+        self.repeats
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::{keygen, Params};
+    use crate::signature::Signature;
+    use crate::types::Message;
+    use amcl_wrapper::field_elem::FieldElementVector;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_reuse_monitor() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(3, &params);
+        let msgs = FieldElementVector::random(3);
+        let sig =
+            Signature::new(&Message::from_field_elements(msgs.as_slice()), &sk, &params).unwrap();
+        let present = || {
+            Presentation::new(&sig, &vk, &params, msgs.as_slice(), HashSet::new(), b"n").unwrap()
+        };
+
+        let mut monitor = ReuseMonitor::new(2);
+        let p1 = present();
+        let p2 = present();
+        assert!(!monitor.observe_presentation(&p1));
+        assert!(!monitor.observe_presentation(&p2));
+        // A prover reusing its randomness gives the same sigma_prime
+        assert!(monitor.observe_presentation(&p1.clone()));
+        assert_eq!(monitor.repeats(), 1);
+
+        // p1 falls out of the window
+        assert!(!monitor.observe_presentation(&present()));
+        assert!(!monitor.observe_presentation(&p1));
+        assert_eq!(monitor.len(), 2);
+    }
+}
@@ -0,0 +1,155 @@
+// Newtypes over `FieldElement` for values whose roles are easily mixed up, like passing a blinding where a
+// challenge is expected. Each wraps the field element it is transparent over. They do not dereference to
+// it, so one kind cannot be passed for another through a `&FieldElement`, the field element is taken out
+// explicitly with `as_field` or `into_inner`.
+
+use crate::errors::PSError;
+use crate::hash_to_field::hash_to_field;
+use crate::utils::{pack_field_element, unpack_field_element, PACKED_FIELD_ELEMENT_SIZE};
+use amcl_wrapper::field_elem::FieldElement;
+use rand::{CryptoRng, RngCore};
+
+macro_rules! impl_field_element_newtype {
+    ( $(#[$attr:meta])* $name:ident ) => {
+        $(#[$attr])*
+        #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        #[serde(transparent)]
+        #[repr(transparent)]
+        pub struct $name(pub FieldElement);
+
+        impl $name {
+            pub fn random() -> Self {
+                Self(FieldElement::random())
+            }
+
+            pub fn random_using_rng<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+                Self(FieldElement::random_using_rng(rng))
+            }
+
+            pub fn as_field(&self) -> &FieldElement {
+                &self.0
+            }
+
+            pub fn into_inner(self) -> FieldElement {
+                self.0
+            }
+        }
+
+        impl From<FieldElement> for $name {
+            fn from(e: FieldElement) -> Self {
+                Self(e)
+            }
+        }
+
+        impl From<$name> for FieldElement {
+            fn from(e: $name) -> Self {
+                e.0
+            }
+        }
+
+        #[cfg(feature = "zeroize")]
+        impl zeroize::Zeroize for $name {
+            fn zeroize(&mut self) {
+                self.0.zeroize();
+            }
+        }
+    };
+}
+
+impl_field_element_newtype!(
+    /// Fiat-Shamir challenge of a proof of knowledge
+    Challenge
+);
+impl_field_element_newtype!(
+    /// Blinding of a commitment, needed to unblind a blind signature
+    Blinding
+);
+impl_field_element_newtype!(
+    /// A signed message
+    Message
+);
+impl_field_element_newtype!(
+    /// Response of a proof of knowledge for one witness
+    ProofResponse
+);
+
+impl Challenge {
+    /// Challenge as the hash of the proof's bytes
+    pub fn from_msg_hash(bytes: &[u8]) -> Self {
+        Self(FieldElement::from_msg_hash(bytes))
+    }
+}
+
+impl Message {
+    /// amcl specific, use `Message::hash_to_field` for messages other implementations recompute
+    pub fn from_msg_hash(bytes: &[u8]) -> Self {
+        Self(FieldElement::from_msg_hash(bytes))
+    }
+
+    /// RFC 9380 hash_to_field of `bytes` with tag `dst`
+    pub fn hash_to_field(bytes: &[u8], dst: &[u8]) -> Self {
+        Self(hash_to_field(bytes, dst))
+    }
+
+    /// Big endian 32 byte integer as a message. Fails with `PSError::MessageOutOfRange` for values not
+    /// less than the curve order instead of reducing them so every encoder gets the same message.
+    pub fn from_bytes32(bytes: &[u8; PACKED_FIELD_ELEMENT_SIZE]) -> Result<Self, PSError> {
+        unpack_field_element(bytes)
+            .map(Self)
+            .map_err(|_| PSError::MessageOutOfRange)
+    }
+
+    /// Inverse of `from_bytes32`
+    pub fn to_bytes32(&self) -> [u8; PACKED_FIELD_ELEMENT_SIZE] {
+        let mut bytes = [0; PACKED_FIELD_ELEMENT_SIZE];
+        bytes.copy_from_slice(&pack_field_element(&self.0));
+        bytes
+    }
+
+    /// Field elements of `messages`
+    pub fn to_field_elements(messages: &[Message]) -> Vec<FieldElement> {
+        messages.iter().map(|m| m.0.clone()).collect()
+    }
+
+    /// Messages of already encoded field elements for the signing and proving APIs
+    pub fn from_field_elements(elements: &[FieldElement]) -> Vec<Message> {
+        elements.iter().cloned().map(Self).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::{keygen, Params};
+    use crate::signature::Signature;
+
+    #[test]
+    fn test_field_element_newtypes() {
+        let params = Params::new("test".as_bytes());
+        let (sk, vk) = keygen(2, &params);
+        let msgs = vec![Message::random(), Message::from_msg_hash(b"Alice")];
+        let sig = Signature::new(&msgs, &sk, &params).unwrap();
+        assert!(sig.verify(&msgs, &vk, &params).unwrap());
+        let encoded = Message::to_field_elements(&msgs);
+        assert_eq!(Message::from_field_elements(&encoded), msgs);
+
+        // The field element is taken out explicitly
+        let c = Challenge::from_msg_hash(b"challenge");
+        assert_eq!(*c.as_field(), FieldElement::from_msg_hash(b"challenge"));
+        assert_eq!(FieldElement::from(c.clone()), c.into_inner());
+
+        let mut passport = [0; 32];
+        passport[24..].copy_from_slice(b"X1234567");
+        let m = Message::from_bytes32(&passport).unwrap();
+        assert_eq!(m.to_bytes32(), passport);
+        // The curve order is rejected instead of becoming 0
+        let mut order = [0; 32];
+        order.copy_from_slice(&pack_field_element(&(-FieldElement::one())));
+        order[31] += 1;
+        match Message::from_bytes32(&order) {
+            Err(PSError::MessageOutOfRange) => (),
+            _ => panic!("Curve order accepted as a message"),
+        }
+        assert!(Message::from_bytes32(&[0xff; 32]).is_err());
+    }
+}
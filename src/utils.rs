@@ -0,0 +1,360 @@
+// Helpers shared across modules
+
+use crate::errors::PSError;
+use crate::validation::{validate_point, ValidationProfile};
+use crate::{OtherGroup, OtherGroupVec};
+use amcl_wrapper::constants::{GroupG2_SIZE, MODBYTES};
+use amcl_wrapper::field_elem::{FieldElement, FieldElementVector};
+use amcl_wrapper::group_elem::{GroupElement, GroupElementVector};
+use amcl_wrapper::group_elem_g1::G1;
+use amcl_wrapper::group_elem_g2::G2;
+use amcl_wrapper::types::{GroupG1, GroupG2};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use sha3::{Digest, Sha3_256};
+
+pub const HASH_SIZE: usize = 32;
+
+/// SHA3-256 over the length prefixed domain separation tag followed by `msg`
+pub fn hash_with_domain(domain: &[u8], msg: &[u8]) -> [u8; HASH_SIZE] {
+    let mut hasher = Sha3_256::new();
+    hasher.input((domain.len() as u64).to_be_bytes());
+    hasher.input(domain);
+    hasher.input(msg);
+    let mut h = [0; HASH_SIZE];
+    h.copy_from_slice(hasher.result().as_slice());
+    h
+}
+
+/// Lowercase hex
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Point compression. amcl can only compress G1 elements so G2 elements are encoded uncompressed.
+pub trait PointEncoding: GroupElement + Send {
+    const COMPRESSED_SIZE: usize;
+
+    fn to_compressed_bytes(&self) -> Vec<u8>;
+
+    fn from_compressed_bytes(bytes: &[u8]) -> Result<Self, PSError>;
+}
+
+impl PointEncoding for G1 {
+    const COMPRESSED_SIZE: usize = MODBYTES + 1;
+
+    /// The identity is encoded as all zeroes
+    fn to_compressed_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0; Self::COMPRESSED_SIZE];
+        if !self.is_identity() {
+            self.to_ecp().tobytes(&mut bytes, true);
+        }
+        bytes
+    }
+
+    fn from_compressed_bytes(bytes: &[u8]) -> Result<Self, PSError> {
+        if bytes.len() != Self::COMPRESSED_SIZE {
+            return Err(PSError::InvalidEncoding {
+                msg: format!(
+                    "Compressed G1 element should be {} bytes but is {} bytes",
+                    Self::COMPRESSED_SIZE,
+                    bytes.len()
+                ),
+            });
+        }
+        if bytes.iter().all(|b| *b == 0) {
+            return Ok(Self::identity());
+        }
+        if bytes[0] != 0x02 && bytes[0] != 0x03 {
+            return Err(PSError::InvalidEncoding {
+                msg: format!("Invalid compression flag {}", bytes[0]),
+            });
+        }
+        let point = GroupG1::frombytes(bytes);
+        if point.is_infinity() {
+            return Err(PSError::InvalidEncoding {
+                msg: String::from("Not a G1 element"),
+            });
+        }
+        Ok(point.into())
+    }
+}
+
+impl PointEncoding for G2 {
+    const COMPRESSED_SIZE: usize = GroupG2_SIZE;
+
+    fn to_compressed_bytes(&self) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    fn from_compressed_bytes(bytes: &[u8]) -> Result<Self, PSError> {
+        Self::from_bytes(bytes).map_err(|e| PSError::InvalidEncoding {
+            msg: format!("{:?}", e),
+        })
+    }
+}
+
+/// Decompress and check with `validate_point`
+pub fn point_from_compressed_bytes<G: PointEncoding>(bytes: &[u8]) -> Result<G, PSError> {
+    let point = G::from_compressed_bytes(bytes)?;
+    validate_point(&point)?;
+    Ok(point)
+}
+
+/// Decode consecutive chunks of `size` bytes, in parallel with feature `rayon`
+pub fn decode_chunks<T, F>(bytes: &[u8], size: usize, decode: F) -> Result<Vec<T>, PSError>
+where
+    T: Send,
+    F: Fn(&[u8]) -> Result<T, PSError> + Sync + Send,
+{
+    if !bytes.len().is_multiple_of(size) {
+        return Err(PSError::InvalidEncoding {
+            msg: format!("Length {} is not a multiple of {}", bytes.len(), size),
+        });
+    }
+    #[cfg(feature = "rayon")]
+    {
+        bytes.par_chunks(size).map(decode).collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        bytes.chunks(size).map(decode).collect()
+    }
+}
+
+/// Pairs buffered by `MultiScalarMul` before multiplying. Feature `low-memory` bounds the buffer so a
+/// verifier never holds copies of all bases of a verkey.
+#[cfg(feature = "low-memory")]
+pub const MSM_CHUNK_SIZE: usize = 4;
+#[cfg(not(feature = "low-memory"))]
+pub const MSM_CHUNK_SIZE: usize = 256;
+
+/// Sum of base^exponent over pairs added one at a time, computed with multi-scalar multiplications of
+/// at most `MSM_CHUNK_SIZE` pairs
+pub struct MultiScalarMul {
+    acc: OtherGroup,
+    bases: OtherGroupVec,
+    exps: FieldElementVector,
+}
+
+impl MultiScalarMul {
+    pub fn new() -> Self {
+        Self {
+            acc: OtherGroup::identity(),
+            bases: OtherGroupVec::with_capacity(MSM_CHUNK_SIZE),
+            exps: FieldElementVector::with_capacity(MSM_CHUNK_SIZE),
+        }
+    }
+
+    pub fn add(&mut self, base: &OtherGroup, exp: &FieldElement) {
+        self.bases.push(base.clone());
+        self.exps.push(exp.clone());
+        if self.bases.len() == MSM_CHUNK_SIZE {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.bases.len() > 0 {
+            self.acc += self.bases.multi_scalar_mul_var_time(&self.exps).unwrap();
+            self.bases = OtherGroupVec::with_capacity(MSM_CHUNK_SIZE);
+            self.exps = FieldElementVector::with_capacity(MSM_CHUNK_SIZE);
+        }
+    }
+
+    pub fn finish(mut self) -> OtherGroup {
+        self.flush();
+        self.acc
+    }
+}
+
+/// Selection of a table entry without secret dependent memory access
+pub trait ConstantTimeSelect: GroupElement {
+    fn select(table: &[Self], index: usize) -> Self;
+}
+
+impl ConstantTimeSelect for G1 {
+    fn select(table: &[Self], index: usize) -> Self {
+        let mut p = GroupG1::new();
+        for (i, e) in table.iter().enumerate() {
+            p.cmove(&e.to_ecp(), (i == index) as isize);
+        }
+        p.into()
+    }
+}
+
+impl ConstantTimeSelect for G2 {
+    fn select(table: &[Self], index: usize) -> Self {
+        let mut p = GroupG2::new();
+        for (i, e) in table.iter().enumerate() {
+            p.cmove(&e.to_ecp(), (i == index) as isize);
+        }
+        p.into()
+    }
+}
+
+const WINDOW_BITS: usize = 4;
+const WINDOW_SIZE: usize = 1 << WINDOW_BITS;
+
+/// Multiples j * 16^i * base for all 4 bit digits j and windows i of a scalar, so multiplying the base
+/// is one addition per window and no doublings. Building the table costs about as much as 5 scalar
+/// multiplications, so it pays off for a base multiplied many times. Entries are selected in constant
+/// time, so scalars may be secret.
+#[derive(Clone, Debug)]
+pub struct FixedBaseTable<G: ConstantTimeSelect> {
+    windows: Vec<Vec<G>>,
+}
+
+impl<G: ConstantTimeSelect> FixedBaseTable<G> {
+    pub fn new(base: &G) -> Self {
+        let mut windows = Vec::with_capacity(PACKED_FIELD_ELEMENT_SIZE * 8 / WINDOW_BITS);
+        let mut b = base.clone();
+        for _ in 0..PACKED_FIELD_ELEMENT_SIZE * 8 / WINDOW_BITS {
+            let mut multiples = vec![G::identity(), b.clone()];
+            for j in 2..WINDOW_SIZE {
+                multiples.push(multiples[j - 1].plus(&b));
+            }
+            for _ in 0..WINDOW_BITS {
+                b.double_mut();
+            }
+            windows.push(multiples);
+        }
+        Self { windows }
+    }
+
+    pub fn mul(&self, scalar: &FieldElement) -> G {
+        let bytes = pack_field_element(scalar);
+        let mut acc = G::identity();
+        // Least significant window first
+        for (i, byte) in bytes.iter().rev().enumerate() {
+            let low = (byte & 0x0f) as usize;
+            let high = (byte >> 4) as usize;
+            acc.add_assign_(&G::select(&self.windows[2 * i], low));
+            acc.add_assign_(&G::select(&self.windows[2 * i + 1], high));
+        }
+        acc
+    }
+}
+
+/// Field elements are less than the curve order, which is less than 2^255, so 32 bytes suffice
+pub const PACKED_FIELD_ELEMENT_SIZE: usize = 32;
+
+pub fn pack_field_element(elem: &FieldElement) -> Vec<u8> {
+    elem.to_bytes()[MODBYTES - PACKED_FIELD_ELEMENT_SIZE..].to_vec()
+}
+
+/// Rejects values not less than the curve order
+pub fn unpack_field_element(bytes: &[u8]) -> Result<FieldElement, PSError> {
+    if bytes.len() != PACKED_FIELD_ELEMENT_SIZE {
+        return Err(PSError::InvalidEncoding {
+            msg: format!(
+                "Packed field element should be {} bytes but is {} bytes",
+                PACKED_FIELD_ELEMENT_SIZE,
+                bytes.len()
+            ),
+        });
+    }
+    let mut padded = vec![0; MODBYTES - PACKED_FIELD_ELEMENT_SIZE];
+    padded.extend_from_slice(bytes);
+    ValidationProfile::Strict.field_element_from_bytes(&padded)
+}
+
+/// Sequential reads from a byte slice with errors instead of panics on short input
+pub struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    pub fn take(&mut self, n: usize) -> Result<&'a [u8], PSError> {
+        if self.bytes.len() - self.pos < n {
+            return Err(PSError::InvalidEncoding {
+                msg: format!(
+                    "Need {} bytes at position {} but only {} remain",
+                    n,
+                    self.pos,
+                    self.bytes.len() - self.pos
+                ),
+            });
+        }
+        let b = &self.bytes[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(b)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, PSError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, PSError> {
+        let mut b = [0; 4];
+        b.copy_from_slice(self.take(4)?);
+        Ok(u32::from_be_bytes(b))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos == self.bytes.len()
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    /// Error unless all bytes were read
+    pub fn finish(self) -> Result<(), PSError> {
+        if self.pos != self.bytes.len() {
+            return Err(PSError::InvalidEncoding {
+                msg: format!("{} trailing bytes", self.bytes.len() - self.pos),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_base_table() {
+        let g1 = G1::random();
+        let t1 = FixedBaseTable::new(&g1);
+        let g2 = G2::random();
+        let t2 = FixedBaseTable::new(&g2);
+        let minus_one = -FieldElement::one();
+        for e in &[
+            FieldElement::zero(),
+            FieldElement::one(),
+            minus_one,
+            FieldElement::random(),
+        ] {
+            assert_eq!(t1.mul(e), &g1 * e);
+            assert_eq!(t2.mul(e), &g2 * e);
+        }
+    }
+
+    #[test]
+    fn test_multi_scalar_mul() {
+        for count in &[
+            0,
+            1,
+            MSM_CHUNK_SIZE,
+            MSM_CHUNK_SIZE + 1,
+            3 * MSM_CHUNK_SIZE - 1,
+        ] {
+            let mut msm = MultiScalarMul::new();
+            let mut expected = OtherGroup::identity();
+            for _ in 0..*count {
+                let b = OtherGroup::random();
+                let e = FieldElement::random();
+                msm.add(&b, &e);
+                expected += &b * &e;
+            }
+            assert_eq!(msm.finish(), expected);
+        }
+    }
+}
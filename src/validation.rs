@@ -0,0 +1,189 @@
+// How aggressive structural checks are when deserializing and verifying. `Compat` only performs the
+// checks the verification equations need (identity signature elements are always rejected) and is the
+// default. `Strict` additionally rejects identity elements everywhere and non-canonical encodings.
+// `Paranoid` additionally checks that every group element has the correct order, which costs a scalar
+// multiplication per element.
+//
+// Independent of the profile, every group element decoded from bytes or deserialized goes through
+// `validate_point`, which rejects the identity and elements outside the prime order subgroup, so a
+// malicious prover cannot feed degenerate signatures, `J` or commitments into verification. The
+// profiles still decide the canonical encoding checks and which elements already in memory
+// `is_valid_point` accepts.
+
+use crate::errors::PSError;
+use crate::keys::Verkey;
+use amcl_wrapper::field_elem::FieldElement;
+use amcl_wrapper::group_elem::GroupElement;
+use serde::{Deserialize, Deserializer};
+
+/// Reject the identity and elements outside the prime order subgroup
+pub fn validate_point<G: GroupElement>(point: &G) -> Result<(), PSError> {
+    if point.is_identity() {
+        return Err(PSError::InvalidEncoding {
+            msg: String::from("Identity group element"),
+        });
+    }
+    if !point.has_correct_order() {
+        return Err(PSError::InvalidEncoding {
+            msg: String::from("Group element outside the prime order subgroup"),
+        });
+    }
+    Ok(())
+}
+
+/// For `#[serde(deserialize_with)]` on group elements, see `validate_point`
+pub fn deserialize_point<'de, D: Deserializer<'de>, G: GroupElement + Deserialize<'de>>(
+    deserializer: D,
+) -> Result<G, D::Error> {
+    let point = G::deserialize(deserializer)?;
+    validate_point(&point).map_err(serde::de::Error::custom)?;
+    Ok(point)
+}
+
+pub fn deserialize_points<'de, D: Deserializer<'de>, G: GroupElement + Deserialize<'de>>(
+    deserializer: D,
+) -> Result<Vec<G>, D::Error> {
+    let points = Vec::<G>::deserialize(deserializer)?;
+    for p in &points {
+        validate_point(p).map_err(serde::de::Error::custom)?;
+    }
+    Ok(points)
+}
+
+pub fn deserialize_point_vecs<'de, D: Deserializer<'de>, G: GroupElement + Deserialize<'de>>(
+    deserializer: D,
+) -> Result<Vec<Vec<G>>, D::Error> {
+    let points = Vec::<Vec<G>>::deserialize(deserializer)?;
+    for p in points.iter().flatten() {
+        validate_point(p).map_err(serde::de::Error::custom)?;
+    }
+    Ok(points)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ValidationProfile {
+    #[default]
+    Compat,
+    Strict,
+    Paranoid,
+}
+
+impl ValidationProfile {
+    pub fn rejects_identity(&self) -> bool {
+        *self != ValidationProfile::Compat
+    }
+
+    pub fn enforces_canonical_encoding(&self) -> bool {
+        *self != ValidationProfile::Compat
+    }
+
+    pub fn checks_subgroup(&self) -> bool {
+        *self == ValidationProfile::Paranoid
+    }
+
+    pub fn is_valid_point<G: GroupElement>(&self, point: &G) -> bool {
+        if self.rejects_identity() && point.is_identity() {
+            return false;
+        }
+        !self.checks_subgroup() || point.has_correct_order()
+    }
+
+    pub fn is_valid_verkey(&self, vk: &Verkey) -> bool {
+        self.is_valid_point(&vk.X_tilde) && vk.Y_tilde.iter().all(|y| self.is_valid_point(y))
+    }
+
+    /// Decode and check with `validate_point`
+    pub fn point_from_bytes<G: GroupElement>(&self, bytes: &[u8]) -> Result<G, PSError> {
+        let point = G::from_bytes(bytes).map_err(|e| PSError::InvalidEncoding {
+            msg: format!("{:?}", e),
+        })?;
+        if self.enforces_canonical_encoding() && point.to_bytes() != bytes {
+            return Err(PSError::InvalidEncoding {
+                msg: String::from("Non-canonical group element"),
+            });
+        }
+        validate_point(&point)?;
+        Ok(point)
+    }
+
+    /// Under `Compat` values not less than the curve order are silently reduced
+    pub fn field_element_from_bytes(&self, bytes: &[u8]) -> Result<FieldElement, PSError> {
+        let elem = FieldElement::from_bytes(bytes).map_err(|e| PSError::InvalidEncoding {
+            msg: format!("{:?}", e),
+        })?;
+        if self.enforces_canonical_encoding() && elem.to_bytes() != bytes {
+            return Err(PSError::InvalidEncoding {
+                msg: String::from("Field element not less than curve order"),
+            });
+        }
+        Ok(elem)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::Signature;
+    use crate::{OtherGroup, SignatureGroup, FIELD_ELEMENT_SIZE};
+
+    #[test]
+    fn test_profiles() {
+        let identity = SignatureGroup::identity();
+        assert!(ValidationProfile::Compat.is_valid_point(&identity));
+        assert!(!ValidationProfile::Strict.is_valid_point(&identity));
+        assert!(!ValidationProfile::Paranoid.is_valid_point(&identity));
+
+        let p = OtherGroup::random();
+        for profile in &[
+            ValidationProfile::Compat,
+            ValidationProfile::Strict,
+            ValidationProfile::Paranoid,
+        ] {
+            assert!(profile.is_valid_point(&p));
+            let q: OtherGroup = profile.point_from_bytes(&p.to_bytes()).unwrap();
+            assert_eq!(p, q);
+            assert!(profile.point_from_bytes::<OtherGroup>(&[0, 1, 2]).is_err());
+        }
+
+        // Value larger than the curve order is not a canonical field element
+        let mut large = vec![0xff; FIELD_ELEMENT_SIZE];
+        large[0] = 0;
+        assert!(ValidationProfile::Compat
+            .field_element_from_bytes(&large)
+            .is_ok());
+        assert!(ValidationProfile::Strict
+            .field_element_from_bytes(&large)
+            .is_err());
+        let e = FieldElement::random();
+        assert_eq!(
+            ValidationProfile::Strict
+                .field_element_from_bytes(&e.to_bytes())
+                .unwrap(),
+            e
+        );
+    }
+
+    #[test]
+    fn test_validate_point() {
+        assert!(validate_point(&SignatureGroup::identity()).is_err());
+        assert!(validate_point(&OtherGroup::identity()).is_err());
+        assert!(validate_point(&OtherGroup::random()).is_ok());
+
+        // Identity elements are rejected when decoding under any profile and when deserializing
+        let sig = Signature {
+            sigma_1: SignatureGroup::identity(),
+            sigma_2: SignatureGroup::random(),
+        };
+        assert!(
+            Signature::from_bytes_with_profile(&sig.to_bytes(), ValidationProfile::Compat).is_err()
+        );
+        let json = serde_json::to_string(&sig).unwrap();
+        assert!(serde_json::from_str::<Signature>(&json).is_err());
+        let sig = Signature {
+            sigma_1: SignatureGroup::random(),
+            sigma_2: SignatureGroup::random(),
+        };
+        let json = serde_json::to_string(&sig).unwrap();
+        assert!(serde_json::from_str::<Signature>(&json).is_ok());
+    }
+}
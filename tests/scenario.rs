@@ -1,9 +1,10 @@
 use amcl_wrapper::field_elem::{FieldElement, FieldElementVector};
-use amcl_wrapper::group_elem::{GroupElement, GroupElementVector};
+use amcl_wrapper::group_elem::GroupElement;
 use ps_sig::keys::{keygen, Params};
 use ps_sig::blind_signature::*;
 use ps_sig::pok_sig::*;
-use ps_sig::{OtherGroupVec, SignatureGroup};
+use ps_sig::types::{Blinding, Message};
+use ps_sig::SignatureGroup;
 use std::collections::{HashMap, HashSet};
 
 #[test]
@@ -18,14 +19,15 @@ fn test_scenario_1() {
 
     let blinding_key = BlindingKey::new(&sk, &params);
     let msgs = FieldElementVector::random(count_msgs);
-    let blinding = FieldElement::random();
+    let messages = Message::from_field_elements(msgs.as_slice());
+    let blinding = Blinding::random();
 
     // User commits to some messages
     let mut comm = SignatureGroup::new();
     for i in 0..count_blinded_msgs {
-        comm += (&blinding_key.Y[i] * &msgs[i]);
+        comm += &blinding_key.Y[i] * &msgs[i];
     }
-    comm += (&params.g * &blinding);
+    comm += &params.g * &blinding.0;
 
     {
         // User and signer engage in a proof of knowledge for the above commitment `comm`
@@ -36,7 +38,7 @@ fn test_scenario_1() {
             hidden_msgs.push(msgs[i].clone());
         }
         bases.push(params.g.clone());
-        hidden_msgs.push(blinding.clone());
+        hidden_msgs.push(blinding.0.clone());
 
         // User creates a random commitment, computes challenge and response. The proof of knowledge consists of commitment and responses
         let mut committing = ProverCommittingSignatureGroup::new();
@@ -57,14 +59,14 @@ fn test_scenario_1() {
     // Get signature, unblind it and then verify.
     let sig_blinded = BlindSignature::new(
         &comm,
-        &msgs.as_slice()[count_blinded_msgs..count_msgs],
+        &messages[count_blinded_msgs..count_msgs],
         &sk,
         &blinding_key,
         &params
     )
     .unwrap();
     let sig_unblinded = BlindSignature::unblind(&sig_blinded, &blinding);
-    assert!(sig_unblinded.verify(msgs.as_slice(), &vk, &params).unwrap());
+    assert!(sig_unblinded.verify(&messages, &vk, &params).unwrap());
 
     // Do a proof of knowledge of the signature and also reveal some of the messages.
     let mut revealed_msg_indices = HashSet::new();
@@ -75,7 +77,7 @@ fn test_scenario_1() {
     let pok = PoKOfSignature::init(
         &sig_unblinded,
         &vk, &params,
-        msgs.as_slice(),
+        &messages,
         None,
         revealed_msg_indices.clone(),
     )
@@ -87,7 +89,7 @@ fn test_scenario_1() {
 
     let mut revealed_msgs = HashMap::new();
     for i in &revealed_msg_indices {
-        revealed_msgs.insert(i.clone(), msgs[*i].clone());
+        revealed_msgs.insert(*i, msgs[*i].clone());
     }
     assert!(proof.verify(&vk, &params, revealed_msgs.clone(), &chal).unwrap());
 }